@@ -1,10 +1,35 @@
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
-use syn::{GenericParam, ItemStruct};
+use syn::{GenericParam, ItemStruct, Path};
 
 use crate::make_generic;
 
 pub fn from_row_impl(item: ItemStruct) -> syn::Result<TokenStream> {
+    let mut table = None;
+    let mut derives = vec![];
+    for attr in &item.attrs {
+        if attr.path().is_ident("from_row") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("table") {
+                    table = Some(meta.value()?.parse::<Path>()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `table`"))
+                }
+            })?;
+        } else if attr.path().is_ident("dummy") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("derive") {
+                    meta.parse_nested_meta(|meta| {
+                        derives.push(meta.path);
+                        Ok(())
+                    })
+                } else {
+                    Err(meta.error("expected `derive`"))
+                }
+            })?;
+        }
+    }
     let name = item.ident;
     let dummy_name = format_ident!("{name}Dummy");
     let original_generics = item.generics.params.iter().map(|x| {
@@ -20,6 +45,19 @@ pub fn from_row_impl(item: ItemStruct) -> syn::Result<TokenStream> {
     let mut constraints = vec![];
     let mut prepared = vec![];
     let mut inits = vec![];
+    // the lifetime of the values in the result, like `TableRow<'t, Album>`
+    let (select_generics, out_lifetime) = match &*original_generics {
+        [] => (quote! {'_t, '_a}, quote! {'_a}),
+        [lt] => (quote! {'_t}, quote! {#lt}),
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &item.generics,
+                "`from_row` supports at most one lifetime",
+            ))
+        }
+    };
+    let mut select_typs = vec![];
+    let mut select_inits = vec![];
     for field in item.fields {
         let Some(name) = field.ident else {
             return Err(syn::Error::new_spanned(
@@ -27,6 +65,9 @@ pub fn from_row_impl(item: ItemStruct) -> syn::Result<TokenStream> {
                 "tuple structs are not supported yet",
             ));
         };
+        let typ = &field.ty;
+        select_typs.push(quote! {impl ::rust_query::private::Dummy<'_t, #out_lifetime, <#table as ::rust_query::Table>::Schema, Out = #typ>});
+        select_inits.push(quote! {#name: row.#name()});
         let name_prepared = format_ident!("{name}_prepared");
         let generic = make_generic(&name);
         let typ = field.ty;
@@ -38,7 +79,33 @@ pub fn from_row_impl(item: ItemStruct) -> syn::Result<TokenStream> {
         inits.push(quote! {#name: (#name_prepared)(row)});
     }
 
+    let mut select = quote! {};
+    if let Some(table) = table {
+        let doc = format!(
+            "Select the columns of [{}] with the same names as the fields.",
+            quote!(#table)
+        );
+        // the fields are checked by the methods that are generated for the columns of the table
+        select = quote! {
+            impl<#(#original_generics),*> #name<#(#original_generics),*> {
+                #[doc = #doc]
+                #[allow(unused)]
+                fn select<#select_generics>(
+                    row: impl ::rust_query::IntoColumn<'_t, <#table as ::rust_query::Table>::Schema, Typ = #table>,
+                ) -> #dummy_name<#(#select_typs),*> {
+                    let row = ::rust_query::IntoColumn::into_column(row);
+                    #dummy_name {
+                        #(#select_inits,)*
+                    }
+                }
+            }
+        };
+    }
+
     Ok(quote! {
+        #select
+
+        #[derive(#(#derives),*)]
         struct #dummy_name<#(#generics),*> {
             #(#defs),*
         }
@@ -55,3 +122,35 @@ pub fn from_row_impl(item: ItemStruct) -> syn::Result<TokenStream> {
         }
     })
 }
+
+pub fn from_raw_impl(item: ItemStruct) -> syn::Result<TokenStream> {
+    let name = item.ident;
+    if !item.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            item.generics,
+            "generics are not supported for raw queries",
+        ));
+    }
+
+    let mut inits = vec![];
+    for field in item.fields {
+        let Some(name) = field.ident else {
+            return Err(syn::Error::new_spanned(
+                field,
+                "tuple structs are not supported",
+            ));
+        };
+        let name_str = name.to_string();
+        inits.push(quote! {#name: row.get(#name_str)?});
+    }
+
+    Ok(quote! {
+        impl ::rust_query::FromRaw for #name {
+            fn from_raw(row: ::rust_query::private::RawRow<'_>) -> ::std::result::Result<Self, ::rust_query::RawError> {
+                Ok(#name {
+                    #(#inits,)*
+                })
+            }
+        }
+    })
+}
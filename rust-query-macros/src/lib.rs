@@ -1,6 +1,6 @@
 use std::{collections::BTreeMap, ops::Not};
 
-use dummy::from_row_impl;
+use dummy::{from_raw_impl, from_row_impl};
 use heck::{ToSnekCase, ToUpperCamelCase};
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
@@ -47,6 +47,618 @@ mod table;
 ///
 /// Optional types are not allowed in unique constraints.
 ///
+/// Every unique constraint gets a marker type in the module of the table, in this case `user::UniqueEmail` and `user::UniqueUsername`.
+/// These can be used to find rows with `txn.lookup::<user::UniqueEmail>((email,))`
+/// and to choose the constraint of `txn.insert_or_update::<user::UniqueEmail>(..)`.
+///
+/// ## Full-text search
+///
+/// A table can have a full-text index on some of its columns with the `fts5` attribute:
+/// ```
+/// #[rust_query::migration::schema]
+/// #[version(0..=0)]
+/// enum Schema {
+///     #[fts5(title, body)]
+///     Post {
+///         title: String,
+///         body: String,
+///     }
+/// }
+/// # fn main() {}
+/// ```
+/// This creates an sqlite `fts5` virtual table called `post_fts` and triggers that keep it
+/// in sync with the `post` table.
+///
+/// The index can be used with the generated `match_` and `match_rank` methods:
+/// ```
+/// # use rust_query::{migration::{schema, Config}, LocalClient, Table};
+/// # #[schema]
+/// # enum Schema {
+/// #     #[fts5(title, body)]
+/// #     Post { title: String, body: String },
+/// # }
+/// # use v0::*;
+/// # let mut client = LocalClient::try_new().unwrap();
+/// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+/// # let txn = client.transaction(&db);
+/// # txn.query(|rows| {
+/// let post = Post::join(rows);
+/// rows.filter(post.match_("sqlite"));
+/// // the best matches have the lowest rank, so they are at the start of the result
+/// rows.into_vec((post.match_rank("sqlite"), post.title()))
+/// # });
+/// ```
+///
+/// ## Soft deletes
+///
+/// Rows of a table with the `soft_delete` attribute are not removed when they are deleted:
+/// ```
+/// #[rust_query::migration::schema]
+/// #[version(0..=0)]
+/// enum Schema {
+///     #[soft_delete]
+///     Post {
+///         title: String,
+///     }
+/// }
+/// # fn main() {}
+/// ```
+/// This adds a `deleted_at` column to the table, which can be read with the generated `deleted_at` method.
+/// The column is set to the current unix time by `txn.delete(post)` and set back to `NULL` by `txn.restore(post)`.
+///
+/// Deleted rows are skipped by `Post::join(rows)` and the generated methods to join child rows.
+/// They can still be found with `Post::join_with_deleted(rows)`, by following a foreign key
+/// and with the unique constraints of the table.
+///
+/// ## Timestamps
+///
+/// A table with the `timestamps` attribute gets `created_at` and `updated_at` columns:
+/// ```
+/// #[rust_query::migration::schema]
+/// #[version(0..=0)]
+/// enum Schema {
+///     #[timestamps]
+///     Post {
+///         title: String,
+///     }
+/// }
+/// # fn main() {}
+/// ```
+/// Both columns are set to the current unix time when a row is inserted
+/// and `updated_at` is set again every time the row is updated.
+/// They can be read with the generated `created_at` and `updated_at` methods.
+///
+/// Migrations keep the timestamps of existing rows.
+///
+/// ## Public ids
+///
+/// The `id` of every row is an integer that counts up, so it shows how many rows there are.
+/// A table with the `public_id` attribute also gets a `public_id` column
+/// with a UUIDv7 (`#[public_id(uuid)]`) or a ULID (`#[public_id(ulid)]`) that is generated when a row is inserted:
+/// ```
+/// #[rust_query::migration::schema]
+/// #[version(0..=0)]
+/// enum Schema {
+///     #[public_id(uuid)]
+///     Post {
+///         title: String,
+///     }
+/// }
+/// # fn main() {}
+/// ```
+/// Both are stored as text and start with the time, so new rows are added at the end of the index.
+/// The column is read with the generated `public_id` method as a `PublicId<Post>`
+/// and `Post::by_public_id(id)` finds the row with that id.
+///
+/// The integer `id` is still the primary key and foreign keys still reference it.
+/// Migrations keep the public ids of existing rows.
+///
+/// ## Optimistic concurrency
+///
+/// A table with the `versioned` attribute gets a `version` column:
+/// ```
+/// #[rust_query::migration::schema]
+/// #[version(0..=0)]
+/// enum Schema {
+///     #[versioned]
+///     Account {
+///         balance: i64,
+///     }
+/// }
+/// # fn main() {}
+/// ```
+/// The version of a new row is zero and every update increments it.
+/// This can be used to update a row that was read in an earlier transaction, without overwriting concurrent changes:
+/// ```
+/// # use rust_query::{migration::{schema, Config}, LocalClient, VersionConflict};
+/// # #[schema]
+/// # enum Schema {
+/// #     #[versioned]
+/// #     Account { balance: i64 },
+/// # }
+/// # use v0::*;
+/// # let mut client = LocalClient::try_new().unwrap();
+/// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+/// # let mut txn = client.transaction_mut(&db);
+/// # let account = txn.insert(Account { balance: 0 });
+/// let (balance, version) = txn.query_one((account.balance(), account.version()));
+/// // ... later, in another transaction
+/// match txn.update_if_version(account, version, Account { balance: balance + 10 }) {
+///     Ok(_new_version) => txn.commit(),
+///     Err(VersionConflict { current }) => { /* read the row again and retry */ }
+/// }
+/// ```
+///
+/// ## Foreign key actions
+///
+/// By default, deleting a row that is still referenced by another row is not allowed.
+/// This can be changed for each column that references a table with the `on_delete` attribute:
+/// ```
+/// #[rust_query::migration::schema]
+/// #[version(0..=0)]
+/// enum Schema {
+///     Artist {
+///         name: String,
+///     },
+///     Album {
+///         title: String,
+///         #[on_delete(cascade)]
+///         artist: Artist,
+///     },
+///     Employee {
+///         name: String,
+///         #[on_delete(set_null)]
+///         reports_to: Option<Employee>,
+///     },
+/// }
+/// # fn main() {}
+/// ```
+/// The supported actions are:
+/// - `cascade` deletes the referencing rows too.
+/// - `restrict` forbids deleting the referenced row, like the default but checked immediately.
+/// - `set_null` sets the column to `NULL`, this is only allowed for `Option` columns.
+///
+/// The action is part of the schema, so changing it requires a new schema version
+/// where the column is replaced.
+///
+/// ## Generated columns
+///
+/// A column can be computed by sqlite from the other columns of the row with the `generated` attribute.
+/// The value is computed when it is read, or when the row is written if the column is `stored`.
+/// ```
+/// #[rust_query::migration::schema]
+/// #[version(0..=0)]
+/// enum Schema {
+///     OrderLine {
+///         price: f64,
+///         quantity: i64,
+///         #[generated("price * quantity")]
+///         total: f64,
+///         #[generated("length(note)", stored)]
+///         note_length: Option<i64>,
+///         note: Option<String>,
+///     },
+/// }
+/// # fn main() {}
+/// ```
+/// Generated columns can be used in queries like other columns, but they are not part of the
+/// table struct, so they are never inserted, updated or migrated.
+/// Just like foreign key actions, changing the expression requires a new schema version where the column is replaced.
+///
+/// ## Default values
+///
+/// A column can have a default value with the `default` attribute.
+/// The value can be a number, a string or `None` for optional columns.
+/// ```
+/// #[rust_query::migration::schema]
+/// #[version(0..=0)]
+/// enum Schema {
+///     Track {
+///         name: String,
+///         #[default = 0]
+///         plays: i64,
+///         #[default = None]
+///         rating: Option<i64>,
+///     },
+/// }
+/// # fn main() {}
+/// ```
+/// Columns with a default value are not part of the table struct, so `Track { name: "Intro" }`
+/// inserts a row with the default values.
+/// The generated `with_` methods set these columns instead:
+/// ```
+/// # use rust_query::{migration::{schema, Config}, LocalClient};
+/// # #[schema]
+/// # enum Schema {
+/// #     Track {
+/// #         name: String,
+/// #         #[default = 0]
+/// #         plays: i64,
+/// #         #[default = None]
+/// #         rating: Option<i64>,
+/// #     },
+/// # }
+/// # use v0::*;
+/// # let mut client = LocalClient::try_new().unwrap();
+/// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+/// # let mut txn = client.transaction_mut(&db);
+/// txn.insert(Track { name: "Outro" }.with_plays(3).with_rating(Some(5)));
+/// ```
+/// The default value is part of the schema, it is also used for new columns in migrations,
+/// so adding a column with a default value does not require any code in the migration.
+/// Columns with a default value can not be part of a unique constraint.
+///
+/// ## Builders
+///
+/// Every table also gets a builder to create new rows one column at a time:
+/// ```
+/// # use rust_query::{migration::{schema, Config}, LocalClient};
+/// # #[schema]
+/// # enum Schema {
+/// #     Track {
+/// #         name: String,
+/// #         #[default = 0]
+/// #         plays: i64,
+/// #         rating: Option<i64>,
+/// #     },
+/// # }
+/// # use v0::*;
+/// # let mut client = LocalClient::try_new().unwrap();
+/// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+/// # let mut txn = client.transaction_mut(&db);
+/// txn.insert(Track::builder().name("Outro").plays(3).build());
+/// ```
+/// The builder only has a `build` method when all required columns are set, so a missing column is a compile error.
+/// Optional columns are `NULL` and columns with a default value use the default value, unless they are set.
+///
+/// ## Reading rows
+///
+/// Every table gets a struct with all of its columns and its `id`, like `TrackRow` for `Track`.
+/// The generated `select_all` method reads the whole row into this struct:
+/// ```
+/// # use rust_query::{migration::{schema, Config}, LocalClient, Table};
+/// # #[schema]
+/// # enum Schema {
+/// #     Track { name: String },
+/// # }
+/// # use v0::*;
+/// # let mut client = LocalClient::try_new().unwrap();
+/// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+/// # let txn = client.transaction(&db);
+/// # txn.query(|rows| {
+/// let track = Track::join(rows);
+/// let tracks: Vec<TrackRow> = rows.into_vec(track.select_all());
+/// # });
+/// ```
+/// To read only some of the columns, derive [FromDummy] with the `from_row` attribute.
+///
+/// To keep a reference to a row after the transaction, every table also gets an id type like `TrackId`.
+/// This is an alias for `RowId<Track>`, so an `AlbumId` can not be used where a `TrackId` is expected:
+/// ```
+/// # use rust_query::{migration::{schema, Config}, LocalClient};
+/// # #[schema]
+/// # enum Schema {
+/// #     Track { name: String },
+/// # }
+/// # use v0::*;
+/// # let mut client = LocalClient::try_new().unwrap();
+/// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+/// # let mut txn = client.transaction_mut(&db);
+/// # let track = txn.insert(Track { name: "Intro" });
+/// let id = TrackId::from(track);
+/// // later in another transaction
+/// let track = txn.find(id).unwrap();
+/// ```
+///
+/// ## All tables
+///
+/// Every schema also gets an enum with a variant for each table, like `SchemaTables` for `Schema`.
+/// It is used to write code that works for every table, like an admin page that shows the number of rows:
+/// ```
+/// # use rust_query::{migration::{schema, Config}, LocalClient};
+/// # #[schema]
+/// # enum Schema {
+/// #     Track { name: String },
+/// # }
+/// # use v0::*;
+/// # let mut client = LocalClient::try_new().unwrap();
+/// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+/// # let txn = client.transaction(&db);
+/// for table in SchemaTables::ALL {
+///     println!("{}: {} rows", table.name(), table.count(&txn));
+/// }
+/// ```
+/// The `columns` method returns the name, type and foreign key of each column as a `ColumnInfo`.
+///
+/// ## Check constraints
+///
+/// Sqlite can check that the values of a row are valid with the `check` attribute.
+/// It can be used on a column or on the table, when the expression uses multiple columns.
+/// ```
+/// #[rust_query::migration::schema]
+/// #[version(0..=0)]
+/// enum Schema {
+///     #[check("start <= end")]
+///     Session {
+///         #[check("length(name) > 0")]
+///         name: String,
+///         start: i64,
+///         end: i64,
+///     },
+/// }
+/// # fn main() {}
+/// ```
+/// Rows that do not satisfy a check can not be inserted or updated.
+/// Use `txn.checked_insert` and `txn.checked_update` to get a `WriteError::Check` instead of a panic.
+/// The checks are part of the schema, so changing them requires a new schema version.
+///
+/// ## Collations
+///
+/// Text columns can use a collation to compare values with the `collate` attribute.
+/// ```
+/// #[rust_query::migration::schema]
+/// #[version(0..=0)]
+/// enum Schema {
+///     User {
+///         #[unique]
+///         #[collate("NOCASE")]
+///         email: String,
+///     },
+/// }
+/// # fn main() {}
+/// ```
+/// The collation is used when comparing and sorting the column, and by unique constraints,
+/// so `"a@b.c"` and `"A@B.C"` are the same email in the schema above.
+/// Custom collations must be registered with `Config::collation` before the database is opened.
+/// Changing the collation of a column requires a new schema version.
+///
+/// ## Multi-tenant scope
+///
+/// A column that references a table can be the scope key of its table with the `scope` attribute.
+/// ```
+/// #[rust_query::migration::schema]
+/// #[version(0..=0)]
+/// enum Schema {
+///     Tenant {
+///         name: String,
+///     },
+///     Invoice {
+///         #[scope]
+///         tenant: Tenant,
+///         amount: i64,
+///     },
+/// }
+/// # fn main() {}
+/// ```
+/// A transaction that is scoped with `txn.scoped(tenant, |txn, tenant| ...)` only sees the invoices of that tenant,
+/// and it panics when it writes an invoice of another tenant.
+/// The scope is not part of the schema, so it can be added without a new schema version.
+///
+/// ## Reverse references
+///
+/// For every column that references another table, the referenced table gets a method
+/// to join the rows that reference it. The method is named after the referencing table:
+/// ```
+/// # use rust_query::{migration::{schema, Config}, LocalClient, Table};
+/// # #[schema]
+/// # enum Schema {
+/// #     Artist { name: String },
+/// #     Album { title: String, artist: Artist },
+/// # }
+/// # use v0::*;
+/// # let mut client = LocalClient::try_new().unwrap();
+/// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+/// # let txn = client.transaction(&db);
+/// # txn.query(|rows| {
+/// // `Album { artist: Artist, .. }` results in `artist.albums(rows)`
+/// let artist = Artist::join(rows);
+/// let album = artist.albums(rows);
+/// rows.into_vec((artist.name(), album.title()))
+/// # });
+/// ```
+/// The generated methods also work in `aggregate` sub-queries:
+/// ```
+/// # use rust_query::{aggregate, migration::{schema, Config}, LocalClient, Table};
+/// # #[schema]
+/// # enum Schema {
+/// #     Artist { name: String },
+/// #     Album { title: String, artist: Artist },
+/// # }
+/// # use v0::*;
+/// # let mut client = LocalClient::try_new().unwrap();
+/// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+/// # let txn = client.transaction(&db);
+/// # txn.query(|rows| {
+/// # let artist = Artist::join(rows);
+/// let album_count = aggregate(|rows| {
+///     let album = artist.albums(rows);
+///     rows.count_distinct(album)
+/// });
+/// # rows.into_vec(album_count)
+/// # });
+/// ```
+/// If a table references the same table with multiple columns, then the method name
+/// also includes the column name, like `employees_by_reports_to`.
+///
+/// ## Hierarchies
+///
+/// A table that references itself, like `Employee { reports_to: Option<Employee>, .. }`,
+/// gets the methods `ancestors` and `descendants` to join all rows that are connected by following
+/// the column up to `depth` times. They are computed with a recursive common table expression.
+/// ```
+/// # use rust_query::{migration::{schema, Config}, LocalClient, Table};
+/// # #[schema]
+/// # enum Schema {
+/// #     Employee { last_name: String, reports_to: Option<Employee> },
+/// # }
+/// # use v0::*;
+/// # let mut client = LocalClient::try_new().unwrap();
+/// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+/// # let txn = client.transaction(&db);
+/// # txn.query(|rows| {
+/// let employee = Employee::join(rows);
+/// // everyone that `employee` reports to, directly or indirectly
+/// let boss = employee.ancestors(rows, 10);
+/// rows.into_vec((employee.last_name(), boss.last_name()))
+/// # });
+/// ```
+/// Just like reverse references, they also work in `aggregate` sub-queries,
+/// for example to count the size of the team of every employee.
+/// If a table references itself with multiple columns, then the method names
+/// include the column name, like `ancestors_by_reports_to`.
+///
+/// ## Namespaces
+///
+/// Multiple schemas can be stored in one database file, for example when a library has tables of its own.
+/// Every schema except one needs a namespace, which is used as prefix for its table names:
+/// ```
+/// #[rust_query::migration::schema(namespace = "blog")]
+/// #[version(0..=0)]
+/// enum Blog {
+///     // this is stored in the `blog_post` table
+///     Post { title: String },
+/// }
+/// # fn main() {}
+/// ```
+/// Each schema has its own version and is migrated separately with `LocalClient::namespace_migrator`,
+/// which uses the same database file as an existing `Database`.
+///
+/// ## Application id
+/// The `application_id` argument is stored in the header of the database file, like the version.
+/// Opening a file that has another `application_id` fails with `Error::ApplicationId`, instead of
+/// trying to migrate a file that belongs to another application.
+/// ```
+/// #[rust_query::migration::schema(application_id = 0x52515259)]
+/// enum Schema {
+///     User { name: String },
+/// }
+/// # fn main() {}
+/// ```
+/// Files without `application_id`, like files that were created before it was added to the schema, get the id when they are opened.
+///
+/// The hash of the schema is also stored in the file, so a file that was created with another schema
+/// with the same version fails with `Error::SchemaHash`.
+///
+/// ## Link tables
+///
+/// A table with the `link_table` attribute connects the rows of two tables in a many-to-many relation:
+/// ```
+/// #[rust_query::migration::schema]
+/// #[version(0..=0)]
+/// enum Schema {
+///     Playlist {
+///         name: String,
+///     },
+///     Track {
+///         name: String,
+///     },
+///     #[link_table]
+///     PlaylistTrack {
+///         playlist: Playlist,
+///         track: Track,
+///     },
+/// }
+/// # fn main() {}
+/// ```
+/// A link table has exactly two columns that reference a table, other columns must be generated or have a default value.
+/// Both referenced tables get methods that are named after the other column:
+/// ```
+/// # use rust_query::{migration::{schema, Config}, LocalClient, Table};
+/// # #[schema]
+/// # enum Schema {
+/// #     Playlist { name: String },
+/// #     Track { name: String },
+/// #     #[link_table]
+/// #     PlaylistTrack { playlist: Playlist, track: Track },
+/// # }
+/// # use v0::*;
+/// # let mut client = LocalClient::try_new().unwrap();
+/// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+/// # let mut txn = client.transaction_mut(&db);
+/// # let playlist = txn.insert(Playlist { name: "rock" });
+/// # let track = txn.insert(Track { name: "intro" });
+/// // returns `false` if the track was already added
+/// playlist.add_track(&mut txn, track);
+/// // `playlist.tracks(rows)` joins the tracks of the playlist, like `track.playlists(rows)`
+/// let names = txn.query(|rows| {
+///     let track = playlist.tracks(rows);
+///     rows.into_vec(track.name())
+/// });
+/// # assert_eq!(names, ["intro"]);
+/// // rows are removed using the `Deletor`
+/// let mut deletor = txn.deletor();
+/// playlist.remove_track(&mut deletor, track);
+/// ```
+///
+/// ## Views
+/// A table with the `view` attribute is a sql view, which is created by the migration.
+/// Views can be joined and queried just like tables, but rows can not be inserted into them.
+/// ```
+/// #[rust_query::migration::schema]
+/// #[version(0..=0)]
+/// enum Schema {
+///     User {
+///         name: String,
+///         score: i64,
+///     },
+///     #[view("SELECT id, name FROM user WHERE score > 100")]
+///     TopUser {
+///         name: String,
+///     },
+/// }
+/// # fn main() {}
+/// ```
+/// The query of the view must select an `id` column and all columns of the view, which is checked by the migration.
+/// Columns of tables can not reference a view and views can not have constraints.
+/// Views are dropped before each migration and created again afterwards, so they can use the tables that are migrated.
+/// The query can only be changed by removing the view in a new version and adding a view with a new name.
+///
+/// ## Triggers
+/// The `trigger` attribute adds a sql trigger to a table, with its name, when it runs and the statements that it runs.
+/// This can be used to keep denormalized columns up to date, like the number of tracks of an album:
+/// ```
+/// #[rust_query::migration::schema]
+/// #[version(0..=0)]
+/// enum Schema {
+///     Album {
+///         title: String,
+///         #[default = 0]
+///         track_count: i64,
+///     },
+///     #[trigger(
+///         track_insert,
+///         "AFTER INSERT",
+///         "UPDATE album SET track_count = track_count + 1 WHERE id = new.album;"
+///     )]
+///     Track {
+///         album: Album,
+///         name: String,
+///     },
+/// }
+/// # fn main() {}
+/// ```
+/// Each statement of the trigger ends with a semicolon and can use the `new` and `old` row.
+/// Triggers are part of the schema, so opening a database where a trigger is missing or different will panic.
+/// They are dropped before each migration and created again afterwards, so they do not run while rows are migrated.
+///
+/// ## Derives
+/// The structs that are generated for the tables can derive traits with the `derive` argument.
+/// The traits are implemented when the values of the columns implement them,
+/// which makes it possible to compare and print the rows that are inserted in tests.
+/// ```
+/// #[rust_query::migration::schema(derive(Debug, Clone, PartialEq))]
+/// enum Schema {
+///     User {
+///         name: String,
+///     },
+/// }
+/// use v0::*;
+/// # fn main() {
+/// let user = User { name: "alice" };
+/// assert_eq!(user.clone(), User { name: "alice" });
+/// # }
+/// ```
+///
 /// ## Multiple versions
 /// The macro uses enum syntax, but it generates multiple modules of types.
 ///
@@ -129,6 +741,9 @@ mod table;
 /// The `migrate` function first creates an empty database if it does not exists.
 /// Then it migrates the database if necessary, where it initializes every user score to the length of their email.
 ///
+/// When a table only loses columns or changes constraints, `Alter::identity()` copies the
+/// rows with a single sql statement instead of reading them in rust.
+///
 /// # Other features
 /// You can delete columns and tables by specifying the version range end.
 /// ```rust,ignore
@@ -148,10 +763,36 @@ pub fn schema(
     attr: proc_macro::TokenStream,
     item: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-    assert!(attr.is_empty());
+    let mut args = SchemaArgs::default();
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("derive") {
+            meta.parse_nested_meta(|meta| {
+                args.derives.push(meta.path);
+                Ok(())
+            })
+        } else if meta.path.is_ident("namespace") {
+            let name: syn::LitStr = meta.value()?.parse()?;
+            let valid = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_';
+            if name.value().is_empty() || !name.value().chars().all(valid) {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "namespace should only use lowercase letters, digits and `_`",
+                ));
+            }
+            args.namespace = Some(name.value());
+            Ok(())
+        } else if meta.path.is_ident("application_id") {
+            let id: syn::LitInt = meta.value()?.parse()?;
+            args.application_id = Some(id.base10_parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("expected `derive`, `namespace` or `application_id`"))
+        }
+    });
+    syn::parse_macro_input!(attr with parser);
     let item = syn::parse_macro_input!(item as ItemEnum);
 
-    match generate(item) {
+    match generate(item, &args) {
         Ok(x) => x,
         Err(e) => e.into_compile_error(),
     }
@@ -212,7 +853,48 @@ pub fn schema(
 ///     })
 /// }
 /// ```
-#[proc_macro_derive(FromDummy)]
+///
+/// With the `from_row` attribute, the struct gets a `select` function that reads the fields
+/// from the columns of a table with the same names.
+/// Fields that are not a column of the table, or that have a different type, are a compile error.
+/// ```
+/// # #[rust_query::migration::schema]
+/// # pub enum Schema {
+/// #     Thing { details: Details, seconds: i64 },
+/// #     Details { name: String },
+/// # }
+/// # use v0::*;
+/// # use rust_query::{FromDummy, Table, TableRow, Transaction};
+/// #[derive(FromDummy)]
+/// #[from_row(table = Thing)]
+/// struct ThingInfo<'t> {
+///     details: TableRow<'t, Details>,
+///     seconds: i64,
+/// }
+///
+/// # fn do_query<'t>(txn: &Transaction<'t, Schema>) -> Vec<ThingInfo<'t>> {
+/// # txn.query(|rows| {
+/// let thing = Thing::join(rows);
+/// rows.into_vec(ThingInfo::select(thing))
+/// # })
+/// # }
+/// # fn main() {}
+/// ```
+///
+/// The `dummy` attribute adds derives to the `*Dummy` struct.
+/// They are implemented when the values of the fields implement them.
+/// ```
+/// # use rust_query::FromDummy;
+/// #[derive(FromDummy)]
+/// #[dummy(derive(Debug, Clone, PartialEq))]
+/// struct Totals {
+///     tracks: i64,
+///     seconds: i64,
+/// }
+/// # let totals = TotalsDummy { tracks: 1, seconds: 2 };
+/// # assert_eq!(totals.clone(), totals);
+/// ```
+#[proc_macro_derive(FromDummy, attributes(from_row, dummy))]
 pub fn from_row(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let item = syn::parse_macro_input!(item as ItemStruct);
     match from_row_impl(item) {
@@ -222,9 +904,48 @@ pub fn from_row(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
     .into()
 }
 
+/// Derive [FromRaw] to decode a struct from the result of a raw sql query.
+///
+/// Every field is read from the result column with the same name.
+/// The type of the values is checked when the query is executed.
+/// ```
+/// # use rust_query::FromRaw;
+/// #[derive(FromRaw)]
+/// struct GenreCount {
+///     name: String,
+///     track_count: i64,
+/// }
+/// ```
+#[proc_macro_derive(FromRaw)]
+pub fn from_raw(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let item = syn::parse_macro_input!(item as ItemStruct);
+    match from_raw_impl(item) {
+        Ok(x) => x,
+        Err(e) => e.into_compile_error(),
+    }
+    .into()
+}
+
+/// The arguments of the `schema` attribute.
+#[derive(Default)]
+struct SchemaArgs {
+    derives: Vec<Path>,
+    namespace: Option<String>,
+    application_id: Option<i32>,
+}
+
 #[derive(Clone)]
 struct Table {
     uniques: Vec<Unique>,
+    fts5: Vec<Ident>,
+    checks: Vec<syn::LitStr>,
+    triggers: Vec<Trigger>,
+    soft_delete: bool,
+    timestamps: bool,
+    versioned: bool,
+    link_table: bool,
+    public_id: Option<Ident>,
+    view: Option<syn::LitStr>,
     prev: Option<Ident>,
     name: Ident,
     columns: BTreeMap<usize, Column>,
@@ -236,10 +957,104 @@ struct Unique {
     columns: Vec<Ident>,
 }
 
+#[derive(Clone)]
+struct Trigger {
+    name: Ident,
+    event: syn::LitStr,
+    body: syn::LitStr,
+}
+
+impl syn::parse::Parse for Trigger {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let event = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let body = input.parse()?;
+        Ok(Self { name, event, body })
+    }
+}
+
 #[derive(Clone)]
 struct Column {
     name: Ident,
     typ: Type,
+    on_delete: Option<Ident>,
+    generated: Option<Generated>,
+    default: Option<DefaultValue>,
+    collate: Option<syn::LitStr>,
+    scope: bool,
+}
+
+#[derive(Clone)]
+struct Generated {
+    expr: syn::LitStr,
+    stored: bool,
+}
+
+impl syn::parse::Parse for Generated {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let expr = input.parse()?;
+        let mut stored = false;
+        if input.parse::<Option<Token![,]>>()?.is_some() {
+            let kind: Ident = input.parse()?;
+            if kind != "stored" {
+                return Err(syn::Error::new_spanned(kind, "expected `stored`"));
+            }
+            stored = true;
+        }
+        Ok(Generated { expr, stored })
+    }
+}
+
+#[derive(Clone)]
+struct DefaultValue {
+    /// The literal as sql.
+    sql: String,
+    /// The literal as rust, to check that it has the type of the column.
+    value: TokenStream,
+}
+
+fn parse_default(attr: &Attribute) -> syn::Result<DefaultValue> {
+    use syn::{Expr, ExprLit, ExprUnary, Lit, UnOp};
+
+    let expr = &attr.meta.require_name_value()?.value;
+    let number = |lit: &Lit| match lit {
+        Lit::Int(x) => Some(x.base10_digits().to_owned()),
+        Lit::Float(x) => Some(x.base10_digits().to_owned()),
+        _ => None,
+    };
+    let sql = match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(x), ..
+        }) => Some(format!("'{}'", x.value().replace('\'', "''"))),
+        Expr::Lit(ExprLit { lit, .. }) => number(lit),
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => match &**expr {
+            Expr::Lit(ExprLit { lit, .. }) => number(lit).map(|x| format!("-{x}")),
+            _ => None,
+        },
+        Expr::Path(path) if path.path.is_ident("None") => {
+            return Ok(DefaultValue {
+                sql: "NULL".to_owned(),
+                value: quote! {::std::option::Option::<::std::convert::Infallible>::None},
+            });
+        }
+        _ => None,
+    };
+    let Some(sql) = sql else {
+        return Err(syn::Error::new_spanned(
+            expr,
+            "expected a number, string or `None` as default value",
+        ));
+    };
+    Ok(DefaultValue {
+        sql,
+        value: quote! {#expr},
+    })
 }
 
 #[derive(Clone)]
@@ -348,7 +1163,12 @@ fn define_table_migration(
     let mut prepare = vec![];
     let prev_columns_uwrapped = prev_columns.unwrap_or(const { &BTreeMap::new() });
 
-    for (i, col) in &table.columns {
+    // generated columns are computed by sqlite
+    for (i, col) in table
+        .columns
+        .iter()
+        .filter(|(_, col)| col.generated.is_none())
+    {
         let name = &col.name;
         let prepared_name = format_ident!("prepared_{name}");
         let name_str = col.name.to_string();
@@ -356,6 +1176,8 @@ fn define_table_migration(
         let generic = make_generic(name);
         if prev_columns_uwrapped.contains_key(i) {
             into_new.push(quote! {reader.col(#name_str, prev.#name())});
+        } else if col.default.is_some() {
+            // new columns with a default value get the default value
         } else {
             defs.push(quote! {pub #name: #generic});
             bounds.push(quote! {#generic: 't + ::rust_query::Dummy<'t, 'a, _PrevSchema, Out = <#typ as ::rust_query::private::MyTyp>::Out<'a>>});
@@ -444,7 +1266,37 @@ fn is_unique(path: &Path) -> Option<Ident> {
     })
 }
 
-fn generate(item: ItemEnum) -> syn::Result<TokenStream> {
+fn parse_on_delete(attr: &Attribute, typ: &Type, item: &ItemEnum) -> syn::Result<Ident> {
+    let action: Ident = attr.parse_args()?;
+    if !["cascade", "restrict", "set_null"]
+        .iter()
+        .any(|x| action == x)
+    {
+        return Err(syn::Error::new_spanned(
+            action,
+            "expected one of `cascade`, `restrict` or `set_null`",
+        ));
+    }
+    let is_fk = table::referenced_table(typ)
+        .is_some_and(|name| item.variants.iter().any(|x| &x.ident == name));
+    if !is_fk {
+        return Err(syn::Error::new_spanned(
+            attr,
+            "`on_delete` can only be used on columns that reference a table",
+        ));
+    }
+    let is_option =
+        matches!(typ, Type::Path(p) if p.path.segments.last().is_some_and(|x| x.ident == "Option"));
+    if action == "set_null" && !is_option {
+        return Err(syn::Error::new_spanned(
+            action,
+            "`set_null` can only be used on optional columns",
+        ));
+    }
+    Ok(action)
+}
+
+fn generate(item: ItemEnum, args: &SchemaArgs) -> syn::Result<TokenStream> {
     let range = parse_version(&item.attrs)?;
     let schema = &item.ident;
 
@@ -453,13 +1305,52 @@ fn generate(item: ItemEnum) -> syn::Result<TokenStream> {
     let mut prev_mod = None;
     for version in range.start..range.end.map(|x| x.end_exclusive()).unwrap_or(1) {
         let mut new_tables: BTreeMap<usize, Table> = BTreeMap::new();
+        let mut new_views: BTreeMap<usize, Table> = BTreeMap::new();
 
         let mut mod_output = TokenStream::new();
         for (i, table) in item.variants.iter().enumerate() {
             let mut other_attrs = vec![];
             let mut uniques = vec![];
+            let mut fts5 = vec![];
+            let mut checks = vec![];
+            let mut triggers = vec![];
+            let mut soft_delete = false;
+            let mut timestamps = false;
+            let mut versioned = false;
+            let mut link_table = false;
+            let mut public_id = None;
+            let mut view = None;
             for attr in &table.attrs {
-                if let Some(unique) = is_unique(attr.path()) {
+                if attr.path().is_ident("soft_delete") {
+                    attr.meta.require_path_only()?;
+                    soft_delete = true;
+                } else if attr.path().is_ident("timestamps") {
+                    attr.meta.require_path_only()?;
+                    timestamps = true;
+                } else if attr.path().is_ident("versioned") {
+                    attr.meta.require_path_only()?;
+                    versioned = true;
+                } else if attr.path().is_ident("link_table") {
+                    attr.meta.require_path_only()?;
+                    link_table = true;
+                } else if attr.path().is_ident("public_id") {
+                    let kind: Ident = attr.parse_args()?;
+                    if kind != "uuid" && kind != "ulid" {
+                        return Err(syn::Error::new_spanned(kind, "expected `uuid` or `ulid`"));
+                    }
+                    public_id = Some(kind);
+                } else if attr.path().is_ident("fts5") {
+                    let idents = attr.parse_args_with(
+                        Punctuated::<Ident, Token![,]>::parse_separated_nonempty,
+                    )?;
+                    fts5.extend(idents);
+                } else if attr.path().is_ident("check") {
+                    checks.push(attr.parse_args()?);
+                } else if attr.path().is_ident("trigger") {
+                    triggers.push(attr.parse_args()?);
+                } else if attr.path().is_ident("view") {
+                    view = Some(attr.parse_args()?);
+                } else if let Some(unique) = is_unique(attr.path()) {
                     let idents = attr.parse_args_with(
                         Punctuated::<Ident, Token![,]>::parse_separated_nonempty,
                     )?;
@@ -493,8 +1384,58 @@ fn generate(item: ItemEnum) -> syn::Result<TokenStream> {
                 };
                 let mut other_attrs = vec![];
                 let mut unique = None;
+                let mut on_delete = None;
+                let mut generated = None;
+                let mut default = None;
+                let mut collate = None;
+                let mut scope = false;
+                let mut check = vec![];
                 for attr in &field.attrs {
-                    if let Some(unique_name) = is_unique(attr.path()) {
+                    if attr.path().is_ident("on_delete") {
+                        on_delete = Some(parse_on_delete(attr, &field.ty, &item)?);
+                    } else if attr.path().is_ident("generated") {
+                        if table::referenced_table(&field.ty)
+                            .is_some_and(|name| item.variants.iter().any(|x| &x.ident == name))
+                        {
+                            return Err(syn::Error::new_spanned(
+                                attr,
+                                "generated columns can not reference a table",
+                            ));
+                        }
+                        generated = Some(attr.parse_args()?);
+                    } else if attr.path().is_ident("default") {
+                        if table::referenced_table(&field.ty)
+                            .is_some_and(|name| item.variants.iter().any(|x| &x.ident == name))
+                        {
+                            return Err(syn::Error::new_spanned(
+                                attr,
+                                "columns that reference a table can not have a default value",
+                            ));
+                        }
+                        default = Some(parse_default(attr)?);
+                    } else if attr.path().is_ident("collate") {
+                        collate = Some(attr.parse_args()?);
+                    } else if attr.path().is_ident("scope") {
+                        attr.meta.require_path_only()?;
+                        if !table::referenced_table(&field.ty)
+                            .is_some_and(|name| item.variants.iter().any(|x| &x.ident == name))
+                        {
+                            return Err(syn::Error::new_spanned(
+                                attr,
+                                "`scope` can only be used on columns that reference a table",
+                            ));
+                        }
+                        if matches!(&field.ty, Type::Path(p) if p.path.segments.last().is_some_and(|x| x.ident == "Option"))
+                        {
+                            return Err(syn::Error::new_spanned(
+                                attr,
+                                "`scope` can not be used on optional columns",
+                            ));
+                        }
+                        scope = true;
+                    } else if attr.path().is_ident("check") {
+                        check.push(attr.parse_args()?);
+                    } else if let Some(unique_name) = is_unique(attr.path()) {
                         let Meta::Path(_) = &attr.meta else {
                             return Err(syn::Error::new_spanned(
                                 attr,
@@ -513,12 +1454,43 @@ fn generate(item: ItemEnum) -> syn::Result<TokenStream> {
                 if !range.includes(version) {
                     continue;
                 }
+                if generated.is_some() && unique.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        &name,
+                        "generated columns can not be part of a unique constraint",
+                    ));
+                }
+                if default.is_some() && unique.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        &name,
+                        "columns with a default value can not be part of a unique constraint",
+                    ));
+                }
+                if generated.is_some() && default.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        &name,
+                        "generated columns can not have a default value",
+                    ));
+                }
                 let col = Column {
                     name,
                     typ: field.ty.clone(),
+                    on_delete,
+                    generated,
+                    default,
+                    collate,
+                    scope,
                 };
+                if scope && columns.values().any(|x: &Column| x.scope) {
+                    return Err(syn::Error::new_spanned(
+                        &col.name,
+                        "a table can only have one `scope` column",
+                    ));
+                }
                 columns.insert(i, col);
                 uniques.extend(unique);
+                // column checks are table checks that only exist as long as the column
+                checks.extend(check);
             }
 
             let table = Table {
@@ -526,12 +1498,73 @@ fn generate(item: ItemEnum) -> syn::Result<TokenStream> {
                 name: table.ident.clone(),
                 columns,
                 uniques,
+                fts5,
+                checks,
+                triggers,
+                soft_delete,
+                timestamps,
+                versioned,
+                link_table,
+                public_id,
+                view,
             };
 
-            mod_output.extend(table::define_table(&table, schema)?);
+            if let Some(sql) = &table.view {
+                if !table.uniques.is_empty()
+                    || !table.fts5.is_empty()
+                    || !table.checks.is_empty()
+                    || !table.triggers.is_empty()
+                    || table.soft_delete
+                    || table.timestamps
+                    || table.versioned
+                    || table.link_table
+                    || table.public_id.is_some()
+                {
+                    return Err(syn::Error::new_spanned(
+                        sql,
+                        "views can not have constraints or other table attributes",
+                    ));
+                }
+                let with_attrs = table.columns.values().find(|col| {
+                    col.on_delete.is_some()
+                        || col.generated.is_some()
+                        || col.default.is_some()
+                        || col.collate.is_some()
+                        || col.scope
+                });
+                if let Some(col) = with_attrs {
+                    return Err(syn::Error::new_spanned(
+                        &col.name,
+                        "columns of a view can not have attributes",
+                    ));
+                }
+                new_views.insert(i, table);
+                continue;
+            }
             new_tables.insert(i, table);
         }
 
+        // views are not stored, so they can not be referenced by tables
+        for col in new_tables.values().flat_map(|x| x.columns.values()) {
+            let Some(name) = table::referenced_table(&col.typ) else {
+                continue;
+            };
+            if new_views.values().any(|x| &x.name == name) {
+                return Err(syn::Error::new_spanned(
+                    &col.typ,
+                    "columns can not reference a view",
+                ));
+            }
+        }
+
+        // all tables need to be known to find the tables that reference each table
+        for table in new_tables.values() {
+            mod_output.extend(table::define_table(table, &new_tables, schema, args)?);
+        }
+        for view in new_views.values() {
+            mod_output.extend(table::define_view(view, &new_tables, schema, args)?);
+        }
+
         let mut schema_table_typs = vec![];
 
         let mut table_defs = vec![];
@@ -582,20 +1615,75 @@ fn generate(item: ItemEnum) -> syn::Result<TokenStream> {
             tables.push(quote! {b.drop_table::<super::super::#prev_mod::#table_ident>()})
         }
 
+        for view in new_views.values() {
+            let view_name = &view.name;
+            let sql = view.view.as_ref().unwrap();
+            schema_table_typs.push(quote! {b.view::<#view_name>(#sql)});
+        }
+
         let version_i64 = version as i64;
+        let namespace = args.namespace.as_ref().map(|name| {
+            quote! {const NAMESPACE: Option<&'static str> = Some(#name);}
+        });
+        let application_id = args.application_id.map(|id| {
+            quote! {const APPLICATION_ID: Option<i32> = Some(#id);}
+        });
+        let tables_ident = format_ident!("{schema}Tables");
+        let tables_doc = format!(
+            "Every table of [{schema}], this can be used to do the same thing for all tables."
+        );
+        let table_idents: Vec<_> = new_tables.values().map(|x| &x.name).collect();
         mod_output.extend(quote! {
+            #[doc = #tables_doc]
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+            pub enum #tables_ident {
+                #(#table_idents,)*
+            }
+
+            #[allow(unused)]
+            impl #tables_ident {
+                /// All tables, in the order of the schema.
+                pub const ALL: &'static [Self] = &[#(Self::#table_idents),*];
+
+                /// The name of the table in the database.
+                pub fn name(self) -> &'static str {
+                    match self {
+                        #(Self::#table_idents => <#table_idents as ::rust_query::Table>::NAME,)*
+                    }
+                }
+
+                /// The columns of the table, starting with its `id`.
+                pub fn columns(self) -> Vec<::rust_query::ColumnInfo> {
+                    match self {
+                        #(Self::#table_idents => ::rust_query::private::columns::<#table_idents>(),)*
+                    }
+                }
+
+                /// Count the rows of the table, like `rows.join::<T>()` followed by `count`.
+                pub fn count(self, txn: &::rust_query::Transaction<'_, #schema>) -> i64 {
+                    match self {
+                        #(Self::#table_idents => ::rust_query::private::count::<#table_idents>(txn),)*
+                    }
+                }
+
+                /// Check if the table has any rows.
+                pub fn exists(self, txn: &::rust_query::Transaction<'_, #schema>) -> bool {
+                    match self {
+                        #(Self::#table_idents => ::rust_query::private::exists::<#table_idents>(txn),)*
+                    }
+                }
+            }
+
             pub struct #schema;
             impl ::rust_query::private::Schema for #schema {
                 const VERSION: i64 = #version_i64;
+                #namespace
+                #application_id
 
                 fn typs(b: &mut ::rust_query::private::TableTypBuilder) {
                     #(#schema_table_typs;)*
                 }
             }
-
-            pub fn assert_hash(expect: ::rust_query::private::Expect) {
-                expect.assert_eq(&::rust_query::private::hash_schema::<#schema>())
-            }
         });
 
         let new_mod = format_ident!("v{version}");
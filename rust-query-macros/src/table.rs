@@ -1,23 +1,45 @@
 use crate::Unique;
 
 use super::make_generic;
-use heck::ToSnekCase;
+use heck::{ToSnekCase, ToUpperCamelCase};
 use quote::{format_ident, quote};
 
 use proc_macro2::TokenStream;
 
-use syn::Ident;
+use std::collections::BTreeMap;
 
-use super::Table;
+use syn::{GenericArgument, Ident, PathArguments, Type};
 
-pub(crate) fn define_table(table: &Table, schema: &Ident) -> syn::Result<TokenStream> {
+use super::{Column, SchemaArgs, Table};
+
+pub(crate) fn define_table(
+    table: &Table,
+    tables: &BTreeMap<usize, Table>,
+    schema: &Ident,
+    args: &SchemaArgs,
+) -> syn::Result<TokenStream> {
+    let derives = &args.derives;
     let table_ident = &table.name;
-    let table_name: &String = &table_ident.to_string().to_snek_case();
-    let table_mod = format_ident!("{table_name}");
+    let table_str = table_ident.to_string().to_snek_case();
+    let table_mod = format_ident!("{table_str}");
+    // the name in sqlite starts with the namespace
+    let table_name: &String = &match &args.namespace {
+        Some(namespace) => format!("{namespace}_{table_str}"),
+        None => table_str,
+    };
+
+    let all_generics: Vec<_> = table
+        .columns
+        .values()
+        .filter(|col| col.generated.is_none() && col.default.is_none())
+        .map(|col| make_generic(&col.name))
+        .collect();
 
     let mut unique_typs = vec![];
     let mut unique_funcs = vec![];
     let mut unique_defs = vec![];
+    let mut unique_markers = vec![];
+    let mut unique_impls = vec![];
     for unique in &table.uniques {
         let column_strs = unique.columns.iter().map(|x| x.to_string());
         let unique_name = &unique.name;
@@ -26,9 +48,10 @@ pub(crate) fn define_table(table: &Table, schema: &Ident) -> syn::Result<TokenSt
         let mut args = vec![];
         let mut generics = vec![];
         let mut constraints = vec![];
+        let mut key_bounds = vec![];
         let mut inits = vec![];
         for col in &unique.columns {
-            let typ = &table
+            let column = table
                 .columns
                 .values()
                 .find(|x| &x.name == col)
@@ -37,12 +60,25 @@ pub(crate) fn define_table(table: &Table, schema: &Ident) -> syn::Result<TokenSt
                         col,
                         "a column exists for every name in the unique constraint",
                     )
-                })?
-                .typ;
+                })?;
+            if column.generated.is_some() {
+                return Err(syn::Error::new_spanned(
+                    col,
+                    "generated columns can not be part of a unique constraint",
+                ));
+            }
+            if column.default.is_some() {
+                return Err(syn::Error::new_spanned(
+                    col,
+                    "columns with a default value can not be part of a unique constraint",
+                ));
+            }
+            let typ = &column.typ;
             let generic = make_generic(col);
 
             args.push(quote! {#col: #generic});
             constraints.push(quote! {#generic: ::rust_query::IntoColumn<'a, #schema, Typ = #typ>});
+            key_bounds.push(quote! {#generic: ::rust_query::IntoColumn<'t, #schema, Typ = #typ>});
             generics.push(generic);
             inits.push(col.clone());
         }
@@ -56,7 +92,306 @@ pub(crate) fn define_table(table: &Table, schema: &Ident) -> syn::Result<TokenSt
                 })
             }
         });
-        unique_defs.push(define_unique(unique, table_name, table_ident, schema));
+        unique_defs.push(define_unique(unique, table_ident, schema));
+
+        let marker = format_ident!("{}", unique_name.to_string().to_upper_camel_case());
+        let doc = format!(
+            "Marker for the unique constraint on `{}`, see [::rust_query::Unique].",
+            unique
+                .columns
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        unique_markers.push(quote! {
+            #[doc = #doc]
+            pub struct #marker;
+        });
+        let idx = (0..unique.columns.len()).map(syn::Index::from);
+        let cols = &unique.columns;
+        unique_impls.push(quote! {
+            impl ::rust_query::Unique for #table_mod::#marker {
+                type Table = #table_ident;
+            }
+
+            impl<'t, #(#key_bounds),*> ::rust_query::UniqueKey<'t, #table_mod::#marker> for (#(#generics,)*) {
+                fn lookup(&self) -> ::rust_query::Column<'t, #schema, Option<#table_ident>> {
+                    #table_ident::#unique_name(#(&self.#idx),*)
+                }
+            }
+
+            impl<'t, #(#all_generics),*> ::rust_query::UniqueKey<'t, #table_mod::#marker> for #table_ident<#(#all_generics),*>
+                where #(#key_bounds),*
+            {
+                fn lookup(&self) -> ::rust_query::Column<'t, #schema, Option<#table_ident>> {
+                    #table_ident::#unique_name(#(&self.#cols),*)
+                }
+            }
+        });
+    }
+
+    let checks = &table.checks;
+    let trigger_typs = table.triggers.iter().map(|trigger| {
+        // trigger names are global, just like table names
+        let name = match &args.namespace {
+            Some(namespace) => format!("{namespace}_{}", trigger.name),
+            None => trigger.name.to_string(),
+        };
+        let (event, body) = (&trigger.event, &trigger.body);
+        quote! {f.trigger(#name, #event, #body)}
+    });
+    let mut fts_typs = vec![];
+    let mut fts_funcs = vec![];
+    if !table.fts5.is_empty() {
+        for col in &table.fts5 {
+            if !table.columns.values().any(|x| &x.name == col) {
+                return Err(syn::Error::new_spanned(
+                    col,
+                    "a column exists for every name in the full-text index",
+                ));
+            }
+        }
+        let column_strs = table.fts5.iter().map(|x| x.to_string());
+        fts_typs.push(quote! {f.fts5(&[#(#column_strs),*])});
+        fts_funcs.push(quote! {
+            pub fn match_(&self, query: impl ::rust_query::IntoColumn<'t, #schema, Typ = String>) -> ::rust_query::Column<'t, #schema, bool> {
+                ::rust_query::private::fts_match(self.0.clone(), query)
+            }
+            pub fn match_rank(&self, query: impl ::rust_query::IntoColumn<'t, #schema, Typ = String>) -> ::rust_query::Column<'t, #schema, Option<f64>> {
+                ::rust_query::private::fts_rank(self.0.clone(), query)
+            }
+        });
+    }
+
+    let mut soft_delete_typs = vec![];
+    let mut soft_delete_funcs = vec![];
+    let mut soft_delete_impls = vec![];
+    let mut soft_delete_marker = vec![];
+    if table.soft_delete {
+        if let Some(col) = table.columns.values().find(|x| x.name == "deleted_at") {
+            return Err(syn::Error::new_spanned(
+                &col.name,
+                "the `deleted_at` column is added by the `soft_delete` attribute",
+            ));
+        }
+        soft_delete_typs.push(quote! {f.col::<Option<i64>>("deleted_at")});
+        soft_delete_funcs.push(quote! {
+            pub fn deleted_at(&self) -> ::rust_query::Column<'t, #schema, Option<i64>> {
+                ::rust_query::IntoColumn::into_column(::rust_query::private::Col::new("deleted_at", self.0.clone()))
+            }
+        });
+        soft_delete_impls.push(quote! {
+            const SOFT_DELETE: bool = true;
+        });
+        soft_delete_marker.push(quote! {
+            impl ::rust_query::SoftDelete for #table_ident {}
+        });
+    }
+
+    let mut timestamp_typs = vec![];
+    let mut timestamp_funcs = vec![];
+    let mut timestamp_impls = vec![];
+    if table.timestamps {
+        for name in ["created_at", "updated_at"] {
+            if let Some(col) = table.columns.values().find(|x| x.name == name) {
+                return Err(syn::Error::new_spanned(
+                    &col.name,
+                    format!("the `{name}` column is added by the `timestamps` attribute"),
+                ));
+            }
+            let ident = format_ident!("{name}");
+            timestamp_typs.push(quote! {f.col::<i64>(#name)});
+            timestamp_funcs.push(quote! {
+                pub fn #ident(&self) -> ::rust_query::Column<'t, #schema, i64> {
+                    ::rust_query::IntoColumn::into_column(::rust_query::private::Col::new(#name, self.0.clone()))
+                }
+            });
+        }
+        timestamp_impls.push(quote! {
+            const TIMESTAMPS: bool = true;
+        });
+    }
+
+    let mut version_typs = vec![];
+    let mut version_funcs = vec![];
+    let mut version_impls = vec![];
+    let mut version_marker = vec![];
+    if table.versioned {
+        if let Some(col) = table.columns.values().find(|x| x.name == "version") {
+            return Err(syn::Error::new_spanned(
+                &col.name,
+                "the `version` column is added by the `versioned` attribute",
+            ));
+        }
+        version_typs.push(quote! {f.col::<i64>("version")});
+        version_funcs.push(quote! {
+            pub fn version(&self) -> ::rust_query::Column<'t, #schema, i64> {
+                ::rust_query::IntoColumn::into_column(::rust_query::private::Col::new("version", self.0.clone()))
+            }
+        });
+        version_impls.push(quote! {
+            const VERSIONED: bool = true;
+        });
+        version_marker.push(quote! {
+            impl ::rust_query::Versioned for #table_ident {}
+        });
+    }
+
+    let mut public_id_typs = vec![];
+    let mut public_id_funcs = vec![];
+    let mut public_id_impls = vec![];
+    let mut public_id_lookup = vec![];
+    if let Some(kind) = &table.public_id {
+        if let Some(col) = table.columns.values().find(|x| x.name == "public_id") {
+            return Err(syn::Error::new_spanned(
+                &col.name,
+                "the `public_id` column is added by the `public_id` attribute",
+            ));
+        }
+        let kind = format_ident!("{}", kind.to_string().to_upper_camel_case());
+        public_id_typs.push(quote! {f.col::<String>("public_id")});
+        public_id_typs.push(quote! {f.unique(&["public_id"])});
+        public_id_funcs.push(quote! {
+            pub fn public_id(&self) -> ::rust_query::Column<'t, #schema, ::rust_query::PublicId<#table_ident>> {
+                ::rust_query::IntoColumn::into_column(::rust_query::private::Col::new("public_id", self.0.clone()))
+            }
+        });
+        public_id_impls.push(quote! {
+            const PUBLIC_ID: Option<::rust_query::private::IdKind> = Some(::rust_query::private::IdKind::#kind);
+        });
+        public_id_lookup.push(quote! {
+            pub fn by_public_id<'a>(id: impl ::rust_query::IntoColumn<'a, #schema, Typ = ::rust_query::PublicId<#table_ident>>) -> ::rust_query::Column<'a, #schema, Option<#table_ident>> {
+                ::rust_query::private::by_public_id(id)
+            }
+        });
+    }
+
+    let mut scope_impls = vec![];
+    if let Some(col) = table.columns.values().find(|col| col.scope) {
+        let name = col.name.to_string();
+        let typ = &col.typ;
+        scope_impls.push(quote! {
+            const SCOPE: Option<(&'static str, &'static str)> = Some((#name, <#typ as ::rust_query::Table>::NAME));
+        });
+    }
+
+    let mut child_funcs = vec![];
+    for child in tables.values() {
+        let fks: Vec<_> = child
+            .columns
+            .values()
+            .filter(|col| referenced_table(&col.typ) == Some(table_ident))
+            .collect();
+        let child_ident = &child.name;
+        let plural = plural(&child_ident.to_string().to_snek_case());
+        for col in &fks {
+            let col_str = col.name.to_string();
+            let func = if fks.len() == 1 {
+                format_ident!("{plural}")
+            } else {
+                format_ident!("{plural}_by_{col_str}")
+            };
+            child_funcs.push(quote! {
+                pub fn #func<'i>(&self, rows: &mut impl ::rust_query::private::JoinChildren<'t, 'i, #schema>) -> ::rust_query::Column<'i, #schema, #child_ident> {
+                    rows.join_children(#col_str, self.0.clone())
+                }
+            });
+        }
+    }
+
+    let mut tree_funcs = vec![];
+    let self_fks: Vec<_> = table
+        .columns
+        .values()
+        .filter(|col| referenced_table(&col.typ) == Some(table_ident))
+        .collect();
+    for col in &self_fks {
+        let col_str = col.name.to_string();
+        let (ancestors, descendants) = if self_fks.len() == 1 {
+            (format_ident!("ancestors"), format_ident!("descendants"))
+        } else {
+            (
+                format_ident!("ancestors_by_{col_str}"),
+                format_ident!("descendants_by_{col_str}"),
+            )
+        };
+        let ancestors_doc =
+            format!("Join the rows that are found by following `{col_str}` at most `depth` times.");
+        let descendants_doc = format!(
+            "Join the rows that reach this row by following `{col_str}` at most `depth` times."
+        );
+        tree_funcs.push(quote! {
+            #[doc = #ancestors_doc]
+            pub fn #ancestors<'i>(&self, rows: &mut impl ::rust_query::private::JoinChildren<'t, 'i, #schema>, depth: i64) -> ::rust_query::Column<'i, #schema, #table_ident> {
+                rows.join_tree(#col_str, self.0.clone(), depth, true)
+            }
+
+            #[doc = #descendants_doc]
+            pub fn #descendants<'i>(&self, rows: &mut impl ::rust_query::private::JoinChildren<'t, 'i, #schema>, depth: i64) -> ::rust_query::Column<'i, #schema, #table_ident> {
+                rows.join_tree(#col_str, self.0.clone(), depth, false)
+            }
+        });
+    }
+
+    if table.link_table {
+        // check that the link table is valid, even if the methods are generated on other tables
+        link_columns(table, tables)?;
+    }
+
+    let mut link_funcs = vec![];
+    let mut link_remove_funcs = vec![];
+    for link in tables.values().filter(|x| x.link_table) {
+        let link_ident = &link.name;
+        let [a, b] = link_columns(link, tables)?;
+        for (this, other) in [(a, b), (b, a)] {
+            if referenced_table(&this.typ) != Some(table_ident) {
+                continue;
+            }
+            let this_ident = &this.name;
+            let this_str = this_ident.to_string();
+            let other_ident = &other.name;
+            let other_str = other_ident.to_string();
+            let other_typ = &other.typ;
+            let func = format_ident!("{}", plural(&other_str));
+            let add_func = format_ident!("add_{other_str}");
+            let remove_func = format_ident!("remove_{other_str}");
+            link_funcs.push(quote! {
+                pub fn #func<'i>(&self, rows: &mut impl ::rust_query::private::JoinChildren<'t, 'i, #schema>) -> ::rust_query::Column<'i, #schema, #other_typ> {
+                    let link: ::rust_query::Column<'i, #schema, #link_ident> = rows.join_children(#this_str, self.0.clone());
+                    ::rust_query::IntoColumn::into_column(::rust_query::private::Col::new(#other_str, link))
+                }
+                pub fn #add_func(
+                    &self,
+                    txn: &mut ::rust_query::TransactionMut<'t, #schema>,
+                    #other_ident: impl ::rust_query::IntoColumn<'t, #schema, Typ = #other_typ>,
+                ) -> bool {
+                    let #other_ident = ::rust_query::IntoColumn::into_column(#other_ident);
+                    let exists = txn.query_one(::rust_query::aggregate(|rows| {
+                        let link: ::rust_query::Column<'_, #schema, #link_ident> =
+                            ::rust_query::private::JoinChildren::join_children(rows, #this_str, self.0.clone());
+                        rows.filter_on(link.#other_ident(), #other_ident.clone());
+                        rows.exists()
+                    }));
+                    !exists && txn.try_insert(#link_ident {
+                        #this_ident: self.0.clone(),
+                        #other_ident,
+                    }).is_ok()
+                }
+            });
+            link_remove_funcs.push(quote! {
+                pub fn #remove_func(
+                    &self,
+                    txn: &mut ::rust_query::private::Deletor<#schema>,
+                    #other_ident: ::rust_query::TableRow<'_, #other_typ>,
+                ) -> bool {
+                    txn.unlink::<#link_ident>(
+                        (#this_str, ::rust_query::RowId::from(self.0).as_i64()),
+                        (#other_str, ::rust_query::RowId::from(#other_ident).as_i64()),
+                    )
+                }
+            });
+        }
     }
 
     let (conflict_type, conflict_dummy) = match &*table.uniques {
@@ -93,12 +428,20 @@ pub(crate) fn define_table(table: &Table, schema: &Ident) -> syn::Result<TokenSt
     let mut typ_asserts = vec![];
     let mut reads = vec![];
     let mut def_typs = vec![];
+    let mut collate_typs = vec![];
     let mut col_defs = vec![];
     let mut generics = vec![];
     let mut generic_defaults = vec![];
     let mut bounds = vec![];
     let mut dummy_columns = vec![];
     let mut dummy_inits = vec![];
+    let mut with_fields = vec![];
+    let mut with_inits = vec![];
+    let mut with_reads = vec![];
+    let mut with_funcs = vec![];
+    let mut start_funcs = vec![];
+    let mut builder_cols = vec![];
+    let mut builder_defaults = vec![];
 
     for col in table.columns.values() {
         let typ = &col.typ;
@@ -116,18 +459,250 @@ pub(crate) fn define_table(table: &Table, schema: &Ident) -> syn::Result<TokenSt
         } else {
             typ_asserts.push(quote!(::rust_query::private::valid_in_schema::<#schema, #typ>();));
         }
+        if let Some(collate) = &col.collate {
+            // the collation is set after all columns are added
+            collate_typs.push(quote!(f.collate(#ident_str, #collate)));
+            typ_asserts.push(quote!(::rust_query::private::valid_collate::<#typ>();));
+        }
+        if let Some(generated) = &col.generated {
+            // generated columns can be read, but not written
+            let expr = &generated.expr;
+            let stored = generated.stored;
+            def_typs.push(quote!(f.col_generated::<#typ>(#ident_str, #expr, #stored)));
+            continue;
+        }
+        if let Some(default) = &col.default {
+            // columns with a default value are set with the `with_` methods
+            let sql = &default.sql;
+            let value = &default.value;
+            def_typs.push(quote!(f.col_default::<#typ>(#ident_str, #sql)));
+            typ_asserts.push(quote!(::rust_query::private::valid_default::<#typ, _>(#value);));
+            let with_ident = format_ident!("with_{ident}");
+            let doc = format!("Set the `{ident}` column instead of using its default value.");
+            with_fields.push(
+                quote! {pub(super) #ident: Option<::rust_query::Column<'t, super::#schema, #typ>>},
+            );
+            with_inits.push(quote! {#ident: None});
+            with_reads.push(quote! {
+                if let Some(val) = &self.#ident {
+                    f.col(#ident_str, val);
+                }
+            });
+            with_funcs.push(quote! {
+                #[doc = #doc]
+                pub fn #with_ident(mut self, #ident: impl ::rust_query::IntoColumn<'t, super::#schema, Typ = #typ>) -> Self {
+                    self.#ident = Some(::rust_query::IntoColumn::into_column(#ident));
+                    self
+                }
+            });
+            start_funcs.push(quote! {
+                #[doc = #doc]
+                pub fn #with_ident<'t>(self, #ident: impl ::rust_query::IntoColumn<'t, #schema, Typ = #typ>) -> #table_mod::With<'t, Self> {
+                    #table_mod::With::new(self).#with_ident(#ident)
+                }
+            });
+            builder_defaults.push((ident, typ));
+            continue;
+        }
         reads.push(quote!(f.col(#ident_str, &self.#ident)));
-        def_typs.push(quote!(f.col::<#typ>(#ident_str)));
+        if let Some(on_delete) = &col.on_delete {
+            let action = format_ident!("{}", on_delete.to_string().to_upper_camel_case());
+            def_typs.push(quote!(f.col_on_delete::<#typ>(#ident_str, ::rust_query::private::OnDelete::#action)));
+        } else {
+            def_typs.push(quote!(f.col::<#typ>(#ident_str)));
+        }
         col_defs.push(quote! {pub #ident: #generic});
         bounds.push(quote! {#generic: ::rust_query::IntoColumn<'t, #schema, Typ = #typ>});
         dummy_columns.push(quote! {::rust_query::Column<'t, #schema, #typ>});
         dummy_inits.push(quote! {#ident: val.#ident()});
         generic_defaults.push(quote! {#generic = ()});
+        builder_cols.push((ident, generic.clone(), typ));
         generics.push(generic);
     }
 
+    let builder_ident = format_ident!("{}Builder", table_ident);
+    let builder_doc = format!(
+        "Builder for a new row of [{table_ident}], see [{table_ident}::builder].\n\n\
+        [{table_ident}Builder::build] can only be called after all required columns are set."
+    );
+    let all_idents: Vec<_> = builder_cols
+        .iter()
+        .map(|(ident, ..)| *ident)
+        .chain(builder_defaults.iter().map(|(ident, _)| *ident))
+        .collect();
+    let mut builder_fields = vec![];
+    let mut builder_inits = vec![];
+    let mut builder_start = vec![];
+    let mut builder_funcs = vec![];
+    for (i, (ident, generic, typ)) in builder_cols.iter().enumerate() {
+        builder_fields.push(quote! {#ident: #generic});
+        // optional columns are `NULL` unless they are set
+        if is_option(typ) {
+            builder_inits.push(quote! {#ident: ::rust_query::Column::none()});
+            builder_start.push(quote! {::rust_query::Column<'t, #schema, #typ>});
+        } else {
+            builder_inits.push(quote! {#ident: ::rust_query::private::Unset});
+            builder_start.push(quote! {::rust_query::private::Unset});
+        }
+        let mut new_generics = all_generics.clone();
+        new_generics[i] = format_ident!("V");
+        let moves = all_idents
+            .iter()
+            .filter(|x| *x != ident)
+            .map(|x| quote! {#x: self.#x});
+        let doc = format!("Set the `{ident}` column.");
+        builder_funcs.push(quote! {
+            #[doc = #doc]
+            pub fn #ident<V: ::rust_query::IntoColumn<'t, #schema, Typ = #typ>>(self, #ident: V) -> #builder_ident<'t, #(#new_generics),*> {
+                #builder_ident {
+                    _p: ::std::marker::PhantomData,
+                    #ident,
+                    #(#moves,)*
+                }
+            }
+        });
+    }
+    for (ident, typ) in &builder_defaults {
+        builder_fields.push(quote! {#ident: Option<::rust_query::Column<'t, #schema, #typ>>});
+        builder_inits.push(quote! {#ident: None});
+        let doc = format!("Set the `{ident}` column instead of using its default value.");
+        builder_funcs.push(quote! {
+            #[doc = #doc]
+            pub fn #ident(mut self, #ident: impl ::rust_query::IntoColumn<'t, #schema, Typ = #typ>) -> Self {
+                self.#ident = Some(::rust_query::IntoColumn::into_column(#ident));
+                self
+            }
+        });
+    }
+    let col_idents = builder_cols.iter().map(|(ident, ..)| ident);
+    let (build_typ, build_value) = if builder_defaults.is_empty() {
+        (
+            quote! {#table_ident<#(#all_generics),*>},
+            quote! {#table_ident { #(#col_idents: self.#col_idents),* }},
+        )
+    } else {
+        let default_idents = builder_defaults.iter().map(|(ident, _)| ident);
+        (
+            quote! {#table_mod::With<'t, #table_ident<#(#all_generics),*>>},
+            quote! {
+                let mut val = #table_mod::With::new(#table_ident { #(#col_idents: self.#col_idents),* });
+                #(val.#default_idents = self.#default_idents;)*
+                val
+            },
+        )
+    };
+    let build_bounds = builder_cols.iter().map(
+        |(_, generic, typ)| quote! {#generic: ::rust_query::IntoColumn<'t, #schema, Typ = #typ>},
+    );
+    let builder = quote! {
+        #[doc = #builder_doc]
+        pub struct #builder_ident<'t, #(#all_generics),*> {
+            _p: ::std::marker::PhantomData<fn(&'t ()) -> &'t ()>,
+            #(#builder_fields,)*
+        }
+
+        impl<'t, #(#all_generics),*> #builder_ident<'t, #(#all_generics),*> {
+            #(#builder_funcs)*
+        }
+
+        impl<'t, #(#build_bounds),*> #builder_ident<'t, #(#all_generics),*> {
+            /// Create the value to insert.
+            pub fn build(self) -> #build_typ {
+                #build_value
+            }
+        }
+
+        impl #table_ident {
+            /// Create a new row by setting one column at a time.
+            pub fn builder<'t>() -> #builder_ident<'t, #(#builder_start),*> {
+                #builder_ident {
+                    _p: ::std::marker::PhantomData,
+                    #(#builder_inits,)*
+                }
+            }
+        }
+    };
+
     let ext_ident = format_ident!("{}Ext", table_ident);
 
+    let row_ident = format_ident!("{}Row", table_ident);
+    let row_doc = format!(
+        "All columns of a row of [{table_ident}], this is the result of [{ext_ident}::select_all]."
+    );
+    let select_doc = format!("Select all columns of the row, the result is a [{row_ident}].");
+    let id_ident = format_ident!("{}Id", table_ident);
+    let id_doc = format!(
+        "Id of a row of [{table_ident}] that can be stored outside of a transaction, see [::rust_query::RowId]."
+    );
+    let select_all_doc =
+        format!("The columns that are selected by [super::{ext_ident}::select_all].");
+    let serialize_row = serialize_row(&row_ident, table.columns.values().map(|x| &x.name));
+    let mut row_fields = vec![];
+    let mut row_caches = vec![];
+    let mut row_inits = vec![];
+    for col in table.columns.values() {
+        let ident = &col.name;
+        let typ = &col.typ;
+        let out = match referenced_table(typ) {
+            Some(other) if tables.values().any(|x| &x.name == other) => {
+                if is_option(typ) {
+                    quote! {Option<::rust_query::TableRow<'t, #other>>}
+                } else {
+                    quote! {::rust_query::TableRow<'t, #other>}
+                }
+            }
+            _ => quote! {#typ},
+        };
+        row_fields.push(quote! {pub #ident: #out});
+        let cached = format_ident!("{ident}_cached");
+        row_caches.push(quote! {let #cached = cacher.cache(self.0.#ident())});
+        row_inits.push(quote! {#ident: row.get(#cached)});
+    }
+
+    let mut with_defs = vec![];
+    let mut with_impls = vec![];
+    if !with_funcs.is_empty() {
+        with_defs.push(quote! {
+            /// Value to write with some of the columns that have a default value set explicitly.
+            pub struct With<'t, W> {
+                pub(super) inner: W,
+                #(#with_fields,)*
+            }
+
+            impl<'t, W> With<'t, W> {
+                pub(super) fn new(inner: W) -> Self {
+                    Self {
+                        inner,
+                        #(#with_inits,)*
+                    }
+                }
+
+                #(#with_funcs)*
+            }
+        });
+        with_impls.push(quote! {
+            impl<#(#all_generics),*> #table_ident<#(#all_generics),*> {
+                #(#start_funcs)*
+            }
+
+            impl<'t, W> ::rust_query::private::Writable<'t> for #table_mod::With<'t, W>
+                where W: ::rust_query::private::Writable<'t, T = #table_ident, Schema = #schema>
+            {
+                type Schema = #schema;
+                type T = #table_ident;
+                fn read(&self, f: ::rust_query::private::Reader<'_, 't, Self::Schema>) {
+                    self.inner.read(f);
+                    #(#with_reads)*
+                }
+
+                type Conflict = W::Conflict;
+                fn get_conflict_unchecked(&self) -> impl ::rust_query::Dummy<'t, 't, Self::Schema, Out = Option<Self::Conflict>> {
+                    self.inner.get_conflict_unchecked()
+                }
+            }
+        });
+    }
+
     Ok(quote! {
         #[repr(transparent)]
         pub struct #ext_ident<T>(T);
@@ -137,8 +712,53 @@ pub(crate) fn define_table(table: &Table, schema: &Ident) -> syn::Result<TokenSt
             where T: ::rust_query::IntoColumn<'t, #schema, Typ = #table_ident>
         {
             #(#defs)*
+            #(#fts_funcs)*
+            #(#soft_delete_funcs)*
+            #(#timestamp_funcs)*
+            #(#version_funcs)*
+            #(#public_id_funcs)*
+            #(#child_funcs)*
+            #(#tree_funcs)*
+            #(#link_funcs)*
+
+            #[doc = #select_doc]
+            pub fn select_all(&self) -> #table_mod::SelectAll<'t> {
+                #table_mod::SelectAll(::rust_query::IntoColumn::into_column(self.0.clone()))
+            }
+        }
+
+        #[doc = #row_doc]
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct #row_ident<'t> {
+            pub id: ::rust_query::TableRow<'t, #table_ident>,
+            #(#row_fields,)*
         }
 
+        #serialize_row
+
+        #[doc = #id_doc]
+        pub type #id_ident = ::rust_query::RowId<#table_ident>;
+
+        impl<'t, 'a> ::rust_query::Dummy<'t, 'a, #schema> for #table_mod::SelectAll<'t> {
+            type Out = #row_ident<'a>;
+
+            fn prepare(self, mut cacher: ::rust_query::private::Cacher<'_, 't, #schema>) -> impl FnMut(::rust_query::private::Row<'_, 't, 'a>) -> Self::Out + 't {
+                let id = cacher.cache(self.0.clone());
+                #(#row_caches;)*
+                move |row| #row_ident {
+                    id: row.get(id),
+                    #(#row_inits,)*
+                }
+            }
+        }
+
+        impl<'t> #ext_ident<::rust_query::TableRow<'t, #table_ident>> {
+            #(#link_remove_funcs)*
+        }
+
+        #builder
+
+        #[derive(#(#derives),*)]
         pub struct #table_ident<#(#generic_defaults),*> {
             #(#col_defs),*
         }
@@ -149,11 +769,24 @@ pub(crate) fn define_table(table: &Table, schema: &Ident) -> syn::Result<TokenSt
 
             fn typs(f: &mut ::rust_query::private::TypBuilder) {
                 #(#def_typs;)*
+                #(#collate_typs;)*
                 #(#unique_typs;)*
+                #(#fts_typs;)*
+                #(f.check(#checks);)*
+                #(#trigger_typs;)*
+                #(#soft_delete_typs;)*
+                #(#timestamp_typs;)*
+                #(#version_typs;)*
+                #(#public_id_typs;)*
             }
 
             const ID: &'static str = "id";
             const NAME: &'static str = #table_name;
+            #(#soft_delete_impls)*
+            #(#timestamp_impls)*
+            #(#version_impls)*
+            #(#public_id_impls)*
+            #(#scope_impls)*
 
             type Dummy<'t> = #table_ident<#(#dummy_columns),*>;
 
@@ -178,27 +811,278 @@ pub(crate) fn define_table(table: &Table, schema: &Ident) -> syn::Result<TokenSt
             }
         }
 
+        #(#soft_delete_marker)*
+        #(#version_marker)*
+
         #[allow(unused)]
         impl #table_ident {
             #(#unique_funcs)*
+            #(#public_id_lookup)*
         }
 
-        mod #table_mod {
+        pub mod #table_mod {
+            #[doc = #select_all_doc]
+            pub struct SelectAll<'t>(pub(super) ::rust_query::Column<'t, super::#schema, super::#table_ident>);
+
+            #(#unique_markers)*
             #(#unique_defs)*
+            #(#with_defs)*
         }
 
+        #(#with_impls)*
+
+        #(#unique_impls)*
+
         const _: fn() = || {
             #(#typ_asserts)*
         };
     })
 }
 
-fn define_unique(
-    unique: &Unique,
-    table_str: &str,
-    table_typ: &Ident,
+/// Views have columns just like tables, but they can not be written.
+pub(crate) fn define_view(
+    view: &Table,
+    tables: &BTreeMap<usize, Table>,
     schema: &Ident,
-) -> TokenStream {
+    args: &SchemaArgs,
+) -> syn::Result<TokenStream> {
+    let derives = &args.derives;
+    let view_ident = &view.name;
+    let view_str = view_ident.to_string().to_snek_case();
+    let view_mod = format_ident!("{view_str}");
+    let view_name: &String = &match &args.namespace {
+        Some(namespace) => format!("{namespace}_{view_str}"),
+        None => view_str,
+    };
+
+    let mut defs = vec![];
+    let mut typ_asserts = vec![];
+    let mut def_typs = vec![];
+    let mut col_defs = vec![];
+    let mut generic_defaults = vec![];
+    let mut dummy_columns = vec![];
+    let mut dummy_inits = vec![];
+    let mut row_fields = vec![];
+    let mut row_caches = vec![];
+    let mut row_inits = vec![];
+    for col in view.columns.values() {
+        let typ = &col.typ;
+        let ident = &col.name;
+        let ident_str = ident.to_string();
+        let generic = make_generic(ident);
+        defs.push(quote! {
+            pub fn #ident(&self) -> ::rust_query::Column<'t, #schema, #typ> {
+                ::rust_query::IntoColumn::into_column(::rust_query::private::Col::new(#ident_str, self.0.clone()))
+            }
+        });
+        typ_asserts.push(quote!(::rust_query::private::valid_in_schema::<#schema, #typ>();));
+        def_typs.push(quote!(f.col::<#typ>(#ident_str)));
+        col_defs.push(quote! {pub #ident: #generic});
+        dummy_columns.push(quote! {::rust_query::Column<'t, #schema, #typ>});
+        dummy_inits.push(quote! {#ident: val.#ident()});
+        generic_defaults.push(quote! {#generic = ()});
+
+        let out = match referenced_table(typ) {
+            Some(other) if tables.values().any(|x| &x.name == other) => {
+                if is_option(typ) {
+                    quote! {Option<::rust_query::TableRow<'t, #other>>}
+                } else {
+                    quote! {::rust_query::TableRow<'t, #other>}
+                }
+            }
+            _ => quote! {#typ},
+        };
+        row_fields.push(quote! {pub #ident: #out});
+        let cached = format_ident!("{ident}_cached");
+        row_caches.push(quote! {let #cached = cacher.cache(self.0.#ident())});
+        row_inits.push(quote! {#ident: row.get(#cached)});
+    }
+
+    let ext_ident = format_ident!("{}Ext", view_ident);
+    let row_ident = format_ident!("{}Row", view_ident);
+    let row_doc = format!(
+        "All columns of a row of [{view_ident}], this is the result of [{ext_ident}::select_all]."
+    );
+    let select_doc = format!("Select all columns of the row, the result is a [{row_ident}].");
+    let select_all_doc =
+        format!("The columns that are selected by [super::{ext_ident}::select_all].");
+    let serialize_row = serialize_row(&row_ident, view.columns.values().map(|x| &x.name));
+
+    Ok(quote! {
+        #[repr(transparent)]
+        pub struct #ext_ident<T>(T);
+        ::rust_query::unsafe_impl_ref_cast! {#ext_ident}
+
+        impl<'t, T> #ext_ident<T>
+            where T: ::rust_query::IntoColumn<'t, #schema, Typ = #view_ident>
+        {
+            #(#defs)*
+
+            #[doc = #select_doc]
+            pub fn select_all(&self) -> #view_mod::SelectAll<'t> {
+                #view_mod::SelectAll(::rust_query::IntoColumn::into_column(self.0.clone()))
+            }
+        }
+
+        #[doc = #row_doc]
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct #row_ident<'t> {
+            pub id: ::rust_query::TableRow<'t, #view_ident>,
+            #(#row_fields,)*
+        }
+
+        #serialize_row
+
+        impl<'t, 'a> ::rust_query::Dummy<'t, 'a, #schema> for #view_mod::SelectAll<'t> {
+            type Out = #row_ident<'a>;
+
+            fn prepare(self, mut cacher: ::rust_query::private::Cacher<'_, 't, #schema>) -> impl FnMut(::rust_query::private::Row<'_, 't, 'a>) -> Self::Out + 't {
+                let id = cacher.cache(self.0.clone());
+                #(#row_caches;)*
+                move |row| #row_ident {
+                    id: row.get(id),
+                    #(#row_inits,)*
+                }
+            }
+        }
+
+        #[derive(#(#derives),*)]
+        pub struct #view_ident<#(#generic_defaults),*> {
+            #(#col_defs),*
+        }
+
+        impl ::rust_query::Table for #view_ident {
+            type Ext<T> = #ext_ident<T>;
+            type Schema = #schema;
+
+            fn typs(f: &mut ::rust_query::private::TypBuilder) {
+                #(#def_typs;)*
+            }
+
+            const ID: &'static str = "id";
+            const NAME: &'static str = #view_name;
+
+            type Dummy<'t> = #view_ident<#(#dummy_columns),*>;
+
+            fn dummy<'t>(val: impl ::rust_query::IntoColumn<'t, Self::Schema, Typ = Self>) -> Self::Dummy<'t> {
+                let val = val.into_column();
+                #view_ident {
+                    #(#dummy_inits,)*
+                }
+            }
+        }
+
+        pub mod #view_mod {
+            #[doc = #select_all_doc]
+            pub struct SelectAll<'t>(pub(super) ::rust_query::Column<'t, super::#schema, super::#view_ident>);
+        }
+
+        const _: fn() = || {
+            #(#typ_asserts)*
+        };
+    })
+}
+
+/// Find the table that is referenced by a column type like `Album` or `Option<Album>`.
+pub(crate) fn referenced_table(typ: &Type) -> Option<&Ident> {
+    let Type::Path(path) = typ else { return None };
+    let segment = path.path.segments.last()?;
+    match &segment.arguments {
+        PathArguments::None => Some(&segment.ident),
+        PathArguments::AngleBracketed(args) if segment.ident == "Option" => {
+            let [GenericArgument::Type(inner)] = &*args.args.iter().collect::<Vec<_>>() else {
+                return None;
+            };
+            referenced_table(inner)
+        }
+        _ => None,
+    }
+}
+
+/// Find the two columns of a link table that reference the linked tables.
+fn link_columns<'a>(
+    table: &'a Table,
+    tables: &BTreeMap<usize, Table>,
+) -> syn::Result<[&'a Column; 2]> {
+    if table.soft_delete {
+        return Err(syn::Error::new_spanned(
+            &table.name,
+            "a link table can not have the `soft_delete` attribute",
+        ));
+    }
+    let mut fks = vec![];
+    for col in table.columns.values() {
+        let is_fk =
+            referenced_table(&col.typ).is_some_and(|name| tables.values().any(|x| &x.name == name));
+        if is_fk {
+            if is_option(&col.typ) {
+                return Err(syn::Error::new_spanned(
+                    &col.name,
+                    "the columns of a link table can not be optional",
+                ));
+            }
+            fks.push(col);
+        } else if col.generated.is_none() && col.default.is_none() {
+            return Err(syn::Error::new_spanned(
+                &col.name,
+                "other columns of a link table must be generated or have a default value",
+            ));
+        }
+    }
+    fks.try_into().map_err(|_| {
+        syn::Error::new_spanned(
+            &table.name,
+            "a link table must have exactly two columns that reference a table",
+        )
+    })
+}
+
+/// Check if a column type is optional like `Option<Album>`.
+fn is_option(typ: &Type) -> bool {
+    matches!(typ, Type::Path(p) if p.path.segments.last().is_some_and(|x| x.ident == "Option"))
+}
+
+/// Implement `Serialize` for the struct that is returned by `select_all`.
+///
+/// This is only generated when rust-query is compiled with the `serde` feature.
+fn serialize_row<'a>(row_ident: &Ident, cols: impl Iterator<Item = &'a Ident>) -> TokenStream {
+    if !cfg!(feature = "serde") {
+        return quote! {};
+    }
+    let cols: Vec<_> = std::iter::once(format_ident!("id"))
+        .chain(cols.cloned())
+        .collect();
+    let names = cols.iter().map(|x| x.to_string());
+    let len = cols.len();
+    let name = row_ident.to_string();
+    quote! {
+        impl ::rust_query::private::serde::Serialize for #row_ident<'_> {
+            fn serialize<S: ::rust_query::private::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use ::rust_query::private::serde::ser::SerializeStruct;
+                let mut s = serializer.serialize_struct(#name, #len)?;
+                #(s.serialize_field(#names, &self.#cols)?;)*
+                s.end()
+            }
+        }
+    }
+}
+
+fn plural(name: &str) -> String {
+    if ["s", "x", "z", "ch", "sh"]
+        .iter()
+        .any(|x| name.ends_with(x))
+    {
+        return format!("{name}es");
+    }
+    if let Some(stem) = name.strip_suffix('y') {
+        if !stem.ends_with(['a', 'e', 'i', 'o', 'u']) {
+            return format!("{stem}ies");
+        }
+    }
+    format!("{name}s")
+}
+
+fn define_unique(unique: &Unique, table_typ: &Ident, schema: &Ident) -> TokenStream {
     let name = &unique.name;
     let typ_name = make_generic(name);
 
@@ -223,6 +1107,7 @@ fn define_unique(
     }
 
     quote! {
+        #[doc(hidden)]
         #[derive(Clone, Copy)]
         pub struct #typ_name<#(#generics),*> {
             #(#fields),*
@@ -231,7 +1116,7 @@ fn define_unique(
         impl<#(#constraints_typed),*> ::rust_query::private::Typed for #typ_name<#(#generics),*> {
             type Typ = Option<super::#table_typ>;
             fn build_expr(&self, b: ::rust_query::private::ValueBuilder) -> ::rust_query::private::SimpleExpr {
-                b.get_unique(#table_str, vec![#(#conds),*])
+                b.get_unique::<super::#table_typ>(vec![#(#conds),*])
             }
         }
         impl<'t, #(#constraints),*> ::rust_query::IntoColumn<'t, super::#schema> for #typ_name<#(#generics),*> {
@@ -1,4 +1,5 @@
 use std::{
+    cell::RefCell,
     marker::PhantomData,
     ops::{Deref, DerefMut},
     rc::Rc,
@@ -10,7 +11,8 @@ use sea_query::{Expr, Func, SelectStatement, SimpleExpr};
 use crate::{
     alias::{Field, MyAlias},
     ast::MySelect,
-    rows::Rows,
+    db::Col,
+    rows::{JoinChildren, Rows},
     value::{
         operations::{Const, IsNotNull, UnwrapOr},
         EqTyp, IntoColumn, MyTyp, NumTyp, Typed, ValueBuilder,
@@ -22,10 +24,12 @@ use crate::{
 ///
 /// While it is possible to join many tables in an aggregate, there can be only one result.
 /// (The result can be a tuple or struct with multiple values though).
-pub struct Aggregate<'outer, 'inner, S> {
+pub struct Aggregate<'outer: 'inner, 'inner, S> {
     // pub(crate) outer_ast: &'inner MySelect,
     pub(crate) conds: Vec<(Field, Rc<dyn 'outer + Fn(ValueBuilder) -> SimpleExpr>)>,
     pub(crate) query: Rows<'inner, S>,
+    // the select with all results so far, it is shared by all results
+    pub(crate) select: Rc<RefCell<SelectStatement>>,
     // pub(crate) table: MyAlias,
     pub(crate) phantom2: PhantomData<fn(&'outer ()) -> &'outer ()>,
 }
@@ -44,16 +48,48 @@ impl<'outer, 'inner, S> DerefMut for Aggregate<'outer, 'inner, S> {
     }
 }
 
+impl<'outer: 'inner, 'inner, S: 'outer> JoinChildren<'outer, 'inner, S>
+    for Aggregate<'outer, 'inner, S>
+{
+    fn join_children<P: Table<Schema = S>, C: Table<Schema = S>>(
+        &mut self,
+        fk: &'static str,
+        parent: impl IntoColumn<'outer, S, Typ = P>,
+    ) -> Column<'inner, S, C> {
+        let child = self.join::<C>();
+        self.filter_on(Col::<P, _>::new(fk, child.clone()), parent);
+        child
+    }
+
+    fn join_tree<T: Table<Schema = S>>(
+        &mut self,
+        fk: &'static str,
+        start: impl IntoColumn<'outer, S, Typ = T>,
+        depth: i64,
+        ancestors: bool,
+    ) -> Column<'inner, S, T> {
+        let (from, node) = self.query.join_pairs(fk, depth, ancestors);
+        self.filter_on(from, start);
+        node
+    }
+}
+
 impl<'outer: 'inner, 'inner, S: 'outer> Aggregate<'outer, 'inner, S> {
-    fn select<T>(&'inner self, expr: impl Into<SimpleExpr>) -> Aggr<'outer, S, Option<T>> {
+    pub(crate) fn select<T>(
+        &'inner self,
+        expr: impl Into<SimpleExpr>,
+    ) -> Aggr<'outer, S, Option<T>> {
         let alias = self
             .ast
             .select
             .get_or_init(expr.into(), || self.ast.scope.new_field());
+        // results can not be added after filtering, so all results aggregate the same rows
+        // and they can share one sub-query that calculates all of them
+        *self.select.borrow_mut() = self.query.ast.build_select(true);
         Aggr {
             _p: PhantomData,
             _p2: PhantomData,
-            select: self.query.ast.build_select(true),
+            select: self.select.clone(),
             field: *alias,
             conds: self.conds.clone(),
         }
@@ -126,7 +162,7 @@ impl<'outer: 'inner, 'inner, S: 'outer> Aggregate<'outer, 'inner, S> {
 pub struct Aggr<'t, S, T> {
     pub(crate) _p: PhantomData<fn(&'t S) -> &'t S>,
     pub(crate) _p2: PhantomData<T>,
-    pub(crate) select: SelectStatement,
+    pub(crate) select: Rc<RefCell<SelectStatement>>,
     pub(crate) conds: Vec<(Field, Rc<dyn 't + Fn(ValueBuilder) -> SimpleExpr>)>,
     pub(crate) field: Field,
 }
@@ -153,7 +189,8 @@ impl<'t, S, T: MyTyp> Typed for Aggr<'t, S, T> {
 impl<'t, S, T> Aggr<'t, S, T> {
     fn build_table(&self, b: crate::value::ValueBuilder) -> MyAlias {
         let conds = self.conds.iter().map(|(field, expr)| (*field, expr(b)));
-        b.get_aggr(self.select.clone(), conds.collect())
+        let select = self.select.borrow().clone();
+        b.get_aggr(select, conds.collect())
     }
 }
 
@@ -177,6 +214,12 @@ impl<S, T: Table> Deref for Aggr<'_, S, T> {
 ///
 /// You can filter the rows in the aggregate based on values from the outer query.
 /// That is the only way to get a different aggregate for each outer row.
+///
+/// The compiler checks that columns are used in the right scope:
+/// - Columns of the outer query can only be used as the second argument of [Aggregate::filter_on].
+/// - Columns of the aggregate can not be returned, only the results like [Aggregate::sum] can.
+/// - Nested aggregates can only use columns of the aggregate that contains them in [Aggregate::filter_on].
+/// - All results aggregate the same rows, so filters can not be added after the first result.
 pub fn aggregate<'outer, S, F, R>(f: F) -> R
 where
     F: for<'a> FnOnce(&'a mut Aggregate<'outer, 'a, S>) -> R,
@@ -189,6 +232,7 @@ where
     let mut group = Aggregate {
         conds: Vec::new(),
         query: inner,
+        select: Rc::default(),
         phantom2: PhantomData,
     };
     f(&mut group)
@@ -8,6 +8,10 @@ pub(super) enum Field {
     Str(&'static str),
 }
 
+/// Names the aliases of a single query.
+///
+/// Every query starts counting at zero, so the same query always has the same sql.
+/// This keeps the statement cache effective and the sql stable between processes.
 #[derive(Default)]
 pub struct Scope {
     iden_num: AtomicU64,
@@ -1,5 +1,9 @@
+use std::cell::RefCell;
+
 use elsa::FrozenVec;
-use sea_query::{Alias, Asterisk, Condition, Expr, NullAlias, SelectStatement, SimpleExpr};
+use sea_query::{
+    Alias, Asterisk, Condition, Expr, NullAlias, SelectStatement, SimpleExpr, WindowStatement,
+};
 
 use crate::{
     alias::{Field, MyAlias, RawAlias, Scope},
@@ -11,25 +15,60 @@ use crate::{
 pub struct MySelect {
     pub(super) scope: Scope,
     // tables to join, adding more requires mutating
-    pub(super) tables: Vec<(String, MyAlias)>,
-    // implicit joins
+    pub(super) tables: Vec<(Joinable, MyAlias)>,
+    // implicit joins, these are deduplicated on their source so that repeated navigation
+    // like `a.b().c()` and `a.b().d()` joins `b` only once and all joins stay flat
     pub(super) extra: MyMap<Source, MyAlias>,
-    // all conditions to check
-    pub(super) filters: FrozenVec<Box<SimpleExpr>>,
+    // all conditions to check, with the extra sources that they use
+    pub(super) filters: FrozenVec<Box<(SimpleExpr, Vec<MyAlias>)>>,
+    // extra sources that are used by the expression that is being built, see [ValueBuilder::track]
+    pub(super) used: RefCell<Vec<MyAlias>>,
     // calculating these results
     pub(super) select: MyMap<SimpleExpr, Field>,
     // values that must be returned/ filtered on
     pub(super) filter_on: FrozenVec<Box<(SimpleExpr, MyAlias)>>,
+    // sorting that goes before sorting on the selected values
+    pub(super) order_by: Vec<(SimpleExpr, sea_query::Order)>,
+}
+
+pub(super) enum Joinable {
+    // table name, rows are identified by the id
+    Table(String),
+    // sub-query, rows are identified by the fields together
+    Subquery(SelectStatement, Vec<Field>),
+    // raw sql or name of a common table expression, rows are identified by the fields together
+    Raw(String, Vec<Field>),
+}
+
+impl Joinable {
+    fn key(&self, alias: MyAlias) -> Vec<SimpleExpr> {
+        match self {
+            Joinable::Table(_) => vec![Expr::col((alias, Alias::new("id"))).into()],
+            Joinable::Subquery(_, fields) | Joinable::Raw(_, fields) => fields
+                .iter()
+                .map(|field| Expr::col((alias, *field)).into())
+                .collect(),
+        }
+    }
 }
 
-#[derive(PartialEq)]
 pub(super) struct Source {
     pub(super) conds: Vec<(Field, SimpleExpr)>,
     pub(super) kind: SourceKind,
+    // the extra sources that are used by the conditions, these are always older
+    pub(super) deps: Vec<MyAlias>,
+}
+
+impl PartialEq for Source {
+    fn eq(&self, other: &Self) -> bool {
+        self.conds == other.conds && self.kind == other.kind
+    }
 }
 
 pub(super) enum SourceKind {
     Aggregate(SelectStatement),
+    // window functions with the ids of the tables
+    Window(SelectStatement),
     // table and pk
     Implicit(String),
 }
@@ -39,6 +78,7 @@ impl PartialEq for SourceKind {
         match (self, other) {
             (Self::Implicit(l0), Self::Implicit(r0)) => l0 == r0,
             (Self::Aggregate(l0), Self::Aggregate(l1)) => l0 == l1,
+            (Self::Window(l0), Self::Window(l1)) => l0 == l1,
             _ => false,
         }
     }
@@ -49,8 +89,18 @@ impl MySelect {
         ValueBuilder { inner: self }
     }
 
+    /// The keys that identify a row of this query, at least one for each joined table.
+    pub fn keys(&self) -> impl Iterator<Item = SimpleExpr> + '_ {
+        self.tables
+            .iter()
+            .flat_map(|(table, alias)| table.key(*alias))
+    }
+
     pub fn simple(&self) -> SelectStatement {
         let mut select = self.build_select(false);
+        for (expr, order) in &self.order_by {
+            select.order_by_expr(expr.clone(), order.clone());
+        }
         for (aggr, _alias) in self.select.iter() {
             select.order_by_expr(aggr.clone(), sea_query::Order::Asc);
         }
@@ -58,11 +108,89 @@ impl MySelect {
     }
 
     pub fn build_select(&self, is_group: bool) -> SelectStatement {
+        let mut select = self.build_rows(|_| true);
+
+        let mut any_expr = false;
+        let mut any_group = false;
+        for (group, alias) in self.filter_on.iter() {
+            any_expr = true;
+
+            select.expr_as(group.clone(), *alias);
+            if is_group {
+                any_group = true;
+                select.add_group_by([group.clone()]);
+            }
+        }
+
+        for (aggr, alias) in self.select.iter() {
+            any_expr = true;
+            select.expr_as(aggr.clone(), *alias);
+        }
+
+        if !any_expr {
+            select.expr_as(Expr::val(1), NullAlias);
+        }
+
+        if !any_group && is_group {
+            select.expr_as(Expr::count(Expr::col(Asterisk)), NullAlias);
+        }
+
+        select
+    }
+
+    /// Calculate a window function for every row, the result can be joined on the keys of all tables.
+    ///
+    /// The window function and the window use the extra sources in `deps`.
+    pub fn build_window(
+        &self,
+        func: SimpleExpr,
+        window: WindowStatement,
+        deps: Vec<MyAlias>,
+    ) -> (SelectStatement, Vec<(Field, SimpleExpr)>, Field) {
+        let mut conds: Vec<(Field, SimpleExpr)> = vec![];
+        for key in self.keys() {
+            conds.push((self.scope.new_field(), key));
+        }
+        if conds.is_empty() {
+            // without tables there is at most one row, it is identified by a constant
+            conds.push((self.scope.new_field(), Expr::val(1).into()));
+        }
+        let field = self.scope.new_field();
+
+        // Joining every previous window would make the query grow exponentially.
+        // Instead we only join the sources that are used to filter rows or calculate this window.
+        let mut used = deps;
+        for (_, deps) in self.filters.iter() {
+            used.extend(deps);
+        }
+        let extra: Vec<_> = self.extra.iter().collect();
+        for (source, alias) in extra.into_iter().rev() {
+            if used.contains(alias) {
+                used.extend(&source.deps);
+            }
+        }
+
+        let mut select = self.build_rows(|alias| used.contains(&alias));
+        for (key, id) in &conds {
+            select.expr_as(id.clone(), *key);
+        }
+        select.expr_window_as(func, window, field);
+        (select, conds, field)
+    }
+
+    /// Build the `FROM` and `WHERE` part of the query.
+    ///
+    /// Joins with extra sources are only included if `keep` returns true for them.
+    fn build_rows(&self, keep: impl Fn(MyAlias) -> bool) -> SelectStatement {
         let mut select = SelectStatement::new();
 
         let mut any_from = false;
         for (table, alias) in &self.tables {
-            select.from_as(RawAlias(table.clone()), *alias);
+            match table {
+                Joinable::Table(name) => select.from_as(RawAlias(name.clone()), *alias),
+                Joinable::Subquery(ast, _) => select.from_subquery(ast.clone(), *alias),
+                Joinable::Raw(sql, _) => select.from_as(RawAlias(sql.clone()), *alias),
+            };
             any_from = true
         }
 
@@ -71,11 +199,18 @@ impl MySelect {
         }
 
         for (source, table_alias) in self.extra.iter() {
+            if !keep(*table_alias) {
+                continue;
+            }
             let mut cond = Condition::all();
             for (field, outer_value) in &source.conds {
                 let id_field = Expr::expr(outer_value.clone());
                 let id_field2 = Expr::col((*table_alias, *field));
-                let filter = id_field.eq(id_field2);
+                let filter = match source.kind {
+                    // keys of rows from `union_all` can be null
+                    SourceKind::Window(_) => id_field.is(id_field2),
+                    _ => id_field.eq(id_field2),
+                };
                 cond = cond.add(filter);
             }
 
@@ -84,6 +219,10 @@ impl MySelect {
                     let join_type = sea_query::JoinType::LeftJoin;
                     select.join_subquery(join_type, ast.clone(), *table_alias, cond);
                 }
+                SourceKind::Window(ast) => {
+                    let join_type = sea_query::JoinType::LeftJoin;
+                    select.join_subquery(join_type, ast.clone(), *table_alias, cond);
+                }
                 SourceKind::Implicit(table) => {
                     let join_type = sea_query::JoinType::LeftJoin;
                     select.join_as(join_type, Alias::new(table), *table_alias, cond);
@@ -91,35 +230,10 @@ impl MySelect {
             }
         }
 
-        for filter in &self.filters {
+        for (filter, _) in self.filters.iter() {
             select.and_where(filter.clone());
         }
 
-        let mut any_expr = false;
-        let mut any_group = false;
-        for (group, alias) in self.filter_on.iter() {
-            any_expr = true;
-
-            select.expr_as(group.clone(), *alias);
-            if is_group {
-                any_group = true;
-                select.add_group_by([group.clone()]);
-            }
-        }
-
-        for (aggr, alias) in self.select.iter() {
-            any_expr = true;
-            select.expr_as(aggr.clone(), *alias);
-        }
-
-        if !any_expr {
-            select.expr_as(Expr::val(1), NullAlias);
-        }
-
-        if !any_group && is_group {
-            select.expr_as(Expr::count(Expr::col(Asterisk)), NullAlias);
-        }
-
         select
     }
 }
@@ -0,0 +1,224 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use rusqlite::OptionalExtension;
+
+use crate::{
+    hook::HookEvent,
+    migrate::{Schema, TableTypBuilder},
+    Database, RowId, Table, Transaction, TransactionMut,
+};
+
+/// Name of the table that stores the audit log.
+pub(crate) const AUDIT_TABLE: &str = "_audit";
+
+/// The kind of change that is recorded in an [AuditEntry].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditOperation {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl AuditOperation {
+    fn as_str(self) -> &'static str {
+        match self {
+            AuditOperation::Insert => "insert",
+            AuditOperation::Update => "update",
+            AuditOperation::Delete => "delete",
+        }
+    }
+}
+
+/// A change of a row that was recorded in the audit log, see [Transaction::audit_history].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub operation: AuditOperation,
+    /// JSON object with the columns that changed and their new values.
+    ///
+    /// For inserts this has all columns and for deletes it has the values before the row was deleted.
+    pub changes: String,
+    /// Unix time in seconds of the change.
+    pub timestamp: i64,
+    /// The actor that was set with [TransactionMut::set_actor].
+    pub actor: Option<String>,
+}
+
+/// Create the audit table and install hooks for all tables of schema `S`.
+pub(crate) fn install<S: Schema>(
+    conn: &rusqlite::Connection,
+    db: &Database<S>,
+) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS \"{AUDIT_TABLE}\" (
+            \"id\" integer PRIMARY KEY,
+            \"table\" text NOT NULL,
+            \"row\" integer NOT NULL,
+            \"operation\" text NOT NULL,
+            \"changes\" text NOT NULL,
+            \"timestamp\" integer NOT NULL,
+            \"actor\" text
+        );
+        CREATE INDEX IF NOT EXISTS \"{AUDIT_TABLE}_row\" ON \"{AUDIT_TABLE}\" (\"table\", \"row\");"
+    ))?;
+
+    let mut b = TableTypBuilder::default();
+    S::typs(&mut b);
+    for (table, def) in &*b.ast.tables {
+        let fields: Vec<_> = def
+            .columns
+            .iter()
+            .map(|col| format!("'{0}', \"{0}\"", col.name))
+            .collect();
+        let select: Arc<str> = format!(
+            "SELECT json_object({}) FROM \"{table}\" WHERE \"id\" = ?",
+            fields.join(", ")
+        )
+        .into();
+
+        let table: Arc<str> = table.as_str().into();
+        let hook = |op: AuditOperation| {
+            let (table, select) = (table.clone(), select.clone());
+            move |txn: &Transaction<'_, S>, row: i64| {
+                let values = row_json(txn, &select, row);
+                record(txn, &table, row, op, &values);
+            }
+        };
+        db.hooks
+            .add(&table, HookEvent::AfterInsert, hook(AuditOperation::Insert));
+        db.hooks.add(
+            &table,
+            HookEvent::BeforeDelete,
+            hook(AuditOperation::Delete),
+        );
+
+        // the values before the update are kept to find the columns that change
+        // they are kept for each thread, because copies made with `clone_in_memory` share the hooks
+        let old = Arc::new(Mutex::new(HashMap::new()));
+        db.hooks.add(&table, HookEvent::BeforeUpdate, {
+            let (old, select) = (old.clone(), select.clone());
+            move |txn: &Transaction<'_, S>, row: i64| {
+                let values = row_json(txn, &select, row);
+                old.lock()
+                    .unwrap()
+                    .insert(thread::current().id(), (row, values));
+            }
+        });
+        db.hooks.add(&table, HookEvent::AfterUpdate, {
+            let table = table.clone();
+            move |txn: &Transaction<'_, S>, row: i64| {
+                let Some((old_row, old)) = old.lock().unwrap().remove(&thread::current().id()) else {
+                    return;
+                };
+                assert_eq!(old_row, row);
+                let new = row_json(txn, &select, row);
+                let changes: String = txn
+                    .transaction()
+                    .query_row(
+                        "SELECT json_group_object(n.key, n.value) FROM json_each(?1) AS n
+                        WHERE NOT EXISTS (SELECT 1 FROM json_each(?2) AS o WHERE o.key = n.key AND o.value IS n.value)",
+                        [&new, &old],
+                        |r| r.get(0),
+                    )
+                    .unwrap();
+                if changes != "{}" {
+                    record(txn, &table, row, AuditOperation::Update, &changes);
+                }
+            }
+        });
+    }
+    Ok(())
+}
+
+fn row_json<S>(txn: &Transaction<'_, S>, select: &str, row: i64) -> String {
+    txn.transaction()
+        .query_row(select, [row], |r| r.get(0))
+        .unwrap()
+}
+
+fn record<S>(txn: &Transaction<'_, S>, table: &str, row: i64, op: AuditOperation, changes: &str) {
+    let sql = format!(
+        "INSERT INTO \"{AUDIT_TABLE}\" (\"table\", \"row\", \"operation\", \"changes\", \"timestamp\", \"actor\")
+        VALUES (?, ?, ?, ?, unixepoch('now'), ?)"
+    );
+    txn.transaction()
+        .prepare_cached(&sql)
+        .unwrap()
+        .execute(rusqlite::params![
+            table,
+            row,
+            op.as_str(),
+            changes,
+            txn.actor.as_deref()
+        ])
+        .unwrap();
+}
+
+impl<'t, S> Transaction<'t, S> {
+    /// Read the changes of a row that were recorded in the audit log, the oldest change comes first.
+    ///
+    /// The history is also available after the row is deleted.
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     User { name: String },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory().audit()).unwrap().finish().unwrap();
+    /// # let mut txn = client.transaction_mut(&db);
+    /// # let user = txn.insert(User { name: "alice" });
+    /// for entry in txn.audit_history(user) {
+    ///     println!("{:?} by {:?}: {}", entry.operation, entry.actor, entry.changes);
+    /// }
+    /// ```
+    /// This function panics if the audit log was not enabled with [crate::migration::Config::audit].
+    pub fn audit_history<T: Table<Schema = S>>(&self, row: impl Into<RowId<T>>) -> Vec<AuditEntry> {
+        let conn = self.transaction();
+        let exists = conn
+            .query_row(
+                "SELECT 1 FROM sqlite_schema WHERE type = 'table' AND name = ?",
+                [AUDIT_TABLE],
+                |_| Ok(()),
+            )
+            .optional()
+            .unwrap();
+        assert!(exists.is_some(), "the audit log is not enabled");
+
+        let sql = format!(
+            "SELECT \"operation\", \"changes\", \"timestamp\", \"actor\" FROM \"{AUDIT_TABLE}\"
+            WHERE \"table\" = ? AND \"row\" = ? ORDER BY \"id\""
+        );
+        let mut stmt = conn.prepare_cached(&sql).unwrap();
+        stmt.query_map(rusqlite::params![T::NAME, row.into().as_i64()], |r| {
+            let operation = match r.get_ref(0)?.as_str()? {
+                "insert" => AuditOperation::Insert,
+                "update" => AuditOperation::Update,
+                "delete" => AuditOperation::Delete,
+                op => panic!("unknown audit operation {op}"),
+            };
+            Ok(AuditEntry {
+                operation,
+                changes: r.get(1)?,
+                timestamp: r.get(2)?,
+                actor: r.get(3)?,
+            })
+        })
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+    }
+}
+
+impl<S> TransactionMut<'_, S> {
+    /// Set the actor that is recorded in the audit log for the changes that are made after this call.
+    ///
+    /// This can be the name of the user that made the request, see [crate::migration::Config::audit].
+    pub fn set_actor(&mut self, actor: impl Into<String>) {
+        self.inner.actor = Some(actor.into());
+    }
+}
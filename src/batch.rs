@@ -0,0 +1,192 @@
+use std::{collections::HashMap, convert::Infallible, marker::PhantomData, ops::Deref};
+
+use crate::{
+    error::unwrap_db,
+    insert::{Writable, WriteError},
+    Table, TableRow, Transaction, TransactionMut,
+};
+
+/// Argument of [TransactionMut::batch] to insert rows that reference each other.
+pub struct Batch<'b, 't, S> {
+    txn: &'b mut TransactionMut<'t, S>,
+    // the next free id of tables that have reserved rows
+    next: HashMap<&'static str, i64>,
+    // reserved rows that are not inserted yet
+    reserved: Vec<(&'static str, i64)>,
+}
+
+/// Result of [TransactionMut::insert_many_lossy].
+#[derive(Debug)]
+pub struct InsertReport<'t, T, C> {
+    /// The rows that were inserted, in the order of the values.
+    pub inserted: Vec<TableRow<'t, T>>,
+    /// The index of every value that could not be inserted, together with the error.
+    pub failed: Vec<(usize, WriteError<C>)>,
+}
+
+impl<'t, S> Deref for Batch<'_, 't, S> {
+    type Target = Transaction<'t, S>;
+
+    fn deref(&self) -> &Self::Target {
+        self.txn
+    }
+}
+
+impl<'t, S: 'static> Batch<'_, 't, S> {
+    /// Reserve a row of table `T` that is inserted later with [Batch::insert_reserved].
+    ///
+    /// The reserved row can be used in other rows of the batch before it is inserted,
+    /// but it can not be queried yet.
+    pub fn reserve<T: Table<Schema = S>>(&mut self) -> TableRow<'t, T> {
+        let next = match self.next.get(T::NAME) {
+            Some(next) => *next,
+            None => {
+                let sql = format!(
+                    "SELECT coalesce(max(\"{}\"), 0) + 1 FROM \"{}\"",
+                    T::ID,
+                    T::NAME
+                );
+                let next = self.txn.transaction().query_row(&sql, [], |row| row.get(0));
+                next.unwrap()
+            }
+        };
+        self.next.insert(T::NAME, next + 1);
+        self.reserved.push((T::NAME, next));
+        TableRow {
+            _p: PhantomData,
+            _local: PhantomData,
+            idx: next,
+        }
+    }
+
+    /// Insert a new row, like [TransactionMut::insert].
+    #[track_caller]
+    pub fn insert<T: Table<Schema = S>>(
+        &mut self,
+        val: impl Writable<'t, T = T, Conflict = Infallible, Schema = S>,
+    ) -> TableRow<'t, T> {
+        // the row can not get the id of a reserved row
+        let id = self.next.get_mut(T::NAME).map(|next| {
+            *next += 1;
+            *next - 1
+        });
+        let Ok(row) = self
+            .txn
+            .insert_with_id(val, id)
+            .map_err(|e| e.unwrap_check());
+        row
+    }
+
+    /// Insert the values of a row that was reserved with [Batch::reserve].
+    ///
+    /// This function panics if the row is not reserved or if it is already inserted.
+    #[track_caller]
+    pub fn insert_reserved<T: Table<Schema = S>>(
+        &mut self,
+        row: TableRow<'t, T>,
+        val: impl Writable<'t, T = T, Conflict = Infallible, Schema = S>,
+    ) {
+        let Some(pos) = self.reserved.iter().position(|x| *x == (T::NAME, row.idx)) else {
+            panic!("row {} of table {} is not reserved", row.idx, T::NAME)
+        };
+        self.reserved.swap_remove(pos);
+        let Ok(_) = self
+            .txn
+            .insert_with_id(val, Some(row.idx))
+            .map_err(|e| e.unwrap_check());
+    }
+}
+
+impl<'t, S: 'static> TransactionMut<'t, S> {
+    /// Insert rows that reference each other, for example a parent row and its children.
+    ///
+    /// Foreign keys are only checked at the end of the batch, so children can be inserted
+    /// before their parent by reserving the parent row with [Batch::reserve].
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     Album { title: String },
+    /// #     Track { album: Album, name: String },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// let mut txn = client.transaction_mut(&db);
+    /// let album = txn.batch(|batch| {
+    ///     let album = batch.reserve::<Album>();
+    ///     batch.insert(Track { album, name: "intro" });
+    ///     batch.insert_reserved(album, Album { title: "debut" });
+    ///     album
+    /// });
+    /// assert_eq!(txn.query_one(album.title()), "debut");
+    /// ```
+    /// This function panics if a reserved row is not inserted at the end of the batch.
+    pub fn batch<R>(&mut self, f: impl FnOnce(&mut Batch<'_, 't, S>) -> R) -> R {
+        let conn = self.transaction();
+        conn.pragma_update(None, "defer_foreign_keys", true)
+            .unwrap();
+        let mut batch = Batch {
+            txn: self,
+            next: HashMap::new(),
+            reserved: vec![],
+        };
+        let res = f(&mut batch);
+        if let Some((table, idx)) = batch.reserved.first() {
+            panic!("reserved row {idx} of table {table} was not inserted")
+        }
+        let conn = self.transaction();
+        conn.pragma_update(None, "defer_foreign_keys", false)
+            .unwrap();
+        res
+    }
+
+    /// Insert all values that can be inserted and report the errors for the others.
+    ///
+    /// Every value is inserted in its own savepoint, so a value that fails does not leave any changes behind.
+    /// The rows that were inserted stay in the transaction and are saved by [TransactionMut::commit].
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     User {
+    /// #         #[unique_email]
+    /// #         email: String,
+    /// #     },
+    /// # }
+    /// # use v0::*;
+    /// # struct NewUser { email: String }
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// # let mut txn = client.transaction_mut(&db);
+    /// # let users = [NewUser { email: "a".to_owned() }, NewUser { email: "a".to_owned() }];
+    /// let report = txn.insert_many_lossy(users.iter().map(|u| User { email: &u.email }));
+    /// for (i, err) in report.failed {
+    ///     println!("user {i} was not inserted: {err:?}");
+    /// }
+    /// ```
+    pub fn insert_many_lossy<T: Table<Schema = S>, C>(
+        &mut self,
+        vals: impl IntoIterator<Item = impl Writable<'t, T = T, Conflict = C, Schema = S>>,
+    ) -> InsertReport<'t, T, C> {
+        let mut report = InsertReport {
+            inserted: vec![],
+            failed: vec![],
+        };
+        for (i, val) in vals.into_iter().enumerate() {
+            let conn = self.transaction();
+            unwrap_db(conn.execute_batch("SAVEPOINT \"insert_many_lossy\""));
+            match self.checked_insert(val) {
+                Ok(row) => report.inserted.push(row),
+                Err(err) => {
+                    let conn = self.transaction();
+                    unwrap_db(conn.execute_batch("ROLLBACK TO \"insert_many_lossy\""));
+                    report.failed.push((i, err));
+                }
+            }
+            let conn = self.transaction();
+            unwrap_db(conn.execute_batch("RELEASE \"insert_many_lossy\""));
+        }
+        report
+    }
+}
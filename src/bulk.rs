@@ -0,0 +1,172 @@
+use std::{convert::Infallible, marker::PhantomData};
+
+use rusqlite::ErrorCode;
+use sea_query::{Alias, DeleteStatement, Expr, Iden, SqliteQueryBuilder};
+use sea_query_rusqlite::RusqliteBinder;
+
+use crate::{
+    ast::MySelect,
+    db::Col,
+    insert::{Reader, Writable},
+    trace::{execute, traced},
+    transaction::Deletor,
+    Column, IntoColumn, Rows, Table, TransactionMut, UnixEpoch,
+};
+
+/// Result of [TransactionMut::update_where] and [Deletor::delete_where].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Changes {
+    /// The number of rows that were changed.
+    pub rows: usize,
+    /// The id of the last row that was inserted on this connection, see
+    /// [sqlite3_last_insert_rowid](https://www.sqlite.org/c3ref/last_insert_rowid.html).
+    ///
+    /// Rows inserted by triggers are not included.
+    pub last_insert_rowid: i64,
+}
+
+type ReadFn<'t, S> = Box<dyn FnOnce(Reader<'_, 't, S>) + 't>;
+
+/// This is the type used to return the new values in [TransactionMut::update_where].
+///
+/// Like [crate::migration::Alter], it dyn boxes the new values so that type inference works
+/// for the higher ranked closure.
+pub struct Update<'t, S, T> {
+    read: ReadFn<'t, S>,
+    _p: PhantomData<T>,
+}
+
+impl<'t, S, T> Update<'t, S, T> {
+    pub fn new(val: impl Writable<'t, T = T, Conflict = Infallible, Schema = S> + 't) -> Self {
+        Self {
+            read: Box::new(move |reader| val.read(reader)),
+            _p: PhantomData,
+        }
+    }
+}
+
+fn new_rows<'t, S>() -> Rows<'t, S> {
+    Rows {
+        phantom: PhantomData,
+        ast: MySelect::default(),
+    }
+}
+
+impl<'t, S: 'static> TransactionMut<'t, S> {
+    /// Update all rows of a table that are selected by the closure with a single `UPDATE` statement.
+    ///
+    /// The closure receives the rows of the table and can filter them using [Rows].
+    /// It returns the new values, which can depend on the current values of the row.
+    /// The number of updated rows is returned in [Changes].
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, IntoColumn, LocalClient, Table, Update};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     User { name: String, score: i64 },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// # let mut txn = client.transaction_mut(&db);
+    /// txn.update_where::<User>(|rows, user| {
+    ///     rows.filter(user.score().lt(0));
+    ///     Update::new(User {
+    ///         score: 0i64.into_column(),
+    ///         ..User::dummy(user.clone())
+    ///     })
+    /// });
+    /// ```
+    pub fn update_where<T: Table<Schema = S>>(
+        &mut self,
+        f: impl for<'a> FnOnce(&mut Rows<'a, S>, Column<'a, S, T>) -> Update<'a, S, T>,
+    ) -> Changes {
+        let mut rows = new_rows();
+        let row = rows.join::<T>();
+        let update = f(&mut rows, row.clone());
+
+        let reader = Reader {
+            ast: &rows.ast,
+            _p: PhantomData,
+            _p2: PhantomData,
+        };
+        (update.read)(reader);
+        if T::TIMESTAMPS {
+            reader.col("updated_at", UnixEpoch);
+        }
+        if T::VERSIONED {
+            let version = Col::<i64, _>::new("version", row.clone());
+            reader.col("version", version.into_column().add(1));
+        }
+
+        let sets: Vec<_> = rows
+            .ast
+            .select
+            .iter()
+            .map(|(_, field)| format!("\"{0}\" = \"_new\".\"{0}\"", field.to_string()))
+            .collect();
+        reader.col(T::ID, row);
+
+        let (select, values) = rows
+            .ast
+            .build_select(false)
+            .build_rusqlite(SqliteQueryBuilder);
+        let table = T::NAME;
+        let id = T::ID;
+        let sql = format!(
+            "UPDATE \"{table}\" SET {} FROM ({select}) AS \"_new\" WHERE \"{table}\".\"{id}\" = \"_new\".\"{id}\"",
+            sets.join(", ")
+        );
+
+        let conn = self.transaction();
+        let mut stmt = conn.prepare_cached(&sql).unwrap();
+        let rows = traced(&sql, &values.0, || execute(&mut stmt, &values)).unwrap();
+        Changes {
+            rows,
+            last_insert_rowid: conn.last_insert_rowid(),
+        }
+    }
+}
+
+impl<S: 'static> Deletor<S> {
+    /// Delete all rows of a table that are selected by the closure with a single `DELETE` statement.
+    ///
+    /// The closure receives the rows of the table and can filter them using [Rows].
+    /// Returns the number of deleted rows in [Changes], or [None] if a foreign key constraint does not allow
+    /// deleting the rows. In that case no rows are deleted.
+    pub fn delete_where<T: Table<Schema = S>>(
+        &mut self,
+        f: impl for<'a> FnOnce(&mut Rows<'a, S>, Column<'a, S, T>),
+    ) -> Option<Changes> {
+        let mut rows = new_rows();
+        let row = rows.join::<T>();
+        f(&mut rows, row.clone());
+        let reader = Reader {
+            ast: &rows.ast,
+            _p: PhantomData,
+            _p2: PhantomData,
+        };
+        reader.col(T::ID, row);
+
+        let stmt = DeleteStatement::new()
+            .from_table(Alias::new(T::NAME))
+            .cond_where(Expr::col(Alias::new(T::ID)).in_subquery(rows.ast.build_select(false)))
+            .to_owned();
+
+        let (query, args) = stmt.build_rusqlite(SqliteQueryBuilder);
+        let conn = self.inner.transaction();
+        let mut stmt = conn.prepare_cached(&query).unwrap();
+
+        match traced(&query, &args.0, || execute(&mut stmt, &args)) {
+            Err(rusqlite::Error::SqliteFailure(kind, Some(_val)))
+                if kind.code == ErrorCode::ConstraintViolation =>
+            {
+                // Some foreign key constraint got violated
+                None
+            }
+            res => Some(Changes {
+                rows: res.unwrap(),
+                last_insert_rowid: conn.last_insert_rowid(),
+            }),
+        }
+    }
+}
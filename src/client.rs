@@ -9,7 +9,7 @@ pub(crate) trait QueryBuilder {
         F: for<'a> FnOnce(&'a mut Query<'s, 'a, S>) -> R;
 }
 
-impl QueryBuilder for rusqlite::Transaction<'_> {
+impl QueryBuilder for rusqlite::Connection {
     fn new_query<'s, F, R, S>(&'s self, f: F) -> R
     where
         F: for<'a> FnOnce(&'a mut Query<'s, 'a, S>) -> R,
@@ -0,0 +1,137 @@
+use std::fmt::Write;
+
+use crate::{
+    hash::{Column, ColumnType},
+    migrate::{Schema, TableTypBuilder},
+    skeleton::upper_camel,
+};
+
+/// Generate TypeScript interfaces for the rows of the tables in schema `S`.
+///
+/// Every table gets an interface with its `id` and all of its columns, including generated columns.
+/// Foreign keys are the `id` of the referenced row.
+/// The output can be written to the frontend from a build script or checked in a test,
+/// so that it is updated together with the schema.
+/// ```no_run
+/// # use rust_query::migration::{schema, typescript_types};
+/// # #[schema]
+/// # enum Schema {
+/// #     User { name: String },
+/// # }
+/// # fn main() -> std::io::Result<()> {
+/// std::fs::write("frontend/src/schema.ts", typescript_types::<v0::Schema>())?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn typescript_types<S: Schema>() -> String {
+    let mut b = TableTypBuilder::default();
+    S::typs(&mut b);
+
+    let mut out = String::new();
+    for (i, (table_name, table)) in b.ast.tables.iter().enumerate() {
+        if i != 0 {
+            writeln!(out).unwrap();
+        }
+        writeln!(out, "export interface {} {{", upper_camel(table_name)).unwrap();
+        writeln!(out, "  id: number;").unwrap();
+        for col in &*table.columns {
+            let mut typ = match col.typ {
+                ColumnType::Integer | ColumnType::Float => "number",
+                ColumnType::String => "string",
+            }
+            .to_owned();
+            if col.nullable {
+                typ.push_str(" | null");
+            }
+            write!(out, "  {}: {typ};", col.name).unwrap();
+            if let Some((table, _)) = &col.fk {
+                write!(out, " // id of {}", upper_camel(table)).unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+    }
+    out
+}
+
+/// Generate a JSON Schema with a definition for the rows of each table in schema `S`.
+///
+/// The definitions are in `$defs` and have the same names and fields as the interfaces of [typescript_types].
+/// Integer columns have type `integer` and nullable columns also allow `null`.
+/// ```no_run
+/// # use rust_query::migration::{json_schema, schema};
+/// # #[schema]
+/// # enum Schema {
+/// #     User { name: String },
+/// # }
+/// # fn main() -> std::io::Result<()> {
+/// std::fs::write("schema.json", json_schema::<v0::Schema>())?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn json_schema<S: Schema>() -> String {
+    let mut b = TableTypBuilder::default();
+    S::typs(&mut b);
+
+    let mut defs = vec![];
+    for (table_name, table) in &*b.ast.tables {
+        let id = Column {
+            name: "id".to_owned(),
+            typ: ColumnType::Integer,
+            nullable: false,
+            fk: None,
+            on_delete: Default::default(),
+            generated: None,
+            default: None,
+            collate: None,
+        };
+        let columns: Vec<_> = std::iter::once(&id).chain(table.columns.iter()).collect();
+
+        let properties: Vec<_> = columns
+            .iter()
+            .map(|col| {
+                let typ = match col.typ {
+                    ColumnType::Integer => "\"integer\"",
+                    ColumnType::Float => "\"number\"",
+                    ColumnType::String => "\"string\"",
+                };
+                let mut prop = match col.nullable {
+                    true => format!("\"type\": [{typ}, \"null\"]"),
+                    false => format!("\"type\": {typ}"),
+                };
+                if let Some((table, _)) = &col.fk {
+                    write!(prop, ", \"description\": \"id of {}\"", upper_camel(table)).unwrap();
+                }
+                format!("        \"{}\": {{ {prop} }}", col.name)
+            })
+            .collect();
+        let required: Vec<_> = columns
+            .iter()
+            .map(|col| format!("\"{}\"", col.name))
+            .collect();
+
+        let mut def = String::new();
+        writeln!(def, "    \"{}\": {{", upper_camel(table_name)).unwrap();
+        writeln!(def, "      \"type\": \"object\",").unwrap();
+        writeln!(def, "      \"properties\": {{").unwrap();
+        writeln!(def, "{}", properties.join(",\n")).unwrap();
+        writeln!(def, "      }},").unwrap();
+        writeln!(def, "      \"required\": [{}],", required.join(", ")).unwrap();
+        writeln!(def, "      \"additionalProperties\": false").unwrap();
+        write!(def, "    }}").unwrap();
+        defs.push(def);
+    }
+
+    let mut out = String::new();
+    writeln!(out, "{{").unwrap();
+    writeln!(
+        out,
+        "  \"$schema\": \"https://json-schema.org/draft/2020-12/schema\","
+    )
+    .unwrap();
+    writeln!(out, "  \"$defs\": {{").unwrap();
+    writeln!(out, "{}", defs.join(",\n")).unwrap();
+    writeln!(out, "  }}").unwrap();
+    writeln!(out, "}}").unwrap();
+    out
+}
@@ -0,0 +1,287 @@
+use std::{collections::HashSet, fmt};
+
+use rusqlite::types::ValueRef;
+use sea_query_rusqlite::{RusqliteValue, RusqliteValues};
+
+use crate::{
+    error::unwrap_db,
+    hash::{self, TypBuilder},
+    migrate::{Schema, TableTypBuilder},
+    trace::{record_steps, traced},
+    ImportValue, Table, TableRow, Transaction,
+};
+
+/// The maximum number of ids in one `IN (..)` list, to stay below the parameter limit of sqlite.
+const CHUNK: usize = 500;
+
+type Redaction = Box<dyn Fn(&ImportValue) -> ImportValue>;
+
+/// Collect all rows that belong to a root row, for example to export the data of a user.
+///
+/// Starting from the root row, every row that has a foreign key to a collected row is collected too.
+/// So for a customer this includes their invoices and the lines of those invoices.
+/// Rows that are only referenced by collected rows, like the tracks on the invoice lines, are not
+/// included because they do not belong to the root row.
+///
+/// Values can be replaced before they are added to the [DataBundle] with [DataExport::redact].
+/// ```
+/// # use rust_query::{migration::{schema, Config}, DataExport, ImportValue, LocalClient};
+/// # #[schema]
+/// # enum Schema {
+/// #     Customer { name: String, email: String },
+/// #     Invoice { customer: Customer, total: f64 },
+/// # }
+/// # use v0::*;
+/// # let mut client = LocalClient::try_new().unwrap();
+/// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+/// # let mut txn = client.transaction_mut(&db);
+/// let customer = txn.insert(Customer { name: "alice", email: "alice@example.com" });
+/// txn.insert(Invoice { customer, total: 9.99 });
+///
+/// let export = DataExport::new().redact::<Customer>("email", |_| ImportValue::from("hidden"));
+/// let bundle = export.collect(&txn, customer);
+/// assert_eq!(bundle.tables.len(), 2);
+/// assert_eq!(bundle.tables[0].rows[0][1], ImportValue::from("hidden"));
+/// ```
+#[derive(Default)]
+pub struct DataExport {
+    redactions: Vec<(&'static str, String, Redaction)>,
+}
+
+impl fmt::Debug for DataExport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let columns = self.redactions.iter().map(|(table, col, _)| (table, col));
+        f.debug_struct("DataExport")
+            .field("redactions", &columns.collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// The rows that were collected by [DataExport::collect].
+///
+/// With the `serde` feature, this serializes as a map from table name to a list of rows,
+/// where every row is a map from column name to value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataBundle {
+    /// The tables that have collected rows, starting with the table of the root row.
+    pub tables: Vec<BundleTable>,
+}
+
+/// The collected rows of one table in a [DataBundle].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundleTable {
+    pub name: String,
+    /// The names of the columns, the `id` comes first and the other columns are sorted by name.
+    pub columns: Vec<String>,
+    /// The rows in order of their id, with a value for each column.
+    pub rows: Vec<Vec<ImportValue>>,
+}
+
+impl DataExport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the values of `column` in table `T` with the result of `f`.
+    ///
+    /// This function will panic if `T` does not have a column with this name.
+    pub fn redact<T: Table>(
+        mut self,
+        column: &str,
+        f: impl Fn(&ImportValue) -> ImportValue + 'static,
+    ) -> Self {
+        let mut b = TypBuilder::default();
+        T::typs(&mut b);
+        assert!(
+            b.ast.columns.iter().any(|col| col.name == column),
+            "table `{}` does not have a column `{column}`",
+            T::NAME
+        );
+        self.redactions
+            .push((T::NAME, column.to_owned(), Box::new(f)));
+        self
+    }
+
+    /// Collect the `root` row and all rows that belong to it.
+    pub fn collect<'t, T: Table>(
+        &self,
+        txn: &Transaction<'t, T::Schema>,
+        root: TableRow<'t, T>,
+    ) -> DataBundle
+    where
+        T::Schema: Schema,
+    {
+        let schema = schema::<T::Schema>();
+        let mut tables = walk(txn, &schema, T::NAME, root.idx, true, |_| true);
+        for table in &mut tables {
+            table.rows.sort_by_key(|row| id(row));
+            for (redact_table, column, f) in &self.redactions {
+                if *redact_table != table.name {
+                    continue;
+                }
+                let i = table.columns.iter().position(|x| x == column).unwrap();
+                for row in &mut table.rows {
+                    row[i] = f(&row[i]);
+                }
+            }
+        }
+        DataBundle { tables }
+    }
+}
+
+pub(crate) fn schema<S: Schema>() -> hash::Schema {
+    let mut b = TableTypBuilder::default();
+    S::typs(&mut b);
+    b.ast
+}
+
+pub(crate) fn find<'a>(schema: &'a hash::Schema, name: &str) -> &'a hash::Table {
+    &schema.tables.iter().find(|(x, _)| x == name).unwrap().1
+}
+
+pub(crate) fn id(row: &[ImportValue]) -> i64 {
+    match row[0] {
+        ImportValue::Integer(id) => id,
+        _ => unreachable!(),
+    }
+}
+
+/// Find the row `root` of table `name` and all rows that depend on it.
+///
+/// A row depends on another row if it has a foreign key to it for which `follow` returns true.
+/// The rows of each table are in the order that they were found,
+/// they only contain the `id` unless `all_columns` is true.
+pub(crate) fn walk<S>(
+    txn: &Transaction<S>,
+    schema: &hash::Schema,
+    name: &str,
+    root: i64,
+    all_columns: bool,
+    follow: impl Fn(&hash::Column) -> bool,
+) -> Vec<BundleTable> {
+    let mut seen = HashSet::new();
+    let mut tables: Vec<BundleTable> = vec![];
+    let mut queue = vec![(name.to_owned(), "id".to_owned(), vec![root])];
+    while let Some((name, column, ids)) = queue.pop() {
+        let table = find(schema, &name);
+        let mut columns = vec!["id".to_owned()];
+        if all_columns {
+            columns.extend(table.columns.iter().map(|col| col.name.clone()));
+        }
+        let mut new = vec![];
+        for chunk in ids.chunks(CHUNK) {
+            for row in select(txn, &name, &columns, &column, chunk) {
+                if seen.insert((name.clone(), id(&row))) {
+                    new.push(row);
+                }
+            }
+        }
+        if new.is_empty() {
+            continue;
+        }
+
+        // the rows that depend on the new rows are found next
+        let new_ids: Vec<_> = new.iter().map(|row| id(row)).collect();
+        for (child, child_table) in schema.tables.iter() {
+            for col in child_table.columns.iter() {
+                if col.fk.as_ref().is_some_and(|(parent, _)| *parent == name) && follow(col) {
+                    queue.push((child.clone(), col.name.clone(), new_ids.clone()));
+                }
+            }
+        }
+
+        let pos = match tables.iter().position(|x| x.name == name) {
+            Some(pos) => pos,
+            None => {
+                tables.push(BundleTable {
+                    name: name.clone(),
+                    columns,
+                    rows: vec![],
+                });
+                tables.len() - 1
+            }
+        };
+        tables[pos].rows.extend(new);
+    }
+    tables
+}
+
+/// Select the rows of `table` where `column` is one of `ids`.
+fn select<S>(
+    txn: &Transaction<S>,
+    table: &str,
+    columns: &[String],
+    column: &str,
+    ids: &[i64],
+) -> Vec<Vec<ImportValue>> {
+    let names: Vec<_> = columns.iter().map(|x| format!("\"{x}\"")).collect();
+    let sql = format!(
+        "SELECT {} FROM \"{table}\" WHERE \"{column}\" IN ({})",
+        names.join(", "),
+        vec!["?"; ids.len()].join(", ")
+    );
+    let values = ids.iter().map(|id| RusqliteValue((*id).into()));
+    let values = RusqliteValues(values.collect());
+
+    let mut stmt = unwrap_db(txn.transaction().prepare(&sql));
+    let res: rusqlite::Result<_> = traced(&sql, &values.0, || {
+        let mut rows = stmt.query(&*values.as_params())?;
+        let mut out = vec![];
+        while let Some(row) = rows.next()? {
+            let row = (0..columns.len()).map(|i| {
+                Ok(match row.get_ref(i)? {
+                    ValueRef::Null => ImportValue::Null,
+                    ValueRef::Integer(x) => ImportValue::Integer(x),
+                    ValueRef::Real(x) => ImportValue::Float(x),
+                    ValueRef::Text(x) | ValueRef::Blob(x) => {
+                        ImportValue::Text(String::from_utf8_lossy(x).into_owned())
+                    }
+                })
+            });
+            out.push(row.collect::<rusqlite::Result<_>>()?);
+        }
+        Ok(out)
+    });
+    record_steps(&stmt);
+    unwrap_db(res)
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for DataBundle {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.tables.len()))?;
+        for table in &self.tables {
+            let rows: Vec<_> = table.rows.iter().map(|row| BundleRow(table, row)).collect();
+            map.serialize_entry(&table.name, &rows)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+struct BundleRow<'a>(&'a BundleTable, &'a [ImportValue]);
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for BundleRow<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.1.len()))?;
+        for (name, value) in self.0.columns.iter().zip(self.1) {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ImportValue {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ImportValue::Null => serializer.serialize_none(),
+            ImportValue::Integer(x) => serializer.serialize_i64(*x),
+            ImportValue::Float(x) => serializer.serialize_f64(*x),
+            ImportValue::Text(x) => serializer.serialize_str(x),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::{fmt::Debug, marker::PhantomData, ops::Deref};
+use std::{fmt::Debug, hash::Hash, marker::PhantomData, ops::Deref};
 
 use ref_cast::RefCast;
 use rusqlite::types::FromSql;
@@ -117,6 +117,9 @@ impl<'t, T: Table> IntoColumn<'t, T::Schema> for Join<'t, T> {
 /// Row reference that can be used in any query in the same transaction.
 ///
 /// [TableRow] is covariant in `'t` and restricted to a single thread to prevent it from being used in a different transaction.
+///
+/// With the `serde` feature, [TableRow] can be serialized as its [i64] id.
+/// It can not be deserialized, use [RowId] for that instead.
 pub struct TableRow<'t, T> {
     pub(crate) _p: PhantomData<&'t T>,
     pub(crate) _local: PhantomData<LocalClient>,
@@ -160,6 +163,104 @@ impl<T> FromSql for TableRow<'_, T> {
     }
 }
 
+/// Id of a row that can be stored outside of a transaction.
+///
+/// Unlike [TableRow], a [RowId] is not guaranteed to refer to an existing row.
+/// It can be converted back to a [TableRow] with [crate::Transaction::find].
+///
+/// With the `serde` feature, [RowId] can be serialized and deserialized as its [i64] id.
+/// ```
+/// # use rust_query::{migration::{schema, Config}, LocalClient, RowId};
+/// # #[schema]
+/// # enum Schema {
+/// #     User { name: String },
+/// # }
+/// # use v0::*;
+/// # let mut client = LocalClient::try_new().unwrap();
+/// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+/// # let mut txn = client.transaction_mut(&db);
+/// # let user = txn.insert(User { name: "alice" });
+/// let id = RowId::from(user);
+/// let raw: i64 = id.as_i64(); // store this somewhere
+///
+/// // later in another transaction
+/// let user = txn.find(RowId::<User>::from_i64(raw));
+/// # assert!(user.is_some());
+/// ```
+pub struct RowId<T> {
+    pub(crate) _p: PhantomData<fn() -> T>,
+    pub(crate) idx: i64,
+}
+
+impl<T> RowId<T> {
+    /// Create a [RowId] from a stored [i64] id.
+    pub fn from_i64(idx: i64) -> Self {
+        Self {
+            _p: PhantomData,
+            idx,
+        }
+    }
+
+    /// Get the [i64] id, this is the same as the `id` column in the database.
+    pub fn as_i64(self) -> i64 {
+        self.idx
+    }
+}
+
+impl<T> From<TableRow<'_, T>> for RowId<T> {
+    fn from(value: TableRow<'_, T>) -> Self {
+        Self::from_i64(value.idx)
+    }
+}
+
+impl<T> PartialEq for RowId<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.idx == other.idx
+    }
+}
+
+impl<T> Eq for RowId<T> {}
+
+impl<T> Hash for RowId<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.idx.hash(state);
+    }
+}
+
+impl<T> Debug for RowId<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "id_{}", self.idx)
+    }
+}
+
+impl<T> Clone for RowId<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T> Copy for RowId<T> {}
+
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for TableRow<'_, T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.idx)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for RowId<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.idx)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for RowId<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        i64::deserialize(deserializer).map(Self::from_i64)
+    }
+}
+
 impl<'t, T> From<TableRow<'t, T>> for sea_query::Value {
     fn from(value: TableRow<T>) -> Self {
         value.idx.into()
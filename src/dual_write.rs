@@ -0,0 +1,59 @@
+/// Prefix of the triggers that write changes of an old table to its new table, see [crate::migration::Migrator::expand],
+/// and of the triggers that make an old table read-only during a journaled migration.
+pub(crate) const TRIGGER_PREFIX: &str = "_dual_write_";
+
+/// Create triggers on table `from`, so that every insert, update and delete is also done on table `tmp`.
+///
+/// The triggers are dropped together with `from` when the migration is finished.
+pub(crate) fn install(
+    conn: &rusqlite::Connection,
+    from: &str,
+    tmp: &str,
+    columns: &[String],
+) -> rusqlite::Result<()> {
+    let id = &columns[0];
+    let names: Vec<_> = columns.iter().map(|x| format!("\"{x}\"")).collect();
+    let names = names.join(", ");
+    let values: Vec<_> = columns.iter().map(|x| format!("new.\"{x}\"")).collect();
+    let values = values.join(", ");
+
+    let insert = format!("INSERT INTO \"{tmp}\" ({names}) VALUES ({values});");
+    let delete = format!("DELETE FROM \"{tmp}\" WHERE \"{id}\" = old.\"{id}\";");
+    conn.execute_batch(&format!(
+        "CREATE TRIGGER IF NOT EXISTS \"{TRIGGER_PREFIX}{tmp}_insert\" AFTER INSERT ON \"{from}\" BEGIN
+            {insert}
+        END;
+        CREATE TRIGGER IF NOT EXISTS \"{TRIGGER_PREFIX}{tmp}_update\" AFTER UPDATE ON \"{from}\" BEGIN
+            {delete}
+            {insert}
+        END;
+        CREATE TRIGGER IF NOT EXISTS \"{TRIGGER_PREFIX}{tmp}_delete\" AFTER DELETE ON \"{from}\" BEGIN
+            {delete}
+        END;"
+    ))
+}
+
+/// Create triggers on `table` that reject every insert, update and delete.
+///
+/// This is used for tables that are read by a copy that can not be kept up to date with [install].
+/// The triggers are removed with [unlock] when the migration is finished.
+pub(crate) fn lock(conn: &rusqlite::Connection, table: &str) -> rusqlite::Result<()> {
+    for event in ["INSERT", "UPDATE", "DELETE"] {
+        let name = format!("{TRIGGER_PREFIX}{table}_lock_{}", event.to_lowercase());
+        conn.execute_batch(&format!(
+            "CREATE TRIGGER IF NOT EXISTS \"{name}\" BEFORE {event} ON \"{table}\" BEGIN
+                SELECT RAISE(ABORT, 'table `{table}` is read-only until the migration is finished');
+            END;"
+        ))?;
+    }
+    Ok(())
+}
+
+/// Remove the triggers that were created by [lock] for `table`.
+pub(crate) fn unlock(conn: &rusqlite::Connection, table: &str) -> rusqlite::Result<()> {
+    for event in ["insert", "update", "delete"] {
+        let name = format!("{TRIGGER_PREFIX}{table}_lock_{event}");
+        conn.execute_batch(&format!("DROP TRIGGER IF EXISTS \"{name}\";"))?;
+    }
+    Ok(())
+}
@@ -2,7 +2,7 @@ use std::marker::PhantomData;
 
 use sea_query::Iden;
 
-use crate::{alias::Field, ast::MySelect, value::MyTyp, IntoColumn};
+use crate::{alias::Field, ast::MySelect, memo::CachedRows, value::MyTyp, IntoColumn};
 
 pub struct Cacher<'x, 't, S> {
     pub(crate) _p: PhantomData<fn(&'t S) -> &'t S>,
@@ -45,13 +45,24 @@ impl<'t, S> Cacher<'_, 't, S> {
 pub struct Row<'x, 't, 'a> {
     pub(crate) _p: PhantomData<fn(&'t ()) -> &'t ()>,
     pub(crate) _p2: PhantomData<fn(&'a ()) -> &'a ()>,
-    pub(crate) row: &'x rusqlite::Row<'x>,
+    pub(crate) row: RowSource<'x>,
+}
+
+/// Where the values of a [Row] are read from.
+#[derive(Clone, Copy)]
+pub(crate) enum RowSource<'x> {
+    Sqlite(&'x rusqlite::Row<'x>),
+    /// A row of a query result that was remembered, see [crate::Transaction::memoize].
+    Cached(&'x CachedRows, usize),
 }
 
 impl<'t, 'a> Row<'_, 't, 'a> {
     pub fn get<T: MyTyp>(&self, val: Cached<'t, T>) -> T::Out<'a> {
         let idx = &*val.field.to_string();
-        self.row.get_unwrap(idx)
+        match self.row {
+            RowSource::Sqlite(row) => row.get_unwrap(idx),
+            RowSource::Cached(rows, i) => rows.get(i, idx),
+        }
     }
 }
 
@@ -69,6 +80,27 @@ pub trait Dummy<'t, 'a, S>: Sized {
     ///
     /// This is useful when retrieving a struct from the database that contains types not supported by the database.
     /// It is also useful in migrations to process rows using arbitrary rust.
+    ///
+    /// The closure is called for every row while the result is retrieved, so no second pass over the [Vec] is needed.
+    /// ```
+    /// # use std::time::Duration;
+    /// # use rust_query::{migration::{schema, Config}, Dummy, LocalClient, Table};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     Track { name: String, milliseconds: i64 },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// # let txn = client.transaction(&db);
+    /// # txn.query(|rows| {
+    /// let track = Track::join(rows);
+    /// let duration = track
+    ///     .milliseconds()
+    ///     .map_dummy(|x| Duration::from_millis(x as u64));
+    /// rows.into_vec((track.name(), duration))
+    /// # });
+    /// ```
     fn map_dummy<T>(self, f: impl FnMut(Self::Out) -> T + 't) -> impl Dummy<'t, 'a, S, Out = T> {
         DummyMap(self, f)
     }
@@ -104,13 +136,49 @@ impl<'t, 'a, S, T: IntoColumn<'t, S, Typ: MyTyp>> Dummy<'t, 'a, S> for T {
     }
 }
 
-impl<'t, 'a, S, A: Dummy<'t, 'a, S>, B: Dummy<'t, 'a, S>> Dummy<'t, 'a, S> for (A, B) {
-    type Out = (A::Out, B::Out);
+macro_rules! impl_dummy_tuple {
+    ($($t:ident $i:tt),+) => {
+        impl<'t, 'a, S, $($t: Dummy<'t, 'a, S>),+> Dummy<'t, 'a, S> for ($($t,)+) {
+            type Out = ($($t::Out,)+);
+
+            #[allow(non_snake_case)]
+            fn prepare(self, cacher: Cacher<'_, 't, S>) -> impl FnMut(Row<'_, 't, 'a>) -> Self::Out + 't {
+                let ($(mut $t,)+) = ($(self.$i.prepare(cacher),)+);
+                move |row| ($($t(row),)+)
+            }
+        }
+    };
+}
+
+impl_dummy_tuple!(A 0, B 1);
+impl_dummy_tuple!(A 0, B 1, C 2);
+impl_dummy_tuple!(A 0, B 1, C 2, D 3);
+impl_dummy_tuple!(A 0, B 1, C 2, D 3, E 4);
+impl_dummy_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_dummy_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_dummy_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+impl_dummy_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+impl_dummy_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+impl_dummy_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+impl_dummy_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
+
+/// Dummy that is only retrieved if the column is not `NULL`, see [crate::Column::optional].
+pub(crate) struct OptionalDummy<'t, S, D> {
+    pub(crate) is_some: crate::Column<'t, S, bool>,
+    pub(crate) dummy: D,
+}
+
+impl<'t, 'a, S, D: Dummy<'t, 'a, S>> Dummy<'t, 'a, S> for OptionalDummy<'t, S, D> {
+    type Out = Option<D::Out>;
 
-    fn prepare(self, cacher: Cacher<'_, 't, S>) -> impl FnMut(Row<'_, 't, 'a>) -> Self::Out + 't {
-        let mut prepared_a = self.0.prepare(cacher);
-        let mut prepared_b = self.1.prepare(cacher);
-        move |row| (prepared_a(row), prepared_b(row))
+    fn prepare(
+        self,
+        mut cacher: Cacher<'_, 't, S>,
+    ) -> impl FnMut(Row<'_, 't, 'a>) -> Self::Out + 't {
+        let is_some = cacher.cache(self.is_some);
+        let mut prepared = self.dummy.prepare(cacher);
+        // the columns of the dummy can be `NULL`, so it is not retrieved if the column is `NULL`
+        move |row| row.get(is_some).then(|| prepared(row))
     }
 }
 
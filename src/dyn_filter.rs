@@ -0,0 +1,259 @@
+use std::{fmt, str::FromStr};
+
+use sea_query::{Alias, Expr, SimpleExpr};
+
+use crate::{
+    alias::MyAlias,
+    hash::{ColumnType, TypBuilder},
+    ImportValue, IntoColumn, Rows, Table,
+};
+
+/// The comparison of a condition in a [DynFilter].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DynOp {
+    /// The column is equal to the value, or `NULL` if the value is [ImportValue::Null].
+    Eq,
+    /// The column is not equal to the value, or not `NULL` if the value is [ImportValue::Null].
+    Ne,
+    /// The column is less than the value.
+    Lt,
+    /// The column is less than or equal to the value.
+    Le,
+    /// The column is greater than the value.
+    Gt,
+    /// The column is greater than or equal to the value.
+    Ge,
+    /// The column matches a sql `LIKE` pattern, this only works for text columns.
+    Like,
+}
+
+/// Parse the short names that are often used in urls: `eq`, `ne`, `lt`, `le`, `gt`, `ge` and `like`.
+impl FromStr for DynOp {
+    type Err = DynFilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "eq" => DynOp::Eq,
+            "ne" => DynOp::Ne,
+            "lt" => DynOp::Lt,
+            "le" => DynOp::Le,
+            "gt" => DynOp::Gt,
+            "ge" => DynOp::Ge,
+            "like" => DynOp::Like,
+            _ => return Err(DynFilterError::UnknownOperator(s.to_owned())),
+        })
+    }
+}
+
+/// Error returned by [Rows::filter_dyn] and [Rows::order_by_dynamic] when the filter or column does not match the table.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynFilterError {
+    /// The table does not have a column with this name.
+    UnknownColumn(String),
+    /// The operator is not one of the names that are parsed by [DynOp].
+    UnknownOperator(String),
+    /// The value can not be compared with the column using the operator.
+    TypeMismatch {
+        column: String,
+        op: DynOp,
+        value: ImportValue,
+    },
+}
+
+impl fmt::Display for DynFilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DynFilterError::UnknownColumn(col) => write!(f, "unknown column `{col}`"),
+            DynFilterError::UnknownOperator(op) => write!(f, "unknown operator `{op}`"),
+            DynFilterError::TypeMismatch { column, op, value } => {
+                write!(
+                    f,
+                    "column `{column}` can not be compared ({op:?}) with `{value}`"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for DynFilterError {}
+
+/// Conditions on the columns of a table that are only known at runtime, for example from the query of a url.
+///
+/// The conditions are checked against the columns of the table by [Rows::filter_dyn]
+/// and all of them need to be true for a row to be included.
+/// ```
+/// # use rust_query::{migration::{schema, Config}, DynFilter, DynFilterError, DynOp, LocalClient, Order, Table};
+/// # #[schema]
+/// # enum Schema {
+/// #     User { name: String, age: i64 },
+/// # }
+/// # use v0::*;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let mut client = LocalClient::try_new().unwrap();
+/// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+/// # let txn = client.transaction(&db);
+/// // for example from `?name=like:a%&age=gt:18`
+/// let filter = DynFilter::new()
+///     .and("name", "like".parse()?, "a%")
+///     .and("age", DynOp::Gt, 18);
+/// let users = txn.query(|rows| {
+///     let user = User::join(rows);
+///     rows.filter_dyn(&user, &filter)?;
+///     Ok::<_, DynFilterError>(rows.into_vec(user.name()))
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DynFilter {
+    conds: Vec<(String, DynOp, ImportValue)>,
+}
+
+impl DynFilter {
+    /// Create a filter without conditions, which includes all rows.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a condition that compares `column` with `value`.
+    pub fn and(
+        mut self,
+        column: impl Into<String>,
+        op: DynOp,
+        value: impl Into<ImportValue>,
+    ) -> Self {
+        self.conds.push((column.into(), op, value.into()));
+        self
+    }
+
+    /// Build the condition for each column of table `T`.
+    fn build<T: Table>(&self, table: MyAlias) -> Result<Vec<SimpleExpr>, DynFilterError> {
+        let mut b = TypBuilder::default();
+        T::typs(&mut b);
+
+        self.conds
+            .iter()
+            .map(|(column, op, value)| {
+                let (typ, nullable) = column_type::<T>(&b, column)?;
+                let col = Expr::col((table, Alias::new(column)));
+                let mismatch = || DynFilterError::TypeMismatch {
+                    column: column.clone(),
+                    op: *op,
+                    value: value.clone(),
+                };
+
+                let val: sea_query::Value = match (typ, value) {
+                    (_, ImportValue::Null) => {
+                        return match op {
+                            DynOp::Eq if nullable => Ok(col.is_null()),
+                            DynOp::Ne if nullable => Ok(col.is_not_null()),
+                            _ => Err(mismatch()),
+                        };
+                    }
+                    (ColumnType::Integer, ImportValue::Integer(x)) => (*x).into(),
+                    (ColumnType::Float, ImportValue::Integer(x)) => (*x as f64).into(),
+                    (ColumnType::Float, ImportValue::Float(x)) => (*x).into(),
+                    (ColumnType::String, ImportValue::Text(x)) => x.clone().into(),
+                    _ => return Err(mismatch()),
+                };
+                Ok(match op {
+                    DynOp::Eq => col.eq(val),
+                    DynOp::Ne => col.ne(val),
+                    DynOp::Lt => col.lt(val),
+                    DynOp::Le => col.lte(val),
+                    DynOp::Gt => col.gt(val),
+                    DynOp::Ge => col.gte(val),
+                    DynOp::Like => match val {
+                        sea_query::Value::String(Some(pattern)) => col.like(*pattern),
+                        _ => return Err(mismatch()),
+                    },
+                })
+            })
+            .collect()
+    }
+}
+
+/// The type of a column of table `T` and whether it is nullable.
+fn column_type<T: Table>(
+    b: &TypBuilder,
+    column: &str,
+) -> Result<(ColumnType, bool), DynFilterError> {
+    if column == T::ID {
+        return Ok((ColumnType::Integer, false));
+    }
+    let col = b.ast.columns.iter().find(|col| col.name == column);
+    let col = col.ok_or_else(|| DynFilterError::UnknownColumn(column.to_owned()))?;
+    Ok((col.typ, col.nullable))
+}
+
+/// The direction of sorting for [Rows::order_by_dynamic].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    /// Smallest values first.
+    Asc,
+    /// Largest values first.
+    Desc,
+}
+
+impl<'inner, S> Rows<'inner, S> {
+    /// Filter the rows of table `T` with the conditions of a [DynFilter].
+    ///
+    /// Returns an error if a column does not exist in `T` or if a value has the wrong type for its column.
+    /// No conditions are added when there is an error.
+    pub fn filter_dyn<T: Table<Schema = S>>(
+        &mut self,
+        row: impl IntoColumn<'inner, S, Typ = T>,
+        filter: &DynFilter,
+    ) -> Result<(), DynFilterError> {
+        let (table, deps) = self.ast.builder().track(|b| row.build_table(b));
+        let conds = filter.build::<T>(table)?;
+        for cond in conds {
+            self.ast.filters.push(Box::new((cond, deps.clone())));
+        }
+        Ok(())
+    }
+
+    /// Sort the results by a column of table `T` that is chosen at runtime.
+    ///
+    /// The results are sorted by the columns in the order that this method is called,
+    /// and then by the values that are retrieved like usual.
+    /// Returns an error if the column does not exist in `T`.
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, DynFilter, DynFilterError, DynOp, LocalClient, Order, Table};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     User { name: String, age: i64 },
+    /// # }
+    /// # use v0::*;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// # let txn = client.transaction(&db);
+    /// let users = txn.query(|rows| {
+    ///     let user = User::join(rows);
+    ///     rows.order_by_dynamic(&user, "age", Order::Desc)?;
+    ///     Ok::<_, DynFilterError>(rows.into_vec(user.name()))
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn order_by_dynamic<T: Table<Schema = S>>(
+        &mut self,
+        row: impl IntoColumn<'inner, S, Typ = T>,
+        column: &str,
+        order: Order,
+    ) -> Result<(), DynFilterError> {
+        let mut b = TypBuilder::default();
+        T::typs(&mut b);
+        column_type::<T>(&b, column)?;
+
+        let table = row.build_table(self.ast.builder());
+        let order = match order {
+            Order::Asc => sea_query::Order::Asc,
+            Order::Desc => sea_query::Order::Desc,
+        };
+        let expr = Expr::col((table, Alias::new(column))).into();
+        self.ast.order_by.push((expr, order));
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use std::fmt;
+
+use rusqlite::ErrorCode;
+
+/// Error returned by the fallible methods when sqlite fails or the database file does not belong to the schema,
+/// like [crate::LocalClient::try_migrator].
+///
+/// The methods without `try_` panic with this error instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The database stayed locked by another connection, see [crate::migration::Config::busy_timeout].
+    Busy,
+    /// The disk or the database is full.
+    Full,
+    /// The database file is corrupt or it is not a database.
+    Corrupt,
+    /// The database file could not be opened.
+    CantOpen,
+    /// Reading from or writing to the database file failed.
+    Io,
+    /// The `application_id` of the database file is not the one of the schema, so the file belongs to another application.
+    ApplicationId { expected: i32, found: i32 },
+    /// The database file has the version of the schema, but it was created with a different schema for that version.
+    SchemaHash {
+        version: i64,
+        expected: String,
+        found: String,
+    },
+    /// The database was opened with [crate::migration::Config::open_read_only], so it can not be migrated.
+    ReadOnly,
+    /// A row of `table` references a row of `parent` that does not exist.
+    ForeignKeyViolation { table: String, parent: String },
+    /// The tables in the database file are not the tables of the schema, this contains the difference.
+    SchemaMismatch(String),
+    /// A column of the schema uses a collation that is not registered, see [crate::migration::Config::collation].
+    MissingCollation {
+        table: String,
+        column: String,
+        collation: String,
+    },
+    /// Any other error, this contains the message from sqlite.
+    Sqlite(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Busy => write!(f, "the database is locked"),
+            Error::Full => write!(f, "the database or disk is full"),
+            Error::Corrupt => write!(f, "the database file is corrupt"),
+            Error::CantOpen => write!(f, "unable to open the database file"),
+            Error::Io => write!(f, "disk I/O error"),
+            Error::ApplicationId { expected, found } => write!(
+                f,
+                "the database file belongs to another application, its application_id is {found} instead of {expected}"
+            ),
+            Error::SchemaHash {
+                version,
+                expected,
+                found,
+            } => write!(
+                f,
+                "the schema hash of version {version} in the database file is {found:?}, but {expected:?} was expected"
+            ),
+            Error::ReadOnly => write!(
+                f,
+                "a database opened in read-only mode can not be migrated"
+            ),
+            Error::ForeignKeyViolation { table, parent } => write!(
+                f,
+                "a row of `{table}` references a row of `{parent}` that does not exist"
+            ),
+            Error::SchemaMismatch(diff) => {
+                write!(f, "schema is different (expected left, but got right):\n{diff}")
+            }
+            Error::MissingCollation {
+                table,
+                column,
+                collation,
+            } => write!(
+                f,
+                "collation {collation:?} of column {table}.{column} is not registered, use `Config::collation` to register it"
+            ),
+            Error::Sqlite(msg) => write!(f, "sql error: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<rusqlite::Error> for Error {
+    fn from(value: rusqlite::Error) -> Self {
+        match value.sqlite_error_code() {
+            Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) => Error::Busy,
+            Some(ErrorCode::DiskFull) => Error::Full,
+            Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase) => Error::Corrupt,
+            Some(ErrorCode::CannotOpen) => Error::CantOpen,
+            Some(ErrorCode::SystemIoFailure) => Error::Io,
+            _ => Error::Sqlite(value.to_string()),
+        }
+    }
+}
+
+/// Unwrap the result of a sqlite call, this is used by the methods without `try_`.
+///
+/// Errors cause a panic with the message of the [Error].
+#[track_caller]
+pub(crate) fn unwrap_db<T>(res: Result<T, impl Into<Error>>) -> T {
+    match res {
+        Ok(val) => val,
+        Err(err) => raise(err.into()),
+    }
+}
+
+/// Panic with the message of `err`.
+#[track_caller]
+pub(crate) fn raise(err: Error) -> ! {
+    panic!("{err}")
+}
@@ -1,15 +1,27 @@
 use std::{
-    cell::Cell,
+    convert::Infallible,
     marker::PhantomData,
     ops::{Deref, DerefMut},
 };
 
-use sea_query::SqliteQueryBuilder;
+use rusqlite::{
+    types::{FromSql, ToSqlOutput, Value},
+    ToSql,
+};
+use sea_query::{
+    Asterisk, Expr, Func, NullAlias, Order, SelectStatement, SimpleExpr, SqliteQueryBuilder,
+};
 use sea_query_rusqlite::RusqliteBinder;
 
+use elsa::FrozenVec;
+
 use crate::{
-    dummy::{Cacher, Dummy, Row},
+    dummy::{Cacher, Dummy, Row, RowSource},
+    group::Group,
+    interrupt::{check_rows, unwrap_interrupt},
+    memo, metrics,
     rows::Rows,
+    trace::{record_steps, traced},
 };
 
 /// This is the top level query type and dereferences to [Rows].
@@ -38,56 +50,281 @@ impl<'outer, 'inner, S> Query<'outer, 'inner, S> {
     /// Turn a database query into a rust [Vec] of results.
     ///
     /// Types that implement [crate::IntoColumn], will also implement [Dummy].
-    /// Tuples of two values also implement [Dummy]. If you want to return more
-    /// than two values, then you should use a struct that derives [crate::FromDummy].
+    /// Tuples of up to twelve values also implement [Dummy]. If you want to return more
+    /// values or give them names, then you should use a struct that derives [crate::FromDummy].
+    /// Values that depend on an optional column can be retrieved with [crate::Column::optional].
+    ///
+    /// The results are sorted by the values of the dummy, after any order from [crate::Rows::order_by_dynamic].
     pub fn into_vec<D>(&'inner self, dummy: D) -> Vec<D::Out>
     where
         D: Dummy<'inner, 'outer, S>,
     {
-        self.into_vec_private(dummy)
+        self.into_vec_private(dummy, |_| {})
     }
 
-    pub(crate) fn into_vec_private<'x, D>(&'inner self, dummy: D) -> Vec<D::Out>
+    /// Retrieve the first result of the query, or [None] if there are no rows.
+    ///
+    /// Just like [Query::into_vec], the results are sorted by the values of the dummy.
+    /// The query is executed with `LIMIT 1`, so no other rows are retrieved.
+    pub fn first<D>(&'inner self, dummy: D) -> Option<D::Out>
     where
-        D: Dummy<'x, 'outer, S>,
-        S: 'x,
+        D: Dummy<'inner, 'outer, S>,
     {
-        let mut f = dummy.prepare(Cacher {
+        self.into_vec_private(dummy, |select| {
+            select.limit(1);
+        })
+        .pop()
+    }
+
+    /// Retrieve at most `n` random results, for example to check the quality of some data.
+    ///
+    /// The results are in random order and they are different every time.
+    /// Use [Query::sample_seeded] to get the same results every time.
+    pub fn sample<D>(&'inner self, n: usize, dummy: D) -> Vec<D::Out>
+    where
+        D: Dummy<'inner, 'outer, S>,
+    {
+        self.into_vec_private(dummy, |select| {
+            select.clear_order_by();
+            select.order_by_expr(Func::random().into(), Order::Asc);
+            select.limit(n as u64);
+        })
+    }
+
+    /// Retrieve at most `n` results like [Query::sample], but the results only depend on the `seed`.
+    ///
+    /// The rows are shuffled based on their ids, so the same rows are retrieved as long as they do not change.
+    pub fn sample_seeded<D>(&'inner self, n: usize, seed: i64, dummy: D) -> Vec<D::Out>
+    where
+        D: Dummy<'inner, 'outer, S>,
+    {
+        // a hash of the row keys, all steps are modulo a prime that fits in 31 bits
+        // to make sure that the multiplications can not overflow
+        const PRIME: i64 = 2147483647;
+        let mut hash: SimpleExpr = Expr::val(seed.rem_euclid(PRIME)).into();
+        for key in self.ast.keys() {
+            hash = Expr::expr(hash.add(key).mul(48271)).modulo(PRIME);
+        }
+        // squaring makes the order of consecutive ids less regular
+        let hash = Expr::expr(hash.clone().mul(hash)).modulo(PRIME);
+        self.into_vec_private(dummy, |select| {
+            let mut orders = vec![hash];
+            orders.extend(self.ast.select.iter().map(|(expr, _)| expr.clone()));
+            select.clear_order_by();
+            for order in orders {
+                select.order_by_expr(order, Order::Asc);
+            }
+            select.limit(n as u64);
+        })
+    }
+
+    /// Retrieve the only result of the query.
+    ///
+    /// Returns an [Err] if there are no rows or if there is more than one row.
+    pub fn one<D>(&'inner self, dummy: D) -> Result<D::Out, OneError>
+    where
+        D: Dummy<'inner, 'outer, S>,
+    {
+        let mut res = self.into_vec_private(dummy, |select| {
+            select.limit(2);
+        });
+        match res.len() {
+            0 => Err(OneError::NoRows),
+            1 => Ok(res.pop().unwrap()),
+            _ => Err(OneError::MultipleRows),
+        }
+    }
+
+    /// Get the sql and the parameters that [Query::into_vec] would execute, without executing it.
+    ///
+    /// This is useful to log queries or to check the generated sql in tests.
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient, Table};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     User { name: String },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// # let txn = client.transaction(&db);
+    /// let (sql, params) = txn.query(|rows| {
+    ///     let user = User::join(rows);
+    ///     rows.filter(user.name().eq("alice"));
+    ///     rows.to_sql(user.name())
+    /// });
+    /// assert!(sql.starts_with("SELECT"));
+    /// assert_eq!(params, ["alice".to_owned().into()]);
+    /// ```
+    pub fn to_sql<D>(&'inner self, dummy: D) -> (String, Vec<Value>)
+    where
+        D: Dummy<'inner, 'outer, S>,
+    {
+        // preparing the dummy adds its values to the select
+        let _ = dummy.prepare(Cacher {
             _p: PhantomData,
             ast: &self.ast,
         });
+        let (sql, values) = self.ast.simple().build_rusqlite(SqliteQueryBuilder);
+        let values = values.0.iter().map(|value| match value.to_sql().unwrap() {
+            ToSqlOutput::Borrowed(value) => value.into(),
+            ToSqlOutput::Owned(value) => value,
+            _ => unreachable!("values from sea-query are borrowed or owned"),
+        });
+        (sql, values.collect())
+    }
 
-        let select = self.ast.simple();
+    /// Count the number of rows in the query.
+    ///
+    /// This is executed as `COUNT(*)` and does not retrieve the rows themselves.
+    pub fn count(&self) -> i64 {
+        let mut select = SelectStatement::new();
+        select.expr(Expr::col(Asterisk).count());
+        select.from_subquery(self.ast.build_select(false), NullAlias);
+        self.query_row(select)
+    }
+
+    /// Check if there are any rows in the query.
+    ///
+    /// This is executed as `EXISTS` and does not retrieve the rows themselves.
+    pub fn exists(&self) -> bool {
+        let mut select = SelectStatement::new();
+        select.expr(Expr::exists(self.ast.build_select(false)));
+        self.query_row(select)
+    }
+
+    fn query_row<T: FromSql>(&self, select: SelectStatement) -> T {
         let (sql, values) = select.build_rusqlite(SqliteQueryBuilder);
-        if SHOW_SQL.get() {
-            println!("{sql}");
-            println!("{values:?}");
+        if let Some(cache) = memo::current() {
+            return cache.rows(self.conn, &sql, &values).first();
         }
+        traced(&sql, &values.0, || {
+            let mut statement = self.conn.prepare_cached(&sql).unwrap();
+            let res = unwrap_interrupt(statement.query_row(&*values.as_params(), |row| row.get(0)));
+            record_steps(&statement);
+            res
+        })
+    }
 
-        let mut statement = self.conn.prepare_cached(&sql).unwrap();
-        let mut rows = statement.query(&*values.as_params()).unwrap();
+    /// Group the rows and retrieve one result for every group.
+    ///
+    /// The groups are defined by adding keys with [Group::key].
+    /// Aggregates like [Group::avg] are computed for each group separately,
+    /// all in a single `GROUP BY` statement.
+    ///
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient, Table};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     Genre { name: String },
+    /// #     Track { genre: Genre },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// # let txn = client.transaction(&db);
+    /// # txn.query(|rows| {
+    /// # let track = Track::join(rows);
+    /// let genre_stats = rows.group_by(|group| {
+    ///     let genre = group.key(track.genre());
+    ///     group.into_vec((genre.name(), group.count()))
+    /// });
+    /// # });
+    /// ```
+    pub fn group_by<F, R>(&'inner self, f: F) -> R
+    where
+        F: for<'g> FnOnce(&'g Group<'outer, 'inner, 'g, S>) -> R,
+    {
+        let group = Group {
+            query: self,
+            keys: FrozenVec::new(),
+            filters: FrozenVec::new(),
+            _p: PhantomData,
+        };
+        f(&group)
+    }
 
+    pub(crate) fn into_vec_private<'x, D>(
+        &'inner self,
+        dummy: D,
+        finish: impl FnOnce(&mut SelectStatement),
+    ) -> Vec<D::Out>
+    where
+        D: Dummy<'x, 'outer, S>,
+        S: 'x,
+    {
         let mut out = vec![];
-        while let Some(row) = rows.next().unwrap() {
-            let row = Row {
-                _p: PhantomData,
-                _p2: PhantomData,
-                row,
-            };
-            out.push(f(row));
-        }
+        let res = self.for_each_private(dummy, finish, |row| {
+            out.push(row);
+            Ok::<_, Infallible>(())
+        });
+        let Ok(()) = res;
         out
     }
-}
 
-thread_local! {
-    static SHOW_SQL: Cell<bool> = const { Cell::new(false) };
+    /// Call `g` for every row of the query, this stops at the first error.
+    pub(crate) fn for_each_private<'x, D, E>(
+        &'inner self,
+        dummy: D,
+        finish: impl FnOnce(&mut SelectStatement),
+        mut g: impl FnMut(D::Out) -> Result<(), E>,
+    ) -> Result<(), E>
+    where
+        D: Dummy<'x, 'outer, S>,
+        S: 'x,
+    {
+        let mut f = dummy.prepare(Cacher {
+            _p: PhantomData,
+            ast: &self.ast,
+        });
+
+        let mut select = self.ast.simple();
+        finish(&mut select);
+        let (sql, values) = select.build_rusqlite(SqliteQueryBuilder);
+
+        if let Some(cache) = memo::current() {
+            let rows = cache.rows(self.conn, &sql, &values);
+            for i in 0..rows.rows.len() {
+                let row = Row {
+                    _p: PhantomData,
+                    _p2: PhantomData,
+                    row: RowSource::Cached(&rows, i),
+                };
+                g(f(row))?;
+            }
+            return Ok(());
+        }
+
+        traced(&sql, &values.0, || {
+            let mut statement = self.conn.prepare_cached(&sql).unwrap();
+            let res = (|| {
+                let mut rows = unwrap_interrupt(statement.query(&*values.as_params()));
+
+                let mut count = 0;
+                while let Some(row) = unwrap_interrupt(rows.next()) {
+                    let row = Row {
+                        _p: PhantomData,
+                        _p2: PhantomData,
+                        row: RowSource::Sqlite(row),
+                    };
+                    g(f(row))?;
+                    count += 1;
+                    check_rows(count);
+                }
+                metrics::rows_returned(count);
+                Ok(())
+            })();
+            record_steps(&statement);
+            res
+        })
+    }
 }
 
-pub fn show_sql<R>(f: impl FnOnce() -> R) -> R {
-    let old = SHOW_SQL.get();
-    SHOW_SQL.set(true);
-    let res = f();
-    SHOW_SQL.set(old);
-    res
+/// Error returned by [Query::one] if the number of rows is not exactly one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OneError {
+    /// The query returned no rows.
+    NoRows,
+    /// The query returned more than one row.
+    MultipleRows,
 }
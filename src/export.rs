@@ -0,0 +1,482 @@
+use std::{fmt, io};
+
+use serde::{
+    ser::{Impossible, SerializeStruct, SerializeTuple, SerializeTupleStruct},
+    Serialize, Serializer,
+};
+
+use crate::{dummy::Dummy, exec::Query};
+
+impl<'outer, 'inner, S> Query<'outer, 'inner, S> {
+    /// Write the results of the query to `writer` as CSV, without collecting them in a [Vec].
+    ///
+    /// The output type of the dummy needs to implement [Serialize], for example by deriving
+    /// both [crate::FromDummy] and [Serialize] on a struct.
+    /// If the output is a struct, the first line contains the names of its fields.
+    /// Nothing is written if there are no rows.
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, FromDummy, LocalClient, Table};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     User { name: String, score: i64 },
+    /// # }
+    /// # use v0::*;
+    /// # #[derive(FromDummy, serde::Serialize)]
+    /// # struct UserInfo {
+    /// #     name: String,
+    /// #     score: i64,
+    /// # }
+    /// # fn main() -> std::io::Result<()> {
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// # let txn = client.transaction(&db);
+    /// # let mut file = vec![];
+    /// # txn.query(|rows| {
+    /// # let user = User::join(rows);
+    /// rows.into_csv(UserInfoDummy { name: user.name(), score: user.score() }, &mut file)?;
+    /// # Ok(())
+    /// # })
+    /// # }
+    /// ```
+    pub fn into_csv<D>(&'inner self, dummy: D, mut writer: impl io::Write) -> io::Result<()>
+    where
+        D: Dummy<'inner, 'outer, S>,
+        D::Out: Serialize,
+    {
+        let mut first = true;
+        self.for_each_private(
+            dummy,
+            |_| {},
+            |row| {
+                let record = row.serialize(RecordSerializer)?;
+                if let (true, Record::Struct(fields)) = (first, &record) {
+                    let names = fields.iter().map(|(name, _)| csv_field(name));
+                    writeln!(writer, "{}", names.collect::<Vec<_>>().join(","))?;
+                }
+                first = false;
+                let cells = record.cells().map(|cell| match cell {
+                    Cell::Null => String::new(),
+                    Cell::Str(val) => csv_field(val),
+                    cell => cell.to_string(),
+                });
+                writeln!(writer, "{}", cells.collect::<Vec<_>>().join(","))
+            },
+        )
+    }
+
+    /// Write the results of the query to `writer` as JSON lines, without collecting them in a [Vec].
+    ///
+    /// Every row is written as a JSON value on its own line.
+    /// Structs become objects with the names of their fields and tuples become arrays.
+    /// Just like [Query::into_csv], the output type of the dummy needs to implement [Serialize].
+    pub fn into_json_lines<D>(&'inner self, dummy: D, mut writer: impl io::Write) -> io::Result<()>
+    where
+        D: Dummy<'inner, 'outer, S>,
+        D::Out: Serialize,
+    {
+        self.for_each_private(
+            dummy,
+            |_| {},
+            |row| {
+                let line = match row.serialize(RecordSerializer)? {
+                    Record::Struct(fields) => {
+                        let fields = fields
+                            .iter()
+                            .map(|(name, cell)| format!("{}:{}", json_str(name), cell.json()));
+                        format!("{{{}}}", fields.collect::<Vec<_>>().join(","))
+                    }
+                    Record::Tuple(cells) => {
+                        let cells = cells.iter().map(Cell::json);
+                        format!("[{}]", cells.collect::<Vec<_>>().join(","))
+                    }
+                    Record::Scalar(cell) => cell.json(),
+                };
+                writeln!(writer, "{line}")
+            },
+        )
+    }
+}
+
+fn csv_field(val: &str) -> String {
+    if val.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", val.replace('"', "\"\""))
+    } else {
+        val.to_owned()
+    }
+}
+
+fn json_str(val: &str) -> String {
+    let mut out = String::from('"');
+    for c in val.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// The serialized output of a single row.
+enum Record {
+    Struct(Vec<(&'static str, Cell)>),
+    Tuple(Vec<Cell>),
+    Scalar(Cell),
+}
+
+impl Record {
+    fn cells(&self) -> Box<dyn Iterator<Item = &Cell> + '_> {
+        match self {
+            Record::Struct(fields) => Box::new(fields.iter().map(|(_, cell)| cell)),
+            Record::Tuple(cells) => Box::new(cells.iter()),
+            Record::Scalar(cell) => Box::new(std::iter::once(cell)),
+        }
+    }
+}
+
+enum Cell {
+    Null,
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    Str(String),
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cell::Null => Ok(()),
+            Cell::Bool(val) => write!(f, "{val}"),
+            Cell::Int(val) => write!(f, "{val}"),
+            Cell::Float(val) => write!(f, "{val:?}"),
+            Cell::Str(val) => write!(f, "{val}"),
+        }
+    }
+}
+
+impl Cell {
+    fn json(&self) -> String {
+        match self {
+            Cell::Null => "null".to_owned(),
+            Cell::Float(val) if !val.is_finite() => "null".to_owned(),
+            Cell::Str(val) => json_str(val),
+            cell => cell.to_string(),
+        }
+    }
+}
+
+/// Error for values that can not be exported, like nested structs.
+#[derive(Debug)]
+struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl serde::ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(value: Error) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, value)
+    }
+}
+
+fn unsupported<T>(what: &str) -> Result<T, Error> {
+    Err(Error(format!("{what} can not be exported")))
+}
+
+/// Serializes a single value of a row.
+struct CellSerializer;
+
+impl Serializer for CellSerializer {
+    type Ok = Cell;
+    type Error = Error;
+    type SerializeSeq = Impossible<Cell, Error>;
+    type SerializeTuple = Impossible<Cell, Error>;
+    type SerializeTupleStruct = Impossible<Cell, Error>;
+    type SerializeTupleVariant = Impossible<Cell, Error>;
+    type SerializeMap = Impossible<Cell, Error>;
+    type SerializeStruct = Impossible<Cell, Error>;
+    type SerializeStructVariant = Impossible<Cell, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<Cell, Error> {
+        Ok(Cell::Bool(v))
+    }
+    fn serialize_i8(self, v: i8) -> Result<Cell, Error> {
+        Ok(Cell::Int(v.into()))
+    }
+    fn serialize_i16(self, v: i16) -> Result<Cell, Error> {
+        Ok(Cell::Int(v.into()))
+    }
+    fn serialize_i32(self, v: i32) -> Result<Cell, Error> {
+        Ok(Cell::Int(v.into()))
+    }
+    fn serialize_i64(self, v: i64) -> Result<Cell, Error> {
+        Ok(Cell::Int(v.into()))
+    }
+    fn serialize_u8(self, v: u8) -> Result<Cell, Error> {
+        Ok(Cell::Int(v.into()))
+    }
+    fn serialize_u16(self, v: u16) -> Result<Cell, Error> {
+        Ok(Cell::Int(v.into()))
+    }
+    fn serialize_u32(self, v: u32) -> Result<Cell, Error> {
+        Ok(Cell::Int(v.into()))
+    }
+    fn serialize_u64(self, v: u64) -> Result<Cell, Error> {
+        Ok(Cell::Int(v.into()))
+    }
+    fn serialize_f32(self, v: f32) -> Result<Cell, Error> {
+        Ok(Cell::Float(v.into()))
+    }
+    fn serialize_f64(self, v: f64) -> Result<Cell, Error> {
+        Ok(Cell::Float(v))
+    }
+    fn serialize_char(self, v: char) -> Result<Cell, Error> {
+        Ok(Cell::Str(v.to_string()))
+    }
+    fn serialize_str(self, v: &str) -> Result<Cell, Error> {
+        Ok(Cell::Str(v.to_owned()))
+    }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Cell, Error> {
+        unsupported("bytes")
+    }
+    fn serialize_none(self) -> Result<Cell, Error> {
+        Ok(Cell::Null)
+    }
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Cell, Error> {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<Cell, Error> {
+        Ok(Cell::Null)
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Cell, Error> {
+        Ok(Cell::Null)
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Cell, Error> {
+        Ok(Cell::Str(variant.to_owned()))
+    }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Cell, Error> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Cell, Error> {
+        unsupported("enum variants with values")
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        unsupported("sequences")
+    }
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        unsupported("nested tuples")
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        unsupported("nested tuple structs")
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        unsupported("enum variants with values")
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        unsupported("maps")
+    }
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        unsupported("nested structs")
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        unsupported("enum variants with values")
+    }
+}
+
+/// Serializes a row, which is a struct, a tuple or a single value.
+struct RecordSerializer;
+
+/// Collects the fields of a struct or tuple.
+struct Fields(Vec<(&'static str, Cell)>);
+
+impl SerializeStruct for Fields {
+    type Ok = Record;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.0.push((key, value.serialize(CellSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Record, Error> {
+        Ok(Record::Struct(self.0))
+    }
+}
+
+impl SerializeTuple for Fields {
+    type Ok = Record;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.0.push(("", value.serialize(CellSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Record, Error> {
+        Ok(Record::Tuple(
+            self.0.into_iter().map(|(_, cell)| cell).collect(),
+        ))
+    }
+}
+
+impl SerializeTupleStruct for Fields {
+    type Ok = Record;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        SerializeTuple::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Record, Error> {
+        SerializeTuple::end(self)
+    }
+}
+
+/// Forward scalar values to [CellSerializer].
+macro_rules! scalar {
+    ($($name:ident($($arg:ident: $typ:ty),*);)*) => {
+        $(fn $name(self, $($arg: $typ),*) -> Result<Record, Error> {
+            CellSerializer.$name($($arg),*).map(Record::Scalar)
+        })*
+    };
+}
+
+impl Serializer for RecordSerializer {
+    type Ok = Record;
+    type Error = Error;
+    type SerializeSeq = Impossible<Record, Error>;
+    type SerializeTuple = Fields;
+    type SerializeTupleStruct = Fields;
+    type SerializeTupleVariant = Impossible<Record, Error>;
+    type SerializeMap = Impossible<Record, Error>;
+    type SerializeStruct = Fields;
+    type SerializeStructVariant = Impossible<Record, Error>;
+
+    scalar! {
+        serialize_bool(v: bool);
+        serialize_i8(v: i8);
+        serialize_i16(v: i16);
+        serialize_i32(v: i32);
+        serialize_i64(v: i64);
+        serialize_u8(v: u8);
+        serialize_u16(v: u16);
+        serialize_u32(v: u32);
+        serialize_u64(v: u64);
+        serialize_f32(v: f32);
+        serialize_f64(v: f64);
+        serialize_char(v: char);
+        serialize_str(v: &str);
+        serialize_bytes(v: &[u8]);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(name: &'static str);
+        serialize_unit_variant(name: &'static str, variant_index: u32, variant: &'static str);
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Record, Error> {
+        CellSerializer.serialize_some(value).map(Record::Scalar)
+    }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Record, Error> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Record, Error> {
+        unsupported("enum variants with values")
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        unsupported("sequences")
+    }
+    fn serialize_tuple(self, len: usize) -> Result<Fields, Error> {
+        Ok(Fields(Vec::with_capacity(len)))
+    }
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Fields, Error> {
+        Ok(Fields(Vec::with_capacity(len)))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        unsupported("enum variants with values")
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        unsupported("maps")
+    }
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Fields, Error> {
+        Ok(Fields(Vec::with_capacity(len)))
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        unsupported("enum variants with values")
+    }
+}
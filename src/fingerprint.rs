@@ -0,0 +1,126 @@
+use rusqlite::OptionalExtension;
+
+use crate::{
+    hash::{schema_hash, HASH_FORMAT},
+    migrate::Schema,
+    Error,
+};
+
+/// Name of the table that stores the schema hash of every namespace.
+pub(crate) const SCHEMA_TABLE: &str = "_schema";
+
+/// Fail with [Error::ApplicationId] if the database file belongs to another application.
+///
+/// Files without `application_id` are accepted, they get one from [set_application_id].
+pub(crate) fn check_application_id<S: Schema>(conn: &rusqlite::Connection) -> Result<(), Error> {
+    let Some(expected) = S::APPLICATION_ID else {
+        return Ok(());
+    };
+    let found = application_id(conn)?;
+    if found != 0 && found != expected {
+        return Err(Error::ApplicationId { expected, found });
+    }
+    Ok(())
+}
+
+pub(crate) fn set_application_id<S: Schema>(conn: &rusqlite::Connection) -> Result<(), Error> {
+    if let Some(id) = S::APPLICATION_ID {
+        if id != application_id(conn)? {
+            conn.pragma_update(None, "application_id", id)?;
+        }
+    }
+    Ok(())
+}
+
+fn application_id(conn: &rusqlite::Connection) -> rusqlite::Result<i32> {
+    conn.query_row("PRAGMA application_id", [], |row| row.get(0))
+}
+
+// the schema without namespace uses the empty name
+fn name<S: Schema>() -> &'static str {
+    S::NAMESPACE.unwrap_or("")
+}
+
+/// The stored hash of schema `S` and the [HASH_FORMAT] that it was computed with.
+fn stored_hash<S: Schema>(conn: &rusqlite::Connection) -> rusqlite::Result<Option<(String, i64)>> {
+    let exists = conn
+        .query_row(
+            "SELECT 1 FROM sqlite_schema WHERE type = 'table' AND name = ?",
+            [SCHEMA_TABLE],
+            |_| Ok(()),
+        )
+        .optional()?;
+    if exists.is_none() {
+        return Ok(None);
+    }
+    // files of older versions do not have the format column, their hashes have format 0
+    let format = match has_format(conn)? {
+        true => "\"format\"",
+        false => "0",
+    };
+    let sql = format!("SELECT \"hash\", {format} FROM \"{SCHEMA_TABLE}\" WHERE \"name\" = ?");
+    conn.query_row(&sql, [name::<S>()], |r| Ok((r.get(0)?, r.get(1)?)))
+        .optional()
+}
+
+fn has_format(conn: &rusqlite::Connection) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT 1 FROM pragma_table_info(?) WHERE name = 'format'",
+        [SCHEMA_TABLE],
+        |_| Ok(()),
+    )
+    .optional()
+    .map(|x| x.is_some())
+}
+
+/// Fail with [Error::SchemaHash] if the database was created with a different schema `S`.
+///
+/// This is checked before the tables are compared, so that a file of another application
+/// that happens to use the same version gives a clear error.
+pub(crate) fn check_schema_hash<S: Schema>(conn: &rusqlite::Connection) -> Result<(), Error> {
+    let Some((found, format)) = stored_hash::<S>(conn)? else {
+        // files that were created before the hash was stored
+        return Ok(());
+    };
+    if format != HASH_FORMAT {
+        // the hash was computed differently, the tables are still compared with the schema
+        return Ok(());
+    }
+    let expected = schema_hash::<S>();
+    if found != expected {
+        return Err(Error::SchemaHash {
+            version: S::VERSION,
+            expected,
+            found,
+        });
+    }
+    Ok(())
+}
+
+/// Store the hash of schema `S`, this is done when the migration to schema `S` is finished.
+///
+/// A hash of an older [HASH_FORMAT] is replaced.
+pub(crate) fn set_schema_hash<S: Schema>(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS \"{SCHEMA_TABLE}\" (
+            \"name\" text PRIMARY KEY,
+            \"hash\" text NOT NULL,
+            \"format\" integer NOT NULL DEFAULT 0
+        );"
+    ))?;
+    if !has_format(conn)? {
+        conn.execute_batch(&format!(
+            "ALTER TABLE \"{SCHEMA_TABLE}\" ADD COLUMN \"format\" integer NOT NULL DEFAULT 0;"
+        ))?;
+    }
+    let sql = format!(
+        "INSERT INTO \"{SCHEMA_TABLE}\" (\"name\", \"hash\", \"format\") VALUES (?, ?, ?)
+        ON CONFLICT (\"name\") DO UPDATE SET \"hash\" = excluded.\"hash\", \"format\" = excluded.\"format\"
+        WHERE \"hash\" != excluded.\"hash\" OR \"format\" != excluded.\"format\""
+    );
+    conn.execute(
+        &sql,
+        rusqlite::params![name::<S>(), schema_hash::<S>(), HASH_FORMAT],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use rusqlite::Connection;
+use sea_query::{Alias, BinOper, Expr, Query, SimpleExpr, SubQueryStatement};
+
+use crate::{
+    value::{IntoColumn, Typed, ValueBuilder},
+    Column, Table,
+};
+
+/// Name of the full-text index for a table.
+pub(crate) fn fts_name(table: &str) -> String {
+    format!("{table}_fts")
+}
+
+/// Create the full-text index of a table and the triggers that keep it in sync.
+///
+/// The index is filled with all rows that are already in the table.
+pub(crate) fn create_fts(
+    conn: &Connection,
+    table: &str,
+    columns: &[String],
+) -> rusqlite::Result<()> {
+    let fts = fts_name(table);
+    let cols = columns.join(", ");
+    let new = columns
+        .iter()
+        .map(|c| format!("new.{c}"))
+        .collect::<Vec<_>>();
+    let old = columns
+        .iter()
+        .map(|c| format!("old.{c}"))
+        .collect::<Vec<_>>();
+    let (new, old) = (new.join(", "), old.join(", "));
+
+    conn.execute_batch(&format!(
+        "CREATE VIRTUAL TABLE {fts} USING fts5({cols}, content='{table}', content_rowid='id');
+        CREATE TRIGGER {fts}_ai AFTER INSERT ON {table} BEGIN
+            INSERT INTO {fts}(rowid, {cols}) VALUES (new.id, {new});
+        END;
+        CREATE TRIGGER {fts}_ad AFTER DELETE ON {table} BEGIN
+            INSERT INTO {fts}({fts}, rowid, {cols}) VALUES ('delete', old.id, {old});
+        END;
+        CREATE TRIGGER {fts}_au AFTER UPDATE ON {table} BEGIN
+            INSERT INTO {fts}({fts}, rowid, {cols}) VALUES ('delete', old.id, {old});
+            INSERT INTO {fts}(rowid, {cols}) VALUES (new.id, {new});
+        END;
+        INSERT INTO {fts}({fts}) VALUES ('rebuild');"
+    ))
+}
+
+/// Drop the full-text index of a table and its triggers if they exist.
+pub(crate) fn drop_fts(conn: &Connection, table: &str) -> rusqlite::Result<()> {
+    let fts = fts_name(table);
+    conn.execute_batch(&format!(
+        "DROP TRIGGER IF EXISTS {fts}_ai;
+        DROP TRIGGER IF EXISTS {fts}_ad;
+        DROP TRIGGER IF EXISTS {fts}_au;
+        DROP TABLE IF EXISTS {fts};"
+    ))
+}
+
+fn search(table: &'static str, query: SimpleExpr) -> sea_query::SelectStatement {
+    let fts = Alias::new(fts_name(table));
+    Query::select()
+        .from(fts.clone())
+        .and_where(Expr::col(fts).binary(BinOper::Custom("MATCH"), query))
+        .take()
+}
+
+#[derive(Clone, Copy)]
+pub struct Match<A, B>(A, B, &'static str);
+
+impl<A: Typed, B: Typed> Typed for Match<A, B> {
+    type Typ = bool;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        let select = search(self.2, self.1.build_expr(b))
+            .column(Alias::new("rowid"))
+            .take();
+        Expr::expr(self.0.build_expr(b)).in_subquery(select)
+    }
+}
+
+impl<'t, S, A: IntoColumn<'t, S>, B: IntoColumn<'t, S>> IntoColumn<'t, S> for Match<A, B> {
+    type Owned = Match<A::Owned, B::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        Match(self.0.into_owned(), self.1.into_owned(), self.2)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct MatchRank<A, B>(A, B, &'static str);
+
+impl<A: Typed, B: Typed> Typed for MatchRank<A, B> {
+    type Typ = Option<f64>;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        let select = search(self.2, self.1.build_expr(b))
+            .column(Alias::new("rank"))
+            .and_where(Expr::col(Alias::new("rowid")).eq(self.0.build_expr(b)))
+            .take();
+        SimpleExpr::SubQuery(None, Box::new(SubQueryStatement::SelectStatement(select)))
+    }
+}
+
+impl<'t, S, A: IntoColumn<'t, S>, B: IntoColumn<'t, S>> IntoColumn<'t, S> for MatchRank<A, B> {
+    type Owned = MatchRank<A::Owned, B::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        MatchRank(self.0.into_owned(), self.1.into_owned(), self.2)
+    }
+}
+
+/// Used by the generated `match_` method of tables with a full-text index.
+pub fn fts_match<'t, S, T: Table>(
+    row: impl IntoColumn<'t, S, Typ = T>,
+    query: impl IntoColumn<'t, S, Typ = String>,
+) -> Column<'t, S, bool> {
+    Match(row, query, T::NAME).into_column()
+}
+
+/// Used by the generated `match_rank` method of tables with a full-text index.
+pub fn fts_rank<'t, S, T: Table>(
+    row: impl IntoColumn<'t, S, Typ = T>,
+    query: impl IntoColumn<'t, S, Typ = String>,
+) -> Column<'t, S, Option<f64>> {
+    MatchRank(row, query, T::NAME).into_column()
+}
@@ -0,0 +1,364 @@
+use std::{
+    cmp::Ordering,
+    marker::PhantomData,
+    panic::{AssertUnwindSafe, UnwindSafe},
+    rc::Rc,
+    sync::{Arc, Mutex},
+};
+
+use rusqlite::{
+    functions::{Context, FunctionFlags},
+    types::{FromSql, ToSqlOutput, Value},
+    Connection, ToSql,
+};
+use sea_query::{Alias, Func, SimpleExpr};
+
+use crate::{
+    aggregate::Aggregate,
+    value::{MyTyp, Typed, ValueBuilder},
+    Column, Database, IntoColumn,
+};
+
+type RawFunction = Arc<dyn Fn(&Context) -> rusqlite::Result<Value> + Send + Sync>;
+type Install = Arc<dyn Fn(&Connection) -> rusqlite::Result<()> + Send + Sync>;
+
+/// Keeps track of the functions registered with [Database::function] and [Database::aggregate_function],
+/// and the collations registered with [crate::migration::Config::collation].
+#[derive(Default)]
+pub(crate) struct Functions {
+    functions: Mutex<Vec<(String, i32, Install)>>,
+    collations: Mutex<Vec<(String, Install)>>,
+}
+
+impl Functions {
+    /// Register all functions and collations on a new connection.
+    pub fn install(&self, conn: &Connection) {
+        for (_, install) in &*self.collations.lock().unwrap() {
+            install(conn).unwrap();
+        }
+        for (_, _, install) in &*self.functions.lock().unwrap() {
+            install(conn).unwrap();
+        }
+    }
+
+    /// Add a collation, a collation with the same name is replaced.
+    pub fn add_collation(
+        &self,
+        name: &str,
+        cmp: impl Fn(&str, &str) -> Ordering + Send + Sync + 'static,
+    ) {
+        let cmp = Arc::new(cmp);
+        let name_owned = name.to_owned();
+        let install = move |conn: &Connection| {
+            // rusqlite catches panics in the collation
+            let cmp = AssertUnwindSafe(cmp.clone());
+            conn.create_collation(&name_owned, move |a, b| {
+                let cmp = &cmp;
+                (cmp.0)(a, b)
+            })
+        };
+        let mut collations = self.collations.lock().unwrap();
+        collations.retain(|(x, _)| !x.eq_ignore_ascii_case(name));
+        collations.push((name.to_owned(), Arc::new(install)));
+    }
+
+    /// Add a function, a function with the same name and number of arguments is replaced.
+    fn add(&self, name: &str, n_args: i32, install: Install) {
+        let mut functions = self.functions.lock().unwrap();
+        functions.retain(|(x, n, _)| !(x == name && *n == n_args));
+        functions.push((name.to_owned(), n_args, install));
+    }
+}
+
+/// Rust closures that can be used as sql functions, see [Database::function].
+pub(crate) trait IntoFunction<Args, Out>: Send + Sync + 'static {
+    const N_ARGS: i32;
+    fn into_raw(self) -> RawFunction;
+}
+
+fn to_value(out: impl ToSql) -> rusqlite::Result<Value> {
+    Ok(match out.to_sql()? {
+        ToSqlOutput::Borrowed(value) => value.into(),
+        ToSqlOutput::Owned(value) => value,
+        _ => unreachable!("only basic types are supported"),
+    })
+}
+
+/// A function written in rust that can be called in queries, see [Database::function].
+pub struct Function<Args, Out> {
+    name: Arc<str>,
+    _p: PhantomData<fn(Args) -> Out>,
+}
+
+impl<Args, Out> Clone for Function<Args, Out> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            _p: PhantomData,
+        }
+    }
+}
+
+type Arg<'t> = Rc<dyn Fn(ValueBuilder) -> SimpleExpr + 't>;
+
+pub struct Call<'t, T> {
+    name: Arc<str>,
+    args: Vec<Arg<'t>>,
+    _p: PhantomData<T>,
+}
+
+impl<T> Clone for Call<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            args: self.args.clone(),
+            _p: PhantomData,
+        }
+    }
+}
+
+impl<T> Typed for Call<'_, T> {
+    type Typ = T;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        let args = self.args.iter().map(|arg| arg(b));
+        Func::cust(Alias::new(&*self.name)).args(args).into()
+    }
+}
+
+impl<'t, S, T: 't> IntoColumn<'t, S> for Call<'t, T> {
+    type Owned = Self;
+
+    fn into_owned(self) -> Self::Owned {
+        self
+    }
+}
+
+fn arg<'t, S>(val: impl IntoColumn<'t, S>) -> Arg<'t> {
+    let val = val.into_owned();
+    Rc::new(move |b| val.build_expr(b))
+}
+
+macro_rules! impl_function {
+    ($n:literal, $($t:ident $a:ident $i:tt),+) => {
+        impl<F, O, $($t),+> IntoFunction<($($t,)+), O> for F
+        where
+            F: Fn($($t),+) -> O + Send + Sync + 'static,
+            O: ToSql,
+            $($t: FromSql),+
+        {
+            const N_ARGS: i32 = $n;
+            fn into_raw(self) -> RawFunction {
+                Arc::new(move |ctx| to_value(self($(ctx.get::<$t>($i)?),+)))
+            }
+        }
+
+        impl<O, $($t),+> Function<($($t,)+), O> {
+            /// Call the function with these arguments.
+            pub fn call<'t, S>(
+                &self,
+                $($a: impl IntoColumn<'t, S, Typ = $t>),+
+            ) -> Column<'t, S, O>
+            where
+                O: 't,
+            {
+                Call {
+                    name: self.name.clone(),
+                    args: vec![$(arg($a)),+],
+                    _p: PhantomData,
+                }
+                .into_column()
+            }
+        }
+    };
+}
+
+impl_function! {1, A a 0}
+impl_function! {2, A a 0, B b 1}
+impl_function! {3, A a 0, B b 1, C c 2}
+impl_function! {4, A a 0, B b 1, C c 2, D d 3}
+
+impl<S> Database<S> {
+    /// Register a rust closure as a sql function with the name `name`, the returned [Function] can be used in queries.
+    ///
+    /// The arguments and the result can have types [i64], [f64], [String], [bool] and [Option] of those.
+    /// This makes it possible to filter on computations that are not available in sql.
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient, Table};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     City { x: f64, y: f64 },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// let distance = db.function("distance", |x: f64, y: f64| x.hypot(y));
+    /// let txn = client.transaction(&db);
+    /// let near = txn.query(|rows| {
+    ///     let city = City::join(rows);
+    ///     rows.filter(distance.call(city.x(), city.y()).lt(10.0));
+    ///     rows.count()
+    /// });
+    /// ```
+    /// Only transactions that are started after this call can use the function.
+    /// A function with the same name and number of arguments replaces the previous function.
+    pub fn function<Args, Out, F: IntoFunction<Args, Out>>(
+        &self,
+        name: &str,
+        f: F,
+    ) -> Function<Args, Out> {
+        let raw = f.into_raw();
+        let name_owned = name.to_owned();
+        let install = move |conn: &Connection| {
+            // rusqlite turns panics in the function into errors
+            let f = AssertUnwindSafe(raw.clone());
+            let flags = FunctionFlags::SQLITE_UTF8;
+            conn.create_scalar_function(&name_owned, F::N_ARGS, flags, move |ctx| {
+                let f = &f;
+                (f.0)(ctx)
+            })
+        };
+        self.functions.add(name, F::N_ARGS, Arc::new(install));
+        Function {
+            name: name.into(),
+            _p: PhantomData,
+        }
+    }
+
+    /// Register a rust aggregate function with the name `name`, the returned [AggregateFunction] can be used in [crate::aggregate].
+    ///
+    /// Every group of rows starts with the state from `init`, then `step` is called for every row
+    /// and finally `finish` computes the result from the state.
+    /// The argument and the result can have the same types as with [Database::function].
+    /// ```
+    /// # use rust_query::{aggregate, migration::{schema, Config}, LocalClient, Table};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     Score { value: f64 },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// let product = db.aggregate_function(
+    ///     "product",
+    ///     || 1.0,
+    ///     |product: &mut f64, value: f64| *product *= value,
+    ///     |product| product,
+    /// );
+    /// let txn = client.transaction(&db);
+    /// let res = txn.query_one(aggregate(|rows| {
+    ///     let score = Score::join(rows);
+    ///     product.call(rows, score.value())
+    /// }));
+    /// assert_eq!(res, Some(1.0));
+    /// ```
+    /// `finish` is also called without rows when the aggregate does not use [crate::args::Aggregate::filter_on].
+    /// Only transactions that are started after this call can use the function.
+    pub fn aggregate_function<Arg, State, Out>(
+        &self,
+        name: &str,
+        init: impl Fn() -> State + Send + Sync + 'static,
+        step: impl Fn(&mut State, Arg) + Send + Sync + 'static,
+        finish: impl Fn(State) -> Out + Send + Sync + 'static,
+    ) -> AggregateFunction<Arg, Out>
+    where
+        Arg: FromSql + 'static,
+        State: 'static,
+        Out: ToSql + 'static,
+    {
+        let fold = Fold(Arc::new(FoldInner {
+            init: Box::new(init),
+            step: Box::new(step),
+            finish: Box::new(finish),
+        }));
+        let name_owned = name.to_owned();
+        let install = move |conn: &Connection| {
+            let flags = FunctionFlags::SQLITE_UTF8;
+            conn.create_aggregate_function(&name_owned, 1, flags, fold.clone())
+        };
+        self.functions.add(name, 1, Arc::new(install));
+        AggregateFunction {
+            name: name.into(),
+            _p: PhantomData,
+        }
+    }
+}
+
+type Step<State, Arg> = Box<dyn Fn(&mut State, Arg) + Send + Sync>;
+
+struct FoldInner<Arg, State, Out> {
+    init: Box<dyn Fn() -> State + Send + Sync>,
+    step: Step<State, Arg>,
+    finish: Box<dyn Fn(State) -> Out + Send + Sync>,
+}
+
+/// Implements a sqlite aggregate with the closures from [Database::aggregate_function].
+struct Fold<Arg, State, Out>(Arc<FoldInner<Arg, State, Out>>);
+
+impl<Arg, State, Out> Clone for Fold<Arg, State, Out> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+// rusqlite turns panics in the closures into errors and the state is not used after that
+impl<Arg, State, Out> UnwindSafe for Fold<Arg, State, Out> {}
+
+impl<Arg: FromSql, State, Out: ToSql> rusqlite::functions::Aggregate<AssertUnwindSafe<State>, Value>
+    for Fold<Arg, State, Out>
+{
+    fn init(&self, _ctx: &mut Context<'_>) -> rusqlite::Result<AssertUnwindSafe<State>> {
+        Ok(AssertUnwindSafe((self.0.init)()))
+    }
+
+    fn step(
+        &self,
+        ctx: &mut Context<'_>,
+        state: &mut AssertUnwindSafe<State>,
+    ) -> rusqlite::Result<()> {
+        (self.0.step)(&mut state.0, ctx.get(0)?);
+        Ok(())
+    }
+
+    fn finalize(
+        &self,
+        _ctx: &mut Context<'_>,
+        state: Option<AssertUnwindSafe<State>>,
+    ) -> rusqlite::Result<Value> {
+        // there is no state if there were no rows
+        let state = state.map_or_else(|| (self.0.init)(), |state| state.0);
+        to_value((self.0.finish)(state))
+    }
+}
+
+/// An aggregate function written in rust, see [Database::aggregate_function].
+pub struct AggregateFunction<Arg, Out> {
+    name: Arc<str>,
+    _p: PhantomData<fn(Arg) -> Out>,
+}
+
+impl<Arg, Out> Clone for AggregateFunction<Arg, Out> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            _p: PhantomData,
+        }
+    }
+}
+
+impl<Arg, Out> AggregateFunction<Arg, Out> {
+    /// Compute the aggregate of `val` over the rows of the [Aggregate].
+    ///
+    /// The result is [None] if there are zero rows.
+    pub fn call<'outer, 'inner, S>(
+        &self,
+        rows: &'inner Aggregate<'outer, 'inner, S>,
+        val: impl IntoColumn<'inner, S, Typ = Arg>,
+    ) -> Column<'outer, S, Option<Out>>
+    where
+        S: 'outer,
+        Out: MyTyp + 'outer,
+    {
+        let expr = Func::cust(Alias::new(&*self.name)).arg(val.build_expr(rows.ast.builder()));
+        rows.select(expr).into_column()
+    }
+}
@@ -0,0 +1,232 @@
+use std::marker::PhantomData;
+
+use rusqlite::{types::Value, ErrorCode};
+
+use crate::{
+    error::unwrap_db,
+    hash::{self, ColumnType, TypBuilder},
+    public_id::{IdKind, PUBLIC_ID},
+    Table, TableRow, TransactionMut,
+};
+
+/// The number of times that a row is generated again when it violates a constraint.
+const MAX_ATTEMPTS: usize = 100;
+/// The maximum number of rows that are inserted for the foreign keys of one row.
+const MAX_DEPTH: usize = 16;
+
+/// Everything that is needed to generate the rows of a table, the foreign keys refer to other tables.
+pub struct TableInfo {
+    name: &'static str,
+    table: hash::Table,
+    parents: Vec<(String, fn() -> TableInfo)>,
+    public_id: Option<IdKind>,
+    timestamps: bool,
+    versioned: bool,
+    soft_delete: bool,
+}
+
+impl TableInfo {
+    pub(crate) fn of<T: Table>() -> Self {
+        let mut b = TypBuilder::default();
+        T::typs(&mut b);
+        Self {
+            name: T::NAME,
+            table: b.ast,
+            parents: b.parents,
+            public_id: T::PUBLIC_ID,
+            timestamps: T::TIMESTAMPS,
+            versioned: T::VERSIONED,
+            soft_delete: T::SOFT_DELETE,
+        }
+    }
+}
+
+/// Inserts rows with random values, to test queries against realistic data.
+///
+/// The values have the right type for each column, columns that are nullable are sometimes `NULL`.
+/// Foreign keys refer to random existing rows, if there are none then a row is generated for
+/// the referenced table first. Rows that violate a unique or check constraint are generated again.
+/// The same seed generates the same values, except for public ids which are always unique.
+/// ```
+/// # use rust_query::{migration::{schema, Config}, testing::RowGenerator, LocalClient, Table};
+/// # #[schema]
+/// # enum Schema {
+/// #     User { name: String },
+/// #     Post { author: User, title: String, likes: Option<i64> },
+/// # }
+/// # use v0::*;
+/// # let mut client = LocalClient::try_new().unwrap();
+/// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+/// let mut txn = client.transaction_mut(&db);
+/// let mut generator = RowGenerator::new(42);
+/// for _ in 0..100 {
+///     generator.insert::<Post>(&mut txn);
+/// }
+/// // every post has an author
+/// assert_eq!(txn.query(|rows| {
+///     let post = Post::join(rows);
+///     rows.filter(post.author().name().eq(post.author().name()));
+///     rows.count()
+/// }), 100);
+/// ```
+/// With the `proptest` feature, [RowGenerator] implements `Arbitrary`, so a new seed is used for each test case.
+///
+/// Rows are inserted with sql directly, so hooks of [crate::Database::hook] are not called.
+#[derive(Debug, Clone)]
+pub struct RowGenerator {
+    state: u64,
+}
+
+impl RowGenerator {
+    /// Create a generator, the seed determines the values of the rows.
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Insert a row with random values into table `T`.
+    pub fn insert<'t, T: Table>(
+        &mut self,
+        txn: &mut TransactionMut<'t, T::Schema>,
+    ) -> TableRow<'t, T> {
+        let conn = txn.transaction();
+        let idx = self.insert_inner(conn, &TableInfo::of::<T>(), 0);
+        TableRow {
+            _p: PhantomData,
+            _local: PhantomData,
+            idx,
+        }
+    }
+
+    fn insert_inner(&mut self, conn: &rusqlite::Connection, info: &TableInfo, depth: usize) -> i64 {
+        assert!(
+            depth < MAX_DEPTH,
+            "can not generate rows for `{}`, the foreign keys form a cycle",
+            info.name
+        );
+        for _ in 0..MAX_ATTEMPTS {
+            let time = self.range(1_600_000_000, 1_800_000_000);
+            let mut names = vec![];
+            let mut values = vec![];
+            // generated columns are computed by sqlite
+            for col in info.table.columns.iter().filter(|x| x.generated.is_none()) {
+                let parent = info.parents.iter().find(|(name, _)| *name == col.name);
+                // these columns are set just like with [TransactionMut::insert]
+                let value = match (col.name.as_str(), info.public_id) {
+                    (PUBLIC_ID, Some(kind)) => Value::Text(kind.generate()),
+                    ("created_at" | "updated_at", _) if info.timestamps => Value::Integer(time),
+                    ("version", _) if info.versioned => Value::Integer(0),
+                    ("deleted_at", _) if info.soft_delete => Value::Null,
+                    _ => match parent {
+                        Some((_, parent)) => self.reference(conn, &parent(), col, depth),
+                        None => self.value(info, col),
+                    },
+                };
+                names.push(format!("\"{}\"", col.name));
+                values.push(value);
+            }
+
+            let sql = if names.is_empty() {
+                format!("INSERT INTO \"{}\" DEFAULT VALUES", info.name)
+            } else {
+                format!(
+                    "INSERT INTO \"{}\" ({}) VALUES ({})",
+                    info.name,
+                    names.join(", "),
+                    vec!["?"; names.len()].join(", ")
+                )
+            };
+            match conn.execute(&sql, rusqlite::params_from_iter(values)) {
+                Ok(_) => return conn.last_insert_rowid(),
+                Err(err) if err.sqlite_error_code() == Some(ErrorCode::ConstraintViolation) => {
+                    continue
+                }
+                Err(err) => unwrap_db(Err(err)),
+            }
+        }
+        panic!(
+            "could not generate a row for `{}` that satisfies its constraints",
+            info.name
+        )
+    }
+
+    /// A random existing row of the referenced table, or a new row if there are none.
+    fn reference(
+        &mut self,
+        conn: &rusqlite::Connection,
+        parent: &TableInfo,
+        col: &hash::Column,
+        depth: usize,
+    ) -> Value {
+        if col.nullable && self.range(0, 5) == 0 {
+            return Value::Null;
+        }
+        let (table, id) = col.fk.as_ref().unwrap();
+        let count: i64 = unwrap_db(conn.query_row(
+            &format!("SELECT count(*) FROM \"{table}\""),
+            [],
+            |row| row.get(0),
+        ));
+        if count == 0 {
+            if col.nullable {
+                return Value::Null;
+            }
+            return Value::Integer(self.insert_inner(conn, parent, depth + 1));
+        }
+        let offset = self.range(0, count);
+        Value::Integer(unwrap_db(conn.query_row(
+            &format!("SELECT \"{id}\" FROM \"{table}\" ORDER BY \"{id}\" LIMIT 1 OFFSET ?"),
+            [offset],
+            |row| row.get(0),
+        )))
+    }
+
+    /// A random value for a column that is not a foreign key.
+    fn value(&mut self, info: &TableInfo, col: &hash::Column) -> Value {
+        if col.nullable && self.range(0, 5) == 0 {
+            return Value::Null;
+        }
+        // values in unique columns are taken from a larger range to make collisions unlikely
+        let unique = info
+            .table
+            .uniques
+            .iter()
+            .any(|x| x.columns.contains(&col.name));
+        let max = if unique { 1 << 40 } else { 1000 };
+        match col.typ {
+            ColumnType::Integer => Value::Integer(self.range(-max, max)),
+            ColumnType::Float => Value::Real(self.range(-max * 100, max * 100) as f64 / 100.),
+            ColumnType::String => {
+                let len = if unique { 12 } else { self.range(1, 12) };
+                let text = (0..len)
+                    .map(|_| (b'a' + self.range(0, 26) as u8) as char)
+                    .collect();
+                Value::Text(text)
+            }
+        }
+    }
+
+    /// A random number in `min..max`.
+    fn range(&mut self, min: i64, max: i64) -> i64 {
+        min + (self.next_u64() % (max - min) as u64) as i64
+    }
+
+    /// The next number of the splitmix64 sequence.
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(feature = "proptest")]
+impl proptest::arbitrary::Arbitrary for RowGenerator {
+    type Parameters = ();
+    type Strategy = proptest::strategy::Map<proptest::num::u64::Any, fn(u64) -> Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        use proptest::strategy::Strategy;
+        proptest::num::u64::ANY.prop_map(RowGenerator::new)
+    }
+}
@@ -0,0 +1,196 @@
+use std::fmt;
+
+use rusqlite::ErrorCode;
+use sea_query_rusqlite::{RusqliteValue, RusqliteValues};
+
+use crate::{
+    data_export::{find, id, schema, walk},
+    error::Error,
+    hash::{self, OnDelete},
+    hook::HookEvent,
+    migrate::Schema,
+    trace::{execute, traced},
+    transaction::Deletor,
+    BundleTable, Table, TableRow,
+};
+
+/// Error returned by [Deletor::delete_graph] when a foreign key prevents deleting a row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForeignKeyError {
+    /// The table with the foreign key.
+    pub table: String,
+    /// The column of the foreign key.
+    pub column: String,
+    /// The table that is referenced by the foreign key.
+    pub parent: String,
+}
+
+impl fmt::Display for ForeignKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the foreign key `{}.{}` still references a row of `{}`",
+            self.table, self.column, self.parent
+        )
+    }
+}
+
+impl std::error::Error for ForeignKeyError {}
+
+/// Error returned by [Deletor::delete_graph], nothing is deleted when this is returned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeleteGraphError {
+    /// A foreign key prevents deleting a row.
+    ForeignKey(ForeignKeyError),
+    /// Sqlite failed to delete the rows, for example because the disk is full.
+    Database(Error),
+}
+
+impl fmt::Display for DeleteGraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeleteGraphError::ForeignKey(err) => err.fmt(f),
+            DeleteGraphError::Database(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for DeleteGraphError {}
+
+impl From<rusqlite::Error> for DeleteGraphError {
+    fn from(value: rusqlite::Error) -> Self {
+        DeleteGraphError::Database(value.into())
+    }
+}
+
+impl<S: Schema> Deletor<S> {
+    /// Delete the `root` row and all rows that depend on it.
+    ///
+    /// These are the same rows that [DataExport::collect](crate::DataExport::collect) finds, except for rows that reference a deleted row
+    /// with a foreign key that has `#[on_delete(set_null)]`. Those rows are kept and sqlite sets the foreign key to `NULL`.
+    /// The order in which the tables are deleted is computed from the schema, such that rows are deleted before the rows that they reference.
+    ///
+    /// With `batch_size`, every statement deletes at most that many rows, otherwise all rows of a table are deleted with one statement.
+    /// Tables that reference themselves are always deleted with one statement.
+    ///
+    /// Returns the number of deleted rows for each table, in the order that the tables were deleted.
+    /// If a foreign key is still violated, for example because tables reference each other in a cycle,
+    /// then this returns [DeleteGraphError::ForeignKey] that names the foreign key.
+    /// All deletes are done in a savepoint, so when an error is returned the database is not changed.
+    pub fn delete_graph<T: Table<Schema = S>>(
+        &mut self,
+        root: TableRow<'_, T>,
+        batch_size: Option<usize>,
+    ) -> Result<Vec<(String, usize)>, DeleteGraphError> {
+        let conn = self.inner.transaction();
+        conn.execute_batch("SAVEPOINT \"delete_graph\"")?;
+        let res = self.delete_graph_inner(root, batch_size);
+        let conn = self.inner.transaction();
+        if res.is_err() {
+            conn.execute_batch("ROLLBACK TO \"delete_graph\"")?;
+        }
+        conn.execute_batch("RELEASE \"delete_graph\"")?;
+        res
+    }
+
+    fn delete_graph_inner<T: Table<Schema = S>>(
+        &mut self,
+        root: TableRow<'_, T>,
+        batch_size: Option<usize>,
+    ) -> Result<Vec<(String, usize)>, DeleteGraphError> {
+        let schema = schema::<S>();
+        let follow = |col: &hash::Column| col.on_delete != OnDelete::SetNull;
+        let mut tables = walk(&self.inner, &schema, T::NAME, root.idx, false, follow);
+
+        // a table can be deleted when no remaining table references it
+        let mut counts = vec![];
+        while !tables.is_empty() {
+            let references = |from: &BundleTable, to: &BundleTable| {
+                from.name != to.name
+                    && find(&schema, &from.name).columns.iter().any(|col| {
+                        col.fk.as_ref().is_some_and(|(x, _)| *x == to.name) && follow(col)
+                    })
+            };
+            let pos = (0..tables.len())
+                .find(|&i| !tables.iter().any(|x| references(x, &tables[i])))
+                // the tables form a cycle, sqlite will report the violated foreign key
+                .unwrap_or(0);
+            let table = tables.remove(pos);
+
+            let self_reference = find(&schema, &table.name)
+                .columns
+                .iter()
+                .any(|col| col.fk.as_ref().is_some_and(|(x, _)| *x == table.name) && follow(col));
+            let batch_size = match batch_size {
+                Some(size) if !self_reference => size.max(1),
+                _ => table.rows.len(),
+            };
+            let mut count = 0;
+            for chunk in table.rows.chunks(batch_size) {
+                let ids: Vec<_> = chunk.iter().map(|row| id(row)).collect();
+                count += self.delete_ids(&table.name, &ids)?;
+            }
+            counts.push((table.name, count));
+        }
+        Ok(counts)
+    }
+}
+
+impl<S: 'static> Deletor<S> {
+    /// Delete the rows of `table` with the given ids, returns the number of deleted rows.
+    pub(crate) fn delete_ids(
+        &mut self,
+        table: &str,
+        ids: &[i64],
+    ) -> Result<usize, DeleteGraphError> {
+        for id in ids {
+            self.hooks
+                .run(&self.inner, table, HookEvent::BeforeDelete, *id);
+        }
+        // the ids are passed as one json array, because sqlite limits the number of parameters
+        let sql =
+            format!("DELETE FROM \"{table}\" WHERE \"id\" IN (SELECT \"value\" FROM json_each(?))");
+        let ids = ids.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        let ids = format!("[{}]", ids.join(","));
+        let values = RusqliteValues(vec![RusqliteValue(ids.clone().into())]);
+
+        let mut stmt = self.inner.transaction().prepare(&sql)?;
+        match traced(&sql, &values.0, || execute(&mut stmt, &values)) {
+            Ok(count) => Ok(count),
+            Err(err) if err.sqlite_error_code() == Some(ErrorCode::ConstraintViolation) => {
+                match self.violation(table, &ids)? {
+                    Some(violation) => Err(DeleteGraphError::ForeignKey(violation)),
+                    None => Err(err.into()),
+                }
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Find a foreign key that prevents deleting the rows of `table` with the ids in the json array `ids`.
+    ///
+    /// The failed statement has been rolled back, so the rows that reference the rows are still there.
+    fn violation(&self, table: &str, ids: &str) -> rusqlite::Result<Option<ForeignKeyError>> {
+        let conn = self.inner.transaction();
+        let mut stmt = conn.prepare(
+            "SELECT m.name, p.\"from\" FROM sqlite_schema m, pragma_foreign_key_list(m.name) p
+            WHERE m.type = 'table' AND p.\"table\" = ? AND p.on_delete IN ('NO ACTION', 'RESTRICT')",
+        )?;
+        let keys: Vec<(String, String)> = stmt
+            .query_map([table], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        for (child, column) in keys {
+            let sql = format!(
+                "SELECT EXISTS (SELECT 1 FROM \"{child}\" WHERE \"{column}\" IN (SELECT \"value\" FROM json_each(?)))"
+            );
+            if conn.query_row(&sql, [ids], |row| row.get(0))? {
+                return Ok(Some(ForeignKeyError {
+                    table: child,
+                    column,
+                    parent: table.to_owned(),
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
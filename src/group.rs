@@ -0,0 +1,107 @@
+use std::marker::PhantomData;
+
+use elsa::FrozenVec;
+use sea_query::{Asterisk, Expr, Func, SimpleExpr};
+
+use crate::{
+    exec::Query,
+    value::{operations::Prebuilt, EqTyp, IntoColumn, MyTyp, NumTyp},
+    Column, Dummy,
+};
+
+/// This is the argument type used for [Query::group_by].
+///
+/// Every group is a set of rows that have the same values for all keys.
+/// Columns from the query can only be used in the result after they are turned into
+/// a key with [Group::key] or aggregated with one of the aggregate methods.
+///
+/// The keys can be used in [crate::args::Aggregate::filter_on], but the aggregates of the group can not,
+/// because sqlite computes the rows of [crate::aggregate] before grouping.
+pub struct Group<'outer, 'inner, 'g, S> {
+    pub(crate) query: &'inner Query<'outer, 'inner, S>,
+    pub(crate) keys: FrozenVec<Box<SimpleExpr>>,
+    pub(crate) filters: FrozenVec<Box<SimpleExpr>>,
+    pub(crate) _p: PhantomData<fn(&'g ()) -> &'g ()>,
+}
+
+impl<'outer, 'inner, 'g, S> Group<'outer, 'inner, 'g, S> {
+    fn build<T>(
+        &self,
+        val: impl IntoColumn<'inner, S>,
+        f: impl FnOnce(SimpleExpr) -> SimpleExpr,
+    ) -> Column<'g, S, T>
+    where
+        T: 'static,
+    {
+        let expr = val.build_expr(self.query.ast.builder());
+        Prebuilt(f(expr), PhantomData).into_column()
+    }
+
+    /// Add a column to the group key.
+    ///
+    /// Returns the same column, but now it can be used in the result of the groups.
+    pub fn key<T: MyTyp>(&self, val: impl IntoColumn<'inner, S, Typ = T>) -> Column<'g, S, T> {
+        self.build(val, |expr| {
+            self.keys.push(Box::new(expr.clone()));
+            expr
+        })
+    }
+
+    /// Filter groups based on a column, this is executed as `HAVING`.
+    ///
+    /// The column can use the group keys and aggregates of this group.
+    pub fn filter(&self, prop: impl IntoColumn<'g, S, Typ = bool>) {
+        let expr = prop.build_expr(self.query.ast.builder());
+        self.filters.push(Box::new(expr));
+    }
+
+    /// Return the average value in a column.
+    pub fn avg(&self, val: impl IntoColumn<'inner, S, Typ = f64>) -> Column<'g, S, f64> {
+        self.build(val, |expr| Func::avg(expr).into())
+    }
+
+    /// Return the maximum value in a column.
+    pub fn max<T: NumTyp>(&self, val: impl IntoColumn<'inner, S, Typ = T>) -> Column<'g, S, T> {
+        self.build(val, |expr| Func::max(expr).into())
+    }
+
+    /// Return the sum of a column.
+    pub fn sum<T: NumTyp>(&self, val: impl IntoColumn<'inner, S, Typ = T>) -> Column<'g, S, T> {
+        self.build(val, |expr| Func::sum(expr).into())
+    }
+
+    /// Return the number of distinct values in a column.
+    pub fn count_distinct<T: EqTyp>(
+        &self,
+        val: impl IntoColumn<'inner, S, Typ = T>,
+    ) -> Column<'g, S, i64> {
+        self.build(val, |expr| Func::count_distinct(expr).into())
+    }
+
+    /// Return the number of rows in the group.
+    pub fn count(&self) -> Column<'g, S, i64> {
+        Prebuilt(Expr::col(Asterisk).count(), PhantomData).into_column()
+    }
+
+    /// Retrieve one result for every group.
+    ///
+    /// Just like [Query::into_vec], the results are sorted by the values of the dummy.
+    pub fn into_vec<D>(&self, dummy: D) -> Vec<D::Out>
+    where
+        D: Dummy<'g, 'outer, S>,
+        S: 'g,
+    {
+        self.query.into_vec_private(dummy, |select| {
+            for key in &self.keys {
+                select.add_group_by([key.clone()]);
+            }
+            if self.keys.is_empty() {
+                // without keys there is one group, unless there are no rows at all
+                select.and_having(Expr::expr(Expr::col(Asterisk).count()).gt(0));
+            }
+            for filter in &self.filters {
+                select.and_having(filter.clone());
+            }
+        })
+    }
+}
@@ -14,9 +14,12 @@ use k12::{
 };
 use sea_query::TableCreateStatement;
 
-use crate::value::MyTyp;
+use crate::{
+    generate::TableInfo,
+    value::{operations::quote_ident, MyTyp},
+};
 
-#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub enum ColumnType {
     Integer = 0,
     Float = 1,
@@ -34,12 +37,76 @@ impl ColumnType {
     }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+/// The action that is taken when a row that is referenced by a foreign key is deleted.
+#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Default, Clone, Copy)]
+pub enum OnDelete {
+    #[default]
+    NoAction,
+    Cascade,
+    Restrict,
+    SetNull,
+}
+
+impl OnDelete {
+    fn sea_action(&self) -> Option<sea_query::ForeignKeyAction> {
+        use sea_query::ForeignKeyAction as A;
+        match self {
+            OnDelete::NoAction => None,
+            OnDelete::Cascade => Some(A::Cascade),
+            OnDelete::Restrict => Some(A::Restrict),
+            OnDelete::SetNull => Some(A::SetNull),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub struct Column {
     pub name: String,
     pub typ: ColumnType,
     pub nullable: bool,
     pub fk: Option<(String, String)>,
+    pub on_delete: OnDelete,
+    pub generated: Option<Generated>,
+    /// The sql literal that is used when the column is not inserted.
+    pub default: Option<String>,
+    /// The name of the collation that is used to compare values of the column.
+    pub collate: Option<String>,
+}
+
+/// The expression of a generated column.
+#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct Generated {
+    pub expr: String,
+    pub stored: bool,
+}
+
+impl Hash for Column {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.typ.hash(state);
+        self.nullable.hash(state);
+        self.fk.hash(state);
+        // keep the hash of columns without foreign key action the same,
+        // the tag keeps the action apart from the other optional fields
+        if self.on_delete != OnDelete::NoAction {
+            "on_delete".hash(state);
+            self.on_delete.hash(state);
+        }
+        // keep the hash of normal columns the same, the tags keep the expression and default apart from other text
+        if let Some(generated) = &self.generated {
+            "generated".hash(state);
+            generated.hash(state);
+        }
+        if let Some(default) = &self.default {
+            "default".hash(state);
+            default.hash(state);
+        }
+        // the tag keeps a collation apart from a default value with the same text
+        if let Some(collate) = &self.collate {
+            "collate".hash(state);
+            collate.hash(state);
+        }
+    }
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Default)]
@@ -47,10 +114,58 @@ pub struct Unique {
     pub columns: MyVec<String>,
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub struct Table {
     pub columns: MyVec<Column>,
     pub uniques: MyVec<Unique>,
+    /// Columns in the full-text index, empty if the table has no full-text index.
+    pub fts5: MyVec<String>,
+    /// Expressions of the `CHECK` constraints.
+    pub checks: MyVec<String>,
+    /// Triggers on the table, other than the triggers of the full-text index.
+    pub triggers: MyVec<Trigger>,
+}
+
+/// A trigger that runs `body` when `event` happens on the rows of a table.
+#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct Trigger {
+    pub name: String,
+    /// When the trigger runs, like `AFTER INSERT` or `BEFORE UPDATE OF "name"`.
+    pub event: String,
+    /// The statements of the trigger, each ending with a semicolon.
+    pub body: String,
+}
+
+impl Trigger {
+    pub fn sql(&self, table: &str) -> String {
+        format!(
+            "CREATE TRIGGER \"{}\" {} ON \"{table}\" BEGIN\n{}\nEND",
+            self.name, self.event, self.body
+        )
+    }
+}
+
+impl Hash for Table {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.columns.hash(state);
+        self.uniques.hash(state);
+        // keep the hash of tables without full-text index the same,
+        // the tag keeps the indexed columns apart from other lists of text
+        if !self.fts5.is_empty() {
+            "fts5".hash(state);
+            self.fts5.hash(state);
+        }
+        // keep the hash of tables without checks the same, the tag works like for the full-text index
+        if !self.checks.is_empty() {
+            "checks".hash(state);
+            self.checks.hash(state);
+        }
+        // keep the hash of tables without triggers the same, the tag works like for the full-text index
+        if !self.triggers.is_empty() {
+            "triggers".hash(state);
+            self.triggers.hash(state);
+        }
+    }
 }
 
 /// Special [Vec] wrapper with a hash that is independent of the item order
@@ -94,13 +209,30 @@ impl Table {
             } else {
                 def.not_null();
             }
+            if let Some(generated) = &col.generated {
+                let kind = if generated.stored {
+                    "STORED"
+                } else {
+                    "VIRTUAL"
+                };
+                def.extra(format!("GENERATED ALWAYS AS ({}) {kind}", generated.expr));
+            }
+            if let Some(default) = &col.default {
+                def.default(Expr::cust(default));
+            }
+            if let Some(collate) = &col.collate {
+                def.extra(format!("COLLATE {}", quote_ident(collate)));
+            }
             create.col(&mut def);
             if let Some((table, fk)) = &col.fk {
-                create.foreign_key(
-                    ForeignKey::create()
-                        .to(Alias::new(table), Alias::new(fk))
-                        .from_col(name),
-                );
+                let mut foreign_key = ForeignKey::create()
+                    .to(Alias::new(table), Alias::new(fk))
+                    .from_col(name)
+                    .take();
+                if let Some(action) = col.on_delete.sea_action() {
+                    foreign_key.on_delete(action);
+                }
+                create.foreign_key(&mut foreign_key);
             }
         }
         for unique in &*self.uniques {
@@ -110,6 +242,9 @@ impl Table {
             }
             create.index(&mut index);
         }
+        for check in &*self.checks {
+            create.check(Expr::cust(check));
+        }
         create
     }
 }
@@ -144,7 +279,16 @@ impl Hasher for KangarooHasher {
     }
 }
 
-pub fn hash_schema<S: crate::migrate::Schema>() -> String {
+/// The version of the hashing done by [schema_hash], it is stored next to the hash in the database file.
+///
+/// This has to be increased when the [Hash] implementations change, because a stored hash
+/// of an older format can not be compared.
+pub(crate) const HASH_FORMAT: i64 = 1;
+
+/// Compute a hash of the tables, columns and constraints of schema `S`.
+///
+/// The hash changes when the schema changes, use [crate::verify_schema] to check that it did not.
+pub fn schema_hash<S: crate::migrate::Schema>() -> String {
     let mut b = crate::migrate::TableTypBuilder::default();
     S::typs(&mut b);
     let mut hasher = KangarooHasher::default();
@@ -152,25 +296,115 @@ pub fn hash_schema<S: crate::migrate::Schema>() -> String {
     format!("{:x}", hasher.finish())
 }
 
+/// Panic if the hash of a schema is not the expected hash.
+///
+/// This makes sure that a schema version that is already in use is not modified by accident.
+/// The expected hash can be found by running the check once with an empty string, the panic message contains the new hash.
+/// ```
+/// # use rust_query::{migration::schema, verify_schema};
+/// # #[schema]
+/// # #[version(0..=1)]
+/// # enum Schema {
+/// #     User {
+/// #         name: String,
+/// #         #[version(1..)]
+/// #         email: String,
+/// #     },
+/// # }
+/// #[test]
+/// fn backwards_compat() {
+///     verify_schema!(v0::Schema, "a57e97b8c243859a");
+///     verify_schema!(v1::Schema, "15e9ff46816e4b45");
+/// }
+/// # fn main() {}
+/// ```
+/// It can also be used at startup, before opening the database.
+#[allow(clippy::test_attr_in_doctest)]
+#[macro_export]
+macro_rules! verify_schema {
+    ($schema:ty, $hash:expr $(,)?) => {
+        $crate::private::verify_schema::<$schema>($hash)
+    };
+}
+
+#[track_caller]
+pub fn verify_schema<S: crate::migrate::Schema>(expected: &str) {
+    let hash = schema_hash::<S>();
+    assert!(
+        hash == expected,
+        "the schema hash is {hash:?}, but {expected:?} was expected. \
+        Schema versions that are in use should never be modified, add a new version instead."
+    );
+}
+
+fn column<T: MyTyp>(name: &'static str, on_delete: OnDelete) -> Column {
+    Column {
+        name: name.to_owned(),
+        typ: T::TYP,
+        nullable: T::NULLABLE,
+        fk: T::FK.map(|(table, fk)| (table.to_owned(), fk.to_owned())),
+        on_delete,
+        generated: None,
+        default: None,
+        collate: None,
+    }
+}
+
 #[derive(Default)]
 pub struct TypBuilder {
     pub(crate) ast: Table,
+    // the tables that are referenced by columns, these are not part of the schema hash
+    pub(crate) parents: Vec<(String, fn() -> TableInfo)>,
 }
 
 impl TypBuilder {
     pub fn col<T: MyTyp>(&mut self, name: &'static str) {
-        let mut item = Column {
-            name: name.to_owned(),
-            typ: T::TYP,
-            nullable: T::NULLABLE,
-            fk: None,
-        };
-        if let Some((table, fk)) = T::FK {
-            item.fk = Some((table.to_owned(), fk.to_owned()))
+        self.col_on_delete::<T>(name, OnDelete::NoAction)
+    }
+
+    pub fn col_on_delete<T: MyTyp>(&mut self, name: &'static str, on_delete: OnDelete) {
+        self.add::<T>(column::<T>(name, on_delete))
+    }
+
+    fn add<T: MyTyp>(&mut self, item: Column) {
+        if let Some(info) = T::TABLE {
+            self.parents.push((item.name.clone(), info));
         }
         self.ast.columns.insert(item)
     }
 
+    pub fn col_generated<T: MyTyp>(
+        &mut self,
+        name: &'static str,
+        expr: &'static str,
+        stored: bool,
+    ) {
+        let mut item = column::<T>(name, OnDelete::NoAction);
+        item.generated = Some(Generated {
+            expr: expr.to_owned(),
+            stored,
+        });
+        self.ast.columns.insert(item)
+    }
+
+    pub fn col_default<T: MyTyp>(&mut self, name: &'static str, default: &'static str) {
+        let mut item = column::<T>(name, OnDelete::NoAction);
+        item.default = Some(default.to_owned());
+        self.add::<T>(item)
+    }
+
+    /// Set the collation of a column that was already added.
+    pub fn collate(&mut self, name: &'static str, collate: &'static str) {
+        // the collation is not part of the ordering, because the names are unique
+        let col = self
+            .ast
+            .columns
+            .inner
+            .iter_mut()
+            .find(|col| col.name == name);
+        col.unwrap().collate = Some(collate.to_owned());
+    }
+
     pub fn unique(&mut self, cols: &[&'static str]) {
         let mut unique = Unique::default();
         for &col in cols {
@@ -178,4 +412,22 @@ impl TypBuilder {
         }
         self.ast.uniques.insert(unique);
     }
+
+    pub fn check(&mut self, expr: &'static str) {
+        self.ast.checks.insert(expr.to_owned());
+    }
+
+    pub fn trigger(&mut self, name: &'static str, event: &'static str, body: &'static str) {
+        self.ast.triggers.insert(Trigger {
+            name: name.to_owned(),
+            event: event.to_owned(),
+            body: body.to_owned(),
+        });
+    }
+
+    pub fn fts5(&mut self, cols: &[&'static str]) {
+        for &col in cols {
+            self.ast.fts5.insert(col.to_owned());
+        }
+    }
 }
@@ -0,0 +1,117 @@
+use std::{
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use crate::{Database, Table, TableRow, Transaction};
+
+/// The moment that a hook is called, see [Database::hook].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HookEvent {
+    /// After a row is inserted.
+    AfterInsert,
+    /// Before a row is updated, the row still has the old values.
+    BeforeUpdate,
+    /// After a row is updated, the row has the new values.
+    AfterUpdate,
+    /// Before a row is deleted.
+    BeforeDelete,
+}
+
+type Hook<S> = Arc<dyn for<'t> Fn(&Transaction<'t, S>, i64) + Send + Sync>;
+
+/// The hooks registered with [Database::hook].
+pub(crate) struct Hooks<S> {
+    hooks: Mutex<Vec<(String, HookEvent, Hook<S>)>>,
+}
+
+impl<S> Default for Hooks<S> {
+    fn default() -> Self {
+        Self {
+            hooks: Default::default(),
+        }
+    }
+}
+
+impl<S> Hooks<S> {
+    /// Add a hook for `event` on `table` that receives the id of the row.
+    pub fn add(
+        &self,
+        table: &str,
+        event: HookEvent,
+        hook: impl for<'t> Fn(&Transaction<'t, S>, i64) + Send + Sync + 'static,
+    ) {
+        let mut hooks = self.hooks.lock().unwrap();
+        hooks.push((table.to_owned(), event, Arc::new(hook)));
+    }
+
+    /// A copy with the same hooks, hooks that are added to the copy are not added to `self`.
+    pub fn copy(&self) -> Self {
+        Self {
+            hooks: Mutex::new(self.hooks.lock().unwrap().clone()),
+        }
+    }
+
+    /// Call the hooks for `event` on `table` with the row that has id `idx`.
+    pub fn run(&self, txn: &Transaction<'_, S>, table: &str, event: HookEvent, idx: i64) {
+        // the lock is released before calling, so that hooks can add more hooks
+        let hooks: Vec<_> = self
+            .hooks
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(name, ev, _)| name == table && *ev == event)
+            .map(|(_, _, hook)| hook.clone())
+            .collect();
+        for hook in hooks {
+            hook(txn, idx)
+        }
+    }
+}
+
+impl<S: 'static> Database<S> {
+    /// Call `hook` for every row of table `T` that is changed by a [crate::TransactionMut], at the moment given by `event`.
+    ///
+    /// The hook is called inside the transaction that makes the change, so it sees the same state of the database.
+    /// It can read the values of the row with the [Transaction], which makes it useful for audit logging and cache invalidation.
+    /// ```
+    /// # use std::sync::{Arc, Mutex};
+    /// # use rust_query::{migration::{schema, Config}, HookEvent, LocalClient};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     User { name: String },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// let log = Arc::new(Mutex::new(vec![]));
+    /// let hook_log = log.clone();
+    /// db.hook::<User>(HookEvent::AfterInsert, move |txn, user| {
+    ///     hook_log.lock().unwrap().push(txn.query_one(user.name()));
+    /// });
+    ///
+    /// let mut txn = client.transaction_mut(&db);
+    /// txn.insert(User { name: "alice" });
+    /// assert_eq!(*log.lock().unwrap(), ["alice"]);
+    /// ```
+    /// Hooks that run before a change are also called when the change fails afterwards, for example because of a unique conflict.
+    /// Soft deletes with [crate::TransactionMut::delete] call the [HookEvent::BeforeDelete] hooks,
+    /// and [crate::TransactionMut::restore] calls the update hooks.
+    ///
+    /// Hooks are not called for changes by [crate::TransactionMut::update_where], `delete_where`,
+    /// [Database::import_csv] and migrations.
+    pub fn hook<T: Table<Schema = S> + 'static>(
+        &self,
+        event: HookEvent,
+        hook: impl for<'t> Fn(&Transaction<'t, S>, TableRow<'t, T>) + Send + Sync + 'static,
+    ) {
+        self.hooks.add(T::NAME, event, move |txn, idx| {
+            let row = TableRow {
+                _p: PhantomData,
+                _local: PhantomData,
+                idx,
+            };
+            hook(txn, row)
+        });
+    }
+}
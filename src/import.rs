@@ -0,0 +1,481 @@
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader},
+};
+
+use rusqlite::{types::Value, TransactionBehavior};
+
+#[cfg(feature = "postgres")]
+mod postgres;
+
+#[cfg(feature = "postgres")]
+pub use postgres::PostgresImporter;
+
+use crate::{
+    error::unwrap_db,
+    hash::{Column, ColumnType, TypBuilder},
+    migrate::schema_version,
+    public_id::{IdKind, PUBLIC_ID},
+    Database, Table,
+};
+
+/// Options for [Database::import_csv].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportOptions {
+    /// The number of rows that are inserted in each transaction.
+    pub batch_size: usize,
+    /// The character that separates the fields of a line.
+    pub delimiter: char,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 1000,
+            delimiter: ',',
+        }
+    }
+}
+
+/// Result of [Database::import_csv].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ImportReport {
+    /// The number of rows that were inserted.
+    pub inserted: usize,
+    /// The lines that could not be inserted.
+    pub errors: Vec<LineError>,
+}
+
+/// A line of the CSV input that could not be inserted, see [ImportReport].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineError {
+    /// The line number where the record starts, the header is line 1.
+    pub line: usize,
+    /// Why the line could not be inserted.
+    pub message: String,
+}
+
+/// Error returned by [Database::import_csv] when nothing can be imported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportError {
+    /// Reading the input failed, this contains the error message.
+    Io(String),
+    /// The input has no header line.
+    MissingHeader,
+    /// The header has a column that the table does not have.
+    UnknownColumn(String),
+    /// The header has the same column more than once.
+    DuplicateColumn(String),
+    /// The table has a column that is required, but not in the header.
+    MissingColumn(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(msg) => write!(f, "io error: {msg}"),
+            ImportError::MissingHeader => write!(f, "missing header"),
+            ImportError::UnknownColumn(col) => write!(f, "unknown column `{col}`"),
+            ImportError::DuplicateColumn(col) => write!(f, "duplicate column `{col}`"),
+            ImportError::MissingColumn(col) => write!(f, "missing column `{col}`"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<io::Error> for ImportError {
+    fn from(value: io::Error) -> Self {
+        ImportError::Io(value.to_string())
+    }
+}
+
+/// A source of rows for [Database::import], for example a table of another database.
+///
+/// With the `postgres` feature, this is implemented by [PostgresImporter].
+pub trait Importer {
+    /// The names of the columns in the rows, this works like the header of [Database::import_csv].
+    fn columns(&self) -> Vec<String>;
+
+    /// Read the next row with a value for each column, this returns [None] when there are no rows left.
+    fn next_row(&mut self) -> Result<Option<Vec<ImportValue>>, ImportError>;
+}
+
+/// A value in a row of an [Importer].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportValue {
+    Null,
+    Integer(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl fmt::Display for ImportValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportValue::Null => write!(f, "NULL"),
+            ImportValue::Integer(x) => write!(f, "{x}"),
+            ImportValue::Float(x) => write!(f, "{x}"),
+            ImportValue::Text(x) => write!(f, "{x}"),
+        }
+    }
+}
+
+impl From<i64> for ImportValue {
+    fn from(value: i64) -> Self {
+        ImportValue::Integer(value)
+    }
+}
+
+impl From<f64> for ImportValue {
+    fn from(value: f64) -> Self {
+        ImportValue::Float(value)
+    }
+}
+
+impl From<String> for ImportValue {
+    fn from(value: String) -> Self {
+        ImportValue::Text(value)
+    }
+}
+
+impl From<&str> for ImportValue {
+    fn from(value: &str) -> Self {
+        ImportValue::Text(value.to_owned())
+    }
+}
+
+impl<T: Into<ImportValue>> From<Option<T>> for ImportValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(ImportValue::Null, Into::into)
+    }
+}
+
+impl<S> Database<S> {
+    /// Insert the rows of a CSV file into table `T`.
+    ///
+    /// The first line of the input is the header with the names of the columns.
+    /// Columns that are nullable or have a default value can be left out, empty fields are imported as `NULL` for nullable columns.
+    /// The `id` column can be included to keep the ids of exported rows, so that other tables can refer to them.
+    /// The header is checked before anything is imported.
+    ///
+    /// The rows are inserted in transactions of [ImportOptions::batch_size] rows, which wait for other
+    /// [crate::TransactionMut]s to finish.
+    /// Lines that do not have the right types or that violate a constraint are skipped and reported in [ImportReport].
+    /// ```no_run
+    /// # use std::fs::File;
+    /// # use rust_query::{migration::{schema, Config}, ImportOptions, LocalClient};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     Track { name: String, milliseconds: i64 },
+    /// # }
+    /// # use v0::*;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// let report = db.import_csv::<Track>(File::open("tracks.csv")?, ImportOptions::default())?;
+    /// for err in report.errors {
+    ///     println!("line {}: {}", err.line, err.message);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// This function will panic if the schema was modified compared to when the [Database] value was created.
+    pub fn import_csv<T: Table<Schema = S>>(
+        &self,
+        reader: impl io::Read,
+        options: ImportOptions,
+    ) -> Result<ImportReport, ImportError> {
+        use r2d2::ManageConnection;
+
+        let mut csv = CsvReader {
+            reader: BufReader::new(reader),
+            line: 0,
+            delimiter: options.delimiter,
+        };
+        let Some((_, header)) = csv.next_record()? else {
+            return Err(ImportError::MissingHeader);
+        };
+        let (columns, sql, public_id) = insert_sql::<T>(&header)?;
+
+        let mut report = ImportReport::default();
+        let mut done = false;
+        while !done {
+            let conn = unwrap_db(self.manager.connect());
+            let txn = self.import_transaction(&conn);
+
+            let mut stmt = unwrap_db(txn.prepare_cached(&sql));
+            for _ in 0..options.batch_size.max(1) {
+                let Some((line, record)) = csv.next_record()? else {
+                    done = true;
+                    break;
+                };
+                let res = parse_record(&columns, record).and_then(|mut params| {
+                    params.extend(public_id.map(|kind| Value::Text(kind.generate())));
+                    stmt.execute(rusqlite::params_from_iter(params))
+                        .map_err(|err| err.to_string())
+                });
+                match res {
+                    Ok(_) => report.inserted += 1,
+                    Err(message) => report.errors.push(LineError { line, message }),
+                }
+            }
+            drop(stmt);
+            self.commit_import(txn);
+        }
+        Ok(report)
+    }
+
+    /// Insert the rows of an [Importer] into table `T`, for example to move the data of a service from another database.
+    ///
+    /// The columns of the importer must match the table just like the header of [Database::import_csv].
+    /// All rows are streamed into one transaction, so nothing is imported if the [Importer] returns an error.
+    /// Rows that do not have the right types or that violate a constraint are skipped and reported in [ImportReport],
+    /// the line of a row is its position in the importer, starting at 1.
+    /// ```no_run
+    /// # #[cfg(feature = "postgres")]
+    /// # use rust_query::{migration::{schema, Config}, LocalClient, PostgresImporter};
+    /// # #[cfg(feature = "postgres")]
+    /// # use postgres::NoTls;
+    /// # #[cfg(not(feature = "postgres"))]
+    /// # fn main() {}
+    /// # #[cfg(feature = "postgres")]
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     Customer { name: String, email: String },
+    /// # }
+    /// # #[cfg(feature = "postgres")]
+    /// # use v0::*;
+    /// # #[cfg(feature = "postgres")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// let mut pg = postgres::Client::connect("host=localhost user=postgres", NoTls)?;
+    /// let importer = PostgresImporter::new(&mut pg, "SELECT id, name, email FROM customer")?;
+    /// let report = db.import::<Customer>(importer)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// This function will panic if the schema was modified compared to when the [Database] value was created.
+    pub fn import<T: Table<Schema = S>>(
+        &self,
+        mut importer: impl Importer,
+    ) -> Result<ImportReport, ImportError> {
+        use r2d2::ManageConnection;
+
+        let (columns, sql, public_id) = insert_sql::<T>(&importer.columns())?;
+
+        let conn = unwrap_db(self.manager.connect());
+        let txn = self.import_transaction(&conn);
+
+        let mut report = ImportReport::default();
+        let mut stmt = unwrap_db(txn.prepare_cached(&sql));
+        let mut line = 0;
+        while let Some(row) = importer.next_row()? {
+            line += 1;
+            let res = check_row(&columns, row).and_then(|mut params| {
+                params.extend(public_id.map(|kind| Value::Text(kind.generate())));
+                stmt.execute(rusqlite::params_from_iter(params))
+                    .map_err(|err| err.to_string())
+            });
+            match res {
+                Ok(_) => report.inserted += 1,
+                Err(message) => report.errors.push(LineError { line, message }),
+            }
+        }
+        drop(stmt);
+        self.commit_import(txn);
+        Ok(report)
+    }
+
+    fn import_transaction<'c>(&self, conn: &'c rusqlite::Connection) -> rusqlite::Transaction<'c> {
+        let txn = self
+            .busy_retry
+            .run(|| rusqlite::Transaction::new_unchecked(conn, TransactionBehavior::Immediate));
+        let txn = unwrap_db(txn);
+        if unwrap_db(schema_version(&txn)) != self.schema_version {
+            panic!("The database schema was updated unexpectedly")
+        }
+        txn
+    }
+
+    fn commit_import(&self, txn: rusqlite::Transaction) {
+        self.watchers.count_changes(&txn);
+        unwrap_db(txn.commit());
+        self.watchers.notify();
+    }
+}
+
+/// Find the columns of table `T` with the names in `header` and make the statement that inserts them.
+///
+/// The returned kind of public id is generated for every row, if it is not in `header`.
+fn insert_sql<T: Table>(
+    header: &[String],
+) -> Result<(Vec<Column>, String, Option<IdKind>), ImportError> {
+    let mut b = TypBuilder::default();
+    T::typs(&mut b);
+    let id = Column {
+        name: T::ID.to_owned(),
+        typ: ColumnType::Integer,
+        nullable: true,
+        fk: None,
+        on_delete: Default::default(),
+        generated: None,
+        default: None,
+        collate: None,
+    };
+    let columns: Vec<Column> = header
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            if header[..i].contains(name) {
+                return Err(ImportError::DuplicateColumn(name.clone()));
+            }
+            // generated columns are computed by sqlite
+            std::iter::once(&id)
+                .chain(b.ast.columns.iter())
+                .find(|col| &col.name == name && col.generated.is_none())
+                .cloned()
+                .ok_or_else(|| ImportError::UnknownColumn(name.clone()))
+        })
+        .collect::<Result<_, _>>()?;
+
+    let mut names: Vec<String> = header.iter().map(|name| format!("\"{name}\"")).collect();
+    let mut values = vec!["?"; header.len()];
+    for col in &*b.ast.columns {
+        let optional = col.nullable || col.generated.is_some() || col.default.is_some();
+        if header.contains(&col.name) || optional {
+            continue;
+        }
+        if col.name == PUBLIC_ID && T::PUBLIC_ID.is_some() {
+            // a new public id is generated for every row, it is the last parameter
+            names.push(format!("\"{PUBLIC_ID}\""));
+            values.push("?");
+            continue;
+        }
+        // these columns are set just like with [crate::TransactionMut::insert]
+        let default = match col.name.as_str() {
+            "created_at" | "updated_at" if T::TIMESTAMPS => "unixepoch('now')",
+            "version" if T::VERSIONED => "0",
+            _ => return Err(ImportError::MissingColumn(col.name.clone())),
+        };
+        names.push(format!("\"{}\"", col.name));
+        values.push(default);
+    }
+    let sql = format!(
+        "INSERT INTO \"{}\" ({}) VALUES ({})",
+        T::NAME,
+        names.join(", "),
+        values.join(", ")
+    );
+    let public_id = T::PUBLIC_ID.filter(|_| !header.iter().any(|x| x == PUBLIC_ID));
+    Ok((columns, sql, public_id))
+}
+
+fn check_row(columns: &[Column], row: Vec<ImportValue>) -> Result<Vec<Value>, String> {
+    if row.len() != columns.len() {
+        return Err(format!(
+            "expected {} values, found {}",
+            columns.len(),
+            row.len()
+        ));
+    }
+    columns
+        .iter()
+        .zip(row)
+        .map(|(col, val)| match (col.typ, val) {
+            (_, ImportValue::Null) if col.nullable => Ok(Value::Null),
+            (ColumnType::Integer, ImportValue::Integer(x)) => Ok(Value::Integer(x)),
+            (ColumnType::Float, ImportValue::Integer(x)) => Ok(Value::Real(x as f64)),
+            (ColumnType::Float, ImportValue::Float(x)) => Ok(Value::Real(x)),
+            (ColumnType::String, ImportValue::Text(x)) => Ok(Value::Text(x)),
+            (_, val) => Err(format!("column `{}` can not be `{val}`", col.name)),
+        })
+        .collect()
+}
+
+fn parse_record(columns: &[Column], record: Vec<String>) -> Result<Vec<Value>, String> {
+    if record.len() != columns.len() {
+        return Err(format!(
+            "expected {} fields, found {}",
+            columns.len(),
+            record.len()
+        ));
+    }
+    columns
+        .iter()
+        .zip(record)
+        .map(|(col, field)| {
+            if col.nullable && field.is_empty() {
+                return Ok(Value::Null);
+            }
+            match col.typ {
+                ColumnType::Integer => field.parse().map(Value::Integer).ok(),
+                ColumnType::Float => field.parse().map(Value::Real).ok(),
+                ColumnType::String => Some(Value::Text(field.clone())),
+            }
+            .ok_or_else(|| format!("column `{}` can not be `{field}`", col.name))
+        })
+        .collect()
+}
+
+/// Reads the records of a CSV file, quoted fields can contain the delimiter, quotes and newlines.
+struct CsvReader<R> {
+    reader: R,
+    line: usize,
+    delimiter: char,
+}
+
+impl<R: BufRead> CsvReader<R> {
+    /// Returns the line number where the record starts and the fields of the record.
+    fn next_record(&mut self) -> io::Result<Option<(usize, Vec<String>)>> {
+        let mut buf = String::new();
+        // empty lines are skipped
+        while buf.trim_end_matches(['\r', '\n']).is_empty() {
+            buf.clear();
+            if self.reader.read_line(&mut buf)? == 0 {
+                return Ok(None);
+            }
+            self.line += 1;
+        }
+        let start = self.line;
+
+        let mut fields = vec![];
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars = buf.chars().collect::<Vec<_>>().into_iter().peekable();
+        loop {
+            let Some(c) = chars.next() else {
+                if !quoted {
+                    break;
+                }
+                // the quoted field continues on the next line
+                let mut next = String::new();
+                if self.reader.read_line(&mut next)? == 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!("unclosed quote on line {start}"),
+                    ));
+                }
+                self.line += 1;
+                chars = next.chars().collect::<Vec<_>>().into_iter().peekable();
+                continue;
+            };
+            match c {
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' if quoted => quoted = false,
+                '"' if field.is_empty() => quoted = true,
+                '\n' | '\r' if !quoted => {}
+                c if c == self.delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+                c => field.push(c),
+            }
+        }
+        fields.push(field);
+        Ok(Some((start, fields)))
+    }
+}
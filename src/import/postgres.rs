@@ -0,0 +1,95 @@
+use postgres::{fallible_iterator::FallibleIterator, types::Type, Client, RowIter};
+
+use super::{ImportError, ImportValue, Importer};
+
+/// [Importer] that streams the results of a query on a Postgres database.
+///
+/// Columns can have the types `bool`, `int2`, `int4`, `int8`, `float4`, `float8`, `text`, `varchar`, `bpchar` or `name`.
+/// Other types can be cast in the query, for example `SELECT created::text FROM ..`.
+pub struct PostgresImporter<'a> {
+    columns: Vec<(String, Type)>,
+    rows: RowIter<'a>,
+}
+
+impl<'a> PostgresImporter<'a> {
+    /// Run `query` on `client`, the rows are retrieved while they are imported.
+    ///
+    /// Returns an error if the query fails or if a column has a type that is not supported.
+    pub fn new(client: &'a mut Client, query: &str) -> Result<Self, ImportError> {
+        let stmt = client.prepare(query)?;
+        let columns = stmt
+            .columns()
+            .iter()
+            .map(|col| {
+                let typ = col.type_().clone();
+                if value_kind(&typ).is_none() {
+                    return Err(ImportError::Io(format!(
+                        "column `{}` has unsupported type `{typ}`",
+                        col.name()
+                    )));
+                }
+                Ok((col.name().to_owned(), typ))
+            })
+            .collect::<Result<_, _>>()?;
+        let rows = client.query_raw(&stmt, std::iter::empty::<i32>())?;
+        Ok(Self { columns, rows })
+    }
+}
+
+enum Kind {
+    Bool,
+    Int2,
+    Int4,
+    Int8,
+    Float4,
+    Float8,
+    Text,
+}
+
+fn value_kind(typ: &Type) -> Option<Kind> {
+    Some(match *typ {
+        Type::BOOL => Kind::Bool,
+        Type::INT2 => Kind::Int2,
+        Type::INT4 => Kind::Int4,
+        Type::INT8 => Kind::Int8,
+        Type::FLOAT4 => Kind::Float4,
+        Type::FLOAT8 => Kind::Float8,
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME => Kind::Text,
+        _ => return None,
+    })
+}
+
+impl Importer for PostgresImporter<'_> {
+    fn columns(&self) -> Vec<String> {
+        self.columns.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    fn next_row(&mut self) -> Result<Option<Vec<ImportValue>>, ImportError> {
+        let Some(row) = self.rows.next()? else {
+            return Ok(None);
+        };
+        self.columns
+            .iter()
+            .enumerate()
+            .map(|(i, (_, typ))| {
+                let val = match value_kind(typ).unwrap() {
+                    Kind::Bool => row.try_get::<_, Option<bool>>(i)?.map(|x| x as i64).into(),
+                    Kind::Int2 => row.try_get::<_, Option<i16>>(i)?.map(i64::from).into(),
+                    Kind::Int4 => row.try_get::<_, Option<i32>>(i)?.map(i64::from).into(),
+                    Kind::Int8 => row.try_get::<_, Option<i64>>(i)?.into(),
+                    Kind::Float4 => row.try_get::<_, Option<f32>>(i)?.map(f64::from).into(),
+                    Kind::Float8 => row.try_get::<_, Option<f64>>(i)?.into(),
+                    Kind::Text => row.try_get::<_, Option<String>>(i)?.into(),
+                };
+                Ok(val)
+            })
+            .collect::<Result<_, _>>()
+            .map(Some)
+    }
+}
+
+impl From<postgres::Error> for ImportError {
+    fn from(value: postgres::Error) -> Self {
+        ImportError::Io(value.to_string())
+    }
+}
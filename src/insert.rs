@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use crate::{alias::Field, ast::MySelect, Dummy, IntoColumn, Table};
+use crate::{alias::Field, ast::MySelect, Column, Dummy, Error, IntoColumn, Table};
 
 /// this trait is not safe to implement
 pub trait Writable<'t> {
@@ -37,6 +37,14 @@ pub struct Reader<'x, 't, S> {
     pub(crate) _p2: PhantomData<fn(&'t ()) -> &'t ()>,
 }
 
+impl<S> Copy for Reader<'_, '_, S> {}
+
+impl<S> Clone for Reader<'_, '_, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
 impl<'t, S> Reader<'_, 't, S> {
     pub fn col(&self, name: &'static str, val: impl IntoColumn<'t, S>) {
         let field = Field::Str(name);
@@ -44,3 +52,50 @@ impl<'t, S> Reader<'_, 't, S> {
         self.ast.select.push(Box::new((expr, field)))
     }
 }
+
+/// Implemented by the marker types of unique constraints.
+///
+/// Every unique constraint has a marker type in the module of its table.
+/// For example the constraint `#[unique_by_email]` on table `Customer` has marker type `customer::UniqueByEmail`.
+/// The marker is used to select a unique constraint in [crate::Transaction::lookup] and [crate::TransactionMut::insert_or_update].
+pub trait Unique {
+    /// The table that has this unique constraint.
+    type Table: Table;
+}
+
+/// Values that can be used to find a row with the unique constraint `U`.
+///
+/// This is implemented for tuples of the constraint columns, like `(email,)`,
+/// and for the table struct that is used to insert rows.
+pub trait UniqueKey<'t, U: Unique> {
+    #[doc(hidden)]
+    fn lookup(&self) -> Column<'t, <U::Table as Table>::Schema, Option<U::Table>>;
+}
+
+/// Error returned by [crate::TransactionMut::checked_insert] and [crate::TransactionMut::checked_update].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WriteError<C> {
+    /// The new values conflict with another row, see [crate::TransactionMut::try_insert] for the type of `C`.
+    Conflict(C),
+    /// The new values do not satisfy a `CHECK` constraint, this contains the message from sqlite.
+    Check(String),
+    /// Sqlite failed to write the row, for example because the disk is full.
+    Database(Error),
+}
+
+impl<C> WriteError<C> {
+    /// Panics on errors that are not conflicts, this is used by the methods that only return conflicts.
+    #[track_caller]
+    pub(crate) fn unwrap_check(self) -> C {
+        match self {
+            WriteError::Conflict(conflict) => conflict,
+            WriteError::Check(msg) => panic!("{msg}"),
+            WriteError::Database(err) => panic!("{err}"),
+        }
+    }
+
+    /// Wrap a sqlite error that is not caused by a constraint.
+    pub(crate) fn db(err: rusqlite::Error) -> Self {
+        WriteError::Database(Error::from(err))
+    }
+}
@@ -0,0 +1,268 @@
+use std::{
+    any::Any,
+    cell::Cell,
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use rusqlite::{Connection, ErrorCode};
+
+use crate::{args::Query, error::unwrap_db, memo::CacheGuard, Database, Transaction};
+
+/// Number of virtual machine instructions between checks for interrupts.
+const CHECK_INTERVAL: i32 = 1000;
+
+#[derive(Clone, Copy)]
+struct Limits {
+    deadline: Option<Instant>,
+    max_rows: Option<usize>,
+}
+
+thread_local! {
+    // limits of the queries that are running on this thread
+    static LIMITS: Cell<Limits> = const {
+        Cell::new(Limits {
+            deadline: None,
+            max_rows: None,
+        })
+    };
+    // the reason that the progress handler stopped the last query
+    static STOPPED: Cell<Option<QueryError>> = const { Cell::new(None) };
+    // whether stopped queries are returned by [Transaction::try_query]
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Restores the previous limits when dropped.
+struct LimitsGuard(Limits);
+
+impl LimitsGuard {
+    /// Add limits for the queries on this thread, limits that are already set can only get stricter.
+    fn new(timeout: Option<Duration>, max_rows: Option<usize>) -> Self {
+        let old = LIMITS.get();
+        let deadline = timeout.map(|x| Instant::now() + x);
+        LIMITS.set(Limits {
+            deadline: min(old.deadline, deadline),
+            max_rows: min(old.max_rows, max_rows),
+        });
+        Self(old)
+    }
+}
+
+impl Drop for LimitsGuard {
+    fn drop(&mut self) {
+        LIMITS.set(self.0);
+    }
+}
+
+fn min<T: Ord>(a: Option<T>, b: Option<T>) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Keeps track of the calls to [InterruptHandle::interrupt].
+#[derive(Default)]
+pub(crate) struct Interrupts {
+    generation: AtomicU64,
+}
+
+impl Interrupts {
+    /// Install a progress handler that stops queries when they are interrupted or past their deadline.
+    ///
+    /// Every transaction uses a new connection, so the connection is interrupted
+    /// if [InterruptHandle::interrupt] is called after it is created.
+    pub fn install(self: &Arc<Self>, conn: &Connection) {
+        let this = self.clone();
+        let start = self.generation.load(Ordering::Relaxed);
+        conn.progress_handler(
+            CHECK_INTERVAL,
+            Some(move || {
+                let reason = if this.generation.load(Ordering::Relaxed) != start {
+                    QueryError::Interrupted
+                } else if LIMITS
+                    .get()
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline)
+                {
+                    QueryError::Timeout
+                } else {
+                    return false;
+                };
+                STOPPED.set(Some(reason));
+                // returning true stops the query
+                true
+            }),
+        );
+    }
+}
+
+/// Unwrap the result of executing a query.
+///
+/// Stopped queries are propagated to [Transaction::try_query] without running the panic hook,
+/// they cause a normal panic when there is no [Transaction::try_query].
+#[track_caller]
+pub(crate) fn unwrap_interrupt<T>(res: rusqlite::Result<T>) -> T {
+    match res {
+        Err(err) if err.sqlite_error_code() == Some(ErrorCode::OperationInterrupted) => {
+            stop(STOPPED.take().unwrap_or(QueryError::Interrupted))
+        }
+        res => unwrap_db(res),
+    }
+}
+
+/// Check that a query does not return more rows than allowed by the [QueryPolicy].
+#[track_caller]
+pub(crate) fn check_rows(rows: usize) {
+    if LIMITS.get().max_rows.is_some_and(|max| rows > max) {
+        stop(QueryError::TooManyRows)
+    }
+}
+
+#[track_caller]
+fn stop(reason: QueryError) -> ! {
+    if CATCHING.get() {
+        panic::resume_unwind(Box::new(reason))
+    }
+    panic!("{reason}")
+}
+
+fn catch_stopped<R>(res: Result<R, Box<dyn Any + Send>>) -> Result<R, QueryError> {
+    match res {
+        Ok(res) => Ok(res),
+        Err(payload) => match payload.downcast::<QueryError>() {
+            Ok(err) => Err(*err),
+            Err(payload) => panic::resume_unwind(payload),
+        },
+    }
+}
+
+/// Error returned by [Transaction::try_query] when a query is stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryError {
+    /// The query was stopped by [InterruptHandle::interrupt].
+    Interrupted,
+    /// The query took longer than [Transaction::query_timeout] or [QueryPolicy::max_duration].
+    Timeout,
+    /// The query returned more rows than [QueryPolicy::max_rows].
+    TooManyRows,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::Interrupted => write!(f, "the query was interrupted"),
+            QueryError::Timeout => write!(f, "the query took longer than its time limit"),
+            QueryError::TooManyRows => write!(f, "the query returned more rows than its limit"),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// Limits for every query of a [Transaction], see [Transaction::set_policy].
+///
+/// This is useful to make sure that a server can not be overloaded with expensive queries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueryPolicy {
+    /// The maximum number of rows that a query can return.
+    pub max_rows: Option<usize>,
+    /// The maximum time that a query can run.
+    pub max_duration: Option<Duration>,
+}
+
+/// Handle to stop the queries of a [Database] from another thread, see [Database::interrupt_handle].
+#[derive(Clone)]
+pub struct InterruptHandle {
+    inner: Arc<Interrupts>,
+}
+
+impl InterruptHandle {
+    /// Stop the queries of all transactions that are in progress.
+    ///
+    /// With [Transaction::try_query] a stopped query returns [QueryError::Interrupted], otherwise it panics.
+    /// Later queries in these transactions can also be stopped, so they should not be used anymore.
+    /// Transactions that are started after this call are not affected.
+    pub fn interrupt(&self) {
+        self.inner.generation.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl<S> Database<S> {
+    /// Get a handle that can stop the transactions of this [Database] from another thread.
+    ///
+    /// This is useful to cancel slow queries, for example when the user of an application
+    /// closes the report that the queries are for.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        InterruptHandle {
+            inner: self.interrupts.clone(),
+        }
+    }
+}
+
+impl<'t, S> Transaction<'t, S> {
+    /// Set limits for all queries of this [Transaction].
+    ///
+    /// A query that exceeds the limits is stopped. It returns a [QueryError] with
+    /// [Transaction::try_query] and panics otherwise.
+    /// ```
+    /// # use std::time::Duration;
+    /// # use rust_query::{QueryError, QueryPolicy, Transaction};
+    /// # fn report<S>(txn: &mut Transaction<S>) -> Result<Vec<i64>, QueryError> {
+    /// txn.set_policy(QueryPolicy {
+    ///     max_rows: Some(1000),
+    ///     max_duration: Some(Duration::from_millis(100)),
+    /// });
+    /// txn.try_query(|rows| rows.into_vec(1))
+    /// # }
+    /// ```
+    pub fn set_policy(&mut self, policy: QueryPolicy) {
+        self.policy = policy;
+    }
+
+    /// Run `f` with the limits of the [QueryPolicy] and the cache of this [Transaction].
+    pub(crate) fn with_policy<R>(&self, f: impl FnOnce() -> R) -> R {
+        let _guard = LimitsGuard::new(self.policy.max_duration, self.policy.max_rows);
+        let _cache = CacheGuard::new(self.cache.as_ref());
+        f()
+    }
+
+    /// Same as [Transaction::query], but returns a [QueryError] if a query is stopped.
+    ///
+    /// Queries are stopped by [InterruptHandle::interrupt], [Transaction::query_timeout]
+    /// and the [QueryPolicy] of the [Transaction].
+    /// After a query is stopped by [InterruptHandle::interrupt], other queries in this [Transaction] can also be stopped.
+    ///
+    /// The stopped query unwinds out of `f`, so this requires `panic = "unwind"`.
+    pub fn try_query<F, R>(&self, f: F) -> Result<R, QueryError>
+    where
+        F: for<'a> FnOnce(&'a mut Query<'t, 'a, S>) -> R,
+    {
+        let prev = CATCHING.replace(true);
+        let res = panic::catch_unwind(AssertUnwindSafe(|| self.query(f)));
+        CATCHING.set(prev);
+        catch_stopped(res)
+    }
+
+    /// Same as [Transaction::try_query], but also stops the queries that are
+    /// still running when `timeout` has passed.
+    /// ```
+    /// # use std::time::Duration;
+    /// # use rust_query::{Transaction, QueryError};
+    /// # fn report<S>(txn: &Transaction<S>) -> Result<i64, QueryError> {
+    /// txn.query_timeout(Duration::from_secs(1), |rows| rows.count())
+    /// # }
+    /// ```
+    pub fn query_timeout<F, R>(&self, timeout: Duration, f: F) -> Result<R, QueryError>
+    where
+        F: for<'a> FnOnce(&'a mut Query<'t, 'a, S>) -> R,
+    {
+        let _guard = LimitsGuard::new(Some(timeout), None);
+        self.try_query(f)
+    }
+}
@@ -0,0 +1,118 @@
+use rusqlite::OptionalExtension;
+
+/// Name of the table that stores the tables that are copied by a journaled migration.
+pub(crate) const JOURNAL_TABLE: &str = "_migration_journal";
+
+/// The tables that were already copied for the migration of schema `name` to `version`.
+pub(crate) fn done(
+    conn: &rusqlite::Connection,
+    name: &str,
+    version: i64,
+) -> rusqlite::Result<Vec<String>> {
+    let exists = conn
+        .query_row(
+            "SELECT 1 FROM sqlite_schema WHERE type = 'table' AND name = ?",
+            [JOURNAL_TABLE],
+            |_| Ok(()),
+        )
+        .optional()?;
+    if exists.is_none() {
+        return Ok(vec![]);
+    }
+    let sql =
+        format!("SELECT \"table\" FROM \"{JOURNAL_TABLE}\" WHERE \"name\" = ? AND \"version\" = ?");
+    let mut stmt = conn.prepare(&sql)?;
+    let tables = stmt.query_map(rusqlite::params![name, version], |r| r.get(0))?;
+    tables.collect()
+}
+
+/// Record that `table` is copied, so that it is not copied again when the migration is resumed.
+///
+/// The record has to be committed together with the copied table.
+pub(crate) fn record(
+    conn: &rusqlite::Connection,
+    name: &str,
+    version: i64,
+    table: &str,
+) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS \"{JOURNAL_TABLE}\" (
+            \"name\" text NOT NULL,
+            \"version\" integer NOT NULL,
+            \"table\" text NOT NULL,
+            PRIMARY KEY (\"name\", \"version\", \"table\")
+        );"
+    ))?;
+    let sql = format!(
+        "INSERT INTO \"{JOURNAL_TABLE}\" (\"name\", \"version\", \"table\") VALUES (?, ?, ?)"
+    );
+    conn.execute(&sql, rusqlite::params![name, version, table])?;
+    Ok(())
+}
+
+/// Remove the tables of a migration that is finished.
+pub(crate) fn clear(conn: &rusqlite::Connection, name: &str, version: i64) -> rusqlite::Result<()> {
+    if done(conn, name, version)?.is_empty() {
+        return Ok(());
+    }
+    let sql = format!("DELETE FROM \"{JOURNAL_TABLE}\" WHERE \"name\" = ? AND \"version\" = ?");
+    conn.execute(&sql, rusqlite::params![name, version])?;
+    Ok(())
+}
+
+/// Exclusive transaction of a migration, started and committed with explicit statements.
+///
+/// A journaled migration commits after every copied table, so this can not be a [rusqlite::Transaction].
+/// The transaction is rolled back when it is dropped before [Owned::commit].
+pub(crate) struct Owned<'t> {
+    conn: &'t rusqlite::Connection,
+}
+
+impl<'t> Owned<'t> {
+    pub(crate) fn begin(conn: &'t rusqlite::Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch("BEGIN EXCLUSIVE")?;
+        Ok(Self { conn })
+    }
+
+    /// Commit the changes so far and continue in a new exclusive transaction.
+    ///
+    /// When the new transaction can not be started, the error is returned and the migration has to stop,
+    /// because statements on the connection would otherwise be committed one by one.
+    pub(crate) fn checkpoint(&self) -> rusqlite::Result<()> {
+        self.checkpoint_with(|_| Ok(()))
+    }
+
+    /// Like [Owned::checkpoint], but run `f` between the transactions.
+    ///
+    /// The new transaction is also started when `f` fails, the error of `f` is returned after that.
+    pub(crate) fn checkpoint_with<T>(
+        &self,
+        f: impl FnOnce(&rusqlite::Connection) -> rusqlite::Result<T>,
+    ) -> rusqlite::Result<T> {
+        self.conn.execute_batch("COMMIT")?;
+        let res = f(self.conn);
+        self.conn.execute_batch("BEGIN EXCLUSIVE")?;
+        res
+    }
+
+    pub(crate) fn commit(self) -> rusqlite::Result<()> {
+        self.conn.execute_batch("COMMIT")
+    }
+}
+
+impl std::ops::Deref for Owned<'_> {
+    type Target = rusqlite::Connection;
+
+    fn deref(&self) -> &Self::Target {
+        self.conn
+    }
+}
+
+impl Drop for Owned<'_> {
+    fn drop(&mut self) {
+        // there is no transaction after a successful commit or a failed checkpoint
+        if !self.conn.is_autocommit() {
+            let _ = self.conn.execute_batch("ROLLBACK");
+        }
+    }
+}
@@ -6,67 +6,165 @@ extern crate self as rust_query;
 mod aggregate;
 mod alias;
 mod ast;
+mod audit;
+mod batch;
+mod bulk;
 mod client;
+mod codegen;
+mod data_export;
 mod db;
+mod dual_write;
 mod dummy;
+mod dyn_filter;
+mod error;
 mod exec;
+#[cfg(feature = "serde")]
+mod export;
+mod fingerprint;
+mod fts;
+mod function;
+mod generate;
+mod graph;
+mod group;
 mod hash;
+mod hook;
+mod import;
 mod insert;
+mod interrupt;
+mod journal;
+mod link;
+mod maintenance;
+mod memo;
+mod metrics;
 mod migrate;
 mod mymap;
+mod namespace;
+mod page;
+mod parallel;
 mod pragma;
+mod public_id;
+mod raw;
 mod ref_cast_impl;
+mod refresh;
+mod retry;
 mod rows;
+mod scope;
+mod skeleton;
+mod span;
+mod statement;
+mod subquery;
+mod tables;
 mod token;
+mod trace;
 mod transaction;
 mod value;
+mod version;
+mod watch;
+mod window;
+mod write_lock;
 
 pub use crate::dummy::Dummy;
 pub use aggregate::aggregate;
-pub use db::TableRow;
+pub use audit::{AuditEntry, AuditOperation};
+pub use batch::InsertReport;
+pub use bulk::{Changes, Update};
+pub use data_export::{BundleTable, DataBundle, DataExport};
+pub use db::{RowId, TableRow};
+pub use dyn_filter::{DynFilter, DynFilterError, DynOp, Order};
+pub use error::Error;
+pub use exec::OneError;
+pub use function::{AggregateFunction, Function};
+pub use graph::{DeleteGraphError, ForeignKeyError};
 use hash::TypBuilder;
+pub use hook::HookEvent;
+#[cfg(feature = "postgres")]
+pub use import::PostgresImporter;
+pub use import::{ImportError, ImportOptions, ImportReport, ImportValue, Importer, LineError};
+pub use insert::{Unique, UniqueKey, WriteError};
+pub use interrupt::{InterruptHandle, QueryError, QueryPolicy};
+pub use maintenance::{
+    Checkpoint, CheckpointMode, DatabaseStats, IndexStats, IntegrityError, TableStats, WalStats,
+};
+pub use page::{Page, Paginated};
+pub use public_id::PublicId;
+pub use raw::{FromRaw, RawError, RawExpr, RawParam, RawTyp};
 use ref_cast::RefCast;
+pub use refresh::{Refresh, RefreshReport};
+pub use retry::RetryPolicy;
 pub use rows::Rows;
-pub use rust_query_macros::FromDummy;
+pub use rust_query_macros::{FromDummy, FromRaw};
+pub use statement::{Statement, StatementError};
+pub use tables::ColumnInfo;
 pub use token::LocalClient;
-pub use transaction::{Database, Transaction, TransactionMut};
+pub use trace::{SlowQuery, Trace};
+pub use transaction::{Database, ReadOnlyDatabase, Transaction, TransactionMut, VersionConflict};
 pub use value::{Column, IntoColumn, UnixEpoch};
+pub use version::Version;
+pub use write_lock::WriteLock;
 
 /// Types that are used as closure arguments.
 ///
 /// You generally don't need to import these types.
 pub mod args {
     pub use crate::aggregate::Aggregate;
+    pub use crate::batch::Batch;
     pub use crate::exec::Query;
+    pub use crate::group::Group;
+    pub use crate::page::Paginate;
+    pub use crate::subquery::SubQuery;
+    pub use crate::value::operations::Case;
+    pub use crate::window::Window;
 }
 
 /// Types to declare schemas and migrations.
 ///
 /// A good starting point is too look at [crate::migration::schema].
 pub mod migration {
-    pub use crate::migrate::{Alter, Config, Create, Migrator, NoTable};
-    pub use expect_test::expect;
+    pub use crate::codegen::{json_schema, typescript_types};
+    pub use crate::hash::schema_hash;
+    pub use crate::migrate::{
+        Alter, Config, Create, Migrator, NoTable, Progress, Synchronous, TempStore,
+    };
+    pub use crate::skeleton::migration_skeleton;
     pub use rust_query_macros::schema;
 }
 
+/// Utilities to test queries against generated data.
+///
+/// See [testing::RowGenerator] for how to fill the tables of a database with random rows.
+pub mod testing {
+    pub use crate::generate::RowGenerator;
+    pub use crate::trace::collect_sql;
+}
+
 /// These items are only exposed for use by the proc macros.
 /// Direct use is unsupported.
 #[doc(hidden)]
 pub mod private {
     pub use crate::db::Col;
     pub use crate::dummy::{Cached, Cacher, Dummy, Row};
-    pub use crate::exec::show_sql;
-    pub use crate::hash::TypBuilder;
-    pub use crate::hash::{hash_schema, KangarooHasher};
+    pub use crate::fts::{fts_match, fts_rank};
+    pub use crate::hash::{verify_schema, KangarooHasher};
+    pub use crate::hash::{OnDelete, TypBuilder};
     pub use crate::insert::{Reader, Writable};
     pub use crate::migrate::{
         Migration, Schema, SchemaBuilder, TableCreation, TableMigration, TableTypBuilder, C, M,
     };
+    pub use crate::public_id::{by_public_id, IdKind};
+    pub use crate::raw::RawRow;
+    pub use crate::rows::JoinChildren;
+    pub use crate::tables::{columns, count, exists};
+    pub use crate::trace::show_sql;
+    pub use crate::transaction::Deletor;
     pub use crate::value::{MyTyp, Typed, ValueBuilder};
 
-    pub use expect_test::Expect;
     pub use ref_cast::RefCast;
     pub use sea_query::SimpleExpr;
+    #[cfg(feature = "serde")]
+    pub use serde;
+
+    /// Marker for a required column that is not set yet in a generated builder.
+    pub struct Unset;
 
     struct Null;
     struct NotNull;
@@ -96,6 +194,27 @@ pub mod private {
     pub fn valid_in_schema<S, T: ValidInSchema<S>>() {}
 
     pub fn valid_in_unique<S, T: ValidInSchema<S, N = NotNull>>() {}
+
+    /// Literals that can be the default value of a column with type `T`.
+    pub trait ValidDefault<T> {}
+
+    impl ValidDefault<i64> for i64 {}
+    impl ValidDefault<Option<i64>> for i64 {}
+    impl ValidDefault<f64> for f64 {}
+    impl ValidDefault<Option<f64>> for f64 {}
+    impl ValidDefault<String> for &str {}
+    impl ValidDefault<Option<String>> for &str {}
+    impl<T> ValidDefault<Option<T>> for Option<std::convert::Infallible> {}
+
+    pub fn valid_default<T, L: ValidDefault<T>>(_: L) {}
+
+    /// Column types that can have a collation.
+    pub trait ValidCollate {}
+
+    impl ValidCollate for String {}
+    impl ValidCollate for Option<String> {}
+
+    pub fn valid_collate<T: ValidCollate>() {}
 }
 
 /// This trait is implemented for all table types as generated by the [crate::migration::schema] macro.
@@ -114,6 +233,13 @@ pub trait Table: Sized + 'static {
         rows.join()
     }
 
+    /// Please refer to [Rows::join_with_deleted].
+    fn join_with_deleted<'inner>(
+        rows: &mut Rows<'inner, Self::Schema>,
+    ) -> Column<'inner, Self::Schema, Self> {
+        rows.join_with_deleted()
+    }
+
     /// The type returned by the [Table::dummy] method.
     type Dummy<'t>;
 
@@ -143,4 +269,31 @@ pub trait Table: Sized + 'static {
     const ID: &'static str = "";
     #[doc(hidden)]
     const NAME: &'static str = "";
+    #[doc(hidden)]
+    const SOFT_DELETE: bool = false;
+    #[doc(hidden)]
+    const TIMESTAMPS: bool = false;
+    #[doc(hidden)]
+    const VERSIONED: bool = false;
+    #[doc(hidden)]
+    const PUBLIC_ID: Option<private::IdKind> = None;
+    /// The column with the `scope` attribute and the name of the table that it references.
+    #[doc(hidden)]
+    const SCOPE: Option<(&'static str, &'static str)> = None;
 }
+
+/// This trait is implemented for tables with the `soft_delete` attribute, see [crate::migration::schema].
+///
+/// Rows of these tables are not removed by [TransactionMut::delete], instead their `deleted_at` column is set.
+/// Rows that are deleted like this are skipped by [Rows::join] and the generated methods to join child rows.
+///
+/// **You can not implement this trait yourself!**
+pub trait SoftDelete: Table {}
+
+/// This trait is implemented for tables with the `versioned` attribute, see [crate::migration::schema].
+///
+/// Rows of these tables have a `version` column that starts at zero and is incremented by every update.
+/// It can be used with [TransactionMut::update_if_version] to detect concurrent changes.
+///
+/// **You can not implement this trait yourself!**
+pub trait Versioned: Table {}
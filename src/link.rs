@@ -0,0 +1,42 @@
+use std::marker::PhantomData;
+
+use crate::{error::unwrap_db, transaction::Deletor, Table, TableRow};
+
+impl<S: 'static> Deletor<S> {
+    /// Delete the rows of link table `L` that reference both rows.
+    ///
+    /// This is used by the `remove_*` methods that are generated for tables with the `link_table` attribute.
+    /// Returns whether any row was deleted.
+    #[doc(hidden)]
+    pub fn unlink<L: Table<Schema = S>>(
+        &mut self,
+        (a, a_id): (&str, i64),
+        (b, b_id): (&str, i64),
+    ) -> bool {
+        let sql = format!(
+            "SELECT \"{}\" FROM \"{}\" WHERE \"{a}\" = ? AND \"{b}\" = ?",
+            L::ID,
+            L::NAME
+        );
+        let conn = self.inner.transaction();
+        let mut stmt = unwrap_db(conn.prepare_cached(&sql));
+        let ids: Vec<i64> = unwrap_db(
+            stmt.query_map([a_id, b_id], |r| r.get(0))
+                .and_then(|rows| rows.collect()),
+        );
+        drop(stmt);
+
+        let mut deleted = false;
+        for idx in ids {
+            let row = TableRow::<L> {
+                _p: PhantomData,
+                _local: PhantomData,
+                idx,
+            };
+            deleted |= self
+                .try_delete(row)
+                .unwrap_or_else(|()| panic!("a row of `{}` is still referenced", L::NAME));
+        }
+        deleted
+    }
+}
@@ -0,0 +1,440 @@
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    path::Path,
+    time::Duration,
+};
+
+use rusqlite::{
+    backup::{Backup, StepResult},
+    types::ValueRef,
+    Connection, OpenFlags, OptionalExtension,
+};
+
+use crate::{
+    fingerprint,
+    hash::{KangarooHasher, TypBuilder},
+    migrate::{foreign_key_check, schema_version, user_version, Schema},
+    Database, Error, Table,
+};
+
+/// Time to wait before retrying when the destination database is locked.
+const BUSY_PAUSE: Duration = Duration::from_millis(10);
+
+fn copy(src: &Connection, dst: &mut Connection) -> rusqlite::Result<()> {
+    let backup = Backup::new(src, dst)?;
+    loop {
+        // all pages are copied in one step to get a consistent snapshot,
+        // in WAL mode this does not block writers of the source database
+        match backup.step(-1)? {
+            StepResult::Done => return Ok(()),
+            StepResult::More => {}
+            // the destination is locked by another connection
+            _ => std::thread::sleep(BUSY_PAUSE),
+        }
+    }
+}
+
+impl<S> Database<S> {
+    /// Write a snapshot of the database to a file using the sqlite
+    /// [online backup API](https://www.sqlite.org/backup.html).
+    ///
+    /// The file is overwritten if it already exists.
+    /// Other [crate::TransactionMut]s can continue while the backup is made,
+    /// the backup contains all changes that were committed when it started.
+    pub fn backup_to(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        use r2d2::ManageConnection;
+        let src = self.manager.connect()?;
+        let mut dst = Connection::open(path)?;
+        copy(&src, &mut dst)?;
+        Ok(())
+    }
+}
+
+impl<S: Schema> Database<S> {
+    /// Replace the content of the database with a backup made by [Database::backup_to].
+    ///
+    /// This waits for other [crate::TransactionMut]s to finish and then blocks them while the backup is restored.
+    /// Restoring a backup changes the `schema_version`, so other [Database] instances for the same file
+    /// will panic when they are used afterwards.
+    ///
+    /// Returns [Error::ApplicationId] if the backup belongs to another application and [Error::SchemaHash]
+    /// if it was made with a different schema for the same version.
+    /// Returns [Error::SchemaMismatch] or [Error::ForeignKeyViolation] if the tables of the backup are not valid for schema `S`.
+    /// This function will panic if the backup does not have the version of schema `S`.
+    pub fn restore_from(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
+        use r2d2::ManageConnection;
+        let flags = OpenFlags::SQLITE_OPEN_READ_ONLY
+            | OpenFlags::SQLITE_OPEN_URI
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        let src = Connection::open_with_flags(path, flags)?;
+
+        let txn = src.unchecked_transaction()?;
+        fingerprint::check_application_id::<S>(&txn)?;
+        assert_eq!(
+            user_version::<S>(&txn)?,
+            S::VERSION,
+            "backup has a different schema version"
+        );
+        fingerprint::check_schema_hash::<S>(&txn)?;
+        foreign_key_check::<S>(&txn)?;
+        drop(txn);
+
+        let mut dst = self.manager.connect()?;
+        copy(&src, &mut dst)?;
+
+        self.schema_version = schema_version(&dst)?;
+        Ok(())
+    }
+}
+
+/// Problem found by [Database::integrity_check].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityError {
+    /// The database file is corrupt, this contains the message from sqlite.
+    Corrupt(String),
+    /// A row references a row that does not exist.
+    ForeignKey {
+        table: String,
+        row: i64,
+        parent: String,
+    },
+}
+
+impl<S> Database<S> {
+    /// Execute a statement that changes the `schema_version` and store the new `schema_version`.
+    fn execute_maintenance(&mut self, sql: &str) -> Result<(), Error> {
+        use r2d2::ManageConnection;
+        let conn = self.manager.connect()?;
+        if schema_version(&conn)? != self.schema_version {
+            panic!("The database schema was updated unexpectedly")
+        }
+        conn.execute_batch(sql)?;
+        self.schema_version = schema_version(&conn)?;
+        Ok(())
+    }
+
+    /// Rebuild the database file to reclaim unused space with `VACUUM`.
+    ///
+    /// This waits for other [crate::TransactionMut]s to finish and then blocks them until it is done.
+    /// `VACUUM` changes the `schema_version`, so other [Database] instances for the same file
+    /// will panic when they are used afterwards.
+    pub fn vacuum(&mut self) -> Result<(), Error> {
+        self.execute_maintenance("VACUUM")
+    }
+
+    /// Write a compact copy of the database to a new file with `VACUUM INTO`.
+    ///
+    /// Unlike [Database::vacuum] this does not block other transactions and the database itself is not changed.
+    /// Returns an error if the file already exists.
+    pub fn vacuum_into(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        use r2d2::ManageConnection;
+        let conn = self.manager.connect()?;
+        let path = path.as_ref().to_string_lossy();
+        conn.execute("VACUUM INTO ?", [&*path])?;
+        Ok(())
+    }
+
+    /// Collect statistics about the tables and indices with `ANALYZE`,
+    /// so that sqlite can choose better query plans.
+    ///
+    /// The first time this is used, it changes the `schema_version`, see [Database::vacuum].
+    pub fn analyze(&mut self) -> Result<(), Error> {
+        self.execute_maintenance("ANALYZE")
+    }
+
+    /// Compute a hash of all rows in table `T`.
+    ///
+    /// The rows are hashed in order of their id, so the digest only depends on the content of the table.
+    /// Comparing the digests of a replica or a backup with the original shows if they have drifted apart,
+    /// without exporting the rows. The digest is computed in one transaction, so it is consistent.
+    pub fn table_digest<T: Table<Schema = S>>(&self) -> Result<String, Error> {
+        use r2d2::ManageConnection;
+        let mut b = TypBuilder::default();
+        T::typs(&mut b);
+        let columns: Vec<_> = std::iter::once(T::ID)
+            .chain(b.ast.columns.iter().map(|col| col.name.as_str()))
+            .map(|name| format!("\"{name}\""))
+            .collect();
+
+        let conn = self.manager.connect()?;
+        let txn = conn.unchecked_transaction()?;
+        let mut stmt = txn.prepare(&format!(
+            "SELECT {} FROM \"{}\" ORDER BY \"{}\"",
+            columns.join(", "),
+            T::NAME,
+            T::ID
+        ))?;
+        let mut hasher = KangarooHasher::default();
+        columns.hash(&mut hasher);
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            for i in 0..columns.len() {
+                // the type is hashed too, so that `1` and `'1'` are different
+                match row.get_ref(i)? {
+                    ValueRef::Null => 0u8.hash(&mut hasher),
+                    ValueRef::Integer(x) => (1u8, x).hash(&mut hasher),
+                    ValueRef::Real(x) => (2u8, x.to_bits()).hash(&mut hasher),
+                    ValueRef::Text(x) => (3u8, x).hash(&mut hasher),
+                    ValueRef::Blob(x) => (4u8, x).hash(&mut hasher),
+                }
+            }
+        }
+        Ok(format!("{:x}", hasher.finish()))
+    }
+
+    /// Check the database for corruption and foreign key violations.
+    ///
+    /// Returns all problems that were found, the result is empty if the database is fine.
+    pub fn integrity_check(&self) -> Result<Vec<IntegrityError>, Error> {
+        use r2d2::ManageConnection;
+        let conn = self.manager.connect()?;
+        let txn = conn.unchecked_transaction()?;
+
+        let mut errors = vec![];
+        let mut stmt = txn.prepare("PRAGMA integrity_check")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let msg: String = row.get(0)?;
+            if msg != "ok" {
+                errors.push(IntegrityError::Corrupt(msg));
+            }
+        }
+
+        let mut stmt = txn.prepare("PRAGMA foreign_key_check")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            errors.push(IntegrityError::ForeignKey {
+                table: row.get(0)?,
+                row: row.get(1)?,
+                parent: row.get(2)?,
+            });
+        }
+        Ok(errors)
+    }
+}
+
+/// Mode for [Database::checkpoint], see the
+/// [sqlite documentation](https://www.sqlite.org/pragma.html#pragma_wal_checkpoint) for details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckpointMode {
+    /// Copy as many frames as possible without waiting for readers or writers.
+    Passive,
+    /// Wait for writers and readers of old frames, then copy all frames.
+    Full,
+    /// Like [CheckpointMode::Full], but also wait for all readers so that the WAL file can be reused.
+    Restart,
+    /// Like [CheckpointMode::Restart], but also truncate the WAL file to zero bytes.
+    Truncate,
+}
+
+impl CheckpointMode {
+    fn sql(self) -> &'static str {
+        match self {
+            CheckpointMode::Passive => "PASSIVE",
+            CheckpointMode::Full => "FULL",
+            CheckpointMode::Restart => "RESTART",
+            CheckpointMode::Truncate => "TRUNCATE",
+        }
+    }
+}
+
+/// Result of [Database::checkpoint].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// The checkpoint could not finish, because of other readers or writers.
+    pub busy: bool,
+    /// The number of frames in the WAL file.
+    pub wal_frames: i64,
+    /// The number of frames that are copied to the database file.
+    pub checkpointed_frames: i64,
+}
+
+/// Result of [Database::wal_stats].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalStats {
+    /// Size of the WAL file in bytes, this is zero if there is no WAL file.
+    pub size: u64,
+    /// The number of frames in the WAL file.
+    pub frames: u64,
+}
+
+impl<S> Database<S> {
+    /// Copy changes from the WAL file to the database file.
+    ///
+    /// Sqlite does this automatically when the WAL file grows over 1000 pages,
+    /// but only with [CheckpointMode::Passive], which falls behind when there are always readers.
+    ///
+    /// For in-memory databases this does nothing, because they do not use a WAL file.
+    pub fn checkpoint(&self, mode: CheckpointMode) -> Result<Checkpoint, Error> {
+        use r2d2::ManageConnection;
+        let conn = self.manager.connect()?;
+        let sql = format!("PRAGMA wal_checkpoint({})", mode.sql());
+        let checkpoint = conn.query_row(&sql, [], |row| {
+            Ok(Checkpoint {
+                busy: row.get(0)?,
+                wal_frames: row.get(1)?,
+                checkpointed_frames: row.get(2)?,
+            })
+        })?;
+        Ok(checkpoint)
+    }
+
+    /// Get the size of the WAL file.
+    pub fn wal_stats(&self) -> Result<WalStats, Error> {
+        use r2d2::ManageConnection;
+        let conn = self.manager.connect()?;
+        let file: String = conn.query_row(
+            "SELECT file FROM pragma_database_list WHERE name = 'main'",
+            [],
+            |row| row.get(0),
+        )?;
+        let page_size: u64 = conn.pragma_query_value(None, "page_size", |row| row.get(0))?;
+
+        // in-memory databases have an empty file name
+        let size = match file.as_str() {
+            "" => 0,
+            file => std::fs::metadata(format!("{file}-wal")).map_or(0, |x| x.len()),
+        };
+        // the WAL file has a header of 32 bytes and every frame has a header of 24 bytes
+        let frames = size.saturating_sub(32) / (page_size + 24);
+        Ok(WalStats { size, frames })
+    }
+}
+
+/// Result of [Database::stats].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseStats {
+    /// Size of the database file in bytes, without the WAL file, see [Database::wal_stats].
+    pub file_size: u64,
+    /// Size of a page in bytes.
+    pub page_size: u64,
+    /// The number of pages in the database file.
+    pub pages: u64,
+    /// The number of unused pages, these are reused for new rows or removed by [Database::vacuum].
+    pub free_pages: u64,
+    /// Statistics for every table, including the internal tables of rust_query.
+    pub tables: Vec<TableStats>,
+}
+
+/// Statistics for a table, see [DatabaseStats].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableStats {
+    pub name: String,
+    /// Approximate number of rows, this is [None] if the table was not analyzed with [Database::analyze].
+    pub rows: Option<u64>,
+    /// Number of pages used by the table without its indices.
+    ///
+    /// This is [None] if sqlite is compiled without the `dbstat` table, it is included with the `bundled` feature.
+    pub pages: Option<u64>,
+    pub indices: Vec<IndexStats>,
+}
+
+/// Statistics for an index, see [TableStats].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexStats {
+    pub name: String,
+    /// The columns of the index, in order.
+    pub columns: Vec<String>,
+    pub unique: bool,
+    /// Number of pages used by the index, see [TableStats::pages].
+    pub pages: Option<u64>,
+}
+
+impl<S> Database<S> {
+    /// Get the size of the database file and statistics for every table and index.
+    ///
+    /// The number of rows is estimated from the statistics that are collected by [Database::analyze],
+    /// so no table is scanned.
+    pub fn stats(&self) -> Result<DatabaseStats, Error> {
+        use r2d2::ManageConnection;
+        let conn = self.manager.connect()?;
+        let txn = conn.unchecked_transaction()?;
+
+        let page_size: u64 = txn.pragma_query_value(None, "page_size", |row| row.get(0))?;
+        let pages: u64 = txn.pragma_query_value(None, "page_count", |row| row.get(0))?;
+        let free_pages: u64 = txn.pragma_query_value(None, "freelist_count", |row| row.get(0))?;
+
+        // the `dbstat` table is only available if sqlite is compiled with it
+        let btree_pages: Option<HashMap<String, u64>> = txn
+            .prepare("SELECT name, count(*) FROM dbstat GROUP BY name")
+            .ok()
+            .map(|mut stmt| {
+                stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                    .collect()
+            })
+            .transpose()?;
+        let has_stat1 = txn
+            .query_row(
+                "SELECT 1 FROM sqlite_schema WHERE type = 'table' AND name = 'sqlite_stat1'",
+                [],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+
+        let mut tables = vec![];
+        let mut stmt = txn.prepare(
+            "SELECT name FROM sqlite_schema WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+        )?;
+        let names: Vec<String> = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        for name in names {
+            let rows = if has_stat1 {
+                // the first number of every statistic is the number of rows in the table
+                let stat: Option<String> = txn
+                    .query_row(
+                        "SELECT stat FROM sqlite_stat1 WHERE tbl = ?",
+                        [&name],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                stat.and_then(|x| x.split(' ').next()?.parse().ok())
+            } else {
+                None
+            };
+
+            let mut indices = vec![];
+            let mut stmt =
+                txn.prepare("SELECT name, \"unique\" FROM pragma_index_list(?) ORDER BY name")?;
+            let index_list: Vec<(String, bool)> = stmt
+                .query_map([&name], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<_, _>>()?;
+            for (index, unique) in index_list {
+                let mut stmt =
+                    txn.prepare("SELECT name FROM pragma_index_info(?) ORDER BY seqno")?;
+                let columns = stmt
+                    .query_map([&index], |row| row.get(0))?
+                    .collect::<Result<_, _>>()?;
+                let pages = btree_pages
+                    .as_ref()
+                    .map(|x| x.get(&index).copied().unwrap_or(0));
+                indices.push(IndexStats {
+                    name: index,
+                    columns,
+                    unique,
+                    pages,
+                });
+            }
+
+            let pages = btree_pages
+                .as_ref()
+                .map(|x| x.get(&name).copied().unwrap_or(0));
+            tables.push(TableStats {
+                name,
+                rows,
+                pages,
+                indices,
+            });
+        }
+
+        Ok(DatabaseStats {
+            file_size: pages * page_size,
+            page_size,
+            pages,
+            free_pages,
+            tables,
+        })
+    }
+}
@@ -0,0 +1,186 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use rusqlite::{
+    types::{FromSql, ToSqlOutput, Value, ValueRef},
+    Connection, ToSql,
+};
+use sea_query_rusqlite::RusqliteValues;
+
+use crate::{
+    error::unwrap_db,
+    interrupt::{check_rows, unwrap_interrupt},
+    metrics,
+    trace::{record_steps, traced},
+    Transaction, TransactionMut,
+};
+
+/// The results of a query for each list of parameters.
+type Entries = Vec<(Vec<Value>, Rc<CachedRows>)>;
+
+/// The results of the queries of a transaction, see [Transaction::memoize].
+#[derive(Default)]
+pub(crate) struct QueryCache {
+    // the result of `total_changes()` when the results were retrieved, if the transaction can write
+    changes: Option<RefCell<i64>>,
+    entries: RefCell<HashMap<String, Entries>>,
+}
+
+/// All rows of a query, with the values of every column.
+pub(crate) struct CachedRows {
+    names: Vec<String>,
+    pub(crate) rows: Vec<Vec<Value>>,
+}
+
+impl CachedRows {
+    /// Get the value of the column called `name` in the row at index `row`.
+    pub(crate) fn get<T: FromSql>(&self, row: usize, name: &str) -> T {
+        let col = self.names.iter().position(|x| x == name).unwrap();
+        T::column_result(ValueRef::from(&self.rows[row][col])).unwrap()
+    }
+
+    /// Get the value of the first column in the first row.
+    pub(crate) fn first<T: FromSql>(&self) -> T {
+        T::column_result(ValueRef::from(&self.rows[0][0])).unwrap()
+    }
+}
+
+thread_local! {
+    // The cache of the transaction that is executing a query on this thread.
+    // There is at most one transaction for each thread, because it borrows the [crate::LocalClient].
+    static CURRENT: RefCell<Option<Rc<QueryCache>>> = const { RefCell::new(None) };
+}
+
+/// Use the cache for the queries on this thread until it is dropped.
+pub(crate) struct CacheGuard {
+    prev: Option<Rc<QueryCache>>,
+}
+
+impl CacheGuard {
+    pub(crate) fn new(cache: Option<&Rc<QueryCache>>) -> Self {
+        Self {
+            prev: CURRENT.replace(cache.cloned()),
+        }
+    }
+}
+
+impl Drop for CacheGuard {
+    fn drop(&mut self) {
+        CURRENT.set(self.prev.take());
+    }
+}
+
+/// The cache of the transaction that is executing a query on this thread.
+pub(crate) fn current() -> Option<Rc<QueryCache>> {
+    CURRENT.with_borrow(Clone::clone)
+}
+
+impl QueryCache {
+    /// Get the rows of a query from the cache, or execute the query if it is not in the cache.
+    pub(crate) fn rows(
+        &self,
+        conn: &Connection,
+        sql: &str,
+        values: &RusqliteValues,
+    ) -> Rc<CachedRows> {
+        if let Some(changes) = &self.changes {
+            let now = unwrap_db(conn.query_row("SELECT total_changes()", [], |r| r.get(0)));
+            if changes.replace(now) != now {
+                // the transaction changed some rows, so the results could be different
+                self.entries.borrow_mut().clear();
+            }
+        }
+
+        let params: Vec<Value> = values
+            .0
+            .iter()
+            .map(|value| match value.to_sql().unwrap() {
+                ToSqlOutput::Borrowed(value) => value.into(),
+                ToSqlOutput::Owned(value) => value,
+                _ => unreachable!("values from sea-query are borrowed or owned"),
+            })
+            .collect();
+        if let Some(entries) = self.entries.borrow().get(sql) {
+            if let Some((_, rows)) = entries.iter().find(|(x, _)| *x == params) {
+                return rows.clone();
+            }
+        }
+
+        let rows = Rc::new(traced(sql, &values.0, || fetch(conn, sql, values)));
+        self.entries
+            .borrow_mut()
+            .entry(sql.to_owned())
+            .or_default()
+            .push((params, rows.clone()));
+        rows
+    }
+}
+
+fn fetch(conn: &Connection, sql: &str, values: &RusqliteValues) -> CachedRows {
+    let mut statement = unwrap_db(conn.prepare_cached(sql));
+    let names = statement
+        .column_names()
+        .into_iter()
+        .map(str::to_owned)
+        .collect();
+    let count = statement.column_count();
+    let mut rows = unwrap_interrupt(statement.query(&*values.as_params()));
+
+    let mut out = vec![];
+    while let Some(row) = unwrap_interrupt(rows.next()) {
+        out.push((0..count).map(|i| row.get_unwrap(i)).collect());
+        check_rows(out.len());
+    }
+    drop(rows);
+    record_steps(&statement);
+    metrics::rows_returned(out.len());
+    CachedRows { names, rows: out }
+}
+
+impl<S> Transaction<'_, S> {
+    /// Remember the results of the queries in this transaction.
+    ///
+    /// Queries that are executed again with the same sql and parameters return the remembered results
+    /// instead of asking sqlite. This is useful when the same queries are executed many times,
+    /// for example when rendering a user interface.
+    /// A [Transaction] always sees the same version of the database, so the results stay correct.
+    ///
+    /// The results are kept in memory until the transaction is dropped.
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient, Table};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     User { name: String },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// let mut txn = client.transaction(&db);
+    /// txn.memoize();
+    /// for _ in 0..10 {
+    ///     // only the first query is executed by sqlite
+    ///     let names = txn.query(|rows| {
+    ///         let user = User::join(rows);
+    ///         rows.into_vec(user.name())
+    ///     });
+    /// }
+    /// ```
+    pub fn memoize(&mut self) {
+        if self.cache.is_none() {
+            self.cache = Some(Rc::default());
+        }
+    }
+}
+
+impl<S> TransactionMut<'_, S> {
+    /// Remember the results of the queries in this transaction, like [Transaction::memoize].
+    ///
+    /// All results are forgotten when this transaction inserts, updates or deletes rows.
+    pub fn memoize(&mut self) {
+        if self.inner.cache.is_none() {
+            self.inner.cache = Some(Rc::new(QueryCache {
+                changes: Some(RefCell::new(0)),
+                entries: RefCell::default(),
+            }));
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! Instrumentation with the `metrics` facade, this is enabled with the `metrics` feature.
+//!
+//! The following metrics are recorded:
+//! - `rust_query_statements_total`: counter of executed sql statements.
+//! - `rust_query_rows_returned`: histogram of the number of rows returned by each query.
+//! - `rust_query_transaction_duration_seconds`: histogram of the time that transactions are open,
+//!   with label `kind` set to `read` or `write`.
+//! - `rust_query_busy_retries_total`: counter of retries to start a [crate::TransactionMut] when the database is busy.
+//!
+//! Without the feature all functions in this module do nothing.
+#![cfg_attr(not(feature = "metrics"), allow(unused_variables, dead_code))]
+
+use std::time::Instant;
+
+/// Count a statement that is executed.
+pub(crate) fn statement() {
+    #[cfg(feature = "metrics")]
+    ::metrics::counter!("rust_query_statements_total").increment(1);
+}
+
+/// Record the number of rows that a query returned.
+pub(crate) fn rows_returned(rows: usize) {
+    #[cfg(feature = "metrics")]
+    ::metrics::histogram!("rust_query_rows_returned").record(rows as f64);
+}
+
+/// Count a retry to start a transaction.
+pub(crate) fn busy_retry() {
+    #[cfg(feature = "metrics")]
+    ::metrics::counter!("rust_query_busy_retries_total").increment(1);
+}
+
+/// Records the duration of a transaction when it is dropped.
+pub(crate) struct TransactionTimer {
+    kind: &'static str,
+    start: Instant,
+}
+
+impl TransactionTimer {
+    pub fn new(kind: &'static str) -> Self {
+        Self {
+            kind,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for TransactionTimer {
+    fn drop(&mut self) {
+        #[cfg(feature = "metrics")]
+        ::metrics::histogram!("rust_query_transaction_duration_seconds", "kind" => self.kind)
+            .record(self.start.elapsed().as_secs_f64());
+    }
+}
@@ -1,22 +1,42 @@
-use std::{marker::PhantomData, path::Path, sync::atomic::AtomicBool};
+use std::{
+    collections::VecDeque,
+    marker::PhantomData,
+    path::Path,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
 
-use rusqlite::{config::DbConfig, Connection};
+use rusqlite::{config::DbConfig, Connection, OpenFlags, OptionalExtension};
 use sea_query::{
-    Alias, ColumnDef, InsertStatement, IntoTableRef, SqliteQueryBuilder, TableDropStatement,
-    TableRenameStatement,
+    Alias, ColumnDef, InsertStatement, IntoTableRef, SelectStatement, SqliteQueryBuilder,
+    TableDropStatement, TableRenameStatement, UnionType,
 };
 use sea_query_rusqlite::RusqliteBinder;
 
 use crate::{
-    alias::{Scope, TmpTable},
+    alias::{Field, Scope, TmpTable},
     ast::MySelect,
-    dummy::{Cached, Cacher},
+    audit,
+    db::Col,
+    dual_write,
+    dummy::{Cached, Cacher, RowSource},
+    error::{unwrap_db, Error},
+    fingerprint,
+    fts::{create_fts, drop_fts},
+    function::Functions,
     hash,
     insert::Reader,
+    interrupt::Interrupts,
+    journal, namespace, parallel,
     pragma::read_schema,
+    public_id::PUBLIC_ID,
+    span,
     token::LocalClient,
-    transaction::Database,
-    value, Column, IntoColumn, Rows, Table,
+    trace::{set_hook, traced, Trace, TraceHook},
+    transaction::{BusyRetry, Database, ReadOnlyDatabase},
+    value, version,
+    watch::Watchers,
+    Column, IntoColumn, Rows, Table,
 };
 
 pub type M<'a, From, To> = Box<
@@ -38,14 +58,31 @@ pub type M<'a, From, To> = Box<
 /// Futhermore [Alter] (and [Create]) also have an implied bound of `'a: 't` which makes it easier to implement migrations.
 pub struct Alter<'t, 'a, From, To> {
     _p: PhantomData<&'t &'a ()>,
-    inner: Box<dyn TableMigration<'t, 'a, From = From, To = To> + 't>,
+    // this is [None] for identity migrations
+    inner: Option<Box<dyn TableMigration<'t, 'a, From = From, To = To> + 't>>,
 }
 
 impl<'t, 'a, From, To> Alter<'t, 'a, From, To> {
     pub fn new(val: impl TableMigration<'t, 'a, From = From, To = To> + 't) -> Self {
         Self {
             _p: PhantomData,
-            inner: Box::new(val),
+            inner: Some(Box::new(val)),
+        }
+    }
+
+    /// Copy all columns without changing them.
+    ///
+    /// The rows are copied with a single `INSERT INTO ... SELECT` statement, which is much faster
+    /// than copying every row with [Alter::new].
+    /// This is useful when only the constraints of the table changed, like the `#[on_delete]` action of a column.
+    ///
+    /// The migration panics if a column of the new table does not exist in the old table
+    /// with the same type and foreign key.
+    /// New columns with a default value are allowed, they get the default value.
+    pub fn identity() -> Self {
+        Self {
+            _p: PhantomData,
+            inner: None,
         }
     }
 }
@@ -96,9 +133,17 @@ impl<'t, 'a, FromSchema, To> TableCreation<'t, 'a> for NeverCreate<FromSchema, T
 #[derive(Default)]
 pub struct TableTypBuilder {
     pub(crate) ast: hash::Schema,
+    // views are not part of the schema hash, because they do not store anything
+    pub(crate) views: Vec<(String, &'static str, hash::Table)>,
 }
 
 impl TableTypBuilder {
+    pub fn view<T: Table>(&mut self, sql: &'static str) {
+        let mut b = hash::TypBuilder::default();
+        T::typs(&mut b);
+        self.views.push((T::NAME.to_owned(), sql, b.ast));
+    }
+
     pub fn table<T: Table>(&mut self) {
         let mut b = hash::TypBuilder::default();
         T::typs(&mut b);
@@ -108,6 +153,10 @@ impl TableTypBuilder {
 
 pub trait Schema: Sized + 'static {
     const VERSION: i64;
+    /// Prefix of the table names, schemas with different namespaces can share a database.
+    const NAMESPACE: Option<&'static str> = None;
+    /// The `application_id` of database files with this schema.
+    const APPLICATION_ID: Option<i32> = None;
     fn typs(b: &mut TableTypBuilder);
 }
 
@@ -144,7 +193,7 @@ struct Wrapper<'t, 'a, From: Table, To>(
     Column<'t, From::Schema, From>,
 );
 
-impl<'t, 'a, From: Table, To> TableCreation<'t, 'a> for Wrapper<'t, 'a, From, To> {
+impl<'t, 'a, From: Table, To: Table> TableCreation<'t, 'a> for Wrapper<'t, 'a, From, To> {
     type FromSchema = From::Schema;
     type To = To;
 
@@ -155,11 +204,36 @@ impl<'t, 'a, From: Table, To> TableCreation<'t, 'a> for Wrapper<'t, 'a, From, To
     where
         'a: 't,
     {
+        // deleted rows stay deleted, timestamps, versions and public ids are kept
+        let deleted_at = (From::SOFT_DELETE && To::SOFT_DELETE)
+            .then(|| cacher.cache(Col::<Option<i64>, _>::new("deleted_at", self.1.clone())));
+        let timestamps = (From::TIMESTAMPS && To::TIMESTAMPS).then(|| {
+            let created_at = Col::<i64, _>::new("created_at", self.1.clone());
+            let updated_at = Col::<i64, _>::new("updated_at", self.1.clone());
+            (cacher.cache(created_at), cacher.cache(updated_at))
+        });
+        let version = (From::VERSIONED && To::VERSIONED)
+            .then(|| cacher.cache(Col::<i64, _>::new("version", self.1.clone())));
+        let public_id = (From::PUBLIC_ID.is_some() && From::PUBLIC_ID == To::PUBLIC_ID)
+            .then(|| cacher.cache(Col::<String, _>::new(PUBLIC_ID, self.1.clone())));
         let db_id = cacher.cache(self.1);
         let mut prepared = Box::new(self.0).prepare(db_id, cacher);
         Box::new(move |row, reader| {
             // keep the ID the same
             reader.col(From::ID, row.get(db_id));
+            if let Some(deleted_at) = deleted_at {
+                reader.col("deleted_at", row.get(deleted_at));
+            }
+            if let Some((created_at, updated_at)) = timestamps {
+                reader.col("created_at", row.get(created_at));
+                reader.col("updated_at", row.get(updated_at));
+            }
+            if let Some(version) = version {
+                reader.col("version", row.get(version));
+            }
+            if let Some(public_id) = public_id {
+                reader.col(PUBLIC_ID, row.get(public_id));
+            }
             prepared(row, reader);
         })
     }
@@ -174,86 +248,400 @@ impl<'inner, S> Rows<'inner, S> {
     }
 }
 
+/// Number of rows that are copied between calls to the [Migrator::on_progress] callback.
+const PROGRESS_CHUNK: u64 = 1000;
+
+/// Maximum number of parameters in a statement for sqlite versions before 3.32.0.
+const MAX_PARAMS: usize = 999;
+
+/// Maximum number of selects that are combined with `UNION ALL` for the default sqlite build.
+const MAX_COMPOUND_SELECT: usize = 500;
+
+/// Rows of [SchemaBuilder::copy_rows] that are inserted with a select, because they read columns of the old row.
+struct Joined {
+    names: Vec<Field>,
+    select: SelectStatement,
+    rows: usize,
+    params: usize,
+}
+
+/// Progress of copying a table in [Migrator::migrate], see [Migrator::on_progress].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// The name of the table that is being copied.
+    pub table: &'static str,
+    /// The number of rows that are copied so far.
+    pub rows: u64,
+    /// This is `true` when all rows of the table are copied.
+    pub done: bool,
+}
+
 pub struct SchemaBuilder<'x, 'a> {
     // this is used to create temporary table names
     scope: Scope,
-    conn: &'x rusqlite::Transaction<'x>,
+    conn: &'x journal::Owned<'x>,
+    progress: &'x mut dyn FnMut(Progress),
     drop: Vec<TableDropStatement>,
     rename: Vec<TableRenameStatement>,
+    // tables that were copied before the migration was interrupted or by [Migrator::expand]
+    done: Vec<String>,
+    // the schema name and version that copied tables are recorded for, if the migration is journaled
+    journal: Option<(&'static str, i64)>,
+    // the maximum number of tables that are copied at the same time, see [Migrator::parallel_copy]
+    workers: usize,
+    // tables that are copied by worker connections and still need to be imported
+    copies: VecDeque<parallel::Worker>,
+    // only copy the tables that can be kept up to date with triggers, see [Migrator::expand]
+    expand: bool,
+    // the tables of the previous schema, they become read-only when a journaled migration copies rows with rust code
+    old_tables: Vec<String>,
+    // the first error, the following tables are skipped when there is an error
+    error: Option<Error>,
     _p: PhantomData<fn(&'a ()) -> &'a ()>,
 }
 
 impl<'a> SchemaBuilder<'_, 'a> {
     pub fn migrate_table<From: Table, To: Table>(&mut self, m: M<'a, From, To>) {
-        self.create_inner::<From::Schema, To>(|rows| {
-            let db_id = From::join(rows);
-            let migration = m(db_id.clone());
-            Create::new(Wrapper(migration.inner, db_id))
-        });
+        if self.error.is_none() {
+            self.error = self.migrate_table_inner(m).err();
+        }
+    }
+
+    fn migrate_table_inner<From: Table, To: Table>(
+        &mut self,
+        m: M<'a, From, To>,
+    ) -> Result<(), Error> {
+        let _span = span::migrate_table(To::NAME);
+        let mut q = Rows::<From::Schema> {
+            phantom: PhantomData,
+            ast: MySelect::default(),
+        };
+        let db_id = From::join_with_deleted(&mut q);
+        if self.is_done::<To>() {
+            self.tmp_table::<To>();
+        } else {
+            match m(db_id.clone()).inner {
+                // tables that are migrated with rust code are copied when the migration is applied
+                Some(_) if self.expand => {
+                    self.tmp_table::<To>();
+                }
+                Some(migration) => {
+                    self.copy_rows::<_, To>(q, Create::new(Wrapper(migration, db_id)))?;
+                    self.lock_old_tables()?;
+                    self.record::<To>()?;
+                }
+                None if self.expand => self.expand_identity::<From, To>()?,
+                None if self.parallel_path().is_some() => self.spawn_copy::<From, To>()?,
+                // writes between the commits of a journaled migration are also done on the copy
+                None if self.journal.is_some() => self.expand_identity::<From, To>()?,
+                None => {
+                    self.copy_identity::<From, To>()?;
+                }
+            }
+        }
 
         self.drop.push(
             sea_query::Table::drop()
                 .table(Alias::new(From::NAME))
                 .take(),
         );
+        Ok(())
     }
 
     pub fn create_from<FromSchema, To: Table>(&mut self, f: C<'a, FromSchema, To>) {
-        self.create_inner::<FromSchema, To>(f);
+        if self.error.is_none() {
+            self.error = self.create_inner::<FromSchema, To>(f).err();
+        }
     }
 
     fn create_inner<FromSchema, To: Table>(
         &mut self,
         f: impl for<'t> FnOnce(&mut Rows<'t, FromSchema>) -> Create<'t, 'a, FromSchema, To>,
-    ) {
-        let new_table_name = self.scope.tmp_table();
-        new_table::<To>(self.conn, new_table_name);
+    ) -> Result<(), Error> {
+        let _span = span::migrate_table(To::NAME);
+        if self.is_done::<To>() || self.expand {
+            self.tmp_table::<To>();
+            return Ok(());
+        }
+        let mut q = Rows::<FromSchema> {
+            phantom: PhantomData,
+            ast: MySelect::default(),
+        };
+        let create = f(&mut q);
+        self.copy_rows(q, create)?;
+        self.lock_old_tables()?;
+        self.record::<To>()
+    }
+
+    /// Check if `To` was already copied by a journaled migration that was interrupted or by [Migrator::expand].
+    fn is_done<To: Table>(&self) -> bool {
+        self.done.iter().any(|x| x == To::NAME)
+    }
+
+    /// Commit the copy of `To` if the migration is journaled.
+    fn record<To: Table>(&self) -> Result<(), Error> {
+        if let Some((name, version)) = self.journal {
+            journal::record(self.conn, name, version, To::NAME)?;
+            self.conn.checkpoint()?;
+        }
+        Ok(())
+    }
+
+    /// Make the tables of the previous schema read-only if the migration is journaled.
+    ///
+    /// Rows that are copied with rust code can read any table and can not be kept up to date with triggers,
+    /// so writes of other connections are rejected until the migration is applied instead of being lost.
+    fn lock_old_tables(&self) -> Result<(), Error> {
+        if self.journal.is_some() {
+            for table in &self.old_tables {
+                dual_write::lock(self.conn, table)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The database file that worker connections read from, if tables are copied in parallel.
+    fn parallel_path(&self) -> Option<&str> {
+        // in-memory databases have an empty file name
+        let path = self.conn.path().filter(|x| !x.is_empty())?;
+        (self.workers > 0 && self.journal.is_some()).then_some(path)
+    }
+
+    /// Copy `From` to the temporary table of `To` with a worker connection.
+    fn spawn_copy<From: Table, To: Table>(&mut self) -> Result<(), Error> {
+        let columns = identity_columns::<From, To>();
+        // the worker reads the committed table, so writes after this commit are rejected
+        dual_write::lock(self.conn, From::NAME)?;
+        self.conn.checkpoint()?;
+        let new_table_name = self.tmp_table::<To>();
+        // the table of a worker that was not imported before an interruption can already exist
+        let tmp = sea_query::Iden::to_string(&new_table_name);
+        self.conn
+            .execute_batch(&format!("DROP TABLE IF EXISTS \"{tmp}\""))?;
+        new_table::<To>(self.conn, new_table_name)?;
+        if self.copies.len() >= self.workers {
+            self.import_copy()?;
+        }
+        let path = self.parallel_path().unwrap();
+        let worker = parallel::spawn(path, From::NAME, To::NAME, tmp, &columns);
+        self.copies.push_back(worker);
+        Ok(())
+    }
+
+    /// Copy `From` to the temporary table of `To` and keep it up to date until the migration is applied.
+    fn expand_identity<From: Table, To: Table>(&mut self) -> Result<(), Error> {
+        let columns = identity_columns::<From, To>();
+        let new_table_name = self.copy_identity::<From, To>()?;
+        let tmp = sea_query::Iden::to_string(&new_table_name);
+        dual_write::install(self.conn, From::NAME, &tmp, &columns)?;
+        self.record::<To>()
+    }
+
+    /// Wait for the oldest worker connection and import its table.
+    fn import_copy(&mut self) -> Result<(), Error> {
+        let Some(worker) = self.copies.pop_front() else {
+            return Ok(());
+        };
+        let (name, version) = self.journal.unwrap();
+        let table = worker.table;
+        let rows = parallel::import(self.conn, worker, name, version)?;
+        (self.progress)(Progress {
+            table,
+            rows: rows as u64,
+            done: true,
+        });
+        Ok(())
+    }
+
+    /// Import the tables of all worker connections, this is done before the old tables are dropped.
+    fn import_all(&mut self) -> Result<(), Error> {
+        while !self.copies.is_empty() {
+            self.import_copy()?;
+        }
+        Ok(())
+    }
+
+    /// Create a temporary table for `To`, it is renamed when the migration is done.
+    fn new_tmp_table<To: Table>(&mut self) -> Result<TmpTable, Error> {
+        let new_table_name = self.tmp_table::<To>();
+        new_table::<To>(self.conn, new_table_name)?;
+        Ok(new_table_name)
+    }
 
+    /// The name of the temporary table for `To`, tables get the same name every time the migration runs.
+    fn tmp_table<To: Table>(&mut self) -> TmpTable {
+        let new_table_name = self.scope.tmp_table();
         self.rename.push(
             sea_query::Table::rename()
                 .table(new_table_name, Alias::new(To::NAME))
                 .take(),
         );
+        new_table_name
+    }
 
-        let mut q = Rows::<FromSchema> {
-            phantom: PhantomData,
-            ast: MySelect::default(),
-        };
-        let create = f(&mut q);
+    fn copy_identity<From: Table, To: Table>(&mut self) -> Result<TmpTable, Error> {
+        let names: Vec<_> = identity_columns::<From, To>()
+            .into_iter()
+            .map(Alias::new)
+            .collect();
+        let new_table_name = self.new_tmp_table::<To>()?;
+
+        let select = sea_query::Query::select()
+            .columns(names.clone())
+            .from(Alias::new(From::NAME))
+            .take();
+        let mut insert = InsertStatement::new();
+        insert.into_table(new_table_name);
+        insert.columns(names);
+        insert.select_from(select).unwrap();
+
+        let (sql, values) = insert.build_rusqlite(SqliteQueryBuilder);
+        let rows = traced(&sql, &values.0, || {
+            self.conn.execute(&sql, &*values.as_params())
+        })?;
+        (self.progress)(Progress {
+            table: To::NAME,
+            rows: rows as u64,
+            done: true,
+        });
+        Ok(new_table_name)
+    }
+
+    fn copy_rows<'t, FromSchema, To: Table>(
+        &mut self,
+        q: Rows<'t, FromSchema>,
+        create: Create<'t, 'a, FromSchema, To>,
+    ) -> Result<(), Error>
+    where
+        'a: 't,
+    {
+        let new_table_name = self.new_tmp_table::<To>()?;
         let mut prepared = create.inner.prepare(q.cacher());
 
         let select = q.ast.simple();
         let (sql, values) = select.build_rusqlite(SqliteQueryBuilder);
 
+        let mut progress = Progress {
+            table: To::NAME,
+            rows: 0,
+            done: false,
+        };
+        // rows are copied with multi-row inserts, this is the statement and its number of rows
+        let mut batch: Option<(InsertStatement, usize)> = None;
+        // rows that read columns of the old row, like unchanged columns, need a join
+        // their selects are combined with `UNION ALL` into one insert
+        let mut joined: Option<Joined> = None;
         // no caching here, migration is only executed once
-        let mut statement = self.conn.prepare(&sql).unwrap();
-        let mut rows = statement.query(&*values.as_params()).unwrap();
-
-        while let Some(row) = rows.next().unwrap() {
-            let row = crate::private::Row {
-                _p: PhantomData,
-                _p2: PhantomData,
-                row,
-            };
-
-            let new_ast = MySelect::default();
-            let reader = Reader {
-                ast: &new_ast,
-                _p: PhantomData,
-                _p2: PhantomData,
-            };
-            prepared(row, reader);
+        let mut statement = self.conn.prepare(&sql)?;
+        traced(&sql, &values.0, || {
+            let mut rows = statement.query(&*values.as_params())?;
+
+            while let Some(row) = rows.next()? {
+                let row = crate::private::Row {
+                    _p: PhantomData,
+                    _p2: PhantomData,
+                    row: RowSource::Sqlite(row),
+                };
+
+                let new_ast = MySelect::default();
+                let reader = Reader {
+                    ast: &new_ast,
+                    _p: PhantomData,
+                    _p2: PhantomData,
+                };
+                prepared(row, reader);
+                if let Some(kind) = To::PUBLIC_ID {
+                    // rows that did not have a public id yet get a new one
+                    if !new_ast.select.iter().any(|x| x.1 == Field::Str(PUBLIC_ID)) {
+                        reader.col(PUBLIC_ID, kind.generate());
+                    }
+                }
+
+                let (names, exprs): (Vec<_>, Vec<_>) = new_ast
+                    .select
+                    .iter()
+                    .map(|(expr, name)| (*name, expr.clone()))
+                    .unzip();
+                if new_ast.tables.is_empty() && new_ast.extra.is_empty() {
+                    // the values are not computed with sql, so they can be inserted with `VALUES`
+                    let max_rows = MAX_PARAMS / names.len().max(1);
+                    let insert = batch.get_or_insert_with(|| {
+                        let mut insert = InsertStatement::new();
+                        insert.into_table(new_table_name);
+                        insert.columns(names);
+                        // used for tables without columns
+                        insert.or_default_values();
+                        (insert, 0)
+                    });
+                    insert.0.values(exprs).unwrap();
+                    insert.1 += 1;
+                    if insert.1 >= max_rows {
+                        self.insert_batch(batch.take())?;
+                    }
+                } else {
+                    // the order of the rows does not matter and is not allowed in a compound select
+                    let mut select = new_ast.simple();
+                    select.clear_order_by();
+                    let params = select.build_rusqlite(SqliteQueryBuilder).1 .0.len();
+                    if joined.as_ref().is_some_and(|x| {
+                        x.rows >= MAX_COMPOUND_SELECT || x.params + params > MAX_PARAMS
+                    }) {
+                        self.insert_joined(new_table_name, joined.take())?;
+                    }
+                    match &mut joined {
+                        Some(x) => {
+                            x.select.union(UnionType::All, select);
+                            x.rows += 1;
+                            x.params += params;
+                        }
+                        None => {
+                            joined = Some(Joined {
+                                names,
+                                select,
+                                rows: 1,
+                                params,
+                            })
+                        }
+                    }
+                }
+
+                progress.rows += 1;
+                // `is_multiple_of` would need a newer rust version
+                #[allow(clippy::manual_is_multiple_of)]
+                let report = progress.rows % PROGRESS_CHUNK == 0;
+                if report {
+                    self.insert_batch(batch.take())?;
+                    self.insert_joined(new_table_name, joined.take())?;
+                    (self.progress)(progress);
+                }
+            }
+            self.insert_batch(batch.take())?;
+            self.insert_joined(new_table_name, joined.take())
+        })?;
+        progress.done = true;
+        (self.progress)(progress);
+        Ok(())
+    }
 
-            let mut insert = InsertStatement::new();
-            let names = new_ast.select.iter().map(|(_field, name)| *name);
-            insert.into_table(new_table_name);
-            insert.columns(names);
-            insert.select_from(new_ast.simple()).unwrap();
+    fn insert_batch(&self, batch: Option<(InsertStatement, usize)>) -> Result<(), Error> {
+        let Some((insert, _)) = batch else {
+            return Ok(());
+        };
+        let (sql, values) = insert.build_rusqlite(SqliteQueryBuilder);
+        let mut statement = self.conn.prepare_cached(&sql)?;
+        traced(&sql, &values.0, || statement.execute(&*values.as_params()))?;
+        Ok(())
+    }
 
-            let (sql, values) = insert.build_rusqlite(SqliteQueryBuilder);
-            let mut statement = self.conn.prepare_cached(&sql).unwrap();
-            statement.execute(&*values.as_params()).unwrap();
-        }
+    fn insert_joined(&self, new_table_name: TmpTable, joined: Option<Joined>) -> Result<(), Error> {
+        let Some(joined) = joined else {
+            return Ok(());
+        };
+        let mut insert = InsertStatement::new();
+        insert.into_table(new_table_name);
+        insert.columns(joined.names);
+        insert.select_from(joined.select).unwrap();
+        self.insert_batch(Some((insert, joined.rows)))
     }
 
     pub fn drop_table<T: Table>(&mut self) {
@@ -263,20 +651,87 @@ impl<'a> SchemaBuilder<'_, 'a> {
     }
 }
 
-fn new_table<T: Table>(conn: &Connection, alias: TmpTable) {
+fn new_table<T: Table>(conn: &Connection, alias: TmpTable) -> rusqlite::Result<()> {
     let mut f = crate::hash::TypBuilder::default();
     T::typs(&mut f);
-    new_table_inner(conn, &f.ast, alias);
+    new_table_inner(conn, &f.ast, alias)
 }
 
-fn new_table_inner(conn: &Connection, table: &crate::hash::Table, alias: impl IntoTableRef) {
+fn new_table_inner(
+    conn: &Connection,
+    table: &crate::hash::Table,
+    alias: impl IntoTableRef,
+) -> rusqlite::Result<()> {
     let mut create = table.create();
     create
         .table(alias)
         .col(ColumnDef::new(Alias::new("id")).integer().primary_key());
     let mut sql = create.to_string(SqliteQueryBuilder);
     sql.push_str(" STRICT");
-    conn.execute(&sql, []).unwrap();
+    traced(&sql, &[], || conn.execute(&sql, []))?;
+    Ok(())
+}
+
+/// Create the views of a schema and check that they have the expected columns.
+fn create_views(conn: &Connection, b: &TableTypBuilder) -> rusqlite::Result<()> {
+    for (name, sql, table) in &b.views {
+        let sql = format!("CREATE VIEW \"{name}\" AS {sql}");
+        traced(&sql, &[], || conn.execute(&sql, []))?;
+
+        let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?) ORDER BY name")?;
+        let found: Vec<String> = stmt
+            .query_map([name], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        let mut expected: Vec<_> = std::iter::once("id")
+            .chain(table.columns.iter().map(|col| col.name.as_str()))
+            .collect();
+        expected.sort();
+        assert_eq!(
+            found, expected,
+            "the columns of view `{name}` are different from the schema"
+        );
+    }
+    Ok(())
+}
+
+/// Drop the views of a schema, they are created again after the migration.
+fn drop_views(conn: &Connection, b: &TableTypBuilder) -> rusqlite::Result<()> {
+    for (name, ..) in &b.views {
+        let sql = format!("DROP VIEW IF EXISTS \"{name}\"");
+        traced(&sql, &[], || conn.execute(&sql, []))?;
+    }
+    Ok(())
+}
+
+/// Create the triggers of all tables in the schema.
+fn create_all_triggers(conn: &Connection, schema: &hash::Schema) -> rusqlite::Result<()> {
+    for (table_name, table) in &*schema.tables {
+        for trigger in &*table.triggers {
+            let sql = trigger.sql(table_name);
+            traced(&sql, &[], || conn.execute(&sql, []))?;
+        }
+    }
+    Ok(())
+}
+
+/// Drop the triggers of all tables in the schema, they are created again after the migration.
+fn drop_all_triggers(conn: &Connection, schema: &hash::Schema) -> rusqlite::Result<()> {
+    for (_, table) in &*schema.tables {
+        for trigger in &*table.triggers {
+            let sql = format!("DROP TRIGGER IF EXISTS \"{}\"", trigger.name);
+            traced(&sql, &[], || conn.execute(&sql, []))?;
+        }
+    }
+    Ok(())
+}
+
+fn create_all_fts(conn: &Connection, schema: &hash::Schema) -> rusqlite::Result<()> {
+    for (table_name, table) in &*schema.tables {
+        if !table.fts5.is_empty() {
+            create_fts(conn, table_name, &table.fts5)?;
+        }
+    }
+    Ok(())
 }
 
 pub trait Migration<'a> {
@@ -292,7 +747,136 @@ pub trait Migration<'a> {
 /// get a [Database] instance.
 pub struct Config {
     manager: r2d2_sqlite::SqliteConnectionManager,
-    init: Box<dyn FnOnce(&rusqlite::Transaction)>,
+    init: Box<dyn FnOnce(&rusqlite::Connection)>,
+    read_only: bool,
+    options: ConnectionOptions,
+    busy_retry: BusyRetry,
+    trace: Option<TraceHook>,
+    audit: bool,
+}
+
+/// Value of the [`synchronous`](https://www.sqlite.org/pragma.html#pragma_synchronous) pragma,
+/// see [Config::synchronous].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Synchronous {
+    /// Do not fsync at all, transactions can be lost or the database corrupted on power loss.
+    Off,
+    /// Fsync only on checkpoints, transactions can be lost on power loss.
+    Normal,
+    /// Fsync after every transaction.
+    Full,
+    /// Like [Synchronous::Full], but also fsync the directory when a file is deleted.
+    Extra,
+}
+
+/// Value of the [`temp_store`](https://www.sqlite.org/pragma.html#pragma_temp_store) pragma,
+/// see [Config::temp_store].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TempStore {
+    /// Use the compile time default of sqlite.
+    Default,
+    /// Store temporary tables and indices in files.
+    File,
+    /// Store temporary tables and indices in memory.
+    Memory,
+}
+
+/// Settings that are applied to every new connection.
+#[derive(Clone)]
+struct ConnectionOptions {
+    #[cfg(feature = "sqlcipher")]
+    key: Arc<std::sync::Mutex<Key>>,
+    watchers: Arc<Watchers>,
+    interrupts: Arc<Interrupts>,
+    functions: Arc<Functions>,
+    busy_timeout: Duration,
+    synchronous: Synchronous,
+    foreign_keys: bool,
+    page_size: Option<u32>,
+    cache_size: Option<i64>,
+    mmap_size: Option<u64>,
+    temp_store: Option<TempStore>,
+}
+
+/// Encryption key used by sqlcipher.
+#[cfg(feature = "sqlcipher")]
+#[derive(Default)]
+struct Key {
+    current: Option<String>,
+    // the new key is set by the first connection, after which it becomes the current key
+    new: Option<String>,
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        Self {
+            #[cfg(feature = "sqlcipher")]
+            key: Default::default(),
+            watchers: Default::default(),
+            interrupts: Default::default(),
+            functions: Default::default(),
+            // This is the same as the default of rusqlite.
+            busy_timeout: Duration::from_secs(5),
+            synchronous: Synchronous::Normal,
+            foreign_keys: true,
+            page_size: None,
+            cache_size: None,
+            mmap_size: None,
+            temp_store: None,
+        }
+    }
+}
+
+impl ConnectionOptions {
+    fn init(&self, conn: &Connection, read_only: bool) -> rusqlite::Result<()> {
+        // the key needs to be set before anything else is read from the database
+        #[cfg(feature = "sqlcipher")]
+        {
+            let mut key = self.key.lock().unwrap();
+            if let Some(current) = &key.current {
+                conn.pragma_update(None, "key", current)?;
+            }
+            if let Some(new) = &key.new {
+                conn.pragma_update(None, "rekey", new)?;
+                key.current = key.new.take();
+            }
+        }
+        self.watchers.install(conn);
+        self.interrupts.install(conn);
+        self.functions.install(conn);
+        conn.busy_timeout(self.busy_timeout)?;
+        // journal_mode and synchronous can not be changed without write access
+        if !read_only {
+            // the page_size can not be changed after switching to WAL mode
+            if let Some(page_size) = self.page_size {
+                conn.pragma_update(None, "page_size", page_size)?;
+            }
+            conn.pragma_update(None, "journal_mode", "WAL")?;
+            let synchronous = match self.synchronous {
+                Synchronous::Off => "OFF",
+                Synchronous::Normal => "NORMAL",
+                Synchronous::Full => "FULL",
+                Synchronous::Extra => "EXTRA",
+            };
+            conn.pragma_update(None, "synchronous", synchronous)?;
+        }
+        conn.pragma_update(None, "foreign_keys", self.foreign_keys)?;
+        if let Some(cache_size) = self.cache_size {
+            conn.pragma_update(None, "cache_size", cache_size)?;
+        }
+        if let Some(mmap_size) = self.mmap_size {
+            conn.pragma_update(None, "mmap_size", mmap_size)?;
+        }
+        if let Some(temp_store) = self.temp_store {
+            let temp_store = match temp_store {
+                TempStore::Default => "DEFAULT",
+                TempStore::File => "FILE",
+                TempStore::Memory => "MEMORY",
+            };
+            conn.pragma_update(None, "temp_store", temp_store)?;
+        }
+        set_db_config(conn)
+    }
 }
 
 static ALLOWED: AtomicBool = AtomicBool::new(true);
@@ -303,36 +887,214 @@ impl Config {
     ///
     /// Opening the same database multiple times at the same time is fine,
     /// as long as they migrate to or use the same schema.
+    /// Schemas with a namespace are added to the same database with [LocalClient::namespace_migrator].
     /// All locking is done by sqlite, so connections can even be made using different client implementations.
     pub fn open(p: impl AsRef<Path>) -> Self {
         let manager = r2d2_sqlite::SqliteConnectionManager::file(p);
         Self::open_internal(manager)
     }
 
+    /// Open a database that is encrypted with [SQLCipher](https://www.zetetic.net/sqlcipher/).
+    /// Creates the database if it does not exist.
+    ///
+    /// Opening an existing database with the wrong key or opening an unencrypted database
+    /// will make [LocalClient::migrator] panic.
+    #[cfg(feature = "sqlcipher")]
+    pub fn open_encrypted(p: impl AsRef<Path>, key: &str) -> Self {
+        let config = Self::open(p);
+        config.options.key.lock().unwrap().current = Some(key.to_owned());
+        config
+    }
+
+    /// Change the encryption key of a database opened with [Config::open_encrypted].
+    ///
+    /// The database is re-encrypted by the first connection, which is made by [LocalClient::migrator].
+    /// Doing this when opening the database makes sure that there are no other connections using the old key.
+    #[cfg(feature = "sqlcipher")]
+    pub fn rekey(self, new_key: &str) -> Self {
+        let mut key = self.options.key.lock().unwrap();
+        assert!(
+            key.current.is_some(),
+            "only databases opened with `Config::open_encrypted` can be rekeyed"
+        );
+        key.new = Some(new_key.to_owned());
+        drop(key);
+        self
+    }
+
     /// Creates a new empty database in memory.
     pub fn open_in_memory() -> Self {
         let manager = r2d2_sqlite::SqliteConnectionManager::memory();
         Self::open_internal(manager)
     }
 
+    /// Open a database that is stored in a file in read-only mode.
+    ///
+    /// The database must already exist and can not be migrated.
+    /// Use [LocalClient::read_only] to check the schema and get a [ReadOnlyDatabase].
+    pub fn open_read_only(p: impl AsRef<Path>) -> Self {
+        let flags = OpenFlags::SQLITE_OPEN_READ_ONLY
+            | OpenFlags::SQLITE_OPEN_URI
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        let manager = r2d2_sqlite::SqliteConnectionManager::file(p).with_flags(flags);
+        assert!(ALLOWED.swap(false, std::sync::atomic::Ordering::Relaxed));
+
+        Self {
+            manager,
+            init: Box::new(|_| {}),
+            read_only: true,
+            options: ConnectionOptions::default(),
+            busy_retry: BusyRetry::default(),
+            trace: None,
+            audit: false,
+        }
+    }
+
     fn open_internal(manager: r2d2_sqlite::SqliteConnectionManager) -> Self {
         assert!(ALLOWED.swap(false, std::sync::atomic::Ordering::Relaxed));
-        let manager = manager.with_init(|inner| {
-            inner.pragma_update(None, "journal_mode", "WAL")?;
-            inner.pragma_update(None, "synchronous", "NORMAL")?;
-            inner.pragma_update(None, "foreign_keys", "ON")?;
-            inner.set_db_config(DbConfig::SQLITE_DBCONFIG_DQS_DDL, false)?;
-            inner.set_db_config(DbConfig::SQLITE_DBCONFIG_DQS_DML, false)?;
-            inner.set_db_config(DbConfig::SQLITE_DBCONFIG_DEFENSIVE, true)?;
-            Ok(())
-        });
 
         Self {
             manager,
             init: Box::new(|_| {}),
+            read_only: false,
+            options: ConnectionOptions::default(),
+            busy_retry: BusyRetry::default(),
+            trace: None,
+            audit: false,
         }
     }
 
+    /// Set how long a connection waits for a lock held by another connection,
+    /// before it fails with `SQLITE_BUSY`. The default is 5 seconds.
+    pub fn busy_timeout(mut self, timeout: Duration) -> Self {
+        self.options.busy_timeout = timeout;
+        self
+    }
+
+    /// Retry starting a [crate::TransactionMut] when the database stays locked for longer than
+    /// the [Config::busy_timeout].
+    ///
+    /// The first retry waits for `initial_delay` and the delay doubles after every retry.
+    /// [crate::LocalClient::transaction_mut] panics when all `retries` failed.
+    /// By default there are no retries.
+    pub fn busy_retries(mut self, retries: u32, initial_delay: Duration) -> Self {
+        self.busy_retry = BusyRetry {
+            retries,
+            initial_delay,
+        };
+        self
+    }
+
+    /// Set the [`synchronous`](https://www.sqlite.org/pragma.html#pragma_synchronous) level.
+    /// The default is [Synchronous::Normal].
+    ///
+    /// This has no effect for databases opened with [Config::open_read_only].
+    pub fn synchronous(mut self, synchronous: Synchronous) -> Self {
+        self.options.synchronous = synchronous;
+        self
+    }
+
+    /// Set the [`page_size`](https://www.sqlite.org/pragma.html#pragma_page_size) in bytes.
+    ///
+    /// This only has an effect when the database is created, the page size of existing databases is not changed.
+    pub fn page_size(mut self, bytes: u32) -> Self {
+        self.options.page_size = Some(bytes);
+        self
+    }
+
+    /// Set the [`cache_size`](https://www.sqlite.org/pragma.html#pragma_cache_size) of every connection.
+    ///
+    /// Positive values are a number of pages, negative values are a number of KiB.
+    pub fn cache_size(mut self, size: i64) -> Self {
+        self.options.cache_size = Some(size);
+        self
+    }
+
+    /// Set the maximum number of bytes to use for memory-mapped I/O with
+    /// [`mmap_size`](https://www.sqlite.org/pragma.html#pragma_mmap_size).
+    pub fn mmap_size(mut self, bytes: u64) -> Self {
+        self.options.mmap_size = Some(bytes);
+        self
+    }
+
+    /// Set where temporary tables and indices are stored with
+    /// [`temp_store`](https://www.sqlite.org/pragma.html#pragma_temp_store).
+    pub fn temp_store(mut self, temp_store: TempStore) -> Self {
+        self.options.temp_store = Some(temp_store);
+        self
+    }
+
+    /// Enable or disable [foreign key constraints](https://www.sqlite.org/foreignkeys.html).
+    /// They are enabled by default.
+    ///
+    /// Disabling them makes it possible to delete rows that are still referenced.
+    /// Queries that use such dangling references will panic.
+    pub fn foreign_keys(mut self, enabled: bool) -> Self {
+        self.options.foreign_keys = enabled;
+        self
+    }
+
+    /// Call `hook` after every sql statement that is executed by queries, inserts, updates, deletes and migrations.
+    ///
+    /// The hook receives the sql text, the number of bound parameters and the time it took to execute the statement.
+    /// ```
+    /// # use rust_query::migration::Config;
+    /// let config = Config::open_in_memory().trace(|trace| {
+    ///     if trace.duration.as_millis() > 100 {
+    ///         eprintln!("slow query: {}", trace.sql);
+    ///     }
+    /// });
+    /// ```
+    pub fn trace(mut self, hook: impl Fn(&Trace) + Send + Sync + 'static) -> Self {
+        self.trace = Some(Box::new(hook));
+        self
+    }
+
+    /// Record every insert, update and delete made through [crate::TransactionMut] in an audit log.
+    ///
+    /// The log is stored in the `_audit` table, which is created by [Migrator::finish] if it does not exist.
+    /// Every entry has the table, the row id, the operation, a JSON object with the changed columns,
+    /// the time and the actor set with [crate::TransactionMut::set_actor].
+    /// The history of a row can be read with [crate::Transaction::audit_history].
+    ///
+    /// The log uses the hooks of [Database::hook], so changes that do not call hooks are not recorded.
+    pub fn audit(mut self) -> Self {
+        self.audit = true;
+        self
+    }
+
+    /// Register a collation with the name `name` on every connection, `cmp` compares two strings.
+    ///
+    /// Collations change how text is compared, sorted and checked for uniqueness.
+    /// They can be used by columns with the `#[collate("name")]` attribute in the [crate::migration::schema]
+    /// and in queries with [crate::Column::collate].
+    /// ```
+    /// # use rust_query::migration::Config;
+    /// let config = Config::open_in_memory().collation("unicase", |a, b| {
+    ///     a.to_lowercase().cmp(&b.to_lowercase())
+    /// });
+    /// ```
+    /// The builtin collations `BINARY`, `NOCASE` and `RTRIM` are always available.
+    /// A collation that is used by the schema must be registered before opening the database,
+    /// otherwise [LocalClient::migrator] and [LocalClient::read_only] panic.
+    pub fn collation(
+        self,
+        name: &str,
+        cmp: impl Fn(&str, &str) -> std::cmp::Ordering + Send + Sync + 'static,
+    ) -> Self {
+        self.options.functions.add_collation(name, cmp);
+        self
+    }
+
+    /// Add the initialization of new connections to the connection manager.
+    fn into_manager(
+        manager: r2d2_sqlite::SqliteConnectionManager,
+        read_only: bool,
+        options: ConnectionOptions,
+    ) -> r2d2_sqlite::SqliteConnectionManager {
+        manager.with_init(move |conn| options.init(conn, read_only))
+    }
+
     /// Execute a raw sql statement if the database was just created.
     ///
     /// The statement is executed after creating the empty database and executingall previous statements.
@@ -347,47 +1109,222 @@ impl Config {
     }
 }
 
+pub(crate) fn init_connection(conn: &mut Connection) -> rusqlite::Result<()> {
+    ConnectionOptions::default().init(conn, false)
+}
+
+fn set_db_config(conn: &Connection) -> rusqlite::Result<()> {
+    conn.set_db_config(DbConfig::SQLITE_DBCONFIG_DQS_DDL, false)?;
+    conn.set_db_config(DbConfig::SQLITE_DBCONFIG_DQS_DML, false)?;
+    conn.set_db_config(DbConfig::SQLITE_DBCONFIG_DEFENSIVE, true)?;
+    Ok(())
+}
+
 impl LocalClient {
     /// Create a [Migrator] to migrate a database.
     ///
     /// Returns [None] if the database `user_version` on disk is older than `S`.
     ///
     /// This function will panic if the schema on disk does not match what is expected for its `user_version`.
+    /// It will also panic if the [Config] was created with [Config::open_read_only].
     pub fn migrator<'t, S: Schema>(&'t mut self, config: Config) -> Option<Migrator<'t, S>> {
-        use r2d2::ManageConnection;
-        let conn = self.conn.insert(config.manager.connect().unwrap());
-        conn.pragma_update(None, "foreign_keys", "OFF").unwrap();
+        unwrap_db(self.try_migrator(config))
+    }
+
+    /// Create a [Migrator] like [LocalClient::migrator], but return an [Error] instead of panicking.
+    ///
+    /// This can happen for example when the database file can not be opened or is locked,
+    /// when the schema on disk does not match ([Error::SchemaMismatch], [Error::ForeignKeyViolation])
+    /// or when the [Config] is read-only ([Error::ReadOnly]).
+    pub fn try_migrator<'t, S: Schema>(
+        &'t mut self,
+        config: Config,
+    ) -> Result<Option<Migrator<'t, S>>, Error> {
+        if config.read_only {
+            return Err(Error::ReadOnly);
+        }
+        if let Some(hook) = config.trace {
+            set_hook(hook);
+        }
+        let watchers = config.options.watchers.clone();
+        let interrupts = config.options.interrupts.clone();
+        let functions = config.options.functions.clone();
+        let manager = Config::into_manager(config.manager, false, config.options);
+        self.migrator_inner(
+            Database {
+                manager: Arc::new(manager),
+                busy_retry: config.busy_retry,
+                watchers,
+                hooks: Default::default(),
+                slow_log: Default::default(),
+                interrupts,
+                functions,
+                schema_version: 0,
+                schema: PhantomData,
+            },
+            config.init,
+            config.audit,
+        )
+    }
 
-        let conn = conn
-            .transaction_with_behavior(rusqlite::TransactionBehavior::Exclusive)
-            .unwrap();
+    /// Create a [Migrator] for schema `N` in the same database file as `db`.
+    ///
+    /// This is used to store schemas with a different namespace in one database, see the `schema` macro.
+    /// The connections are made in the same way as for `db`, with the functions, collations and other
+    /// settings of its [Config]. The tables of `N` are added to the audit log if `db` has an audit log.
+    ///
+    /// Migrating `N` can change the database schema, so `db` will panic when it is used afterwards.
+    /// Create all [Migrator]s before using the databases, or create `db` again with this method.
+    pub fn namespace_migrator<'t, S, N: Schema>(
+        &'t mut self,
+        db: &Database<S>,
+    ) -> Option<Migrator<'t, N>> {
+        use r2d2::ManageConnection;
+        let conn = unwrap_db(db.manager.connect());
+        let audit = unwrap_db(
+            conn.query_row(
+                "SELECT 1 FROM sqlite_schema WHERE type = 'table' AND name = ?",
+                [audit::AUDIT_TABLE],
+                |_| Ok(()),
+            )
+            .optional(),
+        );
+        unwrap_db(self.migrator_inner(
+            Database {
+                manager: db.manager.clone(),
+                busy_retry: db.busy_retry,
+                watchers: db.watchers.clone(),
+                hooks: Default::default(),
+                slow_log: Default::default(),
+                interrupts: db.interrupts.clone(),
+                functions: db.functions.clone(),
+                schema_version: 0,
+                schema: PhantomData,
+            },
+            Box::new(|_| {}),
+            audit.is_some(),
+        ))
+    }
 
-        // check if this database is newly created
-        if schema_version(&conn) == 0 {
+    fn migrator_inner<'t, S: Schema>(
+        &'t mut self,
+        db: Database<S>,
+        init: Box<dyn FnOnce(&rusqlite::Connection)>,
+        audit: bool,
+    ) -> Result<Option<Migrator<'t, S>>, Error> {
+        use r2d2::ManageConnection;
+        let Database {
+            manager,
+            busy_retry,
+            watchers,
+            interrupts,
+            functions,
+            ..
+        } = db;
+        let conn = self.conn.insert(manager.connect()?);
+        conn.pragma_update(None, "foreign_keys", "OFF")?;
+
+        let conn = journal::Owned::begin(conn)?;
+        collation_check::<S>(&conn)?;
+        fingerprint::check_application_id::<S>(&conn)?;
+
+        // check if the tables of this schema are newly created
+        if is_new::<S>(&conn)? {
             let mut b = TableTypBuilder::default();
             S::typs(&mut b);
 
             for (table_name, table) in &*b.ast.tables {
-                new_table_inner(&conn, table, Alias::new(table_name));
+                new_table_inner(&conn, table, Alias::new(table_name))?;
             }
-            (config.init)(&conn);
-            set_user_version(&conn, S::VERSION).unwrap();
+            create_all_fts(&conn, &b.ast)?;
+            create_views(&conn, &b)?;
+            create_all_triggers(&conn, &b.ast)?;
+            init(&conn);
+            set_user_version::<S>(&conn, S::VERSION)?;
         }
 
-        let user_version = user_version(&conn).unwrap();
+        let user_version = user_version::<S>(&conn)?;
         // We can not migrate databases older than `S`
         if user_version < S::VERSION {
-            return None;
+            return Ok(None);
         } else if user_version == S::VERSION {
-            foreign_key_check::<S>(&conn);
+            fingerprint::check_schema_hash::<S>(&conn)?;
+            foreign_key_check::<S>(&conn)?;
         }
+        fingerprint::set_application_id::<S>(&conn)?;
 
-        Some(Migrator {
-            manager: config.manager,
+        Ok(Some(Migrator {
+            manager,
+            progress: Box::new(|_| {}),
+            journal: false,
+            workers: 0,
+            busy_retry,
+            watchers,
+            interrupts,
+            functions,
+            audit,
             transaction: conn,
             _p: PhantomData,
             _local: PhantomData,
-        })
+        }))
+    }
+}
+
+impl LocalClient {
+    /// Check that an existing database has schema `S` and return a [ReadOnlyDatabase].
+    ///
+    /// Nothing is written to the database, so this is a good fit for processes that should
+    /// only consume data written by another process.
+    ///
+    /// Returns [None] if the database `user_version` on disk is not the version of `S`.
+    ///
+    /// This function will panic if the schema on disk does not match what is expected for its `user_version`.
+    pub fn read_only<S: Schema>(&mut self, config: Config) -> Option<ReadOnlyDatabase<S>> {
+        unwrap_db(self.try_read_only(config))
+    }
+
+    /// Create a [ReadOnlyDatabase] like [LocalClient::read_only], but return an [Error] instead of panicking.
+    ///
+    /// This includes the errors for a schema on disk that does not match, like [Error::SchemaMismatch].
+    pub fn try_read_only<S: Schema>(
+        &mut self,
+        config: Config,
+    ) -> Result<Option<ReadOnlyDatabase<S>>, Error> {
+        use r2d2::ManageConnection;
+        if let Some(hook) = config.trace {
+            set_hook(hook);
+        }
+        let watchers = config.options.watchers.clone();
+        let interrupts = config.options.interrupts.clone();
+        let functions = config.options.functions.clone();
+        let manager = Arc::new(Config::into_manager(config.manager, true, config.options));
+        let conn = manager.connect()?;
+        let txn = conn.unchecked_transaction()?;
+        collation_check::<S>(&txn)?;
+        fingerprint::check_application_id::<S>(&txn)?;
+
+        if user_version::<S>(&txn)? != S::VERSION {
+            return Ok(None);
+        }
+        fingerprint::check_schema_hash::<S>(&txn)?;
+        foreign_key_check::<S>(&txn)?;
+
+        let schema_version = schema_version(&txn)?;
+        drop(txn);
+
+        Ok(Some(ReadOnlyDatabase {
+            inner: Database {
+                manager,
+                busy_retry: config.busy_retry,
+                watchers,
+                hooks: Default::default(),
+                slow_log: Default::default(),
+                interrupts,
+                functions,
+                schema_version,
+                schema: PhantomData,
+            },
+        }))
     }
 }
 
@@ -396,8 +1333,16 @@ impl LocalClient {
 /// When all migrations are done, it can be turned into a [Database] instance with
 /// [Migrator::finish].
 pub struct Migrator<'t, S> {
-    manager: r2d2_sqlite::SqliteConnectionManager,
-    transaction: rusqlite::Transaction<'t>,
+    manager: Arc<r2d2_sqlite::SqliteConnectionManager>,
+    progress: Box<dyn FnMut(Progress) + 't>,
+    journal: bool,
+    workers: usize,
+    busy_retry: BusyRetry,
+    watchers: Arc<Watchers>,
+    interrupts: Arc<Interrupts>,
+    functions: Arc<Functions>,
+    audit: bool,
+    transaction: journal::Owned<'t>,
     _p: PhantomData<S>,
     // We want to make sure that Migrator is always used with the same LocalClient
     // so we make it local to the current thread.
@@ -406,97 +1351,394 @@ pub struct Migrator<'t, S> {
 }
 
 impl<'t, S: Schema> Migrator<'t, S> {
+    /// Call `f` with the [Progress] of copying tables in the following migrations.
+    ///
+    /// The callback is called every 1000 rows and when a table is done.
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     User { name: String },
+    /// # }
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let m = client.migrator::<v0::Schema>(Config::open_in_memory()).unwrap();
+    /// let m = m.on_progress(|p| {
+    ///     if p.done {
+    ///         println!("copied {} rows of {}", p.rows, p.table);
+    ///     }
+    /// });
+    /// ```
+    pub fn on_progress(mut self, f: impl FnMut(Progress) + 't) -> Self {
+        self.progress = Box::new(f);
+        self
+    }
+
+    /// Commit the following migrations after every table that is copied, so that they can be resumed.
+    ///
+    /// Normally all migrations run in a single exclusive transaction, which starts again from the
+    /// beginning when the process stops before [Migrator::finish].
+    /// With a journal, the tables that are copied are recorded in the database and a migration that
+    /// was interrupted skips those tables when it runs again.
+    /// Every migration step is also committed when it is done, so that a chain of migrations continues from the last step.
+    ///
+    /// The old tables are only replaced when all tables of a step are copied, so the database
+    /// keeps the previous schema until then. Other connections can write in between the commits:
+    /// writes to tables that are migrated with [Alter::identity] are also done on their copy with triggers.
+    /// Rows that are copied with rust code can not be kept up to date, so after the first of those tables is copied,
+    /// all tables of the previous schema are read-only and writes fail until the migration is applied.
+    pub fn journaled(mut self) -> Self {
+        self.journal = true;
+        self
+    }
+
+    /// Copy up to `workers` tables at the same time in the following journaled migrations.
+    ///
+    /// Tables that are migrated with [Alter::identity] are read by worker connections, which write
+    /// the rows to a file next to the database. The migration continues with the other tables in the
+    /// meantime and the copied rows are inserted when all tables are migrated.
+    ///
+    /// This only has an effect together with [Migrator::journaled] for a database file in WAL mode,
+    /// because the workers read the tables that are committed while the migration continues.
+    pub fn parallel_copy(mut self, workers: usize) -> Self {
+        self.workers = workers;
+        self
+    }
+
+    /// Prepare the migration to schema `N`, while processes that use the current schema `S` keep using the database.
+    ///
+    /// Tables that are migrated with [Alter::identity] are copied to their new table and triggers are created,
+    /// so that every insert, update and delete of the old table by the current version is also done on the new table.
+    /// Other tables are not copied yet. The database keeps the `user_version` of `S`, so the current version
+    /// of the application can still open and query it.
+    ///
+    /// Applying the same migration later with [Migrator::migrate] only copies the other tables and
+    /// replaces the old tables, so the database is locked for a shorter time.
+    /// Writes of the current version fail if they do not fit the new table, for example because of a new unique constraint.
+    pub fn expand<M, N: Schema>(mut self, m: M) -> Self
+    where
+        M: Migration<'t, From = S, To = N>,
+    {
+        unwrap_db(self.expand_inner(m));
+        self
+    }
+
+    fn expand_inner<M, N: Schema>(&mut self, m: M) -> Result<(), Error>
+    where
+        M: Migration<'t, From = S, To = N>,
+    {
+        let _span = span::migration(S::VERSION, N::VERSION);
+        let conn = &self.transaction;
+        collation_check::<N>(conn)?;
+
+        if user_version::<S>(conn)? == S::VERSION {
+            fingerprint::check_schema_hash::<S>(conn)?;
+
+            let name = N::NAMESPACE.unwrap_or("");
+            let mut builder = SchemaBuilder {
+                scope: Default::default(),
+                conn,
+                progress: &mut *self.progress,
+                drop: vec![],
+                rename: vec![],
+                done: journal::done(conn, name, N::VERSION)?,
+                // the copied tables are committed and recorded, so that they are not copied again
+                journal: Some((name, N::VERSION)),
+                workers: 0,
+                copies: VecDeque::new(),
+                expand: true,
+                old_tables: vec![],
+                error: None,
+                _p: PhantomData,
+            };
+            m.tables(&mut builder);
+            if let Some(err) = builder.error {
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
     /// Apply a database migration if the current schema is `S` and return a [Migrator] for the next schema `N`.
     ///
+    /// The migration is done in the exclusive transaction of the [Migrator], which is committed by [Migrator::finish].
+    /// A [Migrator::journaled] migration commits after every copied table and after the migration instead.
+    ///
     /// This function will panic if the schema on disk does not match what is expected for its `user_version`.
     pub fn migrate<M, N: Schema>(self, m: M) -> Migrator<'t, N>
     where
         M: Migration<'t, From = S, To = N>,
     {
-        let conn = &self.transaction;
+        unwrap_db(self.try_migrate(m))
+    }
 
-        if user_version(conn).unwrap() == S::VERSION {
+    /// Apply a database migration like [Migrator::migrate], but return an [Error] when sqlite fails.
+    ///
+    /// The migration transaction is rolled back when an error is returned.
+    /// For a journaled migration this only rolls back the changes since the last commit,
+    /// and an error is also returned when the transaction after a commit can not be started.
+    pub fn try_migrate<M, N: Schema>(mut self, m: M) -> Result<Migrator<'t, N>, Error>
+    where
+        M: Migration<'t, From = S, To = N>,
+    {
+        let _span = span::migration(S::VERSION, N::VERSION);
+        let conn = &self.transaction;
+        collation_check::<N>(conn)?;
+
+        if user_version::<S>(conn)? == S::VERSION {
+            fingerprint::check_schema_hash::<S>(conn)?;
+
+            let name = N::NAMESPACE.unwrap_or("");
+            // worker connections can only read while the migration writes in WAL mode
+            let parallel = self.journal && self.workers > 0 && parallel::supported(conn)?;
+            let workers = if parallel { self.workers } else { 0 };
+            if workers > 0 {
+                // the worker connections can only read the tables that are committed
+                conn.checkpoint()?;
+            }
+            let mut b = TableTypBuilder::default();
+            S::typs(&mut b);
+            let old_tables: Vec<_> = b.ast.tables.iter().map(|(x, _)| x.clone()).collect();
             let mut builder = SchemaBuilder {
                 scope: Default::default(),
                 conn,
+                progress: &mut *self.progress,
                 drop: vec![],
                 rename: vec![],
+                done: journal::done(conn, name, N::VERSION)?,
+                journal: self.journal.then_some((name, N::VERSION)),
+                workers,
+                copies: VecDeque::new(),
+                expand: false,
+                old_tables: old_tables.clone(),
+                error: None,
                 _p: PhantomData,
             };
             m.tables(&mut builder);
+            if let Some(err) = builder.error.take() {
+                return Err(err);
+            }
+            builder.import_all()?;
+
+            // views and triggers can depend on the tables that are migrated
+            drop_views(conn, &b)?;
+            drop_all_triggers(conn, &b.ast)?;
+            for table in &old_tables {
+                dual_write::unlock(conn, table)?;
+            }
+
+            // full-text indexes are recreated, because their tables might have been replaced
+            for (table_name, table) in &*b.ast.tables {
+                if !table.fts5.is_empty() {
+                    drop_fts(conn, table_name)?;
+                }
+            }
+
             for drop in builder.drop {
                 let sql = drop.to_string(SqliteQueryBuilder);
-                conn.execute(&sql, []).unwrap();
+                traced(&sql, &[], || conn.execute(&sql, []))?;
             }
             for rename in builder.rename {
                 let sql = rename.to_string(SqliteQueryBuilder);
-                conn.execute(&sql, []).unwrap();
+                traced(&sql, &[], || conn.execute(&sql, []))?;
+            }
+
+            let mut b = TableTypBuilder::default();
+            N::typs(&mut b);
+            create_all_fts(conn, &b.ast)?;
+            create_views(conn, &b)?;
+            create_all_triggers(conn, &b.ast)?;
+
+            foreign_key_check::<N>(conn)?;
+            set_user_version::<N>(conn, N::VERSION)?;
+            journal::clear(conn, name, N::VERSION)?;
+            if self.journal {
+                // the hash is checked for the committed version when the migration is resumed
+                fingerprint::set_schema_hash::<N>(conn)?;
+                conn.checkpoint()?;
             }
-            foreign_key_check::<N>(conn);
-            set_user_version(conn, N::VERSION).unwrap();
         }
 
-        Migrator {
+        Ok(Migrator {
             manager: self.manager,
+            progress: self.progress,
+            journal: self.journal,
+            workers: self.workers,
+            busy_retry: self.busy_retry,
+            watchers: self.watchers,
+            interrupts: self.interrupts,
+            functions: self.functions,
+            audit: self.audit,
             transaction: self.transaction,
             _p: PhantomData,
             _local: PhantomData,
-        }
+        })
     }
 
     /// Commit the migration transaction and return a [Database].
     ///
     /// Returns [None] if the database schema version is newer than `S`.
     pub fn finish(self) -> Option<Database<S>> {
+        unwrap_db(self.try_finish())
+    }
+
+    /// Commit the migration transaction like [Migrator::finish], but return an [Error] when sqlite fails.
+    pub fn try_finish(self) -> Result<Option<Database<S>>, Error> {
         let conn = &self.transaction;
-        if user_version(conn).unwrap() != S::VERSION {
-            return None;
+        if user_version::<S>(conn)? != S::VERSION {
+            return Ok(None);
         }
 
-        let schema_version = schema_version(conn);
-        self.transaction.commit().unwrap();
-
-        Some(Database {
+        let db = Database {
             manager: self.manager,
-            schema_version,
+            busy_retry: self.busy_retry,
+            watchers: self.watchers,
+            hooks: Default::default(),
+            slow_log: Default::default(),
+            interrupts: self.interrupts,
+            functions: self.functions,
+            schema_version: 0,
             schema: PhantomData,
-        })
+        };
+        if self.audit {
+            audit::install(conn, &db)?;
+        }
+        version::install(conn)?;
+
+        db.watchers.count_changes(conn);
+        // the hash is not counted as a change, it is only stored for the final schema
+        fingerprint::set_schema_hash::<S>(conn)?;
+        let schema_version = schema_version(conn)?;
+        self.transaction.commit()?;
+        // there are no callbacks yet, so this discards the changes made by the migration
+        db.watchers.notify();
+
+        Ok(Some(Database {
+            schema_version,
+            ..db
+        }))
+    }
+}
+
+/// The columns that are copied by [Alter::identity], these are the same in `From` and `To`.
+fn identity_columns<From: Table, To: Table>() -> Vec<String> {
+    let mut from = hash::TypBuilder::default();
+    From::typs(&mut from);
+    let mut to = hash::TypBuilder::default();
+    To::typs(&mut to);
+
+    let mut names = vec![To::ID.to_owned()];
+    // generated columns are computed by sqlite
+    for col in to.ast.columns.iter().filter(|col| col.generated.is_none()) {
+        let same = from.ast.columns.iter().any(|old| {
+            old.name == col.name
+                && old.typ == col.typ
+                && old.fk == col.fk
+                && (col.nullable || !old.nullable)
+        });
+        if !same && col.default.is_some() {
+            // new columns with a default value get the default value
+            continue;
+        }
+        assert!(
+            same,
+            "`Alter::identity` can not be used for table `{}`, column `{}` is not the same in the old table",
+            To::NAME,
+            col.name
+        );
+        names.push(col.name.clone());
     }
+    names
 }
 
-pub fn schema_version(conn: &rusqlite::Transaction) -> i64 {
+pub fn schema_version(conn: &rusqlite::Connection) -> rusqlite::Result<i64> {
     conn.pragma_query_value(None, "schema_version", |r| r.get(0))
-        .unwrap()
 }
 
-// Read user version field from the SQLite db
-fn user_version(conn: &rusqlite::Transaction) -> Result<i64, rusqlite::Error> {
-    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+/// Check if the tables of schema `S` still need to be created.
+fn is_new<S: Schema>(conn: &rusqlite::Connection) -> rusqlite::Result<bool> {
+    match S::NAMESPACE {
+        // other namespaces can already have tables in the database
+        None => {
+            let contains = namespace::contains(conn, None)?;
+            let mut stmt = conn.prepare("SELECT name FROM sqlite_schema WHERE type = 'table'")?;
+            let tables: Vec<String> = stmt
+                .query_map([], |r| r.get(0))?
+                .collect::<Result<_, _>>()?;
+            // internal tables start with `_` or `sqlite_`
+            Ok(!tables
+                .iter()
+                .any(|x| !x.starts_with('_') && !x.starts_with("sqlite_") && contains(x)))
+        }
+        Some(name) => Ok(namespace::version(conn, name)?.is_none()),
+    }
 }
 
-// Set user version field from the SQLite db
-fn set_user_version(conn: &rusqlite::Transaction, v: i64) -> Result<(), rusqlite::Error> {
-    conn.pragma_update(None, "user_version", v)
+// Read the version of schema `S`, this is the user version field of the SQLite db
+// or the version that is stored for the namespace of `S`
+pub(crate) fn user_version<S: Schema>(conn: &rusqlite::Connection) -> Result<i64, rusqlite::Error> {
+    match S::NAMESPACE {
+        None => conn.query_row("PRAGMA user_version", [], |row| row.get(0)),
+        Some(name) => Ok(namespace::version(conn, name)?.unwrap_or(0)),
+    }
 }
 
-fn foreign_key_check<S: Schema>(conn: &rusqlite::Transaction) {
-    let errors = conn
-        .prepare("PRAGMA foreign_key_check")
-        .unwrap()
-        .query_map([], |_| Ok(()))
-        .unwrap()
-        .count();
-    if errors != 0 {
-        panic!("migration violated foreign key constraint")
+// Set the version of schema `S`
+fn set_user_version<S: Schema>(conn: &rusqlite::Connection, v: i64) -> Result<(), rusqlite::Error> {
+    match S::NAMESPACE {
+        None => conn.pragma_update(None, "user_version", v),
+        Some(name) => {
+            namespace::install(conn)?;
+            namespace::set_version(conn, name, v)
+        }
     }
+}
+
+/// Check that every collation that is used by schema `S` is available on the connection.
+fn collation_check<S: Schema>(conn: &rusqlite::Connection) -> Result<(), Error> {
+    let mut stmt = conn.prepare("SELECT name FROM pragma_collation_list")?;
+    let available: Vec<String> = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
 
     let mut b = TableTypBuilder::default();
     S::typs(&mut b);
-    pretty_assertions::assert_eq!(
-        b.ast,
-        read_schema(conn),
-        "schema is different (expected left, but got right)",
-    );
+    for (table_name, table) in &*b.ast.tables {
+        for col in &*table.columns {
+            let Some(collate) = &col.collate else {
+                continue;
+            };
+            if !available.iter().any(|x| x.eq_ignore_ascii_case(collate)) {
+                return Err(Error::MissingCollation {
+                    table: table_name.clone(),
+                    column: col.name.clone(),
+                    collation: collate.clone(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn foreign_key_check<S: Schema>(conn: &rusqlite::Connection) -> Result<(), Error> {
+    let violation = conn
+        .query_row(
+            "SELECT \"table\", parent FROM pragma_foreign_key_check LIMIT 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    if let Some((table, parent)) = violation {
+        return Err(Error::ForeignKeyViolation { table, parent });
+    }
+
+    let mut b = TableTypBuilder::default();
+    S::typs(&mut b);
+    let found = read_schema(conn, S::NAMESPACE)?;
+    if b.ast != found {
+        let diff = pretty_assertions::Comparison::new(&b.ast, &found);
+        return Err(Error::SchemaMismatch(diff.to_string()));
+    }
+    Ok(())
 }
 
 /// Special table name that is used as souce of newly created tables.
@@ -0,0 +1,81 @@
+use rusqlite::OptionalExtension;
+
+/// Name of the table that stores the version of every namespace.
+pub(crate) const NAMESPACE_TABLE: &str = "_namespace";
+
+/// Create the namespace table, this is done when a schema with a namespace is created.
+pub(crate) fn install(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS \"{NAMESPACE_TABLE}\" (
+            \"name\" text PRIMARY KEY,
+            \"version\" integer NOT NULL
+        );"
+    ))
+}
+
+fn exists(conn: &rusqlite::Connection) -> rusqlite::Result<bool> {
+    let exists = conn
+        .query_row(
+            "SELECT 1 FROM sqlite_schema WHERE type = 'table' AND name = ?",
+            [NAMESPACE_TABLE],
+            |_| Ok(()),
+        )
+        .optional()?;
+    Ok(exists.is_some())
+}
+
+/// The version of the schema in namespace `name`, this is [None] if its tables were not created yet.
+pub(crate) fn version(conn: &rusqlite::Connection, name: &str) -> rusqlite::Result<Option<i64>> {
+    if !exists(conn)? {
+        return Ok(None);
+    }
+    let sql = format!("SELECT \"version\" FROM \"{NAMESPACE_TABLE}\" WHERE \"name\" = ?");
+    conn.query_row(&sql, [name], |r| r.get(0)).optional()
+}
+
+pub(crate) fn set_version(
+    conn: &rusqlite::Connection,
+    name: &str,
+    version: i64,
+) -> rusqlite::Result<()> {
+    let sql = format!(
+        "INSERT INTO \"{NAMESPACE_TABLE}\" (\"name\", \"version\") VALUES (?, ?)
+        ON CONFLICT (\"name\") DO UPDATE SET \"version\" = excluded.\"version\""
+    );
+    conn.execute(&sql, rusqlite::params![name, version])?;
+    Ok(())
+}
+
+/// Returns whether a table belongs to a schema, see [contains].
+pub(crate) type Contains = Box<dyn Fn(&str) -> bool>;
+
+/// Check if a table belongs to the schema with namespace `namespace`.
+///
+/// Tables of a namespace start with the name of the namespace,
+/// the schema without namespace has all tables that are not in a namespace.
+pub(crate) fn contains(
+    conn: &rusqlite::Connection,
+    namespace: Option<&str>,
+) -> rusqlite::Result<Contains> {
+    let prefix = |name: &str| format!("{name}_");
+    match namespace {
+        Some(name) => {
+            let prefix = prefix(name);
+            Ok(Box::new(move |table| table.starts_with(&prefix)))
+        }
+        None => {
+            let mut prefixes = vec![];
+            if exists(conn)? {
+                let sql = format!("SELECT \"name\" FROM \"{NAMESPACE_TABLE}\"");
+                let mut stmt = conn.prepare(&sql)?;
+                let names: Vec<String> = stmt
+                    .query_map([], |r| r.get(0))?
+                    .collect::<Result<_, _>>()?;
+                prefixes = names.iter().map(|x| prefix(x)).collect();
+            }
+            Ok(Box::new(move |table| {
+                !prefixes.iter().any(|x| table.starts_with(x))
+            }))
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::marker::PhantomData;
+
+use sea_query::{Expr, Order};
+
+use crate::{
+    exec::Query,
+    value::{operations::Prebuilt, Typed},
+    Column, Dummy, IntoColumn, RowId, Table,
+};
+
+/// One page of results from [Paginate::into_page].
+pub struct Page<O, T> {
+    /// The results of this page, ordered by the id of the paginated table.
+    pub items: Vec<O>,
+    /// Cursor to get the next page with [Paginate::after], this is [None] for the last page.
+    pub next: Option<RowId<T>>,
+}
+
+/// One page of results and the total number of results, see [Query::paginated].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paginated<O> {
+    /// The results of this page.
+    pub items: Vec<O>,
+    /// The number of results of the query on all pages together.
+    pub total: i64,
+}
+
+/// Keyset pagination of a query, see [Query::paginate].
+pub struct Paginate<'outer, 'inner, S, T> {
+    query: &'inner Query<'outer, 'inner, S>,
+    table: Column<'inner, S, T>,
+    after: Option<RowId<T>>,
+    limit: Option<usize>,
+}
+
+impl<'outer, 'inner, S> Query<'outer, 'inner, S> {
+    /// Retrieve the results in pages that are ordered by the id of `table`.
+    ///
+    /// Every page continues after the last row of the previous page, so no rows are scanned twice like with `OFFSET`.
+    /// There should be at most one result for each row of `table`.
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient, Table};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     User { name: String },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// # let txn = client.transaction(&db);
+    /// # let cursor = None;
+    /// # txn.query(|rows| {
+    /// # let user = User::join(rows);
+    /// let page = rows.paginate(&user).after(cursor).limit(20).into_page(user.name());
+    /// // `page.next` is the cursor for the next request
+    /// # });
+    /// ```
+    pub fn paginate<T: Table<Schema = S>>(
+        &'inner self,
+        table: impl IntoColumn<'inner, S, Typ = T>,
+    ) -> Paginate<'outer, 'inner, S, T> {
+        Paginate {
+            query: self,
+            table: table.into_column(),
+            after: None,
+            limit: None,
+        }
+    }
+}
+
+impl<'outer, 'inner, S> Query<'outer, 'inner, S> {
+    /// Retrieve page number `page` of the results, starting at 0, together with the total number of results.
+    ///
+    /// The results are sorted like [Query::into_vec] and split into pages of `per_page` results using `OFFSET`.
+    /// The total is computed with `COUNT(*) OVER ()` in the same query,
+    /// only a page after the last result needs a separate [Query::count].
+    /// Use [Query::paginate] instead for large tables, it does not need to skip the rows of previous pages.
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient, Table};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     User { name: String },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// # let txn = client.transaction(&db);
+    /// # txn.query(|rows| {
+    /// # let user = User::join(rows);
+    /// let page = rows.paginated(user.name(), 2, 20);
+    /// let pages = (page.total + 19) / 20;
+    /// # });
+    /// ```
+    pub fn paginated<D>(&'inner self, dummy: D, page: u64, per_page: u64) -> Paginated<D::Out>
+    where
+        D: Dummy<'inner, 'outer, S>,
+    {
+        let total = Prebuilt::<i64>(Expr::cust("COUNT(*) OVER ()"), PhantomData);
+        let rows = self.into_vec_private((dummy, total), |select| {
+            select.limit(per_page);
+            select.offset(page * per_page);
+        });
+
+        let total = match rows.first() {
+            Some((_, total)) => *total,
+            // the window function has no rows to return the total with
+            None if page > 0 => self.count(),
+            None => 0,
+        };
+        Paginated {
+            items: rows.into_iter().map(|(item, _)| item).collect(),
+            total,
+        }
+    }
+}
+
+impl<'outer, 'inner, S, T: Table<Schema = S>> Paginate<'outer, 'inner, S, T> {
+    /// Only retrieve the rows after `cursor`, this is the first page if the cursor is [None].
+    pub fn after(mut self, cursor: Option<RowId<T>>) -> Self {
+        self.after = cursor;
+        self
+    }
+
+    /// Retrieve at most `limit` results.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Retrieve the page of results.
+    pub fn into_page<D>(self, dummy: D) -> Page<D::Out, T>
+    where
+        D: Dummy<'inner, 'outer, S>,
+    {
+        let query = self.query;
+        let id = self.table.build_expr(query.ast.builder());
+        let mut items = query.into_vec_private((self.table.clone(), dummy), |select| {
+            if let Some(after) = self.after {
+                select.and_where(Expr::expr(id.clone()).gt(after.idx));
+            }
+            select.clear_order_by();
+            select.order_by_expr(id.clone(), Order::Asc);
+            for (expr, _) in query.ast.select.iter() {
+                select.order_by_expr(expr.clone(), Order::Asc);
+            }
+            // one more row is retrieved to know if there is a next page
+            if let Some(limit) = self.limit {
+                select.limit(limit as u64 + 1);
+            }
+        });
+
+        let mut next = None;
+        if let Some(limit) = self.limit.filter(|limit| items.len() > *limit) {
+            items.truncate(limit);
+            next = items.last().map(|(row, _)| RowId::from(*row));
+        }
+        Page {
+            items: items.into_iter().map(|(_, item)| item).collect(),
+            next,
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::{panic::resume_unwind, thread::JoinHandle};
+
+use crate::{journal, trace::traced};
+
+/// A table that is copied by a worker connection, see [crate::migration::Migrator::parallel_copy].
+pub(crate) struct Worker {
+    pub table: &'static str,
+    tmp: String,
+    columns: String,
+    file: String,
+    handle: JoinHandle<rusqlite::Result<()>>,
+}
+
+/// Check if worker connections can be used for the database of `conn`.
+///
+/// The workers read while the migration has an exclusive transaction, this is only possible in WAL mode.
+pub(crate) fn supported(conn: &rusqlite::Connection) -> rusqlite::Result<bool> {
+    let mode: String = conn.pragma_query_value(None, "journal_mode", |r| r.get(0))?;
+    Ok(mode.eq_ignore_ascii_case("wal"))
+}
+
+/// Start a thread that copies `columns` of table `from` in the database at `path` to a new file.
+///
+/// The worker reads the last committed version of `from`, this does not wait for the migration
+/// transaction because the database is in WAL mode, see [supported].
+pub(crate) fn spawn(
+    path: &str,
+    from: &'static str,
+    to: &'static str,
+    tmp: String,
+    columns: &[String],
+) -> Worker {
+    let columns: Vec<_> = columns.iter().map(|x| format!("\"{x}\"")).collect();
+    let columns = columns.join(", ");
+    let file = format!("{path}-{tmp}");
+    let sql = format!("CREATE TABLE \"copy\" AS SELECT {columns} FROM \"old\".\"{from}\"");
+
+    let (path, worker_file) = (path.to_owned(), file.clone());
+    let handle = std::thread::spawn(move || {
+        // the file can be left over from a migration that was interrupted
+        let _ = std::fs::remove_file(&worker_file);
+        let conn = rusqlite::Connection::open(&worker_file)?;
+        conn.execute("ATTACH ? AS \"old\"", [path])?;
+        conn.execute_batch(&sql)
+    });
+    Worker {
+        table: to,
+        tmp,
+        columns,
+        file,
+        handle,
+    }
+}
+
+/// Wait for the worker and insert the copied rows in the temporary table, returns the number of rows.
+///
+/// The copy is recorded in the journal of schema `name` and committed.
+/// The file of the worker is removed, also when there is an error.
+pub(crate) fn import(
+    conn: &journal::Owned,
+    worker: Worker,
+    name: &str,
+    version: i64,
+) -> rusqlite::Result<usize> {
+    let file = worker.file.clone();
+    let res = import_file(conn, worker, name, version);
+    let _ = std::fs::remove_file(&file);
+    res
+}
+
+fn import_file(
+    conn: &journal::Owned,
+    worker: Worker,
+    name: &str,
+    version: i64,
+) -> rusqlite::Result<usize> {
+    let Worker {
+        table,
+        tmp,
+        columns,
+        file,
+        handle,
+    } = worker;
+    match handle.join() {
+        Ok(res) => res?,
+        Err(panic) => resume_unwind(panic),
+    }
+    // when the rows can not be inserted the migration fails, "copy" stays attached until the connection is closed
+    conn.execute("ATTACH ? AS \"copy\"", [&file])?;
+    let sql = format!("INSERT INTO \"{tmp}\" ({columns}) SELECT {columns} FROM \"copy\".\"copy\"");
+    let rows = traced(&sql, &[], || conn.execute(&sql, []))?;
+    journal::record(conn, name, version, table)?;
+    // attached databases can only be detached outside of a transaction
+    conn.checkpoint_with(|conn| conn.execute_batch("DETACH \"copy\""))?;
+    Ok(rows)
+}
@@ -3,7 +3,14 @@ use std::collections::HashMap;
 use ref_cast::RefCast;
 use rust_query_macros::FromDummy;
 
-use crate::{client::QueryBuilder, db::Col, hash, value::IntoColumn, Table};
+use crate::{
+    client::QueryBuilder,
+    db::Col,
+    error::{unwrap_db, Error},
+    hash,
+    value::IntoColumn,
+    Table,
+};
 
 macro_rules! field {
     ($name:ident: $typ:ty) => {
@@ -61,6 +68,8 @@ impl<T: Clone> TableInfoDummy<T> {
     field! {r#type("type"): String}
     field! {notnull: i64}
     field! {pk: i64}
+    field! {hidden: i64}
+    field! {dflt_value: Option<String>}
 }
 
 impl Table for TableInfo {
@@ -68,7 +77,7 @@ impl Table for TableInfo {
     type Schema = Pragma;
 
     fn name(&self) -> String {
-        format!("pragma_table_info('{}', 'main')", self.0)
+        format!("pragma_table_xinfo('{}', 'main')", self.0)
     }
 
     fn typs(_f: &mut hash::TypBuilder) {}
@@ -87,6 +96,7 @@ impl<T: Clone> ForeignKeyListDummy<T> {
     field! {table: String}
     field! {from: String}
     field! {to: String}
+    field! {on_delete: String}
 }
 
 impl Table for ForeignKeyList {
@@ -154,22 +164,44 @@ impl Table for IndexInfo {
     fn dummy<'t>(_: impl IntoColumn<'t, Self::Schema, Typ = Self>) -> Self::Dummy<'t> {}
 }
 
-pub fn read_schema(conn: &rusqlite::Transaction) -> hash::Schema {
+/// Read the tables of the schema with `namespace` from the database.
+///
+/// Returns [Error::SchemaMismatch] if a table uses something that can not be part of a schema,
+/// for example a column type that is not supported.
+pub fn read_schema(
+    conn: &rusqlite::Connection,
+    namespace: Option<&str>,
+) -> Result<hash::Schema, Error> {
     #[derive(Clone, FromDummy)]
     struct Column {
         name: String,
         typ: String,
         pk: bool,
         notnull: bool,
+        hidden: i64,
+        default: Option<String>,
     }
 
     let tables = conn.new_query(|q| {
         let table = q.join_custom(TableList);
         q.filter(table.schema().into_column().eq("main"));
         q.filter(table.r#type().into_column().eq("table"));
-        q.filter(table.name().into_column().eq("sqlite_schema").not());
         q.into_vec(table.name())
     });
+    // internal tables like `sqlite_stat1` (created by `ANALYZE`) are not part of the schema
+    // the audit log, the version counter, the namespace versions and the schema hashes are not part of the schema either
+    let contains = unwrap_db(crate::namespace::contains(conn, namespace));
+    let tables = tables.into_iter().filter(|x| {
+        !x.starts_with("sqlite_")
+            && x != crate::audit::AUDIT_TABLE
+            && x != crate::version::VERSION_TABLE
+            && x != crate::namespace::NAMESPACE_TABLE
+            && x != crate::fingerprint::SCHEMA_TABLE
+            && x != crate::journal::JOURNAL_TABLE
+            // tables that are copied by a journaled migration that was interrupted
+            && !x.starts_with("_tmp")
+            && contains(x)
+    });
 
     let mut output = hash::Schema::default();
 
@@ -182,40 +214,83 @@ pub fn read_schema(conn: &rusqlite::Transaction) -> hash::Schema {
                 typ: table.r#type(),
                 pk: table.pk().into_column().eq(0).not(),
                 notnull: table.notnull().into_column().eq(0).not(),
+                hidden: table.hidden(),
+                default: table.dflt_value(),
             })
         });
 
         let fks: HashMap<_, _> = conn
             .new_query(|q| {
                 let fk = q.join_custom(ForeignKeyList(table_name.to_owned()));
-                q.into_vec((fk.from(), fk.table()))
+                q.into_vec((fk.from(), (fk.table(), fk.on_delete())))
             })
             .into_iter()
             .collect();
 
+        let mismatch = |msg: String| Error::SchemaMismatch(format!("table `{table_name}`: {msg}"));
+        let make_on_delete = |action: &str| match action {
+            "NO ACTION" => Ok(hash::OnDelete::NoAction),
+            "CASCADE" => Ok(hash::OnDelete::Cascade),
+            "RESTRICT" => Ok(hash::OnDelete::Restrict),
+            "SET NULL" => Ok(hash::OnDelete::SetNull),
+            a => Err(mismatch(format!("unknown foreign key action {a}"))),
+        };
+
         let make_type = |col: &Column| match col.typ.as_str() {
-            "INTEGER" => hash::ColumnType::Integer,
-            "TEXT" => hash::ColumnType::String,
-            "REAL" => hash::ColumnType::Float,
-            t => panic!("unknown type {t}"),
+            "INTEGER" => Ok(hash::ColumnType::Integer),
+            "TEXT" => Ok(hash::ColumnType::String),
+            "REAL" => Ok(hash::ColumnType::Float),
+            t => Err(mismatch(format!(
+                "unknown type {t} of column `{}`",
+                col.name
+            ))),
         };
 
         // we only care about columns that are not a unique id and for which we know the type
-        columns.retain(|col| {
-            if col.pk {
-                assert_eq!(col.name, "id");
-                return false;
-            }
-            true
-        });
-
+        if let Some(col) = columns.iter().find(|col| col.pk && col.name != "id") {
+            return Err(mismatch(format!("primary key `{}` is not `id`", col.name)));
+        }
+        columns.retain(|col| !col.pk);
+
+        let sql: String = conn
+            .query_row(
+                "SELECT sql FROM sqlite_schema WHERE type = 'table' AND name = ?",
+                [&table_name],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let defs = table_defs(&sql)
+            .ok_or_else(|| mismatch("the `CREATE TABLE` statement can not be parsed".to_owned()))?;
         let mut table_def = hash::Table::default();
+        for check in check_exprs(&defs) {
+            table_def.checks.insert(check);
+        }
         for col in columns {
+            let fk = fks.get(&col.name);
+            // hidden is 2 for virtual and 3 for stored generated columns
+            let generated = if col.hidden >= 2 {
+                let expr = generated_expr(&defs, &col.name).ok_or_else(|| {
+                    mismatch(format!(
+                        "missing expression of generated column `{}`",
+                        col.name
+                    ))
+                })?;
+                Some(hash::Generated {
+                    expr,
+                    stored: col.hidden == 3,
+                })
+            } else {
+                None
+            };
             let def = hash::Column {
-                fk: fks.get(&col.name).map(|x| (x.clone(), "id".to_owned())),
-                typ: make_type(&col),
+                collate: column_collate(&defs, &col.name),
+                generated,
+                fk: fk.map(|(table, _)| (table.clone(), "id".to_owned())),
+                on_delete: fk.map_or(Ok(hash::OnDelete::NoAction), |(_, x)| make_on_delete(x))?,
+                typ: make_type(&col)?,
                 name: col.name,
                 nullable: !col.notnull,
+                default: col.default,
             };
             table_def.columns.insert(def)
         }
@@ -242,7 +317,215 @@ pub fn read_schema(conn: &rusqlite::Transaction) -> hash::Schema {
             table_def.uniques.insert(unique_def);
         }
 
+        let fts_name = crate::fts::fts_name(&table_name);
+        let has_fts = conn.new_query(|q| {
+            let table = q.join_custom(TableList);
+            q.filter(table.schema().into_column().eq("main"));
+            q.filter(table.r#type().into_column().eq("virtual"));
+            q.filter(table.name().into_column().eq(fts_name.as_str()));
+            q.count()
+        }) != 0;
+        if has_fts {
+            let columns = conn.new_query(|q| {
+                let col = q.join_custom(TableInfo(fts_name.clone()));
+                // the hidden columns of the virtual table are not part of the index
+                q.filter(col.hidden().into_column().eq(0));
+                q.into_vec(col.name())
+            });
+            for column in columns {
+                table_def.fts5.insert(column);
+            }
+        }
+
+        // the triggers that keep the full-text index in sync are not part of the schema
+        let fts_triggers = ["ai", "ad", "au"].map(|x| format!("{fts_name}_{x}"));
+        let triggers: Vec<(String, String)> = conn
+            .prepare("SELECT name, sql FROM sqlite_schema WHERE type = 'trigger' AND tbl_name = ?")
+            .unwrap()
+            .query_map([&table_name], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        for (name, sql) in triggers {
+            // triggers of a migration that is prepared with `Migrator::expand` are not part of the schema either
+            if !fts_triggers.contains(&name) && !name.starts_with(crate::dual_write::TRIGGER_PREFIX)
+            {
+                table_def
+                    .triggers
+                    .insert(trigger_parts(name, &table_name, &sql));
+            }
+        }
+
         output.tables.insert((table_name, table_def))
     }
-    output
+    Ok(output)
+}
+
+/// Split the body of a `CREATE TABLE` statement into column definitions and table constraints.
+fn table_defs(sql: &str) -> Option<Vec<&str>> {
+    let body = &sql[sql.find('(')?..];
+    let (body, _) = split_parens(body)?;
+
+    // split on commas that are not nested or quoted
+    let mut defs = vec![];
+    let mut start = 0;
+    let mut depth = 0;
+    let mut quote = None;
+    for (i, c) in body.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '[') => quote = Some(']'),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                defs.push(body[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    defs.push(body[start..].trim());
+    Some(defs)
+}
+
+/// Split the content of the parentheses at the start of `sql` from the rest.
+fn split_parens(sql: &str) -> Option<(&str, &str)> {
+    let sql = sql.trim_start().strip_prefix('(')?;
+    let mut depth = 0;
+    let mut quote = None;
+    for (i, c) in sql.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '[') => quote = Some(']'),
+            (None, '(') => depth += 1,
+            (None, ')') if depth == 0 => return Some((&sql[..i], &sql[i + 1..])),
+            (None, ')') => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split the name at the start of `sql` from the rest, the name can be quoted in all the ways that sqlite allows.
+fn split_name(sql: &str) -> Option<(String, &str)> {
+    let sql = sql.trim_start();
+    let close = match sql.chars().next()? {
+        '"' => '"',
+        '`' => '`',
+        '[' => ']',
+        _ => {
+            let end = sql
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(sql.len());
+            return (end != 0).then(|| (sql[..end].to_owned(), &sql[end..]));
+        }
+    };
+    let mut name = String::new();
+    let mut chars = sql.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        if c != close {
+            name.push(c);
+        } else if close != ']' && sql[i + 1..].starts_with(close) {
+            // a quote is escaped by repeating it
+            name.push(c);
+            chars.next();
+        } else {
+            return Some((name, &sql[i + 1..]));
+        }
+    }
+    None
+}
+
+/// Strip `keyword` from the start of `sql`, ignoring case.
+fn strip_keyword<'a>(sql: &'a str, keyword: &str) -> Option<&'a str> {
+    let sql = sql.trim_start();
+    let rest = sql.get(keyword.len()..)?;
+    let is_word = rest.starts_with(|c: char| c.is_alphanumeric() || c == '_');
+    (sql[..keyword.len()].eq_ignore_ascii_case(keyword) && !is_word).then_some(rest)
+}
+
+/// Find `keyword` in `sql` outside of parentheses and quotes, ignoring case, and return the rest after it.
+fn after_keyword<'a>(sql: &'a str, keyword: &str) -> Option<&'a str> {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut word_start = true;
+    for (i, c) in sql.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '[') => quote = Some(']'),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, _) if depth == 0 && word_start => {
+                if let Some(rest) = strip_keyword(&sql[i..], keyword) {
+                    return Some(rest);
+                }
+            }
+            _ => {}
+        }
+        word_start = !(c.is_alphanumeric() || c == '_');
+    }
+    None
+}
+
+/// Find the definition of `column` without its name.
+fn column_def<'a>(defs: &[&'a str], column: &str) -> Option<&'a str> {
+    defs.iter().find_map(|def| {
+        let (name, rest) = split_name(def)?;
+        name.eq_ignore_ascii_case(column).then_some(rest)
+    })
+}
+
+/// Find the expression of a generated column in the `CREATE TABLE` statement.
+///
+/// Both `GENERATED ALWAYS AS (expr)` and the short form `AS (expr)` are found.
+fn generated_expr(defs: &[&str], column: &str) -> Option<String> {
+    let def = column_def(defs, column)?;
+    let (expr, _) = split_parens(after_keyword(def, "AS")?)?;
+    Some(expr.to_owned())
+}
+
+/// Find the collation of a column in the `CREATE TABLE` statement.
+fn column_collate(defs: &[&str], column: &str) -> Option<String> {
+    let def = column_def(defs, column)?;
+    let (name, _) = split_name(after_keyword(def, "COLLATE")?)?;
+    Some(name)
+}
+
+/// Split a `CREATE TRIGGER` statement into its event and body, see [hash::Trigger::sql].
+fn trigger_parts(name: String, table: &str, sql: &str) -> hash::Trigger {
+    let prefix = format!("CREATE TRIGGER \"{name}\" ");
+    let marker = format!(" ON \"{table}\" BEGIN\n");
+    let parts = sql
+        .strip_prefix(&prefix)
+        .and_then(|sql| sql.split_once(&marker))
+        .and_then(|(event, body)| Some((event, body.strip_suffix("\nEND")?)));
+    // triggers that were not created from a schema are different from every trigger in the schema
+    let (event, body) = parts.unwrap_or((sql, ""));
+    hash::Trigger {
+        name,
+        event: event.to_owned(),
+        body: body.to_owned(),
+    }
+}
+
+/// Find the expressions of the `CHECK` table constraints in the `CREATE TABLE` statement.
+///
+/// The constraints can have a name like `CONSTRAINT name CHECK (expr)`, the name is not part of the schema.
+fn check_exprs(defs: &[&str]) -> Vec<String> {
+    defs.iter()
+        .filter_map(|def| {
+            let def = match strip_keyword(def, "CONSTRAINT") {
+                Some(named) => split_name(named)?.1,
+                None => def,
+            };
+            let (expr, _) = split_parens(strip_keyword(def, "CHECK")?)?;
+            Some(expr.to_owned())
+        })
+        .collect()
 }
@@ -0,0 +1,271 @@
+use std::{
+    fmt::{Debug, Display},
+    hash::{BuildHasher, Hash, RandomState},
+    marker::PhantomData,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rusqlite::types::{FromSql, FromSqlResult, ValueRef};
+use sea_query::SimpleExpr;
+
+use crate::{
+    hash,
+    value::{MyTyp, Typed, ValueBuilder},
+    Column, IntoColumn, Table,
+};
+
+/// Name of the column that is added by the `public_id` attribute.
+pub(crate) const PUBLIC_ID: &str = "public_id";
+
+/// The format of the public ids of a table, see the `public_id` attribute of [crate::migration::schema].
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdKind {
+    /// A UUID version 7 in its hyphenated lowercase form.
+    Uuid,
+    /// A ULID in its uppercase form.
+    Ulid,
+}
+
+const CROCKFORD: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+impl IdKind {
+    /// Generate a new id, it starts with the current time in milliseconds followed by random bits.
+    pub(crate) fn generate(self) -> String {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let random = (u128::from(random_u64()) << 64) | u128::from(random_u64());
+        // the 48 bits of the time are followed by 80 random bits
+        let mut bits = (millis & 0xffff_ffff_ffff) << 80 | (random >> 48);
+        match self {
+            IdKind::Uuid => {
+                // set the version to 7 and the variant to RFC 4122
+                bits = bits & !(0xf << 76) | (0x7 << 76);
+                bits = bits & !(0x3 << 62) | (0x2 << 62);
+                let hex = format!("{bits:032x}");
+                format!(
+                    "{}-{}-{}-{}-{}",
+                    &hex[0..8],
+                    &hex[8..12],
+                    &hex[12..16],
+                    &hex[16..20],
+                    &hex[20..32]
+                )
+            }
+            IdKind::Ulid => (0..26)
+                .rev()
+                .map(|i| CROCKFORD[(bits >> (5 * i)) as usize & 31] as char)
+                .collect(),
+        }
+    }
+
+    /// Check that `id` has this format and return it in the form that is stored.
+    fn normalize(self, id: &str) -> Option<String> {
+        match self {
+            IdKind::Uuid => {
+                let valid = id.len() == 36
+                    && id.char_indices().all(|(i, c)| match i {
+                        8 | 13 | 18 | 23 => c == '-',
+                        _ => c.is_ascii_hexdigit(),
+                    });
+                valid.then(|| id.to_ascii_lowercase())
+            }
+            IdKind::Ulid => {
+                let id = id.to_ascii_uppercase();
+                let valid = id.len() == 26
+                    && id.bytes().all(|c| CROCKFORD.contains(&c))
+                    // the first character only has three bits
+                    && id.as_bytes()[0] <= b'7';
+                valid.then_some(id)
+            }
+        }
+    }
+}
+
+/// Random bits that are different for every call, they do not need to be unpredictable.
+fn random_u64() -> u64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    RandomState::new().hash_one(COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+/// The public id of a row in table `T`, for tables with the `public_id` attribute.
+///
+/// Public ids can be shown to users and used in urls, because unlike the [i64] id of a [crate::TableRow]
+/// they do not reveal how many rows there are. They are not random enough to be used as a secret.
+/// They are generated when a row is inserted and never change, also not when the table is migrated.
+///
+/// With the `serde` feature, [PublicId] can be serialized and deserialized as a string.
+/// ```
+/// # use rust_query::{migration::{schema, Config}, LocalClient, PublicId};
+/// # #[schema]
+/// # enum Schema {
+/// #     #[public_id(uuid)]
+/// #     Post { title: String },
+/// # }
+/// # use v0::*;
+/// # #[derive(Debug)]
+/// # struct NotFound;
+/// # fn main() -> Result<(), NotFound> {
+/// # let mut client = LocalClient::try_new().unwrap();
+/// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+/// # let mut txn = client.transaction_mut(&db);
+/// # let post = txn.insert(Post { title: "hello" });
+/// let id: PublicId<Post> = txn.query_one(post.public_id());
+/// // later, for example in a request handler
+/// # let path = id.to_string();
+/// let id = PublicId::<Post>::parse(&path).ok_or(NotFound)?;
+/// let post = txn.query_one(Post::by_public_id(id));
+/// # assert!(post.is_some());
+/// # Ok(())
+/// # }
+/// ```
+pub struct PublicId<T> {
+    value: String,
+    _p: PhantomData<fn() -> T>,
+}
+
+impl<T: Table> PublicId<T> {
+    /// Parse a public id of table `T`, returns [None] if `id` does not have the format of the table.
+    ///
+    /// UUIDs and ULIDs are not case sensitive, so they are converted to the case that is stored.
+    pub fn parse(id: &str) -> Option<Self> {
+        let kind = T::PUBLIC_ID.expect("the table does not have the `public_id` attribute");
+        kind.normalize(id).map(|value| Self {
+            value,
+            _p: PhantomData,
+        })
+    }
+}
+
+impl<T> PublicId<T> {
+    /// Get the text of the id, this is the same as the `public_id` column in the database.
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+}
+
+impl<T> Clone for PublicId<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            _p: PhantomData,
+        }
+    }
+}
+
+impl<T> PartialEq for PublicId<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T> Eq for PublicId<T> {}
+
+impl<T> PartialOrd for PublicId<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Ids are ordered by the time that they were generated, up to the millisecond.
+impl<T> Ord for PublicId<T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+impl<T> Hash for PublicId<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+    }
+}
+
+impl<T> Debug for PublicId<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.value, f)
+    }
+}
+
+impl<T> Display for PublicId<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.value)
+    }
+}
+
+impl<T> FromSql for PublicId<T> {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        Ok(Self {
+            value: String::column_result(value)?,
+            _p: PhantomData,
+        })
+    }
+}
+
+impl<T: Table> MyTyp for PublicId<T> {
+    const TYP: hash::ColumnType = hash::ColumnType::String;
+    type Out<'t> = Self;
+    type Sql = String;
+}
+
+impl<T: Table> Typed for PublicId<T> {
+    type Typ = PublicId<T>;
+    fn build_expr(&self, _: ValueBuilder) -> SimpleExpr {
+        SimpleExpr::from(self.value.clone())
+    }
+}
+
+impl<'t, T: Table> IntoColumn<'t, T::Schema> for PublicId<T> {
+    type Owned = Self;
+    fn into_owned(self) -> Self::Owned {
+        self
+    }
+}
+
+/// Find the row of table `T` with a public id, this is used by the generated `by_public_id` functions.
+pub fn by_public_id<'a, T: Table>(
+    id: impl IntoColumn<'a, T::Schema, Typ = PublicId<T>>,
+) -> Column<'a, T::Schema, Option<T>> {
+    ByPublicId(id, PhantomData).into_column()
+}
+
+struct ByPublicId<C, T>(C, PhantomData<fn() -> T>);
+
+impl<C: Clone, T> Clone for ByPublicId<C, T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone(), PhantomData)
+    }
+}
+
+impl<C: Typed<Typ = PublicId<T>>, T: Table> Typed for ByPublicId<C, T> {
+    type Typ = Option<T>;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        b.get_unique::<T>(vec![(PUBLIC_ID, self.0.build_expr(b))])
+    }
+}
+
+impl<'a, C: IntoColumn<'a, T::Schema, Typ = PublicId<T>>, T: Table> IntoColumn<'a, T::Schema>
+    for ByPublicId<C, T>
+{
+    type Owned = ByPublicId<C::Owned, T>;
+    fn into_owned(self) -> Self::Owned {
+        ByPublicId(self.0.into_owned(), PhantomData)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for PublicId<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.value)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Table> serde::Deserialize<'de> for PublicId<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        Self::parse(&id).ok_or_else(|| serde::de::Error::custom("invalid public id"))
+    }
+}
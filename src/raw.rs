@@ -0,0 +1,292 @@
+use std::{fmt, marker::PhantomData, rc::Rc};
+
+use rusqlite::types::{FromSql, Type};
+use sea_query::{Expr, SimpleExpr};
+use sea_query_rusqlite::{RusqliteValue, RusqliteValues};
+
+use crate::{
+    hash::ColumnType,
+    trace::{record_steps, traced},
+    value::{IntoColumn, MyTyp, Typed, ValueBuilder},
+    Column, Transaction,
+};
+
+/// This trait is implemented by types that can be retrieved with [Transaction::raw_query].
+///
+/// Implement it on custom structs using [derive@crate::FromRaw].
+pub trait FromRaw: Sized {
+    #[doc(hidden)]
+    fn from_raw(row: RawRow<'_>) -> Result<Self, RawError>;
+}
+
+/// Error returned by [Transaction::raw_query].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RawError {
+    /// The sql could not be executed, this contains the error message from sqlite.
+    Sql(String),
+    /// The sql statement can modify the database, which is not allowed.
+    NotReadOnly,
+    /// The result does not have a column with this name.
+    MissingColumn(String),
+    /// The value in a column does not have the expected type.
+    WrongType {
+        column: String,
+        expected: &'static str,
+        found: String,
+    },
+}
+
+impl fmt::Display for RawError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RawError::Sql(msg) => write!(f, "sql error: {msg}"),
+            RawError::NotReadOnly => write!(f, "raw queries must be read-only"),
+            RawError::MissingColumn(col) => write!(f, "missing column `{col}`"),
+            RawError::WrongType {
+                column,
+                expected,
+                found,
+            } => write!(f, "column `{column}` is {found}, expected {expected}"),
+        }
+    }
+}
+
+impl std::error::Error for RawError {}
+
+impl From<rusqlite::Error> for RawError {
+    fn from(value: rusqlite::Error) -> Self {
+        RawError::Sql(value.to_string())
+    }
+}
+
+/// A single row of a raw query result.
+pub struct RawRow<'x> {
+    row: &'x rusqlite::Row<'x>,
+}
+
+impl RawRow<'_> {
+    /// Read the value of the column with this name and check its type.
+    pub fn get<T: RawTyp>(&self, name: &str) -> Result<T, RawError> {
+        let idx = self
+            .row
+            .as_ref()
+            .column_index(name)
+            .map_err(|_| RawError::MissingColumn(name.to_owned()))?;
+        let typ = self.row.get_ref_unwrap(idx).data_type();
+        if !T::accepts(typ) {
+            return Err(RawError::WrongType {
+                column: name.to_owned(),
+                expected: T::NAME,
+                found: typ.to_string(),
+            });
+        }
+        Ok(self.row.get_unwrap(idx))
+    }
+}
+
+/// Types that can be read from the columns of a raw query.
+///
+/// [crate::TableRow] is not supported, because it is not possible to check that the row exists.
+pub trait RawTyp: FromSql {
+    #[doc(hidden)]
+    const NAME: &'static str;
+    #[doc(hidden)]
+    fn accepts(typ: Type) -> bool;
+}
+
+impl RawTyp for i64 {
+    const NAME: &'static str = "Integer";
+    fn accepts(typ: Type) -> bool {
+        typ == Type::Integer
+    }
+}
+
+impl RawTyp for f64 {
+    const NAME: &'static str = "Real";
+    fn accepts(typ: Type) -> bool {
+        typ == Type::Real
+    }
+}
+
+impl RawTyp for String {
+    const NAME: &'static str = "Text";
+    fn accepts(typ: Type) -> bool {
+        typ == Type::Text
+    }
+}
+
+impl RawTyp for bool {
+    const NAME: &'static str = "Integer";
+    fn accepts(typ: Type) -> bool {
+        typ == Type::Integer
+    }
+}
+
+impl<T: RawTyp> RawTyp for Option<T> {
+    const NAME: &'static str = T::NAME;
+    fn accepts(typ: Type) -> bool {
+        typ == Type::Null || T::accepts(typ)
+    }
+}
+
+/// Types that can be used as parameters of a raw query.
+pub trait RawParam {
+    #[doc(hidden)]
+    fn to_value(&self) -> sea_query::Value;
+}
+
+impl RawParam for i64 {
+    fn to_value(&self) -> sea_query::Value {
+        (*self).into()
+    }
+}
+
+impl RawParam for f64 {
+    fn to_value(&self) -> sea_query::Value {
+        (*self).into()
+    }
+}
+
+impl RawParam for bool {
+    fn to_value(&self) -> sea_query::Value {
+        (*self).into()
+    }
+}
+
+impl RawParam for &str {
+    fn to_value(&self) -> sea_query::Value {
+        (*self).into()
+    }
+}
+
+impl RawParam for String {
+    fn to_value(&self) -> sea_query::Value {
+        self.as_str().into()
+    }
+}
+
+impl<T: RawParam> RawParam for Option<T> {
+    fn to_value(&self) -> sea_query::Value {
+        match self {
+            Some(val) => val.to_value(),
+            None => sea_query::Value::BigInt(None),
+        }
+    }
+}
+
+impl<'t, S> Transaction<'t, S> {
+    /// Execute a raw sql query and decode the rows.
+    ///
+    /// Every `?` in the sql is replaced with the next parameter.
+    /// Column names and value types of the result are checked while decoding,
+    /// an [Err] is returned if they do not match.
+    ///
+    /// Only read-only statements are allowed.
+    /// ```
+    /// # use rust_query::{FromRaw, Transaction, RawError};
+    /// #[derive(FromRaw)]
+    /// struct Answer {
+    ///     answer: i64,
+    /// }
+    ///
+    /// fn answer<S>(db: &Transaction<S>) -> Result<Vec<Answer>, RawError> {
+    ///     db.raw_query("SELECT ? + 2 AS answer", &[&40i64])
+    /// }
+    /// ```
+    pub fn raw_query<T: FromRaw>(
+        &self,
+        sql: &str,
+        params: &[&dyn RawParam],
+    ) -> Result<Vec<T>, RawError> {
+        let values = params.iter().map(|x| RusqliteValue(x.to_value()));
+        let values = RusqliteValues(values.collect());
+
+        let mut statement = self.transaction().prepare_cached(sql)?;
+        if !statement.readonly() {
+            return Err(RawError::NotReadOnly);
+        }
+        traced(sql, &values.0, || {
+            let res = (|| {
+                let mut rows = statement.query(&*values.as_params())?;
+
+                let mut out = vec![];
+                while let Some(row) = rows.next()? {
+                    out.push(T::from_raw(RawRow { row })?);
+                }
+                Ok(out)
+            })();
+            record_steps(&statement);
+            res
+        })
+    }
+}
+
+/// Raw sql expression that can be used as a [crate::Column].
+///
+/// The value of the expression is converted to the declared type `T` with `CAST`.
+/// `T` can be any type that [RawTyp] is implemented for, so the expression can not be a [crate::TableRow].
+/// Every `?` in the sql is replaced with the next argument.
+/// ```
+/// # use rust_query::{Column, IntoColumn, RawExpr};
+/// # fn contains<'t, S: 't>(name: Column<'t, S, String>) -> Column<'t, S, bool> {
+/// RawExpr::new("instr(?, ?) > 0").arg(name).arg("steve").into_column()
+/// # }
+/// ```
+pub struct RawExpr<'t, S, T> {
+    sql: String,
+    args: Vec<RawArg<'t>>,
+    _p: PhantomData<Column<'t, S, T>>,
+}
+
+type RawArg<'t> = Rc<dyn Fn(ValueBuilder) -> SimpleExpr + 't>;
+
+impl<S, T> Clone for RawExpr<'_, S, T> {
+    fn clone(&self) -> Self {
+        Self {
+            sql: self.sql.clone(),
+            args: self.args.clone(),
+            _p: PhantomData,
+        }
+    }
+}
+
+impl<'t, S, T: MyTyp + RawTyp> RawExpr<'t, S, T> {
+    /// Create a raw sql expression with result type `T`.
+    pub fn new(sql: impl Into<String>) -> Self {
+        Self {
+            sql: sql.into(),
+            args: vec![],
+            _p: PhantomData,
+        }
+    }
+
+    /// Add an argument that replaces the next `?` in the sql.
+    pub fn arg<X>(mut self, val: impl IntoColumn<'t, S, Typ = X>) -> Self {
+        let val = val.into_owned();
+        self.args.push(Rc::new(move |b| val.build_expr(b)));
+        self
+    }
+}
+
+impl<S, T: MyTyp + RawTyp> Typed for RawExpr<'_, S, T> {
+    type Typ = T;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        let args = self.args.iter().map(|arg| arg(b));
+        let typ = match T::TYP {
+            ColumnType::Integer => "integer",
+            ColumnType::Float => "real",
+            ColumnType::String => "text",
+        };
+        // the cast also makes sure that a number is not used as a column position in `ORDER BY`
+        let sql = format!("CAST(({}) AS {typ})", self.sql);
+        Expr::cust_with_exprs(sql, args)
+    }
+}
+
+impl<'t, S: 't, T: MyTyp + RawTyp> IntoColumn<'t, S> for RawExpr<'t, S, T> {
+    type Owned = Self;
+
+    fn into_owned(self) -> Self::Owned {
+        self
+    }
+}
@@ -0,0 +1,144 @@
+use std::{convert::Infallible, ops::Deref};
+
+use crate::{
+    error::unwrap_db,
+    insert::{Unique, UniqueKey, Writable},
+    Database, LocalClient, Table, TableRow, Transaction, TransactionMut,
+};
+
+/// Argument of [LocalClient::refresh] to write the new rows of a derived table.
+pub struct Refresh<'r, 't, T: Table> {
+    txn: &'r mut TransactionMut<'t, T::Schema>,
+    // the rows that were written by this refresh
+    kept: Vec<i64>,
+}
+
+/// Result of [LocalClient::refresh].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefreshReport {
+    /// The number of rows that were inserted or updated.
+    pub written: usize,
+    /// The number of old rows that were deleted because they were not written again.
+    pub deleted: usize,
+}
+
+impl<'t, T: Table> Deref for Refresh<'_, 't, T> {
+    type Target = Transaction<'t, T::Schema>;
+
+    fn deref(&self) -> &Self::Target {
+        self.txn
+    }
+}
+
+impl<'t, T: Table> Refresh<'_, 't, T>
+where
+    T::Schema: 'static,
+{
+    /// Insert a new row, like [TransactionMut::insert].
+    pub fn insert(
+        &mut self,
+        val: impl Writable<'t, T = T, Conflict = Infallible, Schema = T::Schema>,
+    ) -> TableRow<'t, T> {
+        let row = self.txn.insert(val);
+        self.kept.push(row.idx);
+        row
+    }
+
+    /// Insert a new row or update the row with the same key for unique constraint `U`,
+    /// like [TransactionMut::insert_or_update].
+    ///
+    /// Rows that are updated keep their id, so only the rows that changed are written.
+    pub fn upsert<U: Unique<Table = T>>(
+        &mut self,
+        val: impl Writable<'t, T = T, Schema = T::Schema> + UniqueKey<'t, U>,
+    ) -> Option<TableRow<'t, T>> {
+        let row = self.txn.insert_or_update(val)?;
+        self.kept.push(row.idx);
+        Some(row)
+    }
+}
+
+impl LocalClient {
+    /// Recompute the rows of derived table `T` in a [TransactionMut], for example a table with
+    /// aggregates for a dashboard that does not need to be up to date all the time.
+    ///
+    /// `f` can query the database and writes the new rows of `T` with [Refresh::insert] or [Refresh::upsert].
+    /// Afterwards, all rows of `T` that were not written by `f` are deleted and the transaction is committed.
+    /// Using [Refresh::insert] replaces the whole table, while [Refresh::upsert] only updates the rows that changed.
+    ///
+    /// Other tables should not have foreign keys to `T`, because its rows are deleted.
+    /// ```
+    /// # use rust_query::{aggregate, migration::{schema, Config}, LocalClient, Table};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     Genre { name: String },
+    /// #     Track { genre: Genre },
+    /// #     GenreStats {
+    /// #         #[unique_genre]
+    /// #         genre: Genre,
+    /// #         tracks: i64,
+    /// #     },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// client.refresh::<GenreStats>(&db, |txn| {
+    ///     let stats = txn.query(|rows| {
+    ///         let genre = Genre::join(rows);
+    ///         let tracks = aggregate(|rows| {
+    ///             let track = Track::join(rows);
+    ///             rows.filter_on(track.genre(), &genre);
+    ///             rows.count_distinct(track)
+    ///         });
+    ///         rows.into_vec((genre, tracks))
+    ///     });
+    ///     for (genre, tracks) in stats {
+    ///         txn.upsert::<genre_stats::UniqueGenre>(GenreStats { genre, tracks });
+    ///     }
+    /// });
+    /// ```
+    pub fn refresh<T: Table>(
+        &mut self,
+        db: &Database<T::Schema>,
+        f: impl FnOnce(&mut Refresh<'_, '_, T>),
+    ) -> RefreshReport
+    where
+        T::Schema: 'static,
+    {
+        let mut txn = self.transaction_mut(db);
+        let mut refresh = Refresh {
+            txn: &mut txn,
+            kept: vec![],
+        };
+        f(&mut refresh);
+        let mut kept = refresh.kept;
+        kept.sort_unstable();
+        kept.dedup();
+
+        let sql = format!(
+            "SELECT \"{}\" FROM \"{}\" WHERE \"{}\" NOT IN (SELECT \"value\" FROM json_each(?))",
+            T::ID,
+            T::NAME,
+            T::ID
+        );
+        let ids: Vec<_> = kept.iter().map(|x| x.to_string()).collect();
+        let ids = format!("[{}]", ids.join(","));
+        let mut stmt = unwrap_db(txn.transaction().prepare(&sql));
+        let stale: Vec<i64> = unwrap_db(
+            stmt.query_map([&ids], |row| row.get(0))
+                .and_then(|x| x.collect()),
+        );
+        drop(stmt);
+
+        let mut txn = txn.deletor();
+        let deleted = match txn.delete_ids(T::NAME, &stale) {
+            Ok(deleted) => deleted,
+            Err(err) => panic!("{err}"),
+        };
+        txn.commit();
+        RefreshReport {
+            written: kept.len(),
+            deleted,
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use crate::{metrics, Database, Error, LocalClient, TransactionMut};
+
+/// How often [LocalClient::write_with_retry] retries a transaction that failed with a transient error.
+///
+/// The first retry waits for `initial_delay` and the delay doubles after every retry, up to `max_delay`.
+/// The default is 5 retries, starting with a delay of 10 milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The maximum number of times that the transaction is retried.
+    pub retries: u32,
+    /// The delay before the first retry.
+    pub initial_delay: Duration,
+    /// The maximum delay between two retries.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 5,
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl Error {
+    /// Whether the same transaction can succeed when it is tried again later.
+    ///
+    /// This is only the case for [Error::Busy], which includes `SQLITE_BUSY` and `SQLITE_LOCKED`.
+    /// Other errors, like a violated constraint or a full disk, fail again when retried.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Error::Busy)
+    }
+}
+
+impl LocalClient {
+    /// Run `f` in a [TransactionMut] and commit it, retrying when sqlite fails with a transient error.
+    ///
+    /// The transaction is rolled back and `f` is called again in a new transaction when starting
+    /// the transaction or the commit fails with an error that [Error::is_transient].
+    /// Any other sqlite error is returned after the rollback, converted to `E`.
+    /// When `f` returns an error, the transaction is rolled back and the error is returned without retrying.
+    /// Statements in `f` that fail with a sqlite error panic, unless they are the fallible
+    /// variants like [TransactionMut::checked_insert], whose error `f` can return.
+    ///
+    /// Note that `f` can be called more than once, so it should not have other side effects.
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient, RetryPolicy, Error};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     User { name: String },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// let res = client.write_with_retry(&db, RetryPolicy::default(), |txn| {
+    ///     txn.insert(User { name: "alice" });
+    ///     Ok::<_, Error>(())
+    /// });
+    /// assert!(res.is_ok());
+    /// ```
+    pub fn write_with_retry<S, R, E: From<Error>>(
+        &mut self,
+        db: &Database<S>,
+        policy: RetryPolicy,
+        mut f: impl FnMut(&mut TransactionMut<S>) -> Result<R, E>,
+    ) -> Result<R, E>
+    where
+        S: 'static,
+    {
+        let mut delay = policy.initial_delay;
+        let mut retries = policy.retries;
+        loop {
+            let res = self
+                .try_transaction_mut(db)
+                .and_then(|mut txn| match f(&mut txn) {
+                    Ok(res) => txn.try_commit().map(|()| Ok(res)),
+                    Err(err) => Ok(Err(err)),
+                });
+            match res {
+                Ok(res) => return res,
+                Err(err) if retries > 0 && err.is_transient() => {
+                    metrics::busy_retry();
+                    std::thread::sleep(delay);
+                    delay = (delay * 2).min(policy.max_delay);
+                    retries -= 1;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
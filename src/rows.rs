@@ -3,9 +3,15 @@ use std::marker::PhantomData;
 use sea_query::{Expr, SimpleExpr};
 
 use crate::{
-    ast::MySelect,
-    db::Join,
-    value::{operations::Assume, IntoColumn},
+    ast::{Joinable, MySelect},
+    db::{Col, Join},
+    exec::Query,
+    scope::Scope,
+    subquery::SubQuery,
+    value::{
+        operations::{Assume, Eq},
+        IntoColumn, Typed, ValueBuilder,
+    },
     Column, Table,
 };
 
@@ -29,15 +35,40 @@ impl<'inner, S> Rows<'inner, S> {
     /// (Also called the "Carthesian product")
     ///
     /// For convenience there is also [Table::join].
+    ///
+    /// Rows of [crate::SoftDelete] tables that are deleted are not included,
+    /// use [Rows::join_with_deleted] to include them.
     pub fn join<T: Table<Schema = S>>(&mut self) -> Column<'inner, S, T> {
+        let join = self.join_with_deleted::<T>();
+        if T::SOFT_DELETE {
+            let deleted_at = Col::<Option<i64>, _>::new("deleted_at", join.clone());
+            self.filter_none(deleted_at);
+        }
+        join
+    }
+
+    /// Join a table like [Rows::join], but also include the rows that are deleted.
+    ///
+    /// This only makes a difference for [crate::SoftDelete] tables.
+    /// Rows of other tenants are still not included in a transaction that is [crate::Transaction::scoped].
+    /// For convenience there is also [Table::join_with_deleted].
+    pub fn join_with_deleted<T: Table<Schema = S>>(&mut self) -> Column<'inner, S, T> {
         let alias = self.ast.scope.new_alias();
-        self.ast.tables.push((T::NAME.to_owned(), alias));
-        IntoColumn::into_column(Join::new(alias))
+        self.ast
+            .tables
+            .push((Joinable::Table(T::NAME.to_owned()), alias));
+        let join: Column<'inner, S, T> = IntoColumn::into_column(Join::new(alias));
+        // rows of other tenants are not included in a scoped transaction
+        if let Some((col, tenant)) = Scope::building().and_then(Scope::join_filter::<T>) {
+            let col = Col::<i64, _>::new(col, join.clone());
+            self.filter_private(|b| Expr::expr(col.build_expr(b)).eq(tenant));
+        }
+        join
     }
 
     pub(crate) fn join_custom<T: Table>(&mut self, t: T) -> Join<'inner, T> {
         let alias = self.ast.scope.new_alias();
-        self.ast.tables.push((t.name(), alias));
+        self.ast.tables.push((Joinable::Table(t.name()), alias));
         Join::new(alias)
     }
 
@@ -48,11 +79,12 @@ impl<'inner, S> Rows<'inner, S> {
 
     /// Filter rows based on a column.
     pub fn filter(&mut self, prop: impl IntoColumn<'inner, S, Typ = bool>) {
-        self.filter_private(prop.build_expr(self.ast.builder()));
+        self.filter_private(|b| prop.build_expr(b));
     }
 
-    fn filter_private(&mut self, prop: SimpleExpr) {
-        self.ast.filters.push(Box::new(prop));
+    fn filter_private(&mut self, prop: impl FnOnce(ValueBuilder) -> SimpleExpr) {
+        let filter = self.ast.builder().track(prop);
+        self.ast.filters.push(Box::new(filter));
     }
 
     /// Filter out rows where this column is [None].
@@ -62,7 +94,100 @@ impl<'inner, S> Rows<'inner, S> {
         &mut self,
         val: impl IntoColumn<'inner, S, Typ = Option<Typ>>,
     ) -> Column<'inner, S, Typ> {
-        self.filter_private(Expr::expr(val.build_expr(self.ast.builder())).is_not_null());
+        self.filter_private(|b| Expr::expr(val.build_expr(b)).is_not_null());
         Assume(val).into_column()
     }
+
+    /// Filter out rows where this column is [Some].
+    ///
+    /// For example to find the rows that do not reference another row.
+    pub fn filter_none<Typ>(&mut self, val: impl IntoColumn<'inner, S, Typ = Option<Typ>>) {
+        self.filter_private(|b| Expr::expr(val.build_expr(b)).is_null());
+    }
+}
+
+/// Used by the generated methods that join the rows referencing a table.
+///
+/// This is implemented for [Rows] (and the types that wrap it) to join in the same query
+/// and for [crate::args::Aggregate] to join in a sub-query.
+pub trait JoinChildren<'t, 'i, S> {
+    #[doc(hidden)]
+    fn join_children<P: Table<Schema = S>, C: Table<Schema = S>>(
+        &mut self,
+        fk: &'static str,
+        parent: impl IntoColumn<'t, S, Typ = P>,
+    ) -> Column<'i, S, C>;
+
+    #[doc(hidden)]
+    fn join_tree<T: Table<Schema = S>>(
+        &mut self,
+        fk: &'static str,
+        start: impl IntoColumn<'t, S, Typ = T>,
+        depth: i64,
+        ancestors: bool,
+    ) -> Column<'i, S, T>;
+}
+
+impl<'t, S> JoinChildren<'t, 't, S> for Rows<'t, S> {
+    fn join_children<P: Table<Schema = S>, C: Table<Schema = S>>(
+        &mut self,
+        fk: &'static str,
+        parent: impl IntoColumn<'t, S, Typ = P>,
+    ) -> Column<'t, S, C> {
+        let child = self.join::<C>();
+        self.filter(Eq(Col::<P, _>::new(fk, child.clone()), parent));
+        child
+    }
+
+    fn join_tree<T: Table<Schema = S>>(
+        &mut self,
+        fk: &'static str,
+        start: impl IntoColumn<'t, S, Typ = T>,
+        depth: i64,
+        ancestors: bool,
+    ) -> Column<'t, S, T> {
+        let (from, node) = self.join_pairs(fk, depth, ancestors);
+        self.filter(Eq(from, start));
+        node
+    }
+}
+
+impl<'t, S> JoinChildren<'t, 't, S> for Query<'_, 't, S> {
+    fn join_children<P: Table<Schema = S>, C: Table<Schema = S>>(
+        &mut self,
+        fk: &'static str,
+        parent: impl IntoColumn<'t, S, Typ = P>,
+    ) -> Column<'t, S, C> {
+        self.q.join_children(fk, parent)
+    }
+
+    fn join_tree<T: Table<Schema = S>>(
+        &mut self,
+        fk: &'static str,
+        start: impl IntoColumn<'t, S, Typ = T>,
+        depth: i64,
+        ancestors: bool,
+    ) -> Column<'t, S, T> {
+        JoinChildren::join_tree(&mut self.q, fk, start, depth, ancestors)
+    }
+}
+
+impl<'t, S> JoinChildren<'t, 't, S> for SubQuery<'_, 't, S> {
+    fn join_children<P: Table<Schema = S>, C: Table<Schema = S>>(
+        &mut self,
+        fk: &'static str,
+        parent: impl IntoColumn<'t, S, Typ = P>,
+    ) -> Column<'t, S, C> {
+        self.query.join_children(fk, parent)
+    }
+
+    fn join_tree<T: Table<Schema = S>>(
+        &mut self,
+        fk: &'static str,
+        start: impl IntoColumn<'t, S, Typ = T>,
+        depth: i64,
+        ancestors: bool,
+    ) -> Column<'t, S, T> {
+        JoinChildren::join_tree(&mut self.query, fk, start, depth, ancestors)
+    }
 }
@@ -0,0 +1,165 @@
+use std::{cell::Cell, marker::PhantomData};
+
+use crate::{Table, TableRow, Transaction, TransactionMut};
+
+/// The tenant row that rows of scoped tables need to reference.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Scope {
+    table: &'static str,
+    id: i64,
+}
+
+impl Scope {
+    /// The column of `T` that needs to be equal to the id of the tenant when it is written.
+    ///
+    /// Returns [None] if `T` does not have a `scope` column that references the tenant table.
+    pub(crate) fn write_filter<T: Table>(self) -> Option<(&'static str, i64)> {
+        let (col, table) = T::SCOPE?;
+        (table == self.table).then_some((col, self.id))
+    }
+
+    /// The column of `T` that needs to be equal to the id of the tenant when it is joined.
+    ///
+    /// The tenant table itself is filtered on its id, so other tenants can not be joined either.
+    pub(crate) fn join_filter<T: Table>(self) -> Option<(&'static str, i64)> {
+        if T::NAME == self.table {
+            Some((T::ID, self.id))
+        } else {
+            self.write_filter::<T>()
+        }
+    }
+
+    /// The scope of the transaction that queries are built for on this thread.
+    ///
+    /// Columns can be built before they are given to the transaction, for example with [crate::aggregate],
+    /// so the scope can not be read from the transaction when tables are joined.
+    pub(crate) fn building() -> Option<Self> {
+        BUILDING.get()
+    }
+}
+
+thread_local! {
+    // This is only set while the closure of [Transaction::scoped] runs.
+    // Columns that are used with the scoped transaction have its new lifetime, so they are all built in that closure.
+    static BUILDING: Cell<Option<Scope>> = const { Cell::new(None) };
+}
+
+/// Sets the scope of the queries that are built on this thread until it is dropped.
+struct BuildGuard {
+    prev: Option<Scope>,
+}
+
+impl BuildGuard {
+    fn new(scope: Scope) -> Self {
+        Self {
+            prev: BUILDING.replace(Some(scope)),
+        }
+    }
+}
+
+impl Drop for BuildGuard {
+    fn drop(&mut self) {
+        BUILDING.set(self.prev);
+    }
+}
+
+impl<'t, S> Transaction<'t, S> {
+    /// Call `f` with this transaction scoped to the rows of `tenant`.
+    ///
+    /// Tables are scoped by a column with the `scope` attribute, see [crate::migration::schema].
+    /// Queries only include the rows of scoped tables that reference the tenant, the tenant table
+    /// itself only has the tenant row. This includes rows that are found with [Transaction::lookup]
+    /// and rows that are reached through a column that references another table.
+    ///
+    /// The scoped transaction has a new lifetime, so rows that were retrieved before can not be used in `f`.
+    /// The tenant row is given to `f` with the new lifetime.
+    ///
+    /// Raw queries are not scoped.
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient, Table};
+    /// #[schema]
+    /// enum Schema {
+    ///     Tenant { name: String },
+    ///     Invoice {
+    ///         #[scope]
+    ///         tenant: Tenant,
+    ///         amount: i64,
+    ///     },
+    /// }
+    /// use v0::*;
+    ///
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// let mut txn = client.transaction_mut(&db);
+    /// let acme = txn.insert(Tenant { name: "acme" });
+    /// let globex = txn.insert(Tenant { name: "globex" });
+    /// txn.insert(Invoice { tenant: acme, amount: 10 });
+    /// txn.insert(Invoice { tenant: globex, amount: 20 });
+    ///
+    /// let amounts = txn.scoped(acme, |txn, _acme| {
+    ///     txn.query(|rows| {
+    ///         let invoice = Invoice::join(rows);
+    ///         rows.into_vec(invoice.amount())
+    ///     })
+    /// });
+    /// assert_eq!(amounts, [10]);
+    /// ```
+    ///
+    /// This function panics if the transaction is already scoped.
+    pub fn scoped<T: Table<Schema = S>, R>(
+        self,
+        tenant: TableRow<'t, T>,
+        f: impl for<'s> FnOnce(Transaction<'s, S>, TableRow<'s, T>) -> R,
+    ) -> R {
+        let scope = self.new_scope(tenant);
+        let _guard = BuildGuard::new(scope);
+        let txn = Transaction {
+            scope: Some(scope),
+            ..self.rebrand()
+        };
+        f(txn, rebrand(tenant))
+    }
+
+    fn new_scope<T: Table>(&self, tenant: TableRow<'t, T>) -> Scope {
+        if self.scope.is_some() {
+            panic!("the transaction is already scoped")
+        }
+        Scope {
+            table: T::NAME,
+            id: tenant.idx,
+        }
+    }
+}
+
+/// The tenant row with the lifetime of the scoped transaction.
+fn rebrand<'s, T>(tenant: TableRow<'_, T>) -> TableRow<'s, T> {
+    TableRow {
+        _p: PhantomData,
+        _local: PhantomData,
+        idx: tenant.idx,
+    }
+}
+
+impl<'t, S> TransactionMut<'t, S> {
+    /// Call `f` with this transaction scoped to the rows of `tenant`, like [Transaction::scoped].
+    ///
+    /// New rows and updated rows must reference the tenant, otherwise the transaction panics.
+    /// The changes are rolled back if `f` does not commit the scoped transaction.
+    pub fn scoped<T: Table<Schema = S>, R>(
+        self,
+        tenant: TableRow<'t, T>,
+        f: impl for<'s> FnOnce(TransactionMut<'s, S>, TableRow<'s, T>) -> R,
+    ) -> R {
+        let scope = self.inner.new_scope(tenant);
+        let _guard = BuildGuard::new(scope);
+        let txn = TransactionMut {
+            inner: Transaction {
+                scope: Some(scope),
+                ..self.inner.rebrand()
+            },
+            watchers: self.watchers,
+            hooks: self.hooks,
+        };
+        f(txn, rebrand(tenant))
+    }
+}
@@ -0,0 +1,122 @@
+use std::fmt::Write;
+
+use crate::{
+    hash::{Column, ColumnType, Table},
+    migrate::{Schema, TableTypBuilder},
+};
+
+/// Generate the skeleton of the migration from schema `From` to schema `To`.
+///
+/// The skeleton contains a closure with a `todo!()` for every new or changed column.
+/// Tables that only lost columns are copied with [crate::migration::Alter::identity].
+/// It can be printed once and then pasted into the code that migrates the database.
+/// ```
+/// # use rust_query::migration::{migration_skeleton, schema};
+/// # #[schema]
+/// # #[version(0..=1)]
+/// # enum Schema {
+/// #     User {
+/// #         name: String,
+/// #         #[version(1..)]
+/// #         email: String,
+/// #     },
+/// # }
+/// # fn main() {
+/// println!("{}", migration_skeleton::<v0::Schema, v1::Schema>());
+/// # }
+/// ```
+/// The output is only a starting point, columns that were renamed show up as new columns.
+pub fn migration_skeleton<From: Schema, To: Schema>() -> String {
+    let mut from = TableTypBuilder::default();
+    From::typs(&mut from);
+    let mut to = TableTypBuilder::default();
+    To::typs(&mut to);
+
+    // the generated module is next to the schema type
+    let schema = std::any::type_name::<To>();
+    let mut path = schema.rsplit("::").take(2).collect::<Vec<_>>();
+    path.reverse();
+    let (module, name) = (path[0], path[path.len() - 1]);
+
+    let mut out = String::new();
+    writeln!(out, "let m = m.migrate({module}::update::{name} {{").unwrap();
+    for (table_name, table) in &*to.ast.tables {
+        let struct_name = upper_camel(table_name);
+        let prev = from.ast.tables.iter().find(|(name, _)| name == table_name);
+        match prev {
+            Some((_, prev)) => {
+                let new = new_columns(prev, table);
+                if new.is_empty() && prev.columns.len() == table.columns.len() {
+                    // the columns did not change, so the table is not migrated
+                    continue;
+                }
+                let new: Vec<_> = new.into_iter().filter(|col| is_written(col)).collect();
+                if new.is_empty() {
+                    writeln!(out, "    {table_name}: Box::new(|_| {{").unwrap();
+                    writeln!(out, "        Alter::identity()").unwrap();
+                } else {
+                    writeln!(out, "    {table_name}: Box::new(|{table_name}| {{").unwrap();
+                    let migration = format!("{module}::update::{struct_name}Migration");
+                    writeln!(out, "        Alter::new({migration} {{").unwrap();
+                    write_fields(&mut out, &new);
+                    writeln!(out, "        }})").unwrap();
+                }
+            }
+            None => {
+                writeln!(out, "    {table_name}: Box::new(|rows| {{").unwrap();
+                let migration = format!("{module}::update::{struct_name}Migration");
+                writeln!(out, "        Create::new({migration} {{").unwrap();
+                let columns = table.columns.iter().filter(|col| is_written(col));
+                write_fields(&mut out, &columns.collect::<Vec<_>>());
+                writeln!(out, "        }})").unwrap();
+            }
+        }
+        writeln!(out, "    }}),").unwrap();
+    }
+    writeln!(out, "}});").unwrap();
+    out
+}
+
+/// Columns of `table` that do not exist in `prev` or are different.
+fn new_columns<'a>(prev: &Table, table: &'a Table) -> Vec<&'a Column> {
+    table
+        .columns
+        .iter()
+        .filter(|col| !prev.columns.contains(col))
+        .collect()
+}
+
+/// Generated columns are computed by sqlite and new columns with a default value get the default value.
+fn is_written(col: &Column) -> bool {
+    col.generated.is_none() && col.default.is_none()
+}
+
+fn write_fields(out: &mut String, columns: &[&Column]) {
+    for col in columns {
+        let mut typ = match col.typ {
+            ColumnType::Integer => "i64".to_owned(),
+            ColumnType::Float => "f64".to_owned(),
+            ColumnType::String => "String".to_owned(),
+        };
+        if let Some((table, _)) = &col.fk {
+            typ = upper_camel(table);
+        }
+        if col.nullable {
+            typ = format!("Option<{typ}>");
+        }
+        writeln!(out, "            {}: todo!(), // {typ}", col.name).unwrap();
+    }
+}
+
+pub(crate) fn upper_camel(name: &str) -> String {
+    name.split('_')
+        .flat_map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase())
+                .into_iter()
+                .chain(chars)
+        })
+        .collect()
+}
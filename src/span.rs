@@ -0,0 +1,93 @@
+//! Spans for the `tracing` crate, this is enabled with the `tracing` feature.
+//!
+//! The following spans are created:
+//! - `transaction`: open as long as a [crate::Transaction] or [crate::TransactionMut], with fields
+//!   `kind` set to `read` or `write`, `schema` with the name of the schema type and
+//!   `schema_version` with the sqlite `schema_version` of the database.
+//! - `migration`: for every step of [crate::migration::Migrator::migrate], with fields `from` and `to`
+//!   set to the schema versions.
+//! - `migrate_table`: for every table that is copied in a migration, with field `table`.
+//! - `exec`: for every sql statement, with field `sql`. Its parent is the `transaction` span.
+//!
+//! Without the feature all functions in this module do nothing.
+#![cfg_attr(
+    not(feature = "tracing"),
+    allow(unused_variables, clippy::extra_unused_type_parameters)
+)]
+
+#[cfg(feature = "tracing")]
+use std::cell::RefCell;
+
+#[cfg(feature = "tracing")]
+use tracing::{debug_span, info_span, span::EnteredSpan, Span};
+
+#[cfg(feature = "tracing")]
+thread_local! {
+    // The span of the transaction on this thread.
+    // There is at most one transaction for each thread, because it borrows the [crate::LocalClient].
+    static CURRENT: RefCell<Option<Span>> = const { RefCell::new(None) };
+}
+
+/// The span of a transaction, it is closed when this is dropped.
+pub(crate) struct TransactionSpan {
+    #[cfg(feature = "tracing")]
+    prev: Option<Span>,
+}
+
+impl TransactionSpan {
+    pub fn new<S>(kind: &'static str, schema_version: i64) -> Self {
+        #[cfg(feature = "tracing")]
+        {
+            let schema = std::any::type_name::<S>();
+            let span = info_span!("transaction", kind, schema, schema_version);
+            Self {
+                prev: CURRENT.replace(Some(span)),
+            }
+        }
+        #[cfg(not(feature = "tracing"))]
+        Self {}
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for TransactionSpan {
+    fn drop(&mut self) {
+        CURRENT.set(self.prev.take());
+    }
+}
+
+/// A span that is entered until this is dropped.
+pub(crate) struct Entered {
+    #[cfg(feature = "tracing")]
+    _span: EnteredSpan,
+}
+
+/// Enter the span of a migration from schema version `from` to `to`.
+pub(crate) fn migration(from: i64, to: i64) -> Entered {
+    Entered {
+        #[cfg(feature = "tracing")]
+        _span: info_span!("migration", from, to).entered(),
+    }
+}
+
+/// Enter the span of copying `table` in a migration.
+pub(crate) fn migrate_table(table: &str) -> Entered {
+    Entered {
+        #[cfg(feature = "tracing")]
+        _span: info_span!("migrate_table", table).entered(),
+    }
+}
+
+/// Run `f` in the span of executing `sql`.
+pub(crate) fn exec<R>(sql: &str, f: impl FnOnce() -> R) -> R {
+    #[cfg(feature = "tracing")]
+    {
+        let span = match CURRENT.with_borrow(Clone::clone) {
+            Some(parent) => debug_span!(parent: &parent, "exec", sql),
+            None => debug_span!("exec", sql),
+        };
+        span.in_scope(f)
+    }
+    #[cfg(not(feature = "tracing"))]
+    f()
+}
@@ -0,0 +1,236 @@
+use std::{fmt, marker::PhantomData};
+
+use sea_query::{Alias, Expr, Iden, InsertStatement, ReturningClause, SqliteQueryBuilder};
+use sea_query_rusqlite::{RusqliteBinder, RusqliteValues};
+
+use crate::{
+    ast::MySelect,
+    db::Col,
+    error::{unwrap_db, Error},
+    hook::HookEvent,
+    insert::{Reader, Writable},
+    public_id::PUBLIC_ID,
+    scope::Scope,
+    trace::{record_steps, traced},
+    IntoColumn, SoftDelete, Table, TableRow, TransactionMut, UnixEpoch,
+};
+
+/// An insert, update or delete that is turned into sql before it is executed by [TransactionMut::execute_batch_typed].
+///
+/// Statements that do not reference rows can be made before the transaction is started,
+/// so that the transaction only needs to execute them.
+pub struct Statement<'t, S> {
+    table: &'static str,
+    sql: String,
+    values: RusqliteValues,
+    // the row that is changed, this is [None] for inserts
+    row: Option<i64>,
+    before: Option<HookEvent>,
+    after: Option<HookEvent>,
+    scope: fn(Scope) -> Option<(&'static str, i64)>,
+    _p: PhantomData<TableRow<'t, S>>,
+}
+
+impl<'t, S> Statement<'t, S> {
+    /// Insert a new row, like [TransactionMut::insert].
+    pub fn insert<T: Table<Schema = S>>(val: impl Writable<'t, T = T, Schema = S>) -> Self {
+        let ast = MySelect::default();
+        let reader = Reader {
+            ast: &ast,
+            _p: PhantomData,
+            _p2: PhantomData,
+        };
+        val.read(reader);
+        if let Some(kind) = T::PUBLIC_ID {
+            reader.col(PUBLIC_ID, kind.generate());
+        }
+        if T::TIMESTAMPS {
+            reader.col("created_at", UnixEpoch);
+            reader.col("updated_at", UnixEpoch);
+        }
+        if T::VERSIONED {
+            reader.col("version", 0i64);
+        }
+
+        let mut insert = InsertStatement::new();
+        let names = ast.select.iter().map(|(_field, name)| *name);
+        insert.into_table(Alias::new(T::NAME));
+        insert.columns(names);
+        insert.select_from(ast.simple()).unwrap();
+        let id = Expr::col(Alias::new(T::ID)).cast_as(Alias::new("integer"));
+        insert.returning(ReturningClause::Exprs(vec![id]));
+
+        let (sql, values) = insert.build_rusqlite(SqliteQueryBuilder);
+        Self::new::<T>(sql, values, None, Some(HookEvent::AfterInsert))
+    }
+
+    /// Update all columns of a row, like [TransactionMut::update].
+    pub fn update<T: Table<Schema = S>>(
+        row: TableRow<'t, T>,
+        val: impl Writable<'t, T = T, Schema = S>,
+    ) -> Self {
+        let ast = MySelect::default();
+        let reader = Reader {
+            ast: &ast,
+            _p: PhantomData,
+            _p2: PhantomData,
+        };
+        val.read(reader);
+        if T::TIMESTAMPS {
+            reader.col("updated_at", UnixEpoch);
+        }
+        if T::VERSIONED {
+            let version = Col::<i64, _>::new("version", row);
+            reader.col("version", version.into_column().add(1));
+        }
+
+        let sets: Vec<_> = ast
+            .select
+            .iter()
+            .map(|(_, field)| format!("\"{0}\" = \"_new\".\"{0}\"", field.to_string()))
+            .collect();
+        reader.col(T::ID, row);
+
+        let (select, values) = ast.build_select(false).build_rusqlite(SqliteQueryBuilder);
+        let table = T::NAME;
+        let id = T::ID;
+        let sql = format!(
+            "UPDATE \"{table}\" SET {} FROM ({select}) AS \"_new\" WHERE \"{table}\".\"{id}\" = \"_new\".\"{id}\" RETURNING \"{table}\".\"{id}\"",
+            sets.join(", ")
+        );
+        Self::new::<T>(sql, values, Some(row.idx), Some(HookEvent::AfterUpdate))
+    }
+
+    /// Delete a row of a [SoftDelete] table, like [TransactionMut::delete].
+    ///
+    /// Nothing happens if the row is already deleted.
+    pub fn delete<T: SoftDelete<Schema = S>>(row: TableRow<'t, T>) -> Self {
+        let table = T::NAME;
+        let id = T::ID;
+        let sql = format!(
+            "UPDATE \"{table}\" SET \"deleted_at\" = unixepoch('now') WHERE \"{id}\" = ? AND \"deleted_at\" IS NULL RETURNING \"{id}\""
+        );
+        let values = RusqliteValues(vec![sea_query_rusqlite::RusqliteValue(row.idx.into())]);
+        Self::new::<T>(sql, values, Some(row.idx), None)
+    }
+
+    fn new<T: Table>(
+        sql: String,
+        values: RusqliteValues,
+        row: Option<i64>,
+        after: Option<HookEvent>,
+    ) -> Self {
+        let before = row.map(|_| match after {
+            Some(HookEvent::AfterUpdate) => HookEvent::BeforeUpdate,
+            _ => HookEvent::BeforeDelete,
+        });
+        Self {
+            table: T::NAME,
+            sql,
+            values,
+            row,
+            before,
+            after,
+            scope: Scope::write_filter::<T>,
+            _p: PhantomData,
+        }
+    }
+}
+
+/// Error returned by [TransactionMut::execute_batch_typed] when a statement fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatementError {
+    /// The index of the statement that failed.
+    pub index: usize,
+    /// The error from sqlite, for example because a unique constraint is violated.
+    pub error: Error,
+}
+
+impl fmt::Display for StatementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "statement {} failed: {}", self.index, self.error)
+    }
+}
+
+impl std::error::Error for StatementError {}
+
+impl<'t, S: 'static> TransactionMut<'t, S> {
+    /// Execute statements one after the other, without running any queries in between.
+    ///
+    /// This is useful when many rows are written, because the statements can be made before the
+    /// transaction is started and every statement is only executed once.
+    /// The statements are executed in a savepoint, so nothing is changed if one of them fails.
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient, Statement};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     User { name: String },
+    /// # }
+    /// # use v0::*;
+    /// # struct NewUser { name: String }
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// # let users = [NewUser { name: "alice".to_owned() }];
+    /// let statements: Vec<_> = users
+    ///     .iter()
+    ///     .map(|u| Statement::insert(User { name: &u.name }))
+    ///     .collect();
+    /// let mut txn = client.transaction_mut(&db);
+    /// txn.execute_batch_typed(&statements).unwrap();
+    /// txn.commit();
+    /// ```
+    /// Use [TransactionMut::try_insert] and [TransactionMut::try_update] instead when the new row
+    /// or the conflicting row is needed.
+    ///
+    /// This function panics if a statement writes to a table that is scoped by [crate::Transaction::scoped].
+    pub fn execute_batch_typed(
+        &mut self,
+        statements: &[Statement<'t, S>],
+    ) -> Result<(), StatementError> {
+        let scoped = |stmt: &&Statement<S>| self.scope.and_then(stmt.scope).is_some();
+        if let Some(stmt) = statements.iter().find(scoped) {
+            panic!(
+                "statements can not write to `{}` in a scoped transaction",
+                stmt.table
+            )
+        }
+
+        let conn = self.transaction();
+        unwrap_db(conn.execute_batch("SAVEPOINT \"execute_batch_typed\""));
+        for (index, stmt) in statements.iter().enumerate() {
+            if let (Some(row), Some(event)) = (stmt.row, stmt.before) {
+                self.hooks.run(&self.inner, stmt.table, event, row);
+            }
+            let res: rusqlite::Result<Vec<i64>> = traced(&stmt.sql, &stmt.values.0, || {
+                let mut prepared = unwrap_db(self.transaction().prepare_cached(&stmt.sql));
+                let res = prepared
+                    .query_map(&*stmt.values.as_params(), |row| row.get(0))
+                    .and_then(|rows| rows.collect());
+                record_steps(&prepared);
+                res
+            });
+            match res {
+                Ok(rows) => {
+                    for row in rows {
+                        if let Some(event) = stmt.after {
+                            self.hooks.run(&self.inner, stmt.table, event, row);
+                        }
+                    }
+                }
+                Err(err) => {
+                    let conn = self.transaction();
+                    unwrap_db(conn.execute_batch(
+                        "ROLLBACK TO \"execute_batch_typed\"; RELEASE \"execute_batch_typed\"",
+                    ));
+                    return Err(StatementError {
+                        index,
+                        error: Error::from(err),
+                    });
+                }
+            }
+        }
+        let conn = self.transaction();
+        unwrap_db(conn.execute_batch("RELEASE \"execute_batch_typed\""));
+        Ok(())
+    }
+}
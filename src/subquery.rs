@@ -0,0 +1,299 @@
+use std::{
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+use sea_query::{
+    CommonTableExpression, Expr, Iden, SelectStatement, SimpleExpr, SqliteQueryBuilder, UnionType,
+    WithClause,
+};
+
+use crate::{
+    alias::Field,
+    ast::{Joinable, MySelect},
+    rows::Rows,
+    value::{operations::Prebuilt, IntoColumn, MyTyp, Typed},
+    Column, Table,
+};
+
+/// This is the argument type used for sub-queries that are joined, like [Rows::union].
+///
+/// Sub-queries can not use columns from the outer query, but they can use rust values
+/// like [crate::TableRow].
+pub struct SubQuery<'outer: 'inner, 'inner, S> {
+    pub(crate) query: Rows<'inner, S>,
+    pub(crate) phantom: PhantomData<fn(&'outer ()) -> &'outer ()>,
+}
+
+impl<'outer, 'inner, S> Deref for SubQuery<'outer, 'inner, S> {
+    type Target = Rows<'inner, S>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.query
+    }
+}
+
+impl<'outer, 'inner, S> DerefMut for SubQuery<'outer, 'inner, S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.query
+    }
+}
+
+impl<'outer, S> Rows<'outer, S> {
+    /// Join the results of two sub-queries combined with `UNION`.
+    ///
+    /// The result contains every value that is returned by one of the sub-queries, without duplicates.
+    ///
+    /// The sub-queries must return columns of the same type, but tables can be used
+    /// to return whole rows:
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient, Table};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     Genre { name: String },
+    /// #     Track { genre: Genre, milliseconds: i64 },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// # let txn = client.transaction(&db);
+    /// # txn.query(|rows| {
+    /// let track = rows.union(
+    ///     |rows| {
+    ///         let track = Track::join(rows);
+    ///         rows.filter(track.milliseconds().lt(60 * 1000));
+    ///         track
+    ///     },
+    ///     |rows| {
+    ///         let track = Track::join(rows);
+    ///         rows.filter(track.genre().name().eq("Metal"));
+    ///         track
+    ///     },
+    /// );
+    /// # rows.into_vec(track)
+    /// # });
+    /// ```
+    pub fn union<T: MyTyp>(
+        &mut self,
+        a: impl for<'b> FnOnce(&mut SubQuery<'outer, 'b, S>) -> Column<'b, S, T>,
+        b: impl for<'b> FnOnce(&mut SubQuery<'outer, 'b, S>) -> Column<'b, S, T>,
+    ) -> Column<'outer, S, T> {
+        self.join_compound(UnionType::Distinct, a, b)
+    }
+
+    /// Join the results of two sub-queries combined with `UNION ALL`.
+    ///
+    /// This is like [Rows::union], but values that are returned multiple times are kept.
+    pub fn union_all<T: MyTyp>(
+        &mut self,
+        a: impl for<'b> FnOnce(&mut SubQuery<'outer, 'b, S>) -> Column<'b, S, T>,
+        b: impl for<'b> FnOnce(&mut SubQuery<'outer, 'b, S>) -> Column<'b, S, T>,
+    ) -> Column<'outer, S, T> {
+        self.join_compound(UnionType::All, a, b)
+    }
+
+    /// Join the results of two sub-queries combined with `INTERSECT`.
+    ///
+    /// The result contains every value that is returned by both sub-queries, without duplicates.
+    pub fn intersect<T: MyTyp>(
+        &mut self,
+        a: impl for<'b> FnOnce(&mut SubQuery<'outer, 'b, S>) -> Column<'b, S, T>,
+        b: impl for<'b> FnOnce(&mut SubQuery<'outer, 'b, S>) -> Column<'b, S, T>,
+    ) -> Column<'outer, S, T> {
+        self.join_compound(UnionType::Intersect, a, b)
+    }
+
+    /// Join the results of two sub-queries combined with `EXCEPT`.
+    ///
+    /// The result contains every value that is returned by the first sub-query,
+    /// but not by the second sub-query, without duplicates.
+    pub fn except<T: MyTyp>(
+        &mut self,
+        a: impl for<'b> FnOnce(&mut SubQuery<'outer, 'b, S>) -> Column<'b, S, T>,
+        b: impl for<'b> FnOnce(&mut SubQuery<'outer, 'b, S>) -> Column<'b, S, T>,
+    ) -> Column<'outer, S, T> {
+        self.join_compound(UnionType::Except, a, b)
+    }
+
+    fn join_compound<T: MyTyp>(
+        &mut self,
+        typ: UnionType,
+        a: impl for<'b> FnOnce(&mut SubQuery<'outer, 'b, S>) -> Column<'b, S, T>,
+        b: impl for<'b> FnOnce(&mut SubQuery<'outer, 'b, S>) -> Column<'b, S, T>,
+    ) -> Column<'outer, S, T> {
+        // both sub-queries use the same name for the column
+        let field = self.ast.scope.new_field();
+        let (mut select, a_keys) = sub_query(a, field);
+        let (mut other, b_keys) = sub_query(b, field);
+
+        let mut key = vec![field];
+        if let UnionType::All = typ {
+            // values can be returned multiple times, so the rows are identified by the sub-query
+            // they come from and the keys of their row in that sub-query
+            key = vec![self.ast.scope.new_field()];
+            let len = a_keys.len().max(b_keys.len());
+            key.extend((0..len).map(|_| self.ast.scope.new_field()));
+            for (i, (select, keys)) in [(&mut select, a_keys), (&mut other, b_keys)]
+                .into_iter()
+                .enumerate()
+            {
+                select.expr_as(Expr::val(i as i64), key[0]);
+                let mut keys = keys.into_iter();
+                for field in &key[1..] {
+                    let key = keys.next().unwrap_or(Expr::val(None::<i64>).into());
+                    select.expr_as(key, *field);
+                }
+            }
+        }
+        select.union(typ, other);
+
+        let alias = self.ast.scope.new_alias();
+        self.ast
+            .tables
+            .push((Joinable::Subquery(select, key), alias));
+        Prebuilt(Expr::col((alias, field)).into(), PhantomData).into_column()
+    }
+
+    /// Join the results of a recursive sub-query, this uses `WITH RECURSIVE`.
+    ///
+    /// The `base` sub-query returns the initial values.
+    /// The `step` sub-query is then repeated for every new value to find more values,
+    /// until no new values are found. Values that were already found are not repeated,
+    /// so this also works for data with cycles.
+    ///
+    /// For example, to find all employees that report (indirectly) to someone:
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient, Table, TableRow};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     Employee { name: String, reports_to: Option<Employee> },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// # let mut txn = client.transaction_mut(&db);
+    /// # let boss = txn.insert(Employee { name: "boss", reports_to: None::<TableRow<Employee>> });
+    /// # txn.query(|rows| {
+    /// let employee = rows.recursive(
+    ///     |rows| {
+    ///         let employee = Employee::join(rows);
+    ///         let reports_to = rows.filter_some(employee.reports_to());
+    ///         rows.filter(reports_to.eq(boss));
+    ///         employee
+    ///     },
+    ///     |rows, prev| {
+    ///         let employee = Employee::join(rows);
+    ///         let reports_to = rows.filter_some(employee.reports_to());
+    ///         rows.filter(reports_to.eq(prev));
+    ///         employee
+    ///     },
+    /// );
+    /// # rows.into_vec(employee.name())
+    /// # });
+    /// ```
+    pub fn recursive<T: MyTyp>(
+        &mut self,
+        base: impl for<'b> FnOnce(&mut SubQuery<'outer, 'b, S>) -> Column<'b, S, T>,
+        step: impl for<'b> FnOnce(&mut SubQuery<'outer, 'b, S>, Column<'b, S, T>) -> Column<'b, S, T>,
+    ) -> Column<'outer, S, T> {
+        let name = self.ast.scope.tmp_table();
+        let field = self.ast.scope.new_field();
+
+        let (mut select, _) = sub_query(base, field);
+        let (step, _) = sub_query(
+            |rows| {
+                let prev = rows.ast.scope.new_alias();
+                let table = Joinable::Raw(name.to_string(), vec![field]);
+                rows.ast.tables.push((table, prev));
+                let prev = Prebuilt(Expr::col((prev, field)).into(), PhantomData).into_column();
+                step(rows, prev)
+            },
+            field,
+        );
+        select.union(UnionType::Distinct, step);
+
+        let mut cte = CommonTableExpression::new();
+        cte.table_name(name).query(select);
+        let mut with = WithClause::new();
+        with.recursive(true).cte(cte);
+        let query = SelectStatement::new()
+            .column(field)
+            .from(name)
+            .to_owned()
+            .with(with);
+
+        // sea_query can not use a `WITH` clause in a sub-query, so it is added as sql
+        // with all values inlined
+        let sql = format!("({})", query.to_string(SqliteQueryBuilder));
+        let alias = self.ast.scope.new_alias();
+        self.ast
+            .tables
+            .push((Joinable::Raw(sql, vec![field]), alias));
+        Prebuilt(Expr::col((alias, field)).into(), PhantomData).into_column()
+    }
+}
+
+impl<'inner, S> Rows<'inner, S> {
+    /// Join the pairs of rows of `T` that are connected by following the foreign key `fk` of `T` to `T`
+    /// at most `depth` times, this uses `WITH RECURSIVE`.
+    ///
+    /// The pairs are from a row to its ancestors, or from a row to its descendants if `ancestors` is false.
+    /// Returns the columns with the first and the second row of each pair.
+    pub(crate) fn join_pairs<T: Table<Schema = S>>(
+        &mut self,
+        fk: &'static str,
+        depth: i64,
+        ancestors: bool,
+    ) -> (Column<'inner, S, T>, Column<'inner, S, T>) {
+        let (table, id) = (T::NAME, T::ID);
+        let name = self.ast.scope.tmp_table().to_string();
+        let start = self.ast.scope.new_field();
+        let node = self.ast.scope.new_field();
+
+        let (base, next, join) = if ancestors {
+            (format!("\"{id}\", \"{fk}\""), fk, id)
+        } else {
+            (format!("\"{fk}\", \"{id}\""), id, fk)
+        };
+        // the depth also limits the number of steps when the foreign keys contain a cycle
+        let sql = format!(
+            "(WITH RECURSIVE \"{name}\" (\"start\", \"node\", \"depth\") AS (
+                SELECT {base}, 1 FROM \"{table}\" WHERE \"{fk}\" IS NOT NULL AND {depth} > 0
+                UNION
+                SELECT \"prev\".\"start\", \"t\".\"{next}\", \"prev\".\"depth\" + 1
+                FROM \"{name}\" AS \"prev\" JOIN \"{table}\" AS \"t\" ON \"t\".\"{join}\" = \"prev\".\"node\"
+                WHERE \"t\".\"{fk}\" IS NOT NULL AND \"prev\".\"depth\" < {depth}
+            ) SELECT DISTINCT \"start\" AS \"{}\", \"node\" AS \"{}\" FROM \"{name}\")",
+            start.to_string(),
+            node.to_string(),
+        );
+
+        let alias = self.ast.scope.new_alias();
+        // only the pairs are unique
+        let table = Joinable::Raw(sql, vec![start, node]);
+        self.ast.tables.push((table, alias));
+        let column = |field| Prebuilt(Expr::col((alias, field)).into(), PhantomData).into_column();
+        (column(start), column(node))
+    }
+}
+
+/// Build the sub-query with the column named `field`, also returns the keys of its rows.
+fn sub_query<'outer, S, T: MyTyp>(
+    f: impl for<'b> FnOnce(&mut SubQuery<'outer, 'b, S>) -> Column<'b, S, T>,
+    field: Field,
+) -> (SelectStatement, Vec<SimpleExpr>) {
+    let mut sub = SubQuery {
+        query: Rows {
+            phantom: PhantomData,
+            ast: MySelect::default(),
+        },
+        phantom: PhantomData,
+    };
+    let val = f(&mut sub);
+    // this can add implicit joins, so it needs to happen before building the select
+    let expr = val.build_expr(sub.ast.builder());
+    let mut select = sub.ast.build_select(false);
+    select.clear_selects();
+    select.expr_as(expr, field);
+    (select, sub.ast.keys().collect())
+}
@@ -0,0 +1,60 @@
+use crate::{
+    hash::{ColumnType, TypBuilder},
+    Table, Transaction,
+};
+
+/// Information about a column of a table, returned by the `columns` method of the generated tables enum.
+///
+/// Every schema gets an enum with a variant for each table, like `SchemaTables` for `Schema`.
+/// It can be used to write tools that work for all tables, see [crate::migration::schema].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnInfo {
+    /// The name of the column in the database.
+    pub name: String,
+    /// The type of the column in the database: `integer`, `real` or `text`.
+    pub sql_type: &'static str,
+    /// Whether the column can be `NULL`.
+    pub nullable: bool,
+    /// The table that is referenced by the column, if it is a foreign key.
+    pub references: Option<String>,
+}
+
+/// The columns of table `T`, starting with its `id`.
+pub fn columns<T: Table>() -> Vec<ColumnInfo> {
+    let mut b = TypBuilder::default();
+    T::typs(&mut b);
+
+    let id = ColumnInfo {
+        name: T::ID.to_owned(),
+        sql_type: "integer",
+        nullable: false,
+        references: None,
+    };
+    let columns = b.ast.columns.iter().map(|col| ColumnInfo {
+        name: col.name.clone(),
+        sql_type: match col.typ {
+            ColumnType::Integer => "integer",
+            ColumnType::Float => "real",
+            ColumnType::String => "text",
+        },
+        nullable: col.nullable,
+        references: col.fk.as_ref().map(|(table, _)| table.clone()),
+    });
+    std::iter::once(id).chain(columns).collect()
+}
+
+/// The number of rows of table `T` that are joined by [crate::Rows::join].
+pub fn count<T: Table>(txn: &Transaction<'_, T::Schema>) -> i64 {
+    txn.query(|rows| {
+        rows.join::<T>();
+        rows.count()
+    })
+}
+
+/// Check if [crate::Rows::join] finds any rows of table `T`.
+pub fn exists<T: Table>(txn: &Transaction<'_, T::Schema>) -> bool {
+    txn.query(|rows| {
+        rows.join::<T>();
+        rows.exists()
+    })
+}
@@ -2,7 +2,11 @@ use std::cell::Cell;
 
 use rusqlite::Connection;
 
-use crate::{transaction::TransactionYoke, Database, Transaction, TransactionMut};
+use crate::{
+    error::{unwrap_db, Error},
+    transaction::TransactionYoke,
+    Database, ReadOnlyDatabase, Transaction, TransactionMut,
+};
 
 /// The primary interface to the database.
 ///
@@ -25,11 +29,18 @@ impl LocalClient {
     pub fn transaction<S>(&mut self, db: &Database<S>) -> Transaction<S> {
         use r2d2::ManageConnection;
         // TODO: could check here if the existing connection is good to use.
-        let conn = Box::new(db.manager.connect().unwrap());
+        let conn = Box::new(unwrap_db(db.manager.connect()));
         let txn = yoke::Yoke::attach_to_cart(conn, |conn| {
-            TransactionYoke(conn.unchecked_transaction().unwrap())
+            TransactionYoke(unwrap_db(conn.unchecked_transaction()))
         });
-        Transaction::new_checked(txn, db.schema_version)
+        Transaction::new_checked(txn, db.schema_version, &db.slow_log, "read")
+    }
+
+    /// Create a [Transaction] for a [ReadOnlyDatabase].
+    ///
+    /// This works the same as [LocalClient::transaction].
+    pub fn transaction_read_only<S>(&mut self, db: &ReadOnlyDatabase<S>) -> Transaction<'_, S> {
+        self.transaction(&db.inner)
     }
 
     /// Create a [TransactionMut].
@@ -41,27 +52,40 @@ impl LocalClient {
     /// Note: you can create a deadlock if you are holding on to another lock while trying to
     /// get a mutable transaction!
     ///
+    /// If the database stays locked for longer than the [crate::migration::Config::busy_timeout],
+    /// starting the transaction is retried as configured with [crate::migration::Config::busy_retries].
+    /// This function will panic when the database is still locked after that.
+    ///
     /// This function will panic if the schema was modified compared to when the [Database] value
     /// was created. This can happen for example by running another instance of your program with
     /// additional migrations.
     pub fn transaction_mut<S>(&mut self, db: &Database<S>) -> TransactionMut<S> {
+        unwrap_db(self.try_transaction_mut(db))
+    }
+
+    /// Create a [TransactionMut] like [LocalClient::transaction_mut], but return an [Error] when sqlite fails.
+    ///
+    /// This returns [Error::Busy] instead of panicking when the database stays locked.
+    pub fn try_transaction_mut<S>(
+        &mut self,
+        db: &Database<S>,
+    ) -> Result<TransactionMut<'_, S>, Error> {
         use r2d2::ManageConnection;
         // TODO: could check here if the existing connection is good to use.
         // TODO: make sure that when reusing a connection, the foreign keys are checked (migration doesn't)
         // .pragma_update(None, "foreign_keys", "ON").unwrap();
-        let conn = Box::new(db.manager.connect().unwrap());
-        let txn = yoke::Yoke::attach_to_cart(conn, |conn| {
-            TransactionYoke(
-                rusqlite::Transaction::new_unchecked(
-                    conn,
-                    rusqlite::TransactionBehavior::Immediate,
-                )
-                .unwrap(),
-            )
-        });
-        TransactionMut {
-            inner: Transaction::new_checked(txn, db.schema_version),
-        }
+        let txn = db.busy_retry.run(|| {
+            let conn = Box::new(db.manager.connect()?);
+            yoke::Yoke::try_attach_to_cart(conn, |conn| {
+                rusqlite::Transaction::new_unchecked(conn, rusqlite::TransactionBehavior::Immediate)
+                    .map(TransactionYoke)
+            })
+        })?;
+        Ok(TransactionMut {
+            inner: Transaction::new_checked(txn, db.schema_version, &db.slow_log, "write"),
+            watchers: db.watchers.clone(),
+            hooks: db.hooks.clone(),
+        })
     }
 }
 
@@ -0,0 +1,221 @@
+use std::{
+    cell::{Cell, RefCell},
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use rusqlite::StatementStatus;
+use sea_query_rusqlite::{RusqliteValue, RusqliteValues};
+
+use crate::{metrics, span, Database};
+
+/// Information about an executed sql statement, see [crate::migration::Config::trace].
+#[derive(Debug, Clone, Copy)]
+pub struct Trace<'a> {
+    /// The sql text, every `?` is a bound parameter.
+    pub sql: &'a str,
+    /// The number of bound parameters.
+    pub params: usize,
+    /// The time it took to execute the statement and read all result rows.
+    pub duration: Duration,
+}
+
+pub(crate) type TraceHook = Box<dyn Fn(&Trace) + Send + Sync>;
+
+static HOOK: OnceLock<TraceHook> = OnceLock::new();
+
+pub(crate) fn set_hook(hook: TraceHook) {
+    assert!(
+        HOOK.set(hook).is_ok(),
+        "the trace hook can only be set once"
+    );
+}
+
+/// A statement that took longer than the threshold of [Database::slow_query_log].
+#[derive(Debug, Clone, Copy)]
+pub struct SlowQuery<'a> {
+    /// The sql text, every `?` is a bound parameter.
+    pub sql: &'a str,
+    /// The time it took to execute the statement and read all result rows.
+    pub duration: Duration,
+    /// The number of rows that sqlite stepped through in full table scans.
+    ///
+    /// This is `SQLITE_STMTSTATUS_FULLSCAN_STEP`, a high number often means that an index is missing.
+    pub fullscan_steps: i64,
+    /// The number of virtual machine operations that sqlite executed, which is a measure of the total work.
+    ///
+    /// This is `SQLITE_STMTSTATUS_VM_STEP`, it includes the rows that were examined using an index.
+    pub vm_steps: i64,
+}
+
+type SlowQueryHook = Arc<dyn Fn(&SlowQuery) + Send + Sync>;
+
+/// The slow query log of a [Database], see [Database::slow_query_log].
+#[derive(Default)]
+pub(crate) struct SlowLog {
+    inner: Mutex<Option<(Duration, SlowQueryHook)>>,
+}
+
+thread_local! {
+    static SHOW_SQL: Cell<bool> = const { Cell::new(false) };
+    // The slow query log of the transaction on this thread.
+    // There is at most one transaction for each thread, because it borrows the [crate::LocalClient].
+    static SLOW_LOG: RefCell<Option<Arc<SlowLog>>> = const { RefCell::new(None) };
+    // the statement status of the last statement that was executed
+    static STEPS: Cell<(i64, i64)> = const { Cell::new((0, 0)) };
+    // the statements that are collected by [collect_sql]
+    static COLLECT: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Use the slow query log for the statements on this thread until it is dropped.
+pub(crate) struct SlowLogGuard {
+    prev: Option<Arc<SlowLog>>,
+}
+
+impl SlowLog {
+    /// A copy with the same threshold and callback, changing the copy does not change `self`.
+    pub fn copy(&self) -> Self {
+        Self {
+            inner: Mutex::new(self.inner.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl SlowLogGuard {
+    pub fn new(log: &Arc<SlowLog>) -> Self {
+        Self {
+            prev: SLOW_LOG.replace(Some(log.clone())),
+        }
+    }
+}
+
+impl Drop for SlowLogGuard {
+    fn drop(&mut self) {
+        SLOW_LOG.set(self.prev.take());
+    }
+}
+
+/// Remember the statement status of a statement that was just executed, for the slow query log.
+///
+/// The counters are reset, because cached statements are used many times.
+pub(crate) fn record_steps(stmt: &rusqlite::Statement) {
+    let fullscan = stmt.reset_status(StatementStatus::FullscanStep);
+    let vm = stmt.reset_status(StatementStatus::VmStep);
+    STEPS.set((fullscan as i64, vm as i64));
+}
+
+/// Execute a statement and remember its statement status.
+pub(crate) fn execute(
+    stmt: &mut rusqlite::Statement,
+    values: &RusqliteValues,
+) -> rusqlite::Result<usize> {
+    let res = stmt.execute(&*values.as_params());
+    record_steps(stmt);
+    res
+}
+
+impl<S> Database<S> {
+    /// Call `log` for every statement that takes longer than `threshold`.
+    ///
+    /// This applies to all transactions of this [Database] and it replaces any previous slow query log.
+    /// The log receives the sql and statement counters of sqlite, which helps to find missing indices in production.
+    /// ```
+    /// # use std::time::Duration;
+    /// # use rust_query::{migration::{schema, Config}, LocalClient};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     User { name: String },
+    /// # }
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator::<v0::Schema>(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// db.slow_query_log(Duration::from_millis(100), |query| {
+    ///     eprintln!("slow query ({:?}): {}", query.duration, query.sql);
+    /// });
+    /// ```
+    pub fn slow_query_log(
+        &self,
+        threshold: Duration,
+        log: impl Fn(&SlowQuery) + Send + Sync + 'static,
+    ) {
+        *self.slow_log.inner.lock().unwrap() = Some((threshold, Arc::new(log)));
+    }
+}
+
+pub fn show_sql<R>(f: impl FnOnce() -> R) -> R {
+    let old = SHOW_SQL.get();
+    SHOW_SQL.set(true);
+    let res = f();
+    SHOW_SQL.set(old);
+    res
+}
+
+/// Run `f` and return all sql statements that it executed on this thread.
+///
+/// Every statement is on its own line, with whitespace collapsed and every bound parameter as `?`.
+/// This makes the result stable enough to compare with a snapshot, for example using `expect_test`.
+/// ```
+/// # use rust_query::{migration::{schema, Config}, testing::collect_sql, LocalClient, Table};
+/// # #[schema]
+/// # enum Schema {
+/// #     User { name: String },
+/// # }
+/// # use v0::*;
+/// # let mut client = LocalClient::try_new().unwrap();
+/// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+/// let txn = client.transaction(&db);
+/// let sql = collect_sql(|| {
+///     txn.query(|rows| {
+///         let user = User::join(rows);
+///         rows.into_vec(user.name())
+///     });
+/// });
+/// assert_eq!(sql.lines().count(), 1);
+/// ```
+/// Statements are not collected again when the result is cached, for example after [crate::Transaction::memoize].
+pub fn collect_sql(f: impl FnOnce()) -> String {
+    let prev = COLLECT.replace(Some(vec![]));
+    f();
+    let sql = COLLECT.replace(prev).unwrap();
+    sql.iter().map(|x| format!("{x}\n")).collect()
+}
+
+/// Execute a statement and report it to the trace hook.
+pub(crate) fn traced<R>(sql: &str, params: &[RusqliteValue], f: impl FnOnce() -> R) -> R {
+    metrics::statement();
+    let f = || span::exec(sql, f);
+    COLLECT.with_borrow_mut(|collect| {
+        if let Some(collect) = collect {
+            collect.push(sql.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+    });
+    if SHOW_SQL.get() {
+        println!("{sql}");
+        println!("{params:?}");
+    }
+    let hook = HOOK.get();
+    let slow = SLOW_LOG.with_borrow(|log| log.as_ref()?.inner.lock().unwrap().clone());
+    if hook.is_none() && slow.is_none() {
+        return f();
+    }
+    STEPS.set((0, 0));
+    let start = Instant::now();
+    let res = f();
+    let duration = start.elapsed();
+    if let Some(hook) = hook {
+        hook(&Trace {
+            sql,
+            params: params.len(),
+            duration,
+        });
+    }
+    if let Some((_, log)) = slow.filter(|(threshold, _)| duration > *threshold) {
+        let (fullscan_steps, vm_steps) = STEPS.get();
+        log(&SlowQuery {
+            sql,
+            duration,
+            fullscan_steps,
+            vm_steps,
+        });
+    }
+    res
+}
@@ -1,8 +1,11 @@
-use std::{convert::Infallible, marker::PhantomData, ops::Deref};
+use std::{
+    convert::Infallible, marker::PhantomData, ops::Deref, rc::Rc, sync::Arc, time::Duration,
+};
 
 use rusqlite::ErrorCode;
 use sea_query::{
-    Alias, DeleteStatement, Expr, InsertStatement, SqliteQueryBuilder, UpdateStatement, Value,
+    Alias, DeleteStatement, Expr, InsertStatement, SimpleExpr, SqliteQueryBuilder, UpdateStatement,
+    Value,
 };
 use sea_query_rusqlite::RusqliteBinder;
 use yoke::{Yoke, Yokeable};
@@ -11,12 +14,24 @@ use crate::{
     alias::Field,
     ast::MySelect,
     client::private_exec,
+    db::{Col, RowId},
+    error::{unwrap_db, Error},
     exec::Query,
-    insert::{Reader, Writable},
-    migrate::schema_version,
+    function::Functions,
+    hook::{HookEvent, Hooks},
+    insert::{Reader, Unique, UniqueKey, Writable, WriteError},
+    interrupt::{Interrupts, QueryPolicy},
+    memo::QueryCache,
+    metrics::{self, TransactionTimer},
+    migrate::{init_connection, schema_version},
     private::Dummy,
+    public_id::PUBLIC_ID,
+    scope::Scope,
+    span::TransactionSpan,
     token::LocalClient,
-    IntoColumn, Table, TableRow,
+    trace::{execute, record_steps, traced, SlowLog, SlowLogGuard},
+    watch::Watchers,
+    IntoColumn, SoftDelete, Table, TableRow, UnixEpoch, Versioned,
 };
 
 /// [Database] is a proof that the database has been configured.
@@ -42,12 +57,105 @@ use crate::{
 ///
 /// Sqlite is also configured with [`synchronous=NORMAL`](https://www.sqlite.org/pragma.html#pragma_synchronous). This gives better performance by fsyncing less.
 /// The database will not lose transactions due to application crashes, but it might due to system crashes or power loss.
+/// This and other pragmas can be changed with [crate::migration::Config], for example [crate::migration::Config::synchronous].
 pub struct Database<S> {
-    pub(crate) manager: r2d2_sqlite::SqliteConnectionManager,
+    pub(crate) manager: Arc<r2d2_sqlite::SqliteConnectionManager>,
+    pub(crate) busy_retry: BusyRetry,
+    pub(crate) watchers: Arc<Watchers>,
+    pub(crate) hooks: Arc<Hooks<S>>,
+    pub(crate) slow_log: Arc<SlowLog>,
+    pub(crate) interrupts: Arc<Interrupts>,
+    pub(crate) functions: Arc<Functions>,
     pub(crate) schema_version: i64,
     pub(crate) schema: PhantomData<S>,
 }
 
+impl<S> Database<S> {
+    /// Create a copy of this [Database] that is stored in memory.
+    ///
+    /// The copy starts with the same schema and content, but changes to the copy are
+    /// not visible in the original and vice versa.
+    ///
+    /// This is useful for tests; migrate a database and insert fixtures once and then
+    /// give every test its own isolated copy. Tests using different copies can run in parallel.
+    ///
+    /// The copy has the hooks and the slow query log of this database, so changes to the copy
+    /// are also recorded in its audit log. Hooks that are added later are only added to one of them.
+    pub fn clone_in_memory(&self) -> Self {
+        use r2d2::ManageConnection;
+        let watchers = Arc::<Watchers>::default();
+        let interrupts = Arc::<Interrupts>::default();
+        let manager = r2d2_sqlite::SqliteConnectionManager::memory().with_init({
+            let watchers = watchers.clone();
+            let interrupts = interrupts.clone();
+            let functions = self.functions.clone();
+            move |conn| {
+                watchers.install(conn);
+                interrupts.install(conn);
+                functions.install(conn);
+                init_connection(conn)
+            }
+        });
+
+        let src = self.manager.connect().unwrap();
+        let mut dst = manager.connect().unwrap();
+        // copy all pages in one step to get a consistent snapshot
+        let backup = rusqlite::backup::Backup::new(&src, &mut dst).unwrap();
+        let res = backup.step(-1).unwrap();
+        assert!(matches!(res, rusqlite::backup::StepResult::Done));
+        drop(backup);
+
+        let schema_version = schema_version(&dst.unchecked_transaction().unwrap()).unwrap();
+        Database {
+            manager: Arc::new(manager),
+            busy_retry: self.busy_retry,
+            watchers,
+            hooks: Arc::new(self.hooks.copy()),
+            slow_log: Arc::new(self.slow_log.copy()),
+            interrupts,
+            functions: self.functions.clone(),
+            schema_version,
+            schema: PhantomData,
+        }
+    }
+}
+
+/// Same as [Database], but it was opened with [crate::migration::Config::open_read_only].
+///
+/// A [ReadOnlyDatabase] can only be used to create [Transaction]s, see [LocalClient::transaction_read_only].
+pub struct ReadOnlyDatabase<S> {
+    pub(crate) inner: Database<S>,
+}
+
+/// Retry policy for starting a [TransactionMut], see [crate::migration::Config::busy_retries].
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct BusyRetry {
+    pub retries: u32,
+    pub initial_delay: Duration,
+}
+
+impl BusyRetry {
+    /// Run `f` until it does not fail with `SQLITE_BUSY` or there are no retries left.
+    pub fn run<T>(self, mut f: impl FnMut() -> rusqlite::Result<T>) -> rusqlite::Result<T> {
+        let mut delay = self.initial_delay;
+        let mut retries = self.retries;
+        loop {
+            match f() {
+                // the busy_timeout has already passed, so we wait some more before trying again
+                Err(err)
+                    if retries > 0 && err.sqlite_error_code() == Some(ErrorCode::DatabaseBusy) =>
+                {
+                    metrics::busy_retry();
+                    std::thread::sleep(delay);
+                    delay *= 2;
+                    retries -= 1;
+                }
+                res => return res,
+            }
+        }
+    }
+}
+
 /// [Transaction] can be used to query the database.
 ///
 /// From the perspective of a [Transaction] each [TransactionMut] is fully applied or not at all.
@@ -56,9 +164,16 @@ pub struct Database<S> {
 ///
 /// All [TableRow] references retrieved from the database live for at most `'a`.
 /// This makes these references effectively local to this [Transaction].
-#[repr(transparent)]
 pub struct Transaction<'a, S> {
     pub(crate) transaction: YokedTransaction,
+    pub(crate) policy: QueryPolicy,
+    pub(crate) actor: Option<String>,
+    pub(crate) cache: Option<Rc<QueryCache>>,
+    pub(crate) slow_log: SlowLogGuard,
+    pub(crate) timer: TransactionTimer,
+    pub(crate) span: TransactionSpan,
+    // the tenant of a transaction that is scoped with [Transaction::scoped]
+    pub(crate) scope: Option<Scope>,
     pub(crate) _p: PhantomData<fn(&'a S) -> &'a S>,
     pub(crate) _local: PhantomData<LocalClient>,
 }
@@ -69,8 +184,19 @@ pub struct Transaction<'a, S> {
 ///
 /// To make mutations to the database permanent you need to use [TransactionMut::commit].
 /// This is to make sure that if a function panics while holding a mutable transaction, it will roll back those changes.
+///
+/// # Locking
+/// A [TransactionMut] is started with `BEGIN IMMEDIATE`, so it holds the write lock of the database from the start.
+/// This means that waiting for other writers only happens in [crate::LocalClient::transaction_mut],
+/// which fails with [crate::Error::Busy] when using [crate::LocalClient::try_transaction_mut].
+/// Queries, inserts and updates in the transaction itself never fail because another connection is writing.
+///
+/// Sqlite locks the whole database, there are no row locks.
+/// Reading a row in a [TransactionMut] is enough to make sure that no other writer changes it before the commit.
 pub struct TransactionMut<'a, S> {
     pub(crate) inner: Transaction<'a, S>,
+    pub(crate) watchers: Arc<Watchers>,
+    pub(crate) hooks: Arc<Hooks<S>>,
 }
 
 impl<'a, S> Deref for TransactionMut<'a, S> {
@@ -86,14 +212,42 @@ impl<'t, S> Transaction<'t, S> {
         &self.transaction.get().0
     }
 
+    /// The same transaction with another lifetime, rows of this transaction can not be used with the new lifetime.
+    pub(crate) fn rebrand<'b>(self) -> Transaction<'b, S> {
+        Transaction {
+            transaction: self.transaction,
+            policy: self.policy,
+            actor: self.actor,
+            cache: self.cache,
+            slow_log: self.slow_log,
+            timer: self.timer,
+            span: self.span,
+            scope: self.scope,
+            _p: PhantomData,
+            _local: PhantomData,
+        }
+    }
+
     /// This will check the schema version and panic if it is not as expected
-    pub(crate) fn new_checked(txn: YokedTransaction, expected: i64) -> Self {
-        if schema_version(&txn.get().0) != expected {
+    pub(crate) fn new_checked(
+        txn: YokedTransaction,
+        expected: i64,
+        slow_log: &Arc<SlowLog>,
+        kind: &'static str,
+    ) -> Self {
+        if unwrap_db(schema_version(&txn.get().0)) != expected {
             panic!("The database schema was updated unexpectedly")
         }
 
         Transaction {
             transaction: txn,
+            policy: QueryPolicy::default(),
+            actor: None,
+            cache: None,
+            slow_log: SlowLogGuard::new(slow_log),
+            timer: TransactionTimer::new(kind),
+            span: TransactionSpan::new::<S>(kind, expected),
+            scope: None,
             _p: PhantomData,
             _local: PhantomData,
         }
@@ -109,7 +263,44 @@ impl<'t, S> Transaction<'t, S> {
         // Execution already happens in a [Transaction].
         // and thus any [TransactionMut] that it might be borrowed
         // from are borrowed immutably, so the rows can not change.
-        private_exec(&self.transaction(), f)
+        self.with_policy(|| private_exec(&self.transaction(), f))
+    }
+
+    /// Check that the row with this id exists and return it as a [TableRow].
+    ///
+    /// Returns [None] if the row does not exist (anymore).
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient, RowId};
+    /// #[schema]
+    /// enum Schema {
+    ///     User { name: String },
+    /// }
+    /// use v0::*;
+    ///
+    /// let mut client = LocalClient::try_new().unwrap();
+    /// let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    ///
+    /// let mut txn = client.transaction_mut(&db);
+    /// let id = RowId::from(txn.insert(User { name: "alice" })).as_i64();
+    /// txn.commit();
+    ///
+    /// let txn = client.transaction(&db);
+    /// let user = txn.find(RowId::<User>::from_i64(id)).unwrap();
+    /// assert_eq!(txn.query_one(user.name()), "alice");
+    /// assert_eq!(txn.find(RowId::<User>::from_i64(id + 1)), None);
+    /// ```
+    pub fn find<T: Table<Schema = S>>(&self, id: RowId<T>) -> Option<TableRow<'t, T>> {
+        // this row is only used to compare with existing rows
+        let unchecked = TableRow::<T> {
+            _p: PhantomData,
+            _local: PhantomData,
+            idx: id.idx,
+        };
+        self.query(|rows| {
+            let row = T::join(rows);
+            rows.filter(row.eq(unchecked));
+            rows.first(row)
+        })
     }
 
     /// Retrieve a single result from the database.
@@ -122,13 +313,45 @@ impl<'t, S> Transaction<'t, S> {
     {
         // Theoretically this doesn't even need to be in a transaction.
         // We already have one though, so we must use it.
-        let mut res = private_exec(&self.transaction(), |e| {
-            // Cast the static lifetime to any lifetime necessary, this is fine because we know the static lifetime
-            // can not be guaranteed by a query scope.
-            e.into_vec_private(val)
+        let mut res = self.with_policy(|| {
+            private_exec(&self.transaction(), |e| {
+                // Cast the static lifetime to any lifetime necessary, this is fine because we know the static lifetime
+                // can not be guaranteed by a query scope.
+                e.into_vec_private(val, |_| {})
+            })
         });
         res.pop().unwrap()
     }
+
+    /// Find the row with the unique constraint `U` that has the values in `key`.
+    ///
+    /// The key is a tuple with a value for every column in the constraint.
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     Customer {
+    /// #         #[unique_by_email]
+    /// #         email: String,
+    /// #         name: String,
+    /// #     },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// # let mut txn = client.transaction_mut(&db);
+    /// # let email = "alice@example.com";
+    /// let customer = txn.lookup::<customer::UniqueByEmail>((email,));
+    /// ```
+    pub fn lookup<U: Unique<Table: Table<Schema = S>>>(
+        &self,
+        key: impl UniqueKey<'t, U>,
+    ) -> Option<TableRow<'t, U::Table>>
+    where
+        S: 'static,
+    {
+        self.query_one(key.lookup())
+    }
 }
 
 impl<'t, S: 'static> TransactionMut<'t, S> {
@@ -139,10 +362,53 @@ impl<'t, S: 'static> TransactionMut<'t, S> {
     /// - 0 unique constraints => [Infallible]
     /// - 1 unique constraint => [TableRow] reference to the conflicting table row.
     /// - 2+ unique constraints => [()] no further information is provided.
+    ///
+    /// This function panics if the new row does not satisfy a `CHECK` constraint,
+    /// use [TransactionMut::checked_insert] to get an error instead.
+    #[track_caller]
     pub fn try_insert<T: Table<Schema = S>, C>(
         &mut self,
         val: impl Writable<'t, T = T, Conflict = C, Schema = S>,
     ) -> Result<TableRow<'t, T>, C> {
+        self.checked_insert(val).map_err(WriteError::unwrap_check)
+    }
+
+    /// Try inserting a value into the database, like [TransactionMut::try_insert].
+    ///
+    /// This also returns an error when the new row does not satisfy a `CHECK` constraint of the table.
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient, WriteError};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     Track {
+    /// #         name: String,
+    /// #         #[check("milliseconds > 0")]
+    /// #         milliseconds: i64,
+    /// #     },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// # let mut txn = client.transaction_mut(&db);
+    /// match txn.checked_insert(Track { name: "intro", milliseconds: 0 }) {
+    ///     Err(WriteError::Check(msg)) => println!("invalid track: {msg}"),
+    ///     # _ => unreachable!(),
+    ///     // ..
+    /// }
+    /// ```
+    pub fn checked_insert<T: Table<Schema = S>, C>(
+        &mut self,
+        val: impl Writable<'t, T = T, Conflict = C, Schema = S>,
+    ) -> Result<TableRow<'t, T>, WriteError<C>> {
+        self.insert_with_id(val, None)
+    }
+
+    /// Insert a row like [TransactionMut::checked_insert], the new row gets id `id` if it is not [None].
+    pub(crate) fn insert_with_id<T: Table<Schema = S>, C>(
+        &mut self,
+        val: impl Writable<'t, T = T, Conflict = C, Schema = S>,
+        id: Option<i64>,
+    ) -> Result<TableRow<'t, T>, WriteError<C>> {
         let ast = MySelect::default();
 
         let reader = Reader {
@@ -150,9 +416,27 @@ impl<'t, S: 'static> TransactionMut<'t, S> {
             _p: PhantomData,
             _p2: PhantomData,
         };
+        if let Some(id) = id {
+            reader.col(T::ID, id);
+        }
         val.read(reader);
+        if let Some(kind) = T::PUBLIC_ID {
+            reader.col(PUBLIC_ID, kind.generate());
+        }
+        if T::TIMESTAMPS {
+            reader.col("created_at", UnixEpoch);
+            reader.col("updated_at", UnixEpoch);
+        }
+        if T::VERSIONED {
+            reader.col("version", 0i64);
+        }
 
-        let select = ast.simple();
+        let mut select = ast.simple();
+        if let Some((col, tenant)) = self.scope.and_then(Scope::write_filter::<T>) {
+            // nothing is inserted if the row references another tenant
+            let (expr, _) = ast.select.iter().find(|x| x.1 == Field::Str(col)).unwrap();
+            select.and_where(Expr::expr(expr.clone()).eq(tenant));
+        }
 
         let mut insert = InsertStatement::new();
         let names = ast.select.iter().map(|(_field, name)| *name);
@@ -163,21 +447,41 @@ impl<'t, S: 'static> TransactionMut<'t, S> {
 
         let (sql, values) = insert.build_rusqlite(SqliteQueryBuilder);
 
-        let mut statement = self.transaction().prepare_cached(&sql).unwrap();
-        let mut res = statement
-            .query_map(&*values.as_params(), |row| row.get(T::ID))
-            .unwrap();
+        let res: Vec<rusqlite::Result<TableRow<'t, T>>> = traced(&sql, &values.0, || {
+            let mut statement = self.transaction().prepare_cached(&sql)?;
+            // the statement is run to completion, because resetting it early
+            // can roll back the transaction when a trigger writes to a full-text index
+            let res = statement
+                .query_map(&*values.as_params(), |row| row.get(T::ID))?
+                .collect();
+            record_steps(&statement);
+            Ok(res)
+        })
+        .map_err(WriteError::db)?;
 
-        match res.next().unwrap() {
-            Ok(id) => Ok(id),
+        let Some(res) = res.into_iter().next() else {
+            panic!("the new row of `{}` references another tenant", T::NAME)
+        };
+        match res {
+            Ok(id) => {
+                self.hooks
+                    .run(&self.inner, T::NAME, HookEvent::AfterInsert, id.idx);
+                Ok(id)
+            }
+            Err(rusqlite::Error::SqliteFailure(kind, Some(val)))
+                if kind.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_CHECK =>
+            {
+                // val looks like "CHECK constraint failed: milliseconds > 0"
+                Err(WriteError::Check(val))
+            }
             Err(rusqlite::Error::SqliteFailure(kind, Some(_val)))
                 if kind.code == ErrorCode::ConstraintViolation =>
             {
                 // val looks like "UNIQUE constraint failed: playlist_track.playlist, playlist_track.track"
                 let conflict = self.query_one(val.get_conflict_unchecked());
-                Err(conflict.unwrap())
+                Err(WriteError::Conflict(conflict.unwrap()))
             }
-            Err(err) => Err(err).unwrap(),
+            Err(err) => Err(WriteError::db(err)),
         }
     }
 
@@ -209,6 +513,40 @@ impl<'t, S: 'static> TransactionMut<'t, S> {
         }
     }
 
+    /// Update the row that has the same values for the unique constraint `U`, or insert a new row if there is none.
+    ///
+    /// This makes it possible to choose which unique constraint is used when a table has more than one.
+    /// Returns [None] if the new values conflict with another row on a different unique constraint.
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     Customer {
+    /// #         #[unique_by_email]
+    /// #         email: String,
+    /// #         name: String,
+    /// #     },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// # let mut txn = client.transaction_mut(&db);
+    /// txn.insert_or_update::<customer::UniqueByEmail>(Customer {
+    ///     email: "alice@example.com",
+    ///     name: "Alice",
+    /// })
+    /// # .unwrap();
+    /// ```
+    pub fn insert_or_update<U: Unique<Table: Table<Schema = S>>>(
+        &mut self,
+        val: impl Writable<'t, T = U::Table, Schema = S> + UniqueKey<'t, U>,
+    ) -> Option<TableRow<'t, U::Table>> {
+        match self.query_one(val.lookup()) {
+            Some(row) => self.try_update(row, val).ok().map(|()| row),
+            None => self.try_insert(val).ok(),
+        }
+    }
+
     /// Try updating a row in the database to have new column values.
     ///
     /// Updating can fail just like [TransactionMut::try_insert] because of unique constraint conflicts.
@@ -219,11 +557,36 @@ impl<'t, S: 'static> TransactionMut<'t, S> {
     /// - 0 unique constraints => [Infallible]
     /// - 1 unique constraint => [TableRow] reference to the conflicting table row.
     /// - 2+ unique constraints => [()] no further information is provided.
+    ///
+    /// This function panics if the new values do not satisfy a `CHECK` constraint,
+    /// use [TransactionMut::checked_update] to get an error instead.
+    #[track_caller]
     pub fn try_update<T: Table<Schema = S>, C>(
         &mut self,
         row: impl IntoColumn<'t, S, Typ = T>,
         val: impl Writable<'t, T = T, Conflict = C, Schema = S>,
     ) -> Result<(), C> {
+        self.checked_update(row, val)
+            .map_err(WriteError::unwrap_check)
+    }
+
+    /// Try updating a row in the database, like [TransactionMut::try_update].
+    ///
+    /// This also returns an error when the new values do not satisfy a `CHECK` constraint of the table.
+    pub fn checked_update<T: Table<Schema = S>, C>(
+        &mut self,
+        row: impl IntoColumn<'t, S, Typ = T>,
+        val: impl Writable<'t, T = T, Conflict = C, Schema = S>,
+    ) -> Result<(), WriteError<C>> {
+        self.update_inner(row, val)
+    }
+
+    fn update_inner<T: Table<Schema = S>, C>(
+        &mut self,
+        row: impl IntoColumn<'t, S, Typ = T>,
+        val: impl Writable<'t, T = T, Conflict = C, Schema = S>,
+    ) -> Result<(), WriteError<C>> {
+        let row = row.into_column();
         let ast = MySelect::default();
 
         let reader = Reader {
@@ -232,48 +595,92 @@ impl<'t, S: 'static> TransactionMut<'t, S> {
             _p2: PhantomData,
         };
         val.read(reader);
+        if T::TIMESTAMPS {
+            reader.col("updated_at", UnixEpoch);
+        }
+        if T::VERSIONED {
+            let version = Col::<i64, _>::new("version", row.clone());
+            reader.col("version", version.into_column().add(1));
+        }
 
-        let select = ast.simple();
+        let mut select = ast.simple();
+        let scope = self.scope.and_then(Scope::write_filter::<T>);
+        if let Some((col, tenant)) = scope {
+            // no new values are selected if they reference another tenant
+            let (expr, _) = ast.select.iter().find(|x| x.1 == Field::Str(col)).unwrap();
+            select.and_where(Expr::expr(expr.clone()).eq(tenant));
+        }
         let (query, args) = select.build_rusqlite(SqliteQueryBuilder);
-        let mut stmt = self.transaction().prepare_cached(&query).unwrap();
+        let mut stmt = self
+            .transaction()
+            .prepare_cached(&query)
+            .map_err(WriteError::db)?;
 
         let row_id = self.query_one(row).idx;
+        self.hooks
+            .run(&self.inner, T::NAME, HookEvent::BeforeUpdate, row_id);
         let mut update = UpdateStatement::new()
             .table(Alias::new(T::NAME))
             .cond_where(Expr::val(row_id).equals(Alias::new(T::ID)))
             .to_owned();
+        if let Some((col, tenant)) = scope {
+            update.and_where(Expr::col(Alias::new(col)).eq(tenant));
+        }
 
-        stmt.query_row(&*args.as_params(), |row| {
-            for (_, field) in ast.select.iter() {
-                let Field::Str(name) = field else { panic!() };
-
-                let val = match row.get_unwrap::<&str, rusqlite::types::Value>(*name) {
-                    rusqlite::types::Value::Null => Value::BigInt(None),
-                    rusqlite::types::Value::Integer(x) => Value::BigInt(Some(x)),
-                    rusqlite::types::Value::Real(x) => Value::Double(Some(x)),
-                    rusqlite::types::Value::Text(x) => Value::String(Some(Box::new(x))),
-                    rusqlite::types::Value::Blob(_) => todo!(),
-                };
-                update.value(*field, Expr::val(val));
+        traced(&query, &args.0, || {
+            let res = stmt.query_row(&*args.as_params(), |row| {
+                for (_, field) in ast.select.iter() {
+                    let Field::Str(name) = field else { panic!() };
+
+                    let val = match row.get_unwrap::<&str, rusqlite::types::Value>(*name) {
+                        rusqlite::types::Value::Null => Value::BigInt(None),
+                        rusqlite::types::Value::Integer(x) => Value::BigInt(Some(x)),
+                        rusqlite::types::Value::Real(x) => Value::Double(Some(x)),
+                        rusqlite::types::Value::Text(x) => Value::String(Some(Box::new(x))),
+                        rusqlite::types::Value::Blob(_) => todo!(),
+                    };
+                    update.value(*field, Expr::val(val));
+                }
+                Ok(())
+            });
+            match res {
+                Err(rusqlite::Error::QueryReturnedNoRows) => {
+                    panic!("the new values of `{}` reference another tenant", T::NAME)
+                }
+                res => res,
             }
-            Ok(())
         })
-        .unwrap();
+        .map_err(WriteError::db)?;
 
         let (query, args) = update.build_rusqlite(SqliteQueryBuilder);
 
-        let mut stmt = self.transaction().prepare_cached(&query).unwrap();
-        match stmt.execute(&*args.as_params()) {
-            Ok(1) => Ok(()),
+        let mut stmt = self
+            .transaction()
+            .prepare_cached(&query)
+            .map_err(WriteError::db)?;
+        match traced(&query, &args.0, || execute(&mut stmt, &args)) {
+            Ok(1) => {
+                self.hooks
+                    .run(&self.inner, T::NAME, HookEvent::AfterUpdate, row_id);
+                Ok(())
+            }
+            Ok(0) if scope.is_some() => {
+                panic!("the row of `{}` references another tenant", T::NAME)
+            }
             Ok(n) => panic!("unexpected number of updates: {n}"),
+            Err(rusqlite::Error::SqliteFailure(kind, Some(val)))
+                if kind.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_CHECK =>
+            {
+                Err(WriteError::Check(val))
+            }
             Err(rusqlite::Error::SqliteFailure(kind, Some(_val)))
                 if kind.code == ErrorCode::ConstraintViolation =>
             {
                 // val looks like "UNIQUE constraint failed: playlist_track.playlist, playlist_track.track"
                 let conflict = self.query_one(val.get_conflict_unchecked());
-                Err(conflict.unwrap())
+                Err(WriteError::Conflict(conflict.unwrap()))
             }
-            Err(err) => Err(err).unwrap(),
+            Err(err) => Err(WriteError::db(err)),
         }
     }
 
@@ -308,6 +715,91 @@ impl<'t, S: 'static> TransactionMut<'t, S> {
         }
     }
 
+    /// Update a row of a [Versioned] table, but only if its `version` is still `expected`.
+    ///
+    /// This makes it possible to read a row in one transaction and safely update it in another transaction.
+    /// If the row was updated in the meantime, then nothing happens and [Err] is returned with the current version.
+    /// Otherwise the row is updated and [Ok] is returned with the new version.
+    pub fn update_if_version<T: Versioned<Schema = S>>(
+        &mut self,
+        row: impl IntoColumn<'t, S, Typ = T>,
+        expected: i64,
+        val: impl Writable<'t, T = T, Conflict = Infallible, Schema = S>,
+    ) -> Result<i64, VersionConflict> {
+        let row = self.query_one(row);
+        let current = self.query_one(Col::<i64, _>::new("version", row));
+        if current != expected {
+            return Err(VersionConflict { current });
+        }
+        self.update(row, val);
+        Ok(current + 1)
+    }
+
+    /// Delete a row of a [SoftDelete] table by setting its `deleted_at` column to the current unix time.
+    ///
+    /// The row stays in the database, so rows that reference it are not affected,
+    /// but it is no longer included by [crate::Rows::join].
+    /// Returns `false` if the row was already deleted.
+    pub fn delete<T: SoftDelete<Schema = S>>(
+        &mut self,
+        row: impl IntoColumn<'t, S, Typ = T>,
+    ) -> bool {
+        self.set_deleted_at::<T>(row, Expr::cust("unixepoch('now')"), true)
+    }
+
+    /// Undo [TransactionMut::delete] by setting the `deleted_at` column back to `NULL`.
+    ///
+    /// Returns `false` if the row was not deleted.
+    pub fn restore<T: SoftDelete<Schema = S>>(
+        &mut self,
+        row: impl IntoColumn<'t, S, Typ = T>,
+    ) -> bool {
+        self.set_deleted_at::<T>(row, Expr::val(Value::BigInt(None)).into(), false)
+    }
+
+    fn set_deleted_at<T: SoftDelete<Schema = S>>(
+        &mut self,
+        row: impl IntoColumn<'t, S, Typ = T>,
+        val: SimpleExpr,
+        deleted: bool,
+    ) -> bool {
+        let row = self.query_one(row);
+        let row_id = row.idx;
+        let is_deleted = Col::<Option<i64>, _>::new("deleted_at", row);
+        let changes = self.query_one(is_deleted.into_column().is_some()) != deleted;
+        if changes {
+            let event = match deleted {
+                true => HookEvent::BeforeDelete,
+                false => HookEvent::BeforeUpdate,
+            };
+            self.hooks.run(&self.inner, T::NAME, event, row_id);
+        }
+        let deleted_at = Alias::new("deleted_at");
+        let currently = match deleted {
+            true => Expr::col(deleted_at.clone()).is_null(),
+            false => Expr::col(deleted_at.clone()).is_not_null(),
+        };
+        let (query, args) = UpdateStatement::new()
+            .table(Alias::new(T::NAME))
+            .value(deleted_at, val)
+            .cond_where(Expr::col(Alias::new(T::ID)).eq(row_id))
+            .and_where(currently)
+            .build_rusqlite(SqliteQueryBuilder);
+
+        let mut stmt = unwrap_db(self.transaction().prepare_cached(&query));
+        match unwrap_db(traced(&query, &args.0, || execute(&mut stmt, &args))) {
+            0 => false,
+            1 => {
+                if !deleted {
+                    self.hooks
+                        .run(&self.inner, T::NAME, HookEvent::AfterUpdate, row_id);
+                }
+                true
+            }
+            n => panic!("unexpected number of updates: {n}"),
+        }
+    }
+
     /// Make the changes made in this [TransactionMut] permanent.
     ///
     /// If the [TransactionMut] is dropped without calling this function, then the changes are rolled back.
@@ -315,25 +807,45 @@ impl<'t, S: 'static> TransactionMut<'t, S> {
         self.deletor().commit();
     }
 
+    /// Commit like [TransactionMut::commit], but return an [Error] when sqlite fails.
+    ///
+    /// The changes are rolled back when an error is returned.
+    pub fn try_commit(self) -> Result<(), Error> {
+        self.deletor().try_commit()
+    }
+
     pub fn deletor(self) -> Deletor<S> {
         Deletor {
-            transaction: self.inner.transaction,
-            _p: PhantomData,
+            inner: Transaction {
+                cache: None,
+                ..self.inner.rebrand()
+            },
+            watchers: self.watchers,
+            hooks: self.hooks,
         }
     }
 }
 
+/// Error returned by [TransactionMut::update_if_version] when the row has a different version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersionConflict {
+    /// The version of the row in the database.
+    pub current: i64,
+}
+
 #[derive(Yokeable)]
 pub struct TransactionYoke<'a>(pub rusqlite::Transaction<'a>);
 
 type YokedTransaction = yoke::Yoke<TransactionYoke<'static>, Box<rusqlite::Connection>>;
 
-pub struct Deletor<S> {
-    transaction: YokedTransaction,
-    _p: PhantomData<S>,
+pub struct Deletor<S: 'static> {
+    pub(crate) inner: Transaction<'static, S>,
+    pub(crate) watchers: Arc<Watchers>,
+    pub(crate) hooks: Arc<Hooks<S>>,
 }
 
-impl<S> Deletor<S> {
+impl<S: 'static> Deletor<S> {
+    #[allow(clippy::result_unit_err)]
     pub fn try_delete<T: Table>(&mut self, val: TableRow<'_, T>) -> Result<bool, ()> {
         let stmt = DeleteStatement::new()
             .from_table(Alias::new(T::NAME))
@@ -341,9 +853,11 @@ impl<S> Deletor<S> {
             .to_owned();
 
         let (query, args) = stmt.build_rusqlite(SqliteQueryBuilder);
-        let mut stmt = self.transaction.get().0.prepare_cached(&query).unwrap();
+        self.hooks
+            .run(&self.inner, T::NAME, HookEvent::BeforeDelete, val.idx);
+        let mut stmt = unwrap_db(self.inner.transaction().prepare_cached(&query));
 
-        match stmt.execute(&*args.as_params()) {
+        match traced(&query, &args.0, || execute(&mut stmt, &args)) {
             Ok(0) => Ok(false),
             Ok(1) => Ok(true),
             Ok(n) => {
@@ -355,7 +869,7 @@ impl<S> Deletor<S> {
                 // Some foreign key constraint got violated
                 Err(())
             }
-            Err(err) => Err(err).unwrap(),
+            Err(err) => unwrap_db(Err(err)),
         }
     }
 
@@ -380,8 +894,21 @@ impl<S> Deletor<S> {
     ///
     /// If the [Deletor] is dropped without calling this function, then the changes are rolled back.
     pub fn commit(self) {
-        let _: Yoke<(), Box<rusqlite::Connection>> = self
-            .transaction
-            .map_project(|transaction, _| transaction.0.commit().unwrap());
+        unwrap_db(self.try_commit())
+    }
+
+    /// Commit like [Deletor::commit], but return an [Error] when sqlite fails.
+    ///
+    /// The changes are rolled back when an error is returned.
+    pub fn try_commit(self) -> Result<(), Error> {
+        self.watchers.count_changes(self.inner.transaction());
+        let mut res = Ok(());
+        let _: Yoke<(), Box<rusqlite::Connection>> =
+            self.inner.transaction.map_project(|transaction, _| {
+                res = transaction.0.commit();
+            });
+        res?;
+        self.watchers.notify();
+        Ok(())
     }
 }
@@ -2,17 +2,25 @@ pub mod operations;
 
 use std::{marker::PhantomData, ops::Deref, rc::Rc};
 
-use operations::{Add, And, AsFloat, Eq, IsNotNull, Lt, Not, Or, UnwrapOr};
+use operations::{
+    Abs, Add, And, AsFloat, AsInt, AsText, Assume, Case, Ceil, Collate, Eq, Floor, Instr, IsIn,
+    IsNotNull, IsNull, Length, Lower, Lt, Max, Min, Not, Or, Replace, Round, Substr, Trim,
+    UnwrapOr, Upper,
+};
 use ref_cast::RefCast;
 use rusqlite::types::FromSql;
 use sea_query::{Alias, Expr, Nullable, SelectStatement, SimpleExpr};
 
 use crate::{
+    aggregate::{aggregate, Aggregate},
     alias::{Field, MyAlias, RawAlias},
-    ast::{MySelect, Source},
+    ast::{MySelect, Source, SourceKind},
     db::TableRow,
+    dummy::{Dummy, OptionalDummy},
+    generate::TableInfo,
     hash,
     migrate::NoTable,
+    scope::Scope,
     Table,
 };
 
@@ -22,42 +30,66 @@ pub struct ValueBuilder<'x> {
 }
 
 impl<'x> ValueBuilder<'x> {
+    /// Build an expression or table and return the extra sources that it uses.
+    pub(crate) fn track<R>(self, f: impl FnOnce(Self) -> R) -> (R, Vec<MyAlias>) {
+        let outer = self.inner.used.take();
+        let expr = f(self);
+        let used = self.inner.used.replace(outer);
+        self.inner.used.borrow_mut().extend(&used);
+        (expr, used)
+    }
+
+    /// Get the alias of the source, the sources used by the conditions are its dependencies.
+    fn get_source(self, kind: SourceKind, conds: Vec<(Field, SimpleExpr)>) -> MyAlias {
+        let source = Source {
+            kind,
+            conds,
+            deps: self.inner.used.borrow().clone(),
+        };
+        let new_alias = || self.inner.scope.new_alias();
+        let alias = *self.inner.extra.get_or_init(source, new_alias);
+        self.inner.used.borrow_mut().push(alias);
+        alias
+    }
+
     pub(crate) fn get_aggr(
         self,
         aggr: SelectStatement,
         conds: Vec<(Field, SimpleExpr)>,
     ) -> MyAlias {
-        let source = Source {
-            kind: crate::ast::SourceKind::Aggregate(aggr),
-            conds,
-        };
-        let new_alias = || self.inner.scope.new_alias();
-        *self.inner.extra.get_or_init(source, new_alias)
+        self.get_source(SourceKind::Aggregate(aggr), conds)
+    }
+
+    pub(crate) fn get_window(
+        self,
+        window: SelectStatement,
+        conds: Vec<(Field, SimpleExpr)>,
+    ) -> MyAlias {
+        self.get_source(SourceKind::Window(window), conds)
     }
 
     pub(crate) fn get_join<T: Table>(self, expr: SimpleExpr) -> MyAlias {
-        let source = Source {
-            kind: crate::ast::SourceKind::Implicit(T::NAME.to_owned()),
-            conds: vec![(Field::Str(T::ID), expr)],
-        };
-        let new_alias = || self.inner.scope.new_alias();
-        *self.inner.extra.get_or_init(source, new_alias)
+        let kind = SourceKind::Implicit(T::NAME.to_owned());
+        let conds = with_scope::<T>(vec![(Field::Str(T::ID), expr)]);
+        self.get_source(kind, conds)
     }
 
-    pub fn get_unique(
-        self,
-        table: &'static str,
-        conds: Vec<(&'static str, SimpleExpr)>,
-    ) -> SimpleExpr {
-        let source = Source {
-            kind: crate::ast::SourceKind::Implicit(table.to_owned()),
-            conds: conds.into_iter().map(|x| (Field::Str(x.0), x.1)).collect(),
-        };
+    pub fn get_unique<T: Table>(self, conds: Vec<(&'static str, SimpleExpr)>) -> SimpleExpr {
+        let kind = SourceKind::Implicit(T::NAME.to_owned());
+        let conds = conds.into_iter().map(|x| (Field::Str(x.0), x.1)).collect();
+        let table = self.get_source(kind, with_scope::<T>(conds));
+        Expr::col((table, Alias::new("id"))).into()
+    }
+}
 
-        let new_alias = || self.inner.scope.new_alias();
-        let table = self.inner.extra.get_or_init(source, new_alias);
-        Expr::col((*table, Alias::new("id"))).into()
+/// Add the condition that the row of `T` belongs to the tenant of a scoped transaction.
+///
+/// Rows of other tenants are not found, so their columns are null.
+fn with_scope<T: Table>(mut conds: Vec<(Field, SimpleExpr)>) -> Vec<(Field, SimpleExpr)> {
+    if let Some((col, tenant)) = Scope::building().and_then(Scope::join_filter::<T>) {
+        conds.push((Field::Str(col), Expr::val(tenant).into()));
     }
+    conds
 }
 
 pub trait NumTyp: MyTyp + Clone + Copy {
@@ -85,6 +117,7 @@ impl EqTyp for i64 {}
 impl EqTyp for f64 {}
 impl EqTyp for bool {}
 impl<T: Table> EqTyp for T {}
+impl<T: Table> EqTyp for crate::PublicId<T> {}
 
 /// Typ does not depend on scope, so it gets its own trait
 pub trait Typed {
@@ -131,6 +164,85 @@ impl<'t, S, T: NumTyp> Column<'t, S, T> {
     pub fn lt(&self, rhs: impl IntoColumn<'t, S, Typ = T>) -> Column<'t, S, bool> {
         Lt(self, rhs).into_column()
     }
+
+    /// The absolute value of the column.
+    pub fn abs(&self) -> Column<'t, S, T> {
+        Abs(self).into_column()
+    }
+
+    /// Convert the number to its text representation.
+    pub fn as_text(&self) -> Column<'t, S, String> {
+        AsText(self).into_column()
+    }
+
+    /// The smallest of two columns.
+    pub fn min(&self, rhs: impl IntoColumn<'t, S, Typ = T>) -> Column<'t, S, T> {
+        Min(self, rhs).into_column()
+    }
+
+    /// The largest of two columns.
+    pub fn max(&self, rhs: impl IntoColumn<'t, S, Typ = T>) -> Column<'t, S, T> {
+        Max(self, rhs).into_column()
+    }
+}
+
+impl<'t, S> Column<'t, S, String> {
+    /// The number of characters in the string.
+    pub fn length(&self) -> Column<'t, S, i64> {
+        Length(self).into_column()
+    }
+
+    /// Convert the ASCII characters of the string to lower case.
+    pub fn lower(&self) -> Column<'t, S, String> {
+        Lower(self).into_column()
+    }
+
+    /// Convert the ASCII characters of the string to upper case.
+    pub fn upper(&self) -> Column<'t, S, String> {
+        Upper(self).into_column()
+    }
+
+    /// Remove the spaces at the start and the end of the string.
+    pub fn trim(&self) -> Column<'t, S, String> {
+        Trim(self).into_column()
+    }
+
+    /// The part of the string that starts at character `start` and is at most `len` characters long.
+    ///
+    /// The first character of the string has index 1, like in sql.
+    pub fn substr(
+        &self,
+        start: impl IntoColumn<'t, S, Typ = i64>,
+        len: impl IntoColumn<'t, S, Typ = i64>,
+    ) -> Column<'t, S, String> {
+        Substr(self, start, len).into_column()
+    }
+
+    /// Replace every occurrence of `from` in the string with `to`.
+    pub fn replace(
+        &self,
+        from: impl IntoColumn<'t, S, Typ = String>,
+        to: impl IntoColumn<'t, S, Typ = String>,
+    ) -> Column<'t, S, String> {
+        Replace(self, from, to).into_column()
+    }
+
+    /// The index of the first occurrence of `needle` in the string, or 0 if it does not occur.
+    ///
+    /// The first character of the string has index 1, like in sql.
+    pub fn instr(&self, needle: impl IntoColumn<'t, S, Typ = String>) -> Column<'t, S, i64> {
+        Instr(self, needle).into_column()
+    }
+
+    /// Use the collation with name `collation` to compare and sort the string.
+    ///
+    /// Results are sorted by the collation when this column is retrieved,
+    /// and it can be used in [crate::args::Window::order_by] and comparisons like [Column::lt].
+    /// The collation can be builtin, like `NOCASE`, or registered with [crate::migration::Config::collation].
+    /// The query fails if the collation does not exist.
+    pub fn collate(&self, collation: &'static str) -> Column<'t, S, String> {
+        Collate(self, collation).into_column()
+    }
 }
 
 impl<'t, S, T: EqTyp + 't> Column<'t, S, T> {
@@ -138,6 +250,40 @@ impl<'t, S, T: EqTyp + 't> Column<'t, S, T> {
     pub fn eq(&self, rhs: impl IntoColumn<'t, S, Typ = T>) -> Column<'t, S, bool> {
         Eq(self, rhs).into_column()
     }
+
+    /// Check whether the column is equal to one of the values.
+    ///
+    /// The values are added to the query as parameters, so this works well with a list
+    /// of values that was collected in rust:
+    /// ```
+    /// # use rust_query::Column;
+    /// # fn filter<'t, S>(genre_id: Column<'t, S, i64>, genres: Vec<i64>) -> Column<'t, S, bool> {
+    /// genre_id.is_in(genres)
+    /// # }
+    /// ```
+    pub fn is_in(
+        &self,
+        values: impl IntoIterator<Item = impl IntoColumn<'t, S, Typ = T>>,
+    ) -> Column<'t, S, bool> {
+        IsIn(self, values.into_iter().collect()).into_column()
+    }
+
+    /// Check whether the column is equal to one of the values returned by a sub-query.
+    ///
+    /// The sub-query can use [Aggregate::filter_on] to depend on values from the outer query.
+    pub fn is_in_query(
+        &self,
+        f: impl for<'a> FnOnce(&mut Aggregate<'t, 'a, S>) -> Column<'a, S, T>,
+    ) -> Column<'t, S, bool>
+    where
+        S: 't,
+    {
+        aggregate(|rows| {
+            let val = f(rows);
+            rows.filter_on(val, self);
+            rows.exists()
+        })
+    }
 }
 
 impl<'t, S> Column<'t, S, bool> {
@@ -155,6 +301,25 @@ impl<'t, S> Column<'t, S, bool> {
     pub fn or(&self, rhs: impl IntoColumn<'t, S, Typ = bool>) -> Column<'t, S, bool> {
         Or(self, rhs).into_column()
     }
+
+    /// Start a conditional expression that uses this value if the column is true.
+    ///
+    /// More conditions can be added with [Case::when] and the result is finished with [Case::else_].
+    /// This is compiled to an sql `CASE` expression.
+    /// ```
+    /// # use rust_query::Column;
+    /// # fn bucket<'t, S: 't>(len: Column<'t, S, i64>) -> Column<'t, S, String> {
+    /// len.lt(60).then("short").when(len.lt(300), "normal").else_("long")
+    /// # }
+    /// ```
+    pub fn then<T: 't>(&self, val: impl IntoColumn<'t, S, Typ = T>) -> Case<'t, S, T>
+    where
+        S: 't,
+    {
+        Case {
+            branches: vec![(self.clone(), val.into_column())],
+        }
+    }
 }
 
 impl<'t, S, Typ: 't> Column<'t, S, Option<Typ>> {
@@ -170,6 +335,158 @@ impl<'t, S, Typ: 't> Column<'t, S, Option<Typ>> {
     pub fn is_some(&self) -> Column<'t, S, bool> {
         IsNotNull(self).into_column()
     }
+
+    /// Check that the column is [None].
+    pub fn is_none(&self) -> Column<'t, S, bool> {
+        IsNull(self).into_column()
+    }
+
+    /// A column that is always [None].
+    ///
+    /// This is useful to set optional columns to `NULL`, because [None] would need a type annotation.
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, Column, LocalClient, Table};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     Employee {
+    /// #         first_name: String,
+    /// #         last_name: String,
+    /// #         reports_to: Option<Employee>,
+    /// #     },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// # let mut txn = client.transaction_mut(&db);
+    /// # let employee = txn.insert(Employee { first_name: "Ada", last_name: "Lovelace", reports_to: None::<rust_query::TableRow<Employee>> });
+    /// txn.try_update(employee, Employee {
+    ///     reports_to: Column::none(),
+    ///     ..Employee::dummy(employee)
+    /// })
+    /// # .unwrap();
+    /// ```
+    pub fn none() -> Self
+    where
+        S: 't,
+        Typ: MyTyp<Sql: Nullable>,
+    {
+        None::<Column<'t, S, Typ>>.into_column()
+    }
+
+    /// Use the value in the column to compute a new column if it is [Some].
+    ///
+    /// The result is [None] if the column is [None].
+    /// This can be used to follow optional foreign keys:
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient, Table};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     Employee {
+    /// #         first_name: String,
+    /// #         last_name: String,
+    /// #         reports_to: Option<Employee>,
+    /// #     },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// # let txn = client.transaction(&db);
+    /// # txn.query(|rows| {
+    /// let employee = Employee::join(rows);
+    /// let boss_name = employee.reports_to().map(|boss| boss.last_name());
+    /// rows.into_vec((employee.last_name(), boss_name))
+    /// # });
+    /// ```
+    pub fn map<T: MyTyp<Sql: Nullable>>(
+        &self,
+        f: impl FnOnce(Column<'t, S, Typ>) -> Column<'t, S, T>,
+    ) -> Column<'t, S, Option<T>>
+    where
+        S: 't,
+    {
+        let val = f(Assume(self).into_column());
+        self.is_some()
+            .then(Some(val))
+            .else_(None::<Column<'t, S, T>>)
+    }
+
+    /// Use the value in the column to compute a new optional column if it is [Some].
+    ///
+    /// The result is [None] if either column is [None].
+    /// This can be used to follow a chain of optional foreign keys,
+    /// the tables are joined with `LEFT JOIN` so rows with a missing foreign key are kept:
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient, Table};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     Employee {
+    /// #         first_name: String,
+    /// #         last_name: String,
+    /// #         reports_to: Option<Employee>,
+    /// #     },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// # let txn = client.transaction(&db);
+    /// # txn.query(|rows| {
+    /// let employee = Employee::join(rows);
+    /// let grand_boss_name = employee
+    ///     .reports_to()
+    ///     .and_then(|boss| boss.reports_to())
+    ///     .map(|grand_boss| grand_boss.last_name());
+    /// rows.into_vec((employee.last_name(), grand_boss_name))
+    /// # });
+    /// ```
+    pub fn and_then<T: MyTyp<Sql: Nullable>>(
+        &self,
+        f: impl FnOnce(Column<'t, S, Typ>) -> Column<'t, S, Option<T>>,
+    ) -> Column<'t, S, Option<T>>
+    where
+        S: 't,
+    {
+        let val = f(Assume(self).into_column());
+        self.is_some().then(val).else_(None::<Column<'t, S, T>>)
+    }
+
+    /// Retrieve a [Dummy] computed from the value in the column if it is [Some].
+    ///
+    /// The result is [None] if the column is [None].
+    /// This can be used to retrieve multiple values through an optional foreign key:
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient, Table};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     Employee {
+    /// #         first_name: String,
+    /// #         last_name: String,
+    /// #         reports_to: Option<Employee>,
+    /// #     },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// # let txn = client.transaction(&db);
+    /// # txn.query(|rows| {
+    /// let employee = Employee::join(rows);
+    /// let boss = employee
+    ///     .reports_to()
+    ///     .optional(|boss| (boss.first_name(), boss.last_name()));
+    /// rows.into_vec((employee.last_name(), boss))
+    /// # });
+    /// ```
+    pub fn optional<'a, D: Dummy<'t, 'a, S>>(
+        &self,
+        f: impl FnOnce(Column<'t, S, Typ>) -> D,
+    ) -> impl Dummy<'t, 'a, S, Out = Option<D::Out>>
+    where
+        S: 't,
+    {
+        OptionalDummy {
+            is_some: self.is_some(),
+            dummy: f(Assume(self).into_column()),
+        }
+    }
 }
 
 impl<'t, S> Column<'t, S, i64> {
@@ -179,6 +496,28 @@ impl<'t, S> Column<'t, S, i64> {
     }
 }
 
+impl<'t, S> Column<'t, S, f64> {
+    /// Convert the [f64] column to [i64] type, the value is truncated towards zero.
+    pub fn as_int(&self) -> Column<'t, S, i64> {
+        AsInt(self).into_column()
+    }
+
+    /// Round to the nearest integer, rounding half-way cases away from zero.
+    pub fn round(&self) -> Column<'t, S, f64> {
+        Round(self).into_column()
+    }
+
+    /// Round to the largest integer less than or equal to the value.
+    pub fn floor(&self) -> Column<'t, S, f64> {
+        Floor(self).into_column()
+    }
+
+    /// Round to the smallest integer greater than or equal to the value.
+    pub fn ceil(&self) -> Column<'t, S, f64> {
+        Ceil(self).into_column()
+    }
+}
+
 impl<T: Typed<Typ = X>, X: MyTyp<Sql: Nullable>> Typed for Option<T> {
     type Typ = Option<T::Typ>;
 
@@ -320,6 +659,8 @@ pub trait MyTyp: 'static {
     #[doc(hidden)]
     const FK: Option<(&'static str, &'static str)> = None;
     #[doc(hidden)]
+    const TABLE: Option<fn() -> TableInfo> = None;
+    #[doc(hidden)]
     type Out<'t>: FromSql;
     #[doc(hidden)]
     type Sql;
@@ -328,6 +669,7 @@ pub trait MyTyp: 'static {
 impl<T: Table> MyTyp for T {
     const TYP: hash::ColumnType = hash::ColumnType::Integer;
     const FK: Option<(&'static str, &'static str)> = Some((T::NAME, T::ID));
+    const TABLE: Option<fn() -> TableInfo> = Some(TableInfo::of::<T>);
     type Out<'t> = TableRow<'t, Self>;
     type Sql = i64;
 }
@@ -360,6 +702,7 @@ impl<T: MyTyp> MyTyp for Option<T> {
     const TYP: hash::ColumnType = T::TYP;
     const NULLABLE: bool = true;
     const FK: Option<(&'static str, &'static str)> = T::FK;
+    const TABLE: Option<fn() -> TableInfo> = T::TABLE;
     type Out<'t> = Option<T::Out<'t>>;
     type Sql = T::Sql;
 }
@@ -1,6 +1,8 @@
-use sea_query::{Alias, Expr, SimpleExpr};
+use std::marker::PhantomData;
 
-use super::{IntoColumn, NumTyp, Typed, ValueBuilder};
+use sea_query::{Alias, CaseStatement, Expr, Func, SimpleExpr};
+
+use super::{Column, IntoColumn, NumTyp, Typed, ValueBuilder};
 
 #[derive(Clone, Copy)]
 pub struct Add<A, B>(pub(crate) A, pub(crate) B);
@@ -29,6 +31,29 @@ macro_rules! unop {
     };
 }
 
+macro_rules! ternop {
+    ($name:ident) => {
+        impl<'t, S, A: IntoColumn<'t, S>, B: IntoColumn<'t, S>, C: IntoColumn<'t, S>>
+            IntoColumn<'t, S> for $name<A, B, C>
+        {
+            type Owned = $name<A::Owned, B::Owned, C::Owned>;
+
+            fn into_owned(self) -> Self::Owned {
+                $name(
+                    self.0.into_owned(),
+                    self.1.into_owned(),
+                    self.2.into_owned(),
+                )
+            }
+        }
+    };
+}
+
+/// Call the sqlite function with this name.
+fn func(name: &str, args: impl IntoIterator<Item = SimpleExpr>) -> SimpleExpr {
+    Func::cust(Alias::new(name)).args(args).into()
+}
+
 impl<A: Typed, B: Typed> Typed for Add<A, B> {
     type Typ = A::Typ;
     fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
@@ -82,6 +107,25 @@ impl<A: Typed, B: Typed> Typed for Eq<A, B> {
 }
 binop! {Eq}
 
+#[derive(Clone)]
+pub struct IsIn<A, B>(pub(crate) A, pub(crate) Vec<B>);
+
+impl<A: Typed, B: Typed> Typed for IsIn<A, B> {
+    type Typ = bool;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        let values = self.1.iter().map(|x| x.build_expr(b));
+        Expr::expr(self.0.build_expr(b)).is_in(values)
+    }
+}
+impl<'t, S, A: IntoColumn<'t, S>, B: IntoColumn<'t, S>> IntoColumn<'t, S> for IsIn<A, B> {
+    type Owned = IsIn<A::Owned, B::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        let values = self.1.into_iter().map(IntoColumn::into_owned);
+        IsIn(self.0.into_owned(), values.collect())
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct UnwrapOr<A, B>(pub(crate) A, pub(crate) B);
 
@@ -115,6 +159,17 @@ impl<A: Typed> Typed for IsNotNull<A> {
 }
 unop! {IsNotNull}
 
+#[derive(Clone, Copy)]
+pub struct IsNull<A>(pub(crate) A);
+
+impl<A: Typed> Typed for IsNull<A> {
+    type Typ = bool;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        Expr::expr(self.0.build_expr(b)).is_null()
+    }
+}
+unop! {IsNull}
+
 #[derive(Clone, Copy)]
 pub struct Assume<A>(pub(crate) A);
 
@@ -142,6 +197,214 @@ impl<A: Typed> Typed for AsFloat<A> {
 }
 unop! {AsFloat}
 
+#[derive(Clone, Copy)]
+pub struct AsInt<A>(pub(crate) A);
+
+impl<A: Typed> Typed for AsInt<A> {
+    type Typ = i64;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        self.0.build_expr(b).cast_as(Alias::new("integer"))
+    }
+}
+unop! {AsInt}
+
+#[derive(Clone, Copy)]
+pub struct AsText<A>(pub(crate) A);
+
+impl<A: Typed> Typed for AsText<A> {
+    type Typ = String;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        self.0.build_expr(b).cast_as(Alias::new("text"))
+    }
+}
+unop! {AsText}
+
+#[derive(Clone, Copy)]
+pub struct Abs<A>(pub(crate) A);
+
+impl<A: Typed> Typed for Abs<A> {
+    type Typ = A::Typ;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        Func::abs(self.0.build_expr(b)).into()
+    }
+}
+unop! {Abs}
+
+#[derive(Clone, Copy)]
+pub struct Round<A>(pub(crate) A);
+
+impl<A: Typed> Typed for Round<A> {
+    type Typ = f64;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        Func::round(self.0.build_expr(b)).into()
+    }
+}
+unop! {Round}
+
+// `floor` and `ceil` are not available in every sqlite build,
+// so they are computed from the value that is truncated towards zero.
+
+#[derive(Clone, Copy)]
+pub struct Floor<A>(pub(crate) A);
+
+impl<A: Typed> Typed for Floor<A> {
+    type Typ = f64;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        let expr = self.0.build_expr(b);
+        let int = expr.clone().cast_as(Alias::new("integer"));
+        let below = Expr::expr(expr).lt(int.clone());
+        int.sub(below).cast_as(Alias::new("real"))
+    }
+}
+unop! {Floor}
+
+#[derive(Clone, Copy)]
+pub struct Ceil<A>(pub(crate) A);
+
+impl<A: Typed> Typed for Ceil<A> {
+    type Typ = f64;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        let expr = self.0.build_expr(b);
+        let int = expr.clone().cast_as(Alias::new("integer"));
+        let above = Expr::expr(expr).gt(int.clone());
+        int.add(above).cast_as(Alias::new("real"))
+    }
+}
+unop! {Ceil}
+
+#[derive(Clone, Copy)]
+pub struct Min<A, B>(pub(crate) A, pub(crate) B);
+
+impl<A: Typed, B: Typed> Typed for Min<A, B> {
+    type Typ = A::Typ;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        func("min", [self.0.build_expr(b), self.1.build_expr(b)])
+    }
+}
+binop! {Min}
+
+#[derive(Clone, Copy)]
+pub struct Max<A, B>(pub(crate) A, pub(crate) B);
+
+impl<A: Typed, B: Typed> Typed for Max<A, B> {
+    type Typ = A::Typ;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        func("max", [self.0.build_expr(b), self.1.build_expr(b)])
+    }
+}
+binop! {Max}
+
+#[derive(Clone, Copy)]
+pub struct Length<A>(pub(crate) A);
+
+impl<A: Typed> Typed for Length<A> {
+    type Typ = i64;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        func("length", [self.0.build_expr(b)])
+    }
+}
+unop! {Length}
+
+#[derive(Clone, Copy)]
+pub struct Lower<A>(pub(crate) A);
+
+impl<A: Typed> Typed for Lower<A> {
+    type Typ = A::Typ;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        func("lower", [self.0.build_expr(b)])
+    }
+}
+unop! {Lower}
+
+#[derive(Clone, Copy)]
+pub struct Upper<A>(pub(crate) A);
+
+impl<A: Typed> Typed for Upper<A> {
+    type Typ = A::Typ;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        func("upper", [self.0.build_expr(b)])
+    }
+}
+unop! {Upper}
+
+#[derive(Clone, Copy)]
+pub struct Trim<A>(pub(crate) A);
+
+impl<A: Typed> Typed for Trim<A> {
+    type Typ = A::Typ;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        func("trim", [self.0.build_expr(b)])
+    }
+}
+unop! {Trim}
+
+#[derive(Clone, Copy)]
+pub struct Instr<A, B>(pub(crate) A, pub(crate) B);
+
+impl<A: Typed, B: Typed> Typed for Instr<A, B> {
+    type Typ = i64;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        func("instr", [self.0.build_expr(b), self.1.build_expr(b)])
+    }
+}
+binop! {Instr}
+
+#[derive(Clone, Copy)]
+pub struct Substr<A, B, C>(pub(crate) A, pub(crate) B, pub(crate) C);
+
+impl<A: Typed, B: Typed, C: Typed> Typed for Substr<A, B, C> {
+    type Typ = A::Typ;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        let args = [
+            self.0.build_expr(b),
+            self.1.build_expr(b),
+            self.2.build_expr(b),
+        ];
+        func("substr", args)
+    }
+}
+ternop! {Substr}
+
+#[derive(Clone, Copy)]
+pub struct Replace<A, B, C>(pub(crate) A, pub(crate) B, pub(crate) C);
+
+impl<A: Typed, B: Typed, C: Typed> Typed for Replace<A, B, C> {
+    type Typ = A::Typ;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        let args = [
+            self.0.build_expr(b),
+            self.1.build_expr(b),
+            self.2.build_expr(b),
+        ];
+        func("replace", args)
+    }
+}
+ternop! {Replace}
+
+/// Quote the name of a collation for sql, a `"` in the name is escaped as `""`.
+pub(crate) fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[derive(Clone)]
+pub struct Collate<A>(pub(crate) A, pub(crate) &'static str);
+
+impl<A: Typed> Typed for Collate<A> {
+    type Typ = A::Typ;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        let sql = format!("(?) COLLATE {}", quote_ident(self.1));
+        Expr::cust_with_expr(sql, self.0.build_expr(b))
+    }
+}
+
+impl<'t, S, A: IntoColumn<'t, S>> IntoColumn<'t, S> for Collate<A> {
+    type Owned = Collate<A::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        Collate(self.0.into_owned(), self.1)
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct Const<A>(pub(crate) A);
 
@@ -158,3 +421,79 @@ impl<'t, S, A: NumTyp> IntoColumn<'t, S> for Const<A> {
         self
     }
 }
+
+/// This is the type used to build conditional expressions, see [Column::then].
+///
+/// It is converted to a [Column] with [Case::else_].
+pub struct Case<'t, S, T> {
+    pub(crate) branches: Vec<(Column<'t, S, bool>, Column<'t, S, T>)>,
+}
+
+impl<'t, S: 't, T: 't> Case<'t, S, T> {
+    /// Use this value if the condition is true and all previous conditions are false.
+    pub fn when(
+        mut self,
+        cond: impl IntoColumn<'t, S, Typ = bool>,
+        val: impl IntoColumn<'t, S, Typ = T>,
+    ) -> Self {
+        self.branches.push((cond.into_column(), val.into_column()));
+        self
+    }
+
+    /// Use this value if all conditions are false.
+    pub fn else_(self, val: impl IntoColumn<'t, S, Typ = T>) -> Column<'t, S, T> {
+        CaseWhen(self.branches, val.into_column()).into_column()
+    }
+}
+
+pub struct CaseWhen<'t, S, T>(
+    Vec<(Column<'t, S, bool>, Column<'t, S, T>)>,
+    Column<'t, S, T>,
+);
+
+impl<S, T> Clone for CaseWhen<'_, S, T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone(), self.1.clone())
+    }
+}
+
+impl<S, T> Typed for CaseWhen<'_, S, T> {
+    type Typ = T;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        let mut case = CaseStatement::new();
+        for (cond, val) in &self.0 {
+            case = case.case(cond.build_expr(b), val.build_expr(b));
+        }
+        case.finally(self.1.build_expr(b)).into()
+    }
+}
+impl<'t, S: 't, T: 't> IntoColumn<'t, S> for CaseWhen<'t, S, T> {
+    type Owned = Self;
+
+    fn into_owned(self) -> Self::Owned {
+        self
+    }
+}
+
+/// An expression that was already built for a specific query.
+pub struct Prebuilt<T>(pub(crate) SimpleExpr, pub(crate) PhantomData<T>);
+
+impl<T> Clone for Prebuilt<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone(), PhantomData)
+    }
+}
+
+impl<T> Typed for Prebuilt<T> {
+    type Typ = T;
+    fn build_expr(&self, _b: ValueBuilder) -> SimpleExpr {
+        self.0.clone()
+    }
+}
+impl<'t, S, T: 'static> IntoColumn<'t, S> for Prebuilt<T> {
+    type Owned = Self;
+
+    fn into_owned(self) -> Self::Owned {
+        self
+    }
+}
@@ -0,0 +1,137 @@
+use std::time::{Duration, Instant};
+
+use rusqlite::OptionalExtension;
+
+use crate::{
+    error::unwrap_db, Database, LocalClient, ReadOnlyDatabase, Transaction, TransactionMut,
+};
+
+/// Name of the table that stores the version counter.
+pub(crate) const VERSION_TABLE: &str = "_version";
+
+/// A version of the database that was written by [TransactionMut::version].
+///
+/// This can be used to wait until a write is visible to a connection, see [LocalClient::transaction_at_least].
+/// It can be sent to other processes with [Version::as_i64].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version(i64);
+
+impl Version {
+    /// Get the number of the version, this can be turned back into a [Version] with [Version::from_i64].
+    pub fn as_i64(self) -> i64 {
+        self.0
+    }
+
+    /// Create a [Version] from a number that was returned by [Version::as_i64].
+    pub fn from_i64(version: i64) -> Self {
+        Self(version)
+    }
+}
+
+/// Create the version table, this is done when the database is migrated.
+pub(crate) fn install(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS \"{VERSION_TABLE}\" (
+            \"id\" integer PRIMARY KEY,
+            \"version\" integer NOT NULL
+        );"
+    ))
+}
+
+impl<S> Transaction<'_, S> {
+    /// The latest [Version] that is visible in this transaction.
+    ///
+    /// This is the version of the last committed [TransactionMut::version] call.
+    pub fn version(&self) -> Version {
+        let conn = self.transaction();
+        let exists = unwrap_db(
+            conn.query_row(
+                "SELECT 1 FROM sqlite_schema WHERE type = 'table' AND name = ?",
+                [VERSION_TABLE],
+                |_| Ok(()),
+            )
+            .optional(),
+        );
+        if exists.is_none() {
+            // the database was not migrated by a version that has the version table yet
+            return Version(0);
+        }
+        let sql = format!("SELECT coalesce(max(\"version\"), 0) FROM \"{VERSION_TABLE}\"");
+        Version(unwrap_db(conn.query_row(&sql, [], |r| r.get(0))))
+    }
+}
+
+impl<S> TransactionMut<'_, S> {
+    /// Create a new [Version] that contains the changes of this transaction.
+    ///
+    /// The version is visible to other transactions after [TransactionMut::commit].
+    /// It can be returned to a client that reads from another connection or a replica of the database
+    /// to make sure that it reads its own writes, using [LocalClient::transaction_at_least].
+    /// ```
+    /// # use std::time::Duration;
+    /// # use rust_query::{migration::{schema, Config}, LocalClient};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     Post { title: String },
+    /// #     Comment { post: Post, text: String },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// # let mut txn = client.transaction_mut(&db);
+    /// # let post = txn.insert(Post { title: "hello" });
+    /// # let text = "hi";
+    /// txn.insert(Comment { post, text });
+    /// let version = txn.version();
+    /// txn.commit();
+    /// // later, on another connection
+    /// let txn = client.transaction_at_least(&db, version, Duration::from_secs(1)).unwrap();
+    /// ```
+    pub fn version(&mut self) -> Version {
+        let sql = format!(
+            "INSERT INTO \"{VERSION_TABLE}\" (\"id\", \"version\") VALUES (0, 1)
+            ON CONFLICT (\"id\") DO UPDATE SET \"version\" = \"version\" + 1
+            RETURNING \"version\""
+        );
+        let conn = self.transaction();
+        Version(unwrap_db(conn.query_row(&sql, [], |r| r.get(0))))
+    }
+}
+
+impl LocalClient {
+    /// Create a [Transaction] that includes the changes of `version`.
+    ///
+    /// New transactions are started until one of them sees the version, for example because
+    /// another process is still copying the changes to a replica of the database file.
+    /// Returns [None] if the version is still not visible when `timeout` has passed.
+    pub fn transaction_at_least<S>(
+        &mut self,
+        db: &Database<S>,
+        version: Version,
+        timeout: Duration,
+    ) -> Option<Transaction<'_, S>> {
+        let start = Instant::now();
+        let mut delay = Duration::from_millis(1);
+        while self.transaction(db).version() < version {
+            if start.elapsed() >= timeout {
+                return None;
+            }
+            std::thread::sleep(delay.min(timeout.saturating_sub(start.elapsed())));
+            delay = (delay * 2).min(Duration::from_millis(100));
+        }
+        // versions only increase, so this transaction also sees the version
+        Some(self.transaction(db))
+    }
+
+    /// Create a [Transaction] for a [ReadOnlyDatabase] that includes the changes of `version`.
+    ///
+    /// This works the same as [LocalClient::transaction_at_least].
+    pub fn transaction_read_only_at_least<S>(
+        &mut self,
+        db: &ReadOnlyDatabase<S>,
+        version: Version,
+        timeout: Duration,
+    ) -> Option<Transaction<'_, S>> {
+        self.transaction_at_least(&db.inner, version, timeout)
+    }
+}
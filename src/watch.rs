@@ -0,0 +1,127 @@
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use rusqlite::Connection;
+
+use crate::{Database, Table};
+
+type Callback = Arc<dyn Fn() + Send + Sync>;
+
+/// Keeps track of the tables that are changed and the callbacks registered with [Database::watch].
+#[derive(Default)]
+pub(crate) struct Watchers {
+    changes: Mutex<Changes>,
+    callbacks: Mutex<Vec<(&'static str, Callback)>>,
+    // number of rows changed by committed transactions
+    total: AtomicU64,
+}
+
+#[derive(Default)]
+struct Changes {
+    // tables changed by the transaction that is in progress
+    pending: HashSet<String>,
+    // tables changed by transactions that are committed, but not notified yet
+    committed: HashSet<String>,
+}
+
+impl Watchers {
+    /// Install hooks on the connection to record which tables are changed.
+    ///
+    /// Only one connection can write at a time, so all connections can share the same [Changes].
+    pub fn install(self: &Arc<Self>, conn: &Connection) {
+        let this = self.clone();
+        conn.update_hook(Some(move |_, _: &str, table: &str, _| {
+            let mut changes = this.changes.lock().unwrap();
+            if !changes.pending.contains(table) {
+                changes.pending.insert(table.to_owned());
+            }
+        }));
+        let this = self.clone();
+        conn.commit_hook(Some(move || {
+            let changes = &mut *this.changes.lock().unwrap();
+            changes.committed.extend(changes.pending.drain());
+            // returning false allows the commit to continue
+            false
+        }));
+        let this = self.clone();
+        conn.rollback_hook(Some(move || {
+            this.changes.lock().unwrap().pending.clear();
+        }));
+    }
+
+    /// Count the rows that were changed on `conn`, this is called right before the transaction is committed.
+    ///
+    /// Every transaction uses a new connection, so all changes of the connection are part of the transaction.
+    pub fn count_changes(&self, conn: &Connection) {
+        let rows: u64 = conn
+            .query_row("SELECT total_changes()", [], |row| row.get(0))
+            .unwrap();
+        self.total.fetch_add(rows, Ordering::Relaxed);
+    }
+
+    /// Call the callbacks of all tables that were changed by committed transactions.
+    pub fn notify(&self) {
+        let committed = std::mem::take(&mut self.changes.lock().unwrap().committed);
+        if committed.is_empty() {
+            return;
+        }
+        // the lock is released before calling, so that callbacks can add more callbacks
+        let callbacks: Vec<_> = self
+            .callbacks
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(table, _)| committed.contains(*table))
+            .map(|(_, callback)| callback.clone())
+            .collect();
+        for callback in callbacks {
+            callback()
+        }
+    }
+}
+
+impl<S> Database<S> {
+    /// Call `callback` every time that a [crate::TransactionMut] which changed table `T` is committed.
+    ///
+    /// The callback is called on the thread that committed the transaction, after the commit is finished.
+    /// Changes made by other processes are not noticed.
+    /// ```
+    /// # use std::sync::atomic::{AtomicBool, Ordering};
+    /// # use std::sync::Arc;
+    /// # use rust_query::{migration::{schema, Config}, LocalClient};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     User { name: String },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// let changed = Arc::new(AtomicBool::new(false));
+    /// let flag = changed.clone();
+    /// db.watch::<User>(move || flag.store(true, Ordering::Relaxed));
+    ///
+    /// let mut txn = client.transaction_mut(&db);
+    /// txn.insert(User { name: "alice".to_owned() });
+    /// txn.commit();
+    /// assert!(changed.load(Ordering::Relaxed));
+    /// ```
+    pub fn watch<T: Table<Schema = S>>(&self, callback: impl Fn() + Send + Sync + 'static) {
+        let mut callbacks = self.watchers.callbacks.lock().unwrap();
+        callbacks.push((T::NAME, Arc::new(callback)));
+    }
+
+    /// The number of rows that were inserted, updated or deleted by committed transactions.
+    ///
+    /// This includes the rows that were copied by migrations, but not changes made by other processes
+    /// or by other [Database] instances.
+    /// Like [`total_changes()`](https://www.sqlite.org/lang_corefunc.html#total_changes) in sqlite,
+    /// rows that are changed by triggers and foreign key actions are not counted.
+    pub fn total_changes(&self) -> u64 {
+        self.watchers.total.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,168 @@
+use std::{marker::PhantomData, rc::Rc};
+
+use sea_query::{
+    Alias, Expr, Func, Order, OverStatement, SelectStatement, SimpleExpr, WindowStatement,
+};
+
+use crate::{
+    alias::{Field, MyAlias},
+    rows::Rows,
+    value::{IntoColumn, MyTyp, Typed, ValueBuilder},
+    Column,
+};
+
+/// This is the type used to calculate window functions, see [Rows::window].
+///
+/// Window functions calculate a value for every row based on other rows in the same partition.
+/// The partition and order within the partition are configured with [Window::partition_by] and [Window::order_by].
+pub struct Window<'a, 'inner, S> {
+    rows: &'a Rows<'inner, S>,
+    window: WindowStatement,
+    // the extra sources that are used by the window
+    deps: Vec<MyAlias>,
+}
+
+impl<'inner, S> Rows<'inner, S> {
+    /// Start a window over the current rows.
+    ///
+    /// The window only sees the rows as they are at this point.
+    /// Joins and filters that are added later do not change the results of the window functions,
+    /// which means that it is possible to filter on the result of a window function.
+    ///
+    /// ```
+    /// # use rust_query::{Rows, Column};
+    /// # fn top_two<'t, S>(rows: &mut Rows<'t, S>, group: Column<'t, S, i64>, score: Column<'t, S, i64>) {
+    /// let rank = rows.window().partition_by(group).order_by_desc(score).row_number();
+    /// rows.filter(rank.lt(3));
+    /// # }
+    /// ```
+    pub fn window(&self) -> Window<'_, 'inner, S> {
+        Window {
+            rows: self,
+            window: WindowStatement::new(),
+            deps: vec![],
+        }
+    }
+}
+
+impl<'inner, S> Window<'_, 'inner, S> {
+    /// Split the rows into partitions with the same value for this column.
+    ///
+    /// This can be called multiple times to partition on multiple columns.
+    pub fn partition_by<T: MyTyp>(mut self, val: impl IntoColumn<'inner, S, Typ = T>) -> Self {
+        let (expr, deps) = self.rows.ast.builder().track(|b| val.build_expr(b));
+        self.deps.extend(deps);
+        self.window.add_partition_by(expr);
+        self
+    }
+
+    /// Order the rows in each partition by this column, smallest first.
+    pub fn order_by<T: MyTyp>(mut self, val: impl IntoColumn<'inner, S, Typ = T>) -> Self {
+        let (expr, deps) = self.rows.ast.builder().track(|b| val.build_expr(b));
+        self.deps.extend(deps);
+        self.window.order_by_expr(expr, Order::Asc);
+        self
+    }
+
+    /// Order the rows in each partition by this column, largest first.
+    pub fn order_by_desc<T: MyTyp>(mut self, val: impl IntoColumn<'inner, S, Typ = T>) -> Self {
+        let (expr, deps) = self.rows.ast.builder().track(|b| val.build_expr(b));
+        self.deps.extend(deps);
+        self.window.order_by_expr(expr, Order::Desc);
+        self
+    }
+
+    fn build<T: 'static>(
+        &self,
+        func: impl FnOnce(ValueBuilder) -> SimpleExpr,
+    ) -> Column<'inner, S, T> {
+        let ast = &self.rows.ast;
+        let (func, mut deps) = ast.builder().track(func);
+        deps.extend(&self.deps);
+        let (select, conds, field) = ast.build_window(func, self.window.clone(), deps);
+        WindowValue {
+            select: Rc::new(select),
+            conds: Rc::new(conds),
+            field,
+            _p: PhantomData,
+        }
+        .into_column()
+    }
+
+    /// The number of the row in its partition, starting at 1.
+    ///
+    /// Rows that have the same order get different numbers.
+    pub fn row_number(&self) -> Column<'inner, S, i64> {
+        self.build(|_| Func::cust(Alias::new("ROW_NUMBER")).into())
+    }
+
+    /// The rank of the row in its partition, starting at 1.
+    ///
+    /// Rows that have the same order get the same rank, leaving gaps after them.
+    pub fn rank(&self) -> Column<'inner, S, i64> {
+        self.build(|_| Func::cust(Alias::new("RANK")).into())
+    }
+
+    /// The rank of the row in its partition, starting at 1.
+    ///
+    /// Rows that have the same order get the same rank, without leaving gaps after them.
+    pub fn dense_rank(&self) -> Column<'inner, S, i64> {
+        self.build(|_| Func::cust(Alias::new("DENSE_RANK")).into())
+    }
+
+    /// The value of the column in the previous row of the partition.
+    ///
+    /// This is [None] for the first row of every partition.
+    pub fn lag<T: MyTyp>(
+        &self,
+        val: impl IntoColumn<'inner, S, Typ = T>,
+    ) -> Column<'inner, S, Option<T>> {
+        self.build(|b| Func::cust(Alias::new("LAG")).arg(val.build_expr(b)).into())
+    }
+
+    /// The value of the column in the next row of the partition.
+    ///
+    /// This is [None] for the last row of every partition.
+    pub fn lead<T: MyTyp>(
+        &self,
+        val: impl IntoColumn<'inner, S, Typ = T>,
+    ) -> Column<'inner, S, Option<T>> {
+        self.build(|b| Func::cust(Alias::new("LEAD")).arg(val.build_expr(b)).into())
+    }
+}
+
+/// The result of a window function, using it joins the window.
+struct WindowValue<T> {
+    select: Rc<SelectStatement>,
+    conds: Rc<Vec<(Field, SimpleExpr)>>,
+    field: Field,
+    _p: PhantomData<T>,
+}
+
+impl<T> Clone for WindowValue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            select: self.select.clone(),
+            conds: self.conds.clone(),
+            field: self.field,
+            _p: PhantomData,
+        }
+    }
+}
+
+impl<T> Typed for WindowValue<T> {
+    type Typ = T;
+    fn build_expr(&self, b: ValueBuilder) -> SimpleExpr {
+        let select = SelectStatement::clone(&self.select);
+        let table = b.get_window(select, Vec::clone(&self.conds));
+        Expr::col((table, self.field)).into()
+    }
+}
+
+impl<'t, S, T: 'static> IntoColumn<'t, S> for WindowValue<T> {
+    type Owned = Self;
+
+    fn into_owned(self) -> Self::Owned {
+        self
+    }
+}
@@ -0,0 +1,81 @@
+use std::sync::{Arc, Mutex};
+
+use rusqlite::Connection;
+
+use crate::{error::unwrap_db, Database, LocalClient, Transaction};
+
+/// A [WriteLock] holds the global write lock of the database, so that multiple threads can read the same state.
+///
+/// It can be cloned cheaply and sent to other threads, where [LocalClient::transaction_locked]
+/// creates [Transaction]s that all see the same state of the database.
+///
+/// This is not a WAL snapshot, it works by starting a `BEGIN IMMEDIATE` transaction that is kept open
+/// until all clones of the [WriteLock] are dropped.
+/// The [LocalClient] used to create the [WriteLock] is borrowed, which prevents it from making a [crate::TransactionMut].
+/// All writers, including [crate::TransactionMut]s on other threads and in other processes, will wait for the [WriteLock] to be dropped,
+/// so it should only be kept for a short time.
+pub struct WriteLock<'a, S> {
+    db: &'a Database<S>,
+    // the connection is never used, it only holds the write lock
+    _lock: Arc<Mutex<Connection>>,
+}
+
+impl<S> Clone for WriteLock<'_, S> {
+    fn clone(&self) -> Self {
+        Self {
+            db: self.db,
+            _lock: self._lock.clone(),
+        }
+    }
+}
+
+impl LocalClient {
+    /// Take the write lock of the database and return a [WriteLock] that can be shared with other threads.
+    ///
+    /// This needs to wait for all other [crate::TransactionMut]s to be finished, just like [LocalClient::transaction_mut].
+    /// No other connection can write to the database until the [WriteLock] is dropped.
+    /// ```
+    /// # use rust_query::{migration::{schema, Config}, LocalClient, Table};
+    /// # #[schema]
+    /// # enum Schema {
+    /// #     User { name: String },
+    /// # }
+    /// # use v0::*;
+    /// # let mut client = LocalClient::try_new().unwrap();
+    /// # let db = client.migrator(Config::open_in_memory()).unwrap().finish().unwrap();
+    /// let lock = client.write_lock(&db);
+    /// std::thread::scope(|s| {
+    ///     for _ in 0..4 {
+    ///         let lock = lock.clone();
+    ///         s.spawn(move || {
+    ///             let mut client = LocalClient::try_new().unwrap();
+    ///             let txn = client.transaction_locked(&lock);
+    ///             txn.query(|rows| {
+    ///                 let user = User::join(rows);
+    ///                 rows.into_vec(user.name())
+    ///             })
+    ///         });
+    ///     }
+    /// });
+    /// ```
+    pub fn write_lock<'a, S>(&'a mut self, db: &'a Database<S>) -> WriteLock<'a, S> {
+        use r2d2::ManageConnection;
+        let conn = db.busy_retry.run(|| {
+            let conn = db.manager.connect()?;
+            conn.execute_batch("BEGIN IMMEDIATE")?;
+            Ok(conn)
+        });
+        let conn = unwrap_db(conn);
+        WriteLock {
+            db,
+            _lock: Arc::new(Mutex::new(conn)),
+        }
+    }
+
+    /// Create a [Transaction] that reads the state of the database that is kept by the [WriteLock].
+    ///
+    /// This works the same as [LocalClient::transaction].
+    pub fn transaction_locked<S>(&mut self, lock: &WriteLock<'_, S>) -> Transaction<'_, S> {
+        self.transaction(lock.db)
+    }
+}
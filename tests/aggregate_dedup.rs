@@ -0,0 +1,54 @@
+use std::sync::Mutex;
+
+use rust_query::{
+    aggregate,
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    Genre {
+        name: String,
+    },
+    Track {
+        genre: Genre,
+        bytes: i64,
+        milliseconds: i64,
+    },
+}
+use v0::*;
+
+static TRACES: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+#[test]
+fn aggregate_dedup() {
+    let config = Config::open_in_memory()
+        .init_stmt("INSERT INTO genre (name) VALUES ('rock')")
+        .init_stmt(
+            "INSERT INTO track (genre, bytes, milliseconds) VALUES (1, 10, 100), (1, 30, 300)",
+        )
+        .trace(|trace| TRACES.lock().unwrap().push(trace.sql.to_owned()));
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client.migrator(config).unwrap().finish().unwrap();
+    let txn = client.transaction(&db);
+
+    TRACES.lock().unwrap().clear();
+    let res = txn.query(|rows| {
+        let genre = Genre::join(rows);
+        let (bytes, milis) = aggregate(|rows| {
+            let track = Track::join(rows);
+            rows.filter_on(track.genre(), &genre);
+            (
+                rows.avg(track.bytes().as_float()),
+                rows.avg(track.milliseconds().as_float()),
+            )
+        });
+        rows.into_vec((genre.name(), bytes, milis))
+    });
+    assert_eq!(res, [("rock".to_owned(), Some(20.), Some(200.))]);
+    // both averages are calculated by the same sub-query
+    let traces = std::mem::take(&mut *TRACES.lock().unwrap());
+    let [sql] = &traces[..] else { panic!() };
+    assert_eq!(sql.matches("LEFT JOIN (SELECT").count(), 1, "{sql}");
+}
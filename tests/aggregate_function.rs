@@ -0,0 +1,75 @@
+use rust_query::{
+    aggregate,
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    Category {
+        name: String,
+    },
+    Item {
+        category: Category,
+        name: String,
+        price: f64,
+    },
+}
+use v0::*;
+
+#[test]
+fn aggregate_function() {
+    let config = Config::open_in_memory()
+        .init_stmt("INSERT INTO category (name) VALUES ('fruit'), ('tools'), ('empty')")
+        .init_stmt(
+            "INSERT INTO item (category, name, price) VALUES
+            (1, 'apple', 1.0), (1, 'pear', 5.0), (1, 'kiwi', 2.0), (2, 'saw', 20.0)",
+        );
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client.migrator(config).unwrap().finish().unwrap();
+
+    let median = db.aggregate_function(
+        "median",
+        Vec::new,
+        |values: &mut Vec<f64>, value: f64| values.push(value),
+        |mut values| {
+            values.sort_by(f64::total_cmp);
+            values[values.len() / 2]
+        },
+    );
+    let names = db.aggregate_function(
+        "names",
+        Vec::new,
+        |names: &mut Vec<String>, name: String| names.push(name),
+        |mut names| {
+            names.sort();
+            names.join(", ")
+        },
+    );
+
+    let txn = client.transaction(&db);
+    let res = txn.query(|rows| {
+        let category = Category::join(rows);
+        let (median, names) = aggregate(|rows| {
+            let item = Item::join(rows);
+            rows.filter_on(item.category(), &category);
+            (
+                median.call(rows, item.price()),
+                names.call(rows, item.name()),
+            )
+        });
+        rows.into_vec((category.name(), median, names))
+    });
+    assert_eq!(
+        res,
+        [
+            ("empty".to_owned(), None, None),
+            (
+                "fruit".to_owned(),
+                Some(2.0),
+                Some("apple, kiwi, pear".to_owned())
+            ),
+            ("tools".to_owned(), Some(20.0), Some("saw".to_owned())),
+        ]
+    );
+}
@@ -0,0 +1,41 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    Employee {
+        name: String,
+        reports_to: Option<Employee>,
+    },
+}
+use v0::*;
+
+#[test]
+fn and_then() {
+    let config = Config::open_in_memory().init_stmt(
+        "INSERT INTO employee (name, reports_to) VALUES ('ceo', NULL), ('cto', 1), ('dev', 2)",
+    );
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client.migrator(config).unwrap().finish().unwrap();
+    let txn = client.transaction(&db);
+
+    let res = txn.query(|rows| {
+        let employee = Employee::join(rows);
+        let grand_boss = employee
+            .reports_to()
+            .and_then(|boss| boss.reports_to())
+            .map(|grand_boss| grand_boss.name());
+        rows.into_vec((employee.name(), grand_boss))
+    });
+    // employees without a (grand) boss are not filtered out
+    assert_eq!(
+        res,
+        [
+            ("ceo".to_owned(), None),
+            ("cto".to_owned(), None),
+            ("dev".to_owned(), Some("ceo".to_owned()))
+        ]
+    );
+}
@@ -0,0 +1,78 @@
+use rust_query::{
+    migration::{schema, Config},
+    AuditOperation, IntoColumn, LocalClient, RowId, Table,
+};
+
+#[schema]
+enum Schema {
+    #[soft_delete]
+    User {
+        name: String,
+        score: i64,
+    },
+    Note {
+        text: String,
+    },
+}
+use v0::*;
+
+#[test]
+fn audit_log() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory().audit())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    txn.set_actor("admin");
+    let alice = txn.insert(User {
+        name: "alice",
+        score: 10,
+    });
+    txn.update(
+        alice,
+        User {
+            score: 12i64.into_column(),
+            ..User::dummy(alice)
+        },
+    );
+    // nothing changes, so nothing is recorded
+    txn.update(alice, User::dummy(alice));
+    txn.delete(alice);
+    let alice_id = RowId::from(alice);
+    let note = txn.insert(Note {
+        text: "it's \"quoted\"",
+    });
+    let note_id = RowId::from(note);
+
+    let mut txn = txn.deletor();
+    assert_eq!(txn.try_delete(note), Ok(true));
+    txn.commit();
+
+    let txn = client.transaction(&db);
+    let history = txn.audit_history(alice_id);
+    let ops: Vec<_> = history.iter().map(|x| x.operation).collect();
+    assert_eq!(
+        ops,
+        [
+            AuditOperation::Insert,
+            AuditOperation::Update,
+            AuditOperation::Delete
+        ]
+    );
+    assert_eq!(
+        history[0].changes,
+        r#"{"deleted_at":null,"name":"alice","score":10}"#
+    );
+    assert_eq!(history[1].changes, r#"{"score":12}"#);
+    assert!(history.iter().all(|x| x.actor.as_deref() == Some("admin")));
+    assert!(history.iter().all(|x| x.timestamp > 0));
+
+    // the history of deleted rows can still be read
+    let history = txn.audit_history(note_id);
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[1].operation, AuditOperation::Delete);
+    assert_eq!(history[1].changes, r#"{"text":"it's \"quoted\""}"#);
+}
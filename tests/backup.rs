@@ -0,0 +1,52 @@
+use rust_query::{
+    migration::{schema, Config},
+    Database, LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    User { name: String },
+}
+use v0::*;
+
+fn user_names(client: &mut LocalClient, db: &Database<Schema>) -> Vec<String> {
+    let txn = client.transaction(db);
+    txn.query(|rows| {
+        let user = User::join(rows);
+        rows.into_vec(user.name())
+    })
+}
+
+#[test]
+fn backup_and_restore() {
+    let mut client = LocalClient::try_new().unwrap();
+    let mut db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    txn.insert(User { name: "alice" });
+    txn.commit();
+
+    let path =
+        std::env::temp_dir().join(format!("rust_query_backup_{}.sqlite", std::process::id()));
+    db.backup_to(&path).unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    txn.insert(User { name: "bob" });
+    txn.commit();
+    assert_eq!(user_names(&mut client, &db), ["alice", "bob"]);
+
+    db.restore_from(&path).unwrap();
+    assert_eq!(user_names(&mut client, &db), ["alice"]);
+
+    // the database can still be changed after restoring
+    let mut txn = client.transaction_mut(&db);
+    txn.insert(User { name: "carol" });
+    txn.commit();
+    assert_eq!(user_names(&mut client, &db), ["alice", "carol"]);
+
+    std::fs::remove_file(path).unwrap();
+}
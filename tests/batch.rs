@@ -0,0 +1,57 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    Album { title: String },
+    Track { album: Album, name: String },
+}
+use v0::*;
+
+#[test]
+fn batch() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    txn.insert(Album { title: "first" });
+    let (second, third) = txn.batch(|batch| {
+        let second = batch.reserve::<Album>();
+        for name in ["a", "b"] {
+            batch.insert(Track {
+                album: second,
+                name,
+            });
+        }
+        // the new album does not get the id of the reserved album
+        let third = batch.insert(Album { title: "third" });
+        batch.insert(Track {
+            album: third,
+            name: "c",
+        });
+        batch.insert_reserved(second, Album { title: "second" });
+        (second, third)
+    });
+    assert_eq!(txn.query_one(second.title()), "second");
+    assert_eq!(txn.query_one(third.title()), "third");
+
+    let tracks = txn.query(|rows| {
+        let track = Track::join(rows);
+        rows.into_vec((track.album().title(), track.name()))
+    });
+    assert_eq!(
+        tracks,
+        [
+            ("second".to_owned(), "a".to_owned()),
+            ("second".to_owned(), "b".to_owned()),
+            ("third".to_owned(), "c".to_owned()),
+        ]
+    );
+    txn.commit();
+}
@@ -0,0 +1,57 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    Artist {
+        name: String,
+        bio: Option<String>,
+        #[default = 0]
+        plays: i64,
+    },
+    Album {
+        title: String,
+        artist: Artist,
+    },
+}
+use v0::*;
+
+#[test]
+fn builder() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let a = txn.insert(Artist::builder().name("a").build());
+    let b = txn.insert(
+        Artist::builder()
+            .plays(3)
+            .bio(Some("from b"))
+            .name("b")
+            .build(),
+    );
+    txn.insert(Album::builder().artist(b).title("first").build());
+
+    let artists = txn.query(|rows| {
+        let artist = Artist::join(rows);
+        rows.into_vec((artist.name(), artist.bio(), artist.plays()))
+    });
+    assert_eq!(
+        artists,
+        [
+            ("a".to_owned(), None, 0),
+            ("b".to_owned(), Some("from b".to_owned()), 3)
+        ]
+    );
+    let titles = txn.query(|rows| {
+        let album = a.albums(rows);
+        rows.into_vec(album.title())
+    });
+    assert!(titles.is_empty());
+}
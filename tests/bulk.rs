@@ -0,0 +1,93 @@
+use rust_query::{
+    migration::{schema, Config},
+    IntoColumn, LocalClient, Table, Transaction, Update,
+};
+
+#[schema]
+enum Schema {
+    User { name: String, score: i64 },
+    Post { author: User, title: String },
+}
+use v0::*;
+
+fn scores(txn: &Transaction<Schema>) -> Vec<(String, i64)> {
+    txn.query(|rows| {
+        let user = User::join(rows);
+        rows.into_vec((user.name(), user.score()))
+    })
+}
+
+#[test]
+fn update_and_delete_where() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let alice = txn.insert(User {
+        name: "alice",
+        score: 10,
+    });
+    txn.insert(User {
+        name: "bob",
+        score: -5,
+    });
+    txn.insert(User {
+        name: "carol",
+        score: -1,
+    });
+    txn.insert(Post {
+        author: alice,
+        title: "hello",
+    });
+
+    // the new value can depend on the old value
+    let updated = txn.update_where::<User>(|rows, user| {
+        rows.filter(user.score().lt(0));
+        Update::new(User {
+            score: user.score().add(-1),
+            ..User::dummy(user.clone())
+        })
+    });
+    assert_eq!(updated.rows, 2);
+
+    // the filter can use other tables
+    let updated = txn.update_where::<User>(|rows, user| {
+        user.posts(rows);
+        Update::new(User {
+            name: "author".into_column(),
+            ..User::dummy(user.clone())
+        })
+    });
+    assert_eq!(updated.rows, 1);
+
+    let mut expected = vec![
+        ("author".to_owned(), 10),
+        ("bob".to_owned(), -6),
+        ("carol".to_owned(), -2),
+    ];
+    let mut res = scores(&txn);
+    res.sort();
+    assert_eq!(res, expected);
+
+    let mut del = txn.deletor();
+    // alice is still referenced by a post
+    assert_eq!(del.delete_where::<User>(|_, _| {}), None);
+    let deleted = del.delete_where::<User>(|rows, user| {
+        rows.filter(user.score().lt(-3));
+    });
+    assert_eq!(deleted.map(|x| x.rows), Some(1));
+    assert_eq!(db.total_changes(), 0);
+    del.commit();
+    // 4 inserts, 3 updates and 1 delete
+    assert_eq!(db.total_changes(), 8);
+
+    expected.remove(1);
+    let txn = client.transaction(&db);
+    let mut res = scores(&txn);
+    res.sort();
+    assert_eq!(res, expected);
+}
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient,
+};
+
+#[schema]
+enum Schema {
+    User { name: String },
+}
+use v0::*;
+
+#[test]
+fn busy_retry() {
+    let path = std::env::temp_dir().join(format!("rust_query_busy_{}.sqlite", std::process::id()));
+    let config = Config::open(&path)
+        .busy_timeout(Duration::from_millis(10))
+        .busy_retries(10, Duration::from_millis(10));
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client.migrator(config).unwrap().finish().unwrap();
+
+    // another connection holds the write lock for longer than the busy_timeout
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute_batch("BEGIN IMMEDIATE").unwrap();
+    let handle = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(200));
+        conn.execute_batch("COMMIT").unwrap();
+    });
+
+    let mut txn = client.transaction_mut(&db);
+    txn.insert(User {
+        name: "alice".to_owned(),
+    });
+    txn.commit();
+    handle.join().unwrap();
+
+    for ext in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{ext}", path.display()));
+    }
+}
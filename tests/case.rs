@@ -0,0 +1,56 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    Track { name: String, milliseconds: i64 },
+}
+use v0::*;
+
+#[test]
+fn case() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    for (name, milliseconds) in [("a", 30_000), ("b", 60_000), ("c", 200_000), ("d", 400_000)] {
+        txn.insert(Track { name, milliseconds });
+    }
+
+    let lengths = txn.query(|rows| {
+        let track = Track::join(rows);
+        let millis = track.milliseconds();
+        // the first condition that is true is used
+        let length = millis
+            .lt(60 * 1000)
+            .then("short")
+            .when(millis.lt(5 * 60 * 1000), "normal")
+            .else_("long");
+        rows.into_vec((track.name(), length))
+    });
+    assert_eq!(
+        lengths,
+        [
+            ("a".to_owned(), "short".to_owned()),
+            ("b".to_owned(), "normal".to_owned()),
+            ("c".to_owned(), "normal".to_owned()),
+            ("d".to_owned(), "long".to_owned()),
+        ]
+    );
+
+    let buckets = txn.query(|rows| {
+        let track = Track::join(rows);
+        let long = track.milliseconds().lt(5 * 60 * 1000).then(0).else_(1);
+        rows.group_by(|group| {
+            let long = group.key(long);
+            group.into_vec((long, group.count()))
+        })
+    });
+    assert_eq!(buckets, [(0, 3), (1, 1)]);
+}
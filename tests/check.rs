@@ -0,0 +1,70 @@
+use rust_query::{
+    migration::{schema, Config},
+    IntoColumn, LocalClient, Table, WriteError,
+};
+
+#[schema]
+#[version(0..=0)]
+enum Schema {
+    #[check("length(name) > 0")]
+    Track {
+        #[unique_name]
+        name: String,
+        #[check("milliseconds > 0")]
+        milliseconds: i64,
+    },
+}
+use v0::*;
+
+#[test]
+fn check_constraints() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let Err(WriteError::Check(msg)) = txn.checked_insert(Track {
+        name: "intro",
+        milliseconds: 0,
+    }) else {
+        panic!("expected check violation")
+    };
+    assert!(msg.contains("milliseconds > 0"), "{msg}");
+
+    let Err(WriteError::Check(msg)) = txn.checked_insert(Track {
+        name: "",
+        milliseconds: 1000,
+    }) else {
+        panic!("expected check violation")
+    };
+    assert!(msg.contains("length(name) > 0"), "{msg}");
+
+    let intro = txn
+        .checked_insert(Track {
+            name: "intro",
+            milliseconds: 1000,
+        })
+        .unwrap();
+
+    // unique conflicts are still reported as conflicts
+    let res = txn.checked_insert(Track {
+        name: "intro",
+        milliseconds: 2000,
+    });
+    assert_eq!(res, Err(WriteError::Conflict(intro)));
+
+    let res = txn.checked_update(
+        intro,
+        Track {
+            milliseconds: (-1i64).into_column(),
+            ..Track::dummy(intro)
+        },
+    );
+    assert!(matches!(res, Err(WriteError::Check(_))));
+
+    let milliseconds = txn.query_one(intro.milliseconds());
+    assert_eq!(milliseconds, 1000);
+}
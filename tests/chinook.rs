@@ -1,10 +1,12 @@
 mod chinook_schema;
 
-use std::fmt::Debug;
+use std::{fmt::Debug, time::Duration};
 
 use chinook_schema::*;
 use expect_test::expect_file;
-use rust_query::{aggregate, Dummy, FromDummy, LocalClient, Table, TableRow, Transaction};
+use rust_query::{
+    aggregate, Dummy, FromDummy, FromRaw, LocalClient, RowId, Table, TableRow, Transaction,
+};
 
 /// requires [PartialEq] to get rid of unused warnings.
 fn assert_dbg(val: impl Debug + PartialEq, file_name: &str) {
@@ -20,18 +22,79 @@ fn test_queries() {
 
     let res = invoice_info(&db);
     assert_dbg(&res[..20], "invoice_info");
+    let id = RowId::from(res[0].ivl_id).as_i64();
+    assert_eq!(db.find(RowId::from_i64(id)), Some(res[0].ivl_id));
     let res = playlist_track_count(&db);
     assert_dbg(&res[..], "playlist_track_count");
     let res = avg_album_track_count_for_artist(&db);
     assert_dbg(&res[..20], "avg_album_track_count_for_artist");
+    assert_eq!(avg_album_track_count_reverse(&db), res);
     let res = count_reporting(&db);
     assert_dbg(&res[..], "count_reporting");
     let res = list_all_genres(&db);
     assert_dbg(&res[..20], "list_all_genres");
+    assert_eq!(genre_count(&db), res.len() as i64);
+    assert_eq!(first_genre(&db), res.first().cloned());
     let res = filtered_track(&db, "Metal", 1000 * 60);
     assert_dbg(&res[..], "filtered_track");
     let res = genre_statistics(&db);
     assert_dbg(&res[..20], "genre_statistics");
+    let res = genre_track_count(&db);
+    assert_eq!(res.len(), genre_count(&db) as usize);
+    let popular = popular_genres(&db, 100);
+    assert_eq!(
+        popular,
+        res.into_iter().filter(|x| x.1 >= 100).collect::<Vec<_>>()
+    );
+    let res = longest_track_per_genre(&db);
+    assert_eq!(res.len(), genre_count(&db) as usize);
+    assert!(genres_without_tracks(&db).is_empty());
+    assert_eq!(genres_with_tracks(&db).len(), genre_count(&db) as usize);
+    let metal = db.query(|rows| {
+        let genre = Genre::join(rows);
+        rows.filter(genre.name().is_in(["Metal", "Heavy Metal"]));
+        rows.into_vec(genre)
+    });
+    assert_eq!(
+        tracks_in_genres(&db, metal).len(),
+        filtered_track(&db, "Metal", i64::MAX).len()
+            + filtered_track(&db, "Heavy Metal", i64::MAX).len()
+    );
+    let employee_count = db.query(|rows| {
+        Employee::join(rows);
+        rows.count()
+    });
+    for boss in top_employees(&db) {
+        assert_eq!(all_reports(&db, boss).len() as i64, employee_count - 1);
+    }
+    let bosses = employee_boss_names(&db);
+    assert_eq!(bosses.len() as i64, employee_count);
+    assert_eq!(
+        bosses.iter().filter(|x| x.1.is_none()).count(),
+        top_employees(&db).len()
+    );
+    let bosses = employee_bosses(&db);
+    assert_eq!(bosses.len() as i64, employee_count);
+    for (first_name, last_name, email, boss) in bosses {
+        assert!(!first_name.is_empty() && !last_name.is_empty() && !email.is_empty());
+        if let Some((boss_first_name, boss_last_name)) = boss {
+            assert!(!boss_first_name.is_empty() && !boss_last_name.is_empty());
+        }
+    }
+    let buckets = track_length_buckets(&db);
+    let longest = longest_tracks(&db);
+    assert!(longest.windows(2).all(|x| x[0].1 >= x[1].1));
+    let track_count = db.query(|rows| {
+        Track::join(rows);
+        rows.count()
+    });
+    assert_eq!(buckets.iter().map(|x| x.1).sum::<i64>(), track_count);
+    let res = raw_genre_track_count(&db);
+    assert_eq!(res.len(), genre_count(&db) as usize);
+    assert_eq!(res.iter().map(|x| x.track_count).sum::<i64>(), track_count);
+    for (total, floor, ceil) in rounded_invoice_totals(&db) {
+        assert!(floor <= total && total <= ceil && ceil - floor <= 1.);
+    }
     let res = all_customer_spending(&db);
     assert_dbg(&res[..20], "customer_spending");
 
@@ -107,6 +170,21 @@ fn avg_album_track_count_for_artist(db: &Transaction<Schema>) -> Vec<(String, Op
     })
 }
 
+fn avg_album_track_count_reverse(db: &Transaction<Schema>) -> Vec<(String, Option<f64>)> {
+    db.query(|rows| {
+        let artist = Artist::join(rows);
+        let avg_track_count = aggregate(|rows| {
+            let album = artist.albums(rows);
+            let track_count = aggregate(|rows| {
+                let track = album.tracks(rows);
+                rows.count_distinct(track)
+            });
+            rows.avg(track_count.as_float())
+        });
+        rows.into_vec((artist.name(), avg_track_count))
+    })
+}
+
 fn count_reporting(db: &Transaction<Schema>) -> Vec<(String, i64)> {
     db.query(|rows| {
         let receiver = Employee::join(rows);
@@ -129,6 +207,20 @@ fn list_all_genres(db: &Transaction<Schema>) -> Vec<String> {
     })
 }
 
+fn genre_count(db: &Transaction<Schema>) -> i64 {
+    db.query(|rows| {
+        Genre::join(rows);
+        rows.count()
+    })
+}
+
+fn first_genre(db: &Transaction<Schema>) -> Option<String> {
+    db.query(|rows| {
+        let genre = Genre::join(rows);
+        rows.first(genre.name())
+    })
+}
+
 #[derive(Debug, FromDummy, PartialEq)]
 struct FilteredTrack {
     track_name: String,
@@ -182,6 +274,175 @@ fn genre_statistics(db: &Transaction<Schema>) -> Vec<GenreStats> {
     })
 }
 
+fn genre_track_count(db: &Transaction<Schema>) -> Vec<(String, i64)> {
+    db.query(|rows| {
+        let track = Track::join(rows);
+        rows.group_by(|group| {
+            let genre = group.key(track.genre());
+            group.into_vec((genre.name(), group.count()))
+        })
+    })
+}
+
+fn popular_genres(db: &Transaction<Schema>, min_tracks: i64) -> Vec<(String, i64)> {
+    db.query(|rows| {
+        let track = Track::join(rows);
+        rows.group_by(|group| {
+            let genre = group.key(track.genre());
+            let track_count = group.count();
+            group.filter(track_count.lt(min_tracks).not());
+            group.into_vec((genre.name(), track_count))
+        })
+    })
+}
+
+fn longest_track_per_genre(db: &Transaction<Schema>) -> Vec<(String, String)> {
+    db.query(|rows| {
+        let track = Track::join(rows);
+        let nr = rows
+            .window()
+            .partition_by(track.genre())
+            .order_by_desc(track.milliseconds())
+            .row_number();
+        rows.filter(nr.eq(1));
+        rows.into_vec((track.genre().name(), track.name()))
+    })
+}
+
+fn longest_tracks(db: &Transaction<Schema>) -> Vec<(String, Duration)> {
+    let mut res = db.query(|rows| {
+        let track = Track::join(rows);
+        let duration = track
+            .milliseconds()
+            .map_dummy(|x| Duration::from_millis(x as u64));
+        rows.into_vec((track.name(), duration))
+    });
+    res.sort_by_key(|x| std::cmp::Reverse(x.1));
+    res.truncate(10);
+    res
+}
+
+fn track_length_buckets(db: &Transaction<Schema>) -> Vec<(String, i64)> {
+    db.query(|rows| {
+        let track = Track::join(rows);
+        let milis = track.milliseconds();
+        let bucket = milis
+            .lt(60 * 1000)
+            .then("short")
+            .when(milis.lt(5 * 60 * 1000), "normal")
+            .else_("long");
+        rows.group_by(|group| {
+            let bucket = group.key(bucket);
+            group.into_vec((bucket, group.count()))
+        })
+    })
+}
+
+fn genres_without_tracks(db: &Transaction<Schema>) -> Vec<String> {
+    db.query(|rows| {
+        let genre = rows.except(|rows| Genre::join(rows), |rows| Track::join(rows).genre());
+        rows.into_vec(genre.name())
+    })
+}
+
+fn genres_with_tracks(db: &Transaction<Schema>) -> Vec<String> {
+    db.query(|rows| {
+        let genre = Genre::join(rows);
+        rows.filter(genre.is_in_query(|rows| Track::join(rows).genre()));
+        rows.into_vec(genre.name())
+    })
+}
+
+fn tracks_in_genres<'a>(
+    db: &Transaction<'a, Schema>,
+    genres: Vec<TableRow<'a, Genre>>,
+) -> Vec<String> {
+    db.query(|rows| {
+        let track = Track::join(rows);
+        rows.filter(track.genre().is_in(genres));
+        rows.into_vec(track.name())
+    })
+}
+
+fn top_employees<'a>(db: &Transaction<'a, Schema>) -> Vec<TableRow<'a, Employee>> {
+    db.query(|rows| {
+        let employee = Employee::join(rows);
+        rows.filter_none(employee.reports_to());
+        rows.into_vec(employee)
+    })
+}
+
+fn employee_boss_names(db: &Transaction<Schema>) -> Vec<(String, Option<String>)> {
+    db.query(|rows| {
+        let employee = Employee::join(rows);
+        let boss_name = employee.reports_to().map(|boss| boss.last_name());
+        rows.into_vec((employee.last_name(), boss_name))
+    })
+}
+
+type EmployeeBoss = (String, String, String, Option<(String, String)>);
+
+fn employee_bosses(db: &Transaction<Schema>) -> Vec<EmployeeBoss> {
+    db.query(|rows| {
+        let employee = Employee::join(rows);
+        let boss = employee
+            .reports_to()
+            .optional(|boss| (boss.first_name(), boss.last_name()));
+        rows.into_vec((
+            employee.first_name(),
+            employee.last_name(),
+            employee.email(),
+            boss,
+        ))
+    })
+}
+
+fn all_reports<'a>(db: &Transaction<'a, Schema>, boss: TableRow<'a, Employee>) -> Vec<String> {
+    db.query(|rows| {
+        let employee = rows.recursive(
+            |rows| {
+                let employee = Employee::join(rows);
+                let reports_to = rows.filter_some(employee.reports_to());
+                rows.filter(reports_to.eq(boss));
+                employee
+            },
+            |rows, prev| {
+                let employee = Employee::join(rows);
+                let reports_to = rows.filter_some(employee.reports_to());
+                rows.filter(reports_to.eq(prev));
+                employee
+            },
+        );
+        rows.into_vec(employee.last_name())
+    })
+}
+
+fn rounded_invoice_totals(db: &Transaction<Schema>) -> Vec<(f64, f64, f64)> {
+    db.query(|rows| {
+        let invoice = Invoice::join(rows);
+        let total = invoice.total();
+        let res = rows.into_vec((total.clone(), (total.floor(), total.ceil())));
+        res.into_iter().map(|(a, (b, c))| (a, b, c)).collect()
+    })
+}
+
+#[derive(Debug, FromRaw)]
+struct GenreTrackCount {
+    #[allow(unused)]
+    name: String,
+    track_count: i64,
+}
+
+fn raw_genre_track_count(db: &Transaction<Schema>) -> Vec<GenreTrackCount> {
+    db.raw_query(
+        "SELECT genre.name AS name, COUNT(track.id) AS track_count
+        FROM genre LEFT JOIN track ON track.genre = genre.id
+        GROUP BY genre.id",
+        &[],
+    )
+    .unwrap()
+}
+
 #[derive(Debug, FromDummy, PartialEq)]
 struct CustomerSpending {
     customer_name: String,
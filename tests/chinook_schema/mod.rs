@@ -185,11 +185,11 @@ pub fn migrate(client: &mut LocalClient) -> Database<v2::Schema> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rust_query::migration::expect;
+    use rust_query::verify_schema;
 
     #[test]
     fn backwards_compat() {
-        v0::assert_hash(expect!["a57e97b8c243859a"]);
-        v1::assert_hash(expect!["15e9ff46816e4b45"]);
+        verify_schema!(v0::Schema, "a57e97b8c243859a");
+        verify_schema!(v1::Schema, "15e9ff46816e4b45");
     }
 }
@@ -0,0 +1,50 @@
+use rust_query::migration::{json_schema, schema, typescript_types};
+
+#[schema]
+enum Schema {
+    Artist {
+        name: String,
+    },
+    Album {
+        title: String,
+        artist: Artist,
+        rating: Option<f64>,
+        plays: i64,
+    },
+}
+
+#[test]
+fn codegen() {
+    let expected = "\
+export interface Album {
+  id: number;
+  artist: number; // id of Artist
+  plays: number;
+  rating: number | null;
+  title: string;
+}
+
+export interface Artist {
+  id: number;
+  name: string;
+}
+";
+    assert_eq!(typescript_types::<v0::Schema>(), expected);
+
+    let schema: serde_json::Value = serde_json::from_str(&json_schema::<v0::Schema>()).unwrap();
+    let album = &schema["$defs"]["Album"];
+    assert_eq!(album["properties"]["id"]["type"], "integer");
+    assert_eq!(album["properties"]["artist"]["description"], "id of Artist");
+    assert_eq!(
+        album["properties"]["rating"]["type"],
+        serde_json::json!(["number", "null"])
+    );
+    assert_eq!(
+        album["required"],
+        serde_json::json!(["id", "artist", "plays", "rating", "title"])
+    );
+    assert_eq!(
+        schema["$defs"]["Artist"]["properties"]["name"]["type"],
+        "string"
+    );
+}
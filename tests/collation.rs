@@ -0,0 +1,81 @@
+use std::cmp::Ordering;
+
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    File {
+        #[unique]
+        #[collate("natural")]
+        name: String,
+    },
+}
+use v0::*;
+
+/// Compare numbers in the strings by their value and ignore the case of letters.
+fn natural(a: &str, b: &str) -> Ordering {
+    fn parts(s: &str) -> Vec<(String, u64)> {
+        let mut res = vec![];
+        let mut rest = s;
+        while !rest.is_empty() {
+            let text_len = rest
+                .find(|c: char| c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let (text, tail) = rest.split_at(text_len);
+            let num_len = tail
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(tail.len());
+            let (num, tail) = tail.split_at(num_len);
+            res.push((text.to_lowercase(), num.parse().unwrap_or(0)));
+            rest = tail;
+        }
+        res
+    }
+    parts(a).cmp(&parts(b))
+}
+
+#[test]
+fn collation() {
+    let config = Config::open_in_memory().collation("natural", natural);
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client.migrator(config).unwrap().finish().unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    for name in ["file10", "file2", "File1"] {
+        txn.try_insert(File { name }).unwrap();
+    }
+    // the unique constraint uses the collation of the column
+    assert!(txn.try_insert(File { name: "FILE2" }).is_err());
+    assert!(txn.lookup::<file::Unique>(("file01",)).is_some());
+
+    let names = txn.query(|rows| {
+        let file = File::join(rows);
+        rows.into_vec(file.name())
+    });
+    assert_eq!(names, ["File1", "file2", "file10"]);
+
+    let names = txn.query(|rows| {
+        let file = File::join(rows);
+        rows.into_vec(file.name().collate("BINARY"))
+    });
+    assert_eq!(names, ["File1", "file10", "file2"]);
+
+    let rank = txn.query(|rows| {
+        let file = File::join(rows);
+        let name = file.name().collate("BINARY");
+        let rank = rows.window().order_by(name.clone()).row_number();
+        rows.filter(name.eq("file10"));
+        rows.into_vec(rank)
+    });
+    assert_eq!(rank, [2]);
+
+    // the name of the collation can not break out of the quotes
+    let (sql, _) = txn.query(|rows| {
+        let file = File::join(rows);
+        rows.to_sql(file.name().collate("BINARY\" --"))
+    });
+    assert!(sql.contains(r#"COLLATE "BINARY"" --""#));
+}
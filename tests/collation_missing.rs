@@ -0,0 +1,33 @@
+use rust_query::{
+    migration::{schema, Config},
+    Error, LocalClient,
+};
+
+#[schema]
+enum Schema {
+    File {
+        #[collate("natural")]
+        name: String,
+    },
+}
+
+#[test]
+fn collation_missing() {
+    let mut client = LocalClient::try_new().unwrap();
+    let err = client
+        .try_migrator::<v0::Schema>(Config::open_in_memory())
+        .err()
+        .unwrap();
+    assert_eq!(
+        err,
+        Error::MissingCollation {
+            table: "file".to_owned(),
+            column: "name".to_owned(),
+            collation: "natural".to_owned(),
+        }
+    );
+    // `LocalClient::migrator` panics with this message
+    assert!(err
+        .to_string()
+        .starts_with("collation \"natural\" of column file.name is not registered"));
+}
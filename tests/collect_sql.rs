@@ -0,0 +1,46 @@
+use expect_test::expect;
+use rust_query::{
+    migration::{schema, Config},
+    testing::collect_sql,
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    User { name: String },
+    Post { author: User, title: String },
+}
+use v0::*;
+
+#[test]
+fn collect_sql_snapshot() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let sql = collect_sql(|| {
+        let user = txn.insert(User { name: "alice" });
+        txn.insert(Post {
+            author: user,
+            title: "hello",
+        });
+        txn.query(|rows| {
+            let post = Post::join(rows);
+            rows.filter(post.author().name().eq("alice"));
+            rows.into_vec(post.title())
+        });
+    });
+    expect![[r#"
+        INSERT INTO "user" ("name") SELECT ? AS "name" FROM (VALUES (?)) AS "" ORDER BY ? ASC RETURNING "id"
+        INSERT INTO "post" ("author", "title") SELECT ? AS "author", ? AS "title" FROM (VALUES (?)) AS "" ORDER BY ? ASC, ? ASC RETURNING "id"
+        SELECT "_0"."title" AS "_2" FROM post AS "_0" LEFT JOIN "user" AS "_1" ON "_0"."author" = "_1"."id" WHERE "_1"."name" = ? ORDER BY "_0"."title" ASC
+    "#]]
+    .assert_eq(&sql);
+
+    // the statements of the previous closure are not collected again
+    assert_eq!(collect_sql(|| {}), "");
+}
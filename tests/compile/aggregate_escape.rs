@@ -0,0 +1,27 @@
+use rust_query::{aggregate, Database, LocalClient, Table};
+use rust_query_macros::schema;
+
+#[schema]
+enum Schema {
+    MyTable { name: String, score: i64 },
+}
+use v0::*;
+
+fn test(db: Database<Schema>) {
+    let mut client = LocalClient::try_new().unwrap();
+
+    let txn = client.transaction(&db);
+    let names = txn.query(|rows| {
+        let _item = MyTable::join(rows);
+        // columns of the aggregate can not be used outside of it
+        let name = aggregate(|agg| {
+            let other = MyTable::join(agg);
+            other.name()
+        });
+        rows.into_vec(name)
+    });
+
+    println!("{names:?}");
+}
+
+fn main() {}
@@ -0,0 +1,29 @@
+use rust_query::{aggregate, Database, LocalClient, Table};
+use rust_query_macros::schema;
+
+#[schema]
+enum Schema {
+    MyTable { name: String, score: i64 },
+}
+use v0::*;
+
+fn test(db: Database<Schema>) {
+    let mut client = LocalClient::try_new().unwrap();
+
+    let txn = client.transaction(&db);
+    let scores = txn.query(|rows| {
+        let item = MyTable::join(rows);
+        // all results of an aggregate use the same rows, so filters can not be added later
+        let score = aggregate(|agg| {
+            let other = MyTable::join(agg);
+            let score = agg.sum(other.score());
+            agg.filter_on(other.name(), item.name());
+            score
+        });
+        rows.into_vec(score)
+    });
+
+    println!("{scores:?}");
+}
+
+fn main() {}
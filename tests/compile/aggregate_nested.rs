@@ -0,0 +1,32 @@
+use rust_query::{aggregate, Database, LocalClient, Table};
+use rust_query_macros::schema;
+
+#[schema]
+enum Schema {
+    MyTable { name: String, score: i64 },
+}
+use v0::*;
+
+fn test(db: Database<Schema>) {
+    let mut client = LocalClient::try_new().unwrap();
+
+    let txn = client.transaction(&db);
+    let scores = txn.query(|rows| {
+        let item = MyTable::join(rows);
+        // nested aggregates can only use columns of the aggregate that contains them
+        let score = aggregate(|agg| {
+            let other = MyTable::join(agg);
+            let count = aggregate(|nested| {
+                let third = MyTable::join(nested);
+                nested.filter_on(third.name(), item.name());
+                nested.count_distinct(third)
+            });
+            agg.sum(other.score().add(count))
+        });
+        rows.into_vec(score)
+    });
+
+    println!("{scores:?}");
+}
+
+fn main() {}
@@ -0,0 +1,28 @@
+use rust_query::{aggregate, Database, LocalClient, Table};
+use rust_query_macros::schema;
+
+#[schema]
+enum Schema {
+    MyTable { name: String, score: i64 },
+}
+use v0::*;
+
+fn test(db: Database<Schema>) {
+    let mut client = LocalClient::try_new().unwrap();
+
+    let txn = client.transaction(&db);
+    let scores = txn.query(|rows| {
+        let item = MyTable::join(rows);
+        // columns of the outer query can only be used with `filter_on`
+        let score = aggregate(|agg| {
+            let other = MyTable::join(agg);
+            agg.filter(other.name().eq(item.name()));
+            agg.sum(other.score())
+        });
+        rows.into_vec(score)
+    });
+
+    println!("{scores:?}");
+}
+
+fn main() {}
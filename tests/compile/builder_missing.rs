@@ -0,0 +1,11 @@
+use rust_query::migration::schema;
+
+#[schema]
+enum Schema {
+    Artist { name: String, bio: Option<String> },
+}
+use v0::*;
+
+fn main() {
+    let _ = Artist::builder().bio(Some("no name")).build();
+}
@@ -0,0 +1,13 @@
+use rust_query::{migration::schema, IntoColumn, RawExpr, Transaction};
+
+#[schema]
+enum Schema {
+    User { name: String },
+}
+use v0::*;
+
+fn forged(txn: &Transaction<Schema>) {
+    txn.query(|rows| rows.into_vec(RawExpr::<_, User>::new("12345").into_column()));
+}
+
+fn main() {}
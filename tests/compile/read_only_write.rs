@@ -0,0 +1,19 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient,
+};
+
+#[schema]
+enum Schema {
+    User { name: String },
+}
+use v0::*;
+
+fn main() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .read_only::<Schema>(Config::open_read_only("test.sqlite"))
+        .unwrap();
+    let mut txn = client.transaction_mut(&db);
+    txn.insert(User { name: "alice" });
+}
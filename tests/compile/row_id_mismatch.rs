@@ -0,0 +1,14 @@
+use rust_query::migration::schema;
+
+#[schema]
+enum Schema {
+    Album { title: String },
+    Track { album: Album, name: String },
+}
+use v0::*;
+
+fn play(_track: TrackId) {}
+
+fn main() {
+    play(AlbumId::from_i64(1));
+}
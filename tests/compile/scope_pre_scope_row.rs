@@ -0,0 +1,30 @@
+use rust_query::{Database, LocalClient};
+use rust_query_macros::schema;
+
+#[schema]
+enum Schema {
+    Tenant {
+        name: String,
+    },
+    Invoice {
+        #[scope]
+        tenant: Tenant,
+        amount: i64,
+    },
+}
+use v0::*;
+
+fn test(db: Database<Schema>) {
+    let mut client = LocalClient::try_new().unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let acme = txn.insert(Tenant { name: "acme" });
+    let globex = txn.insert(Tenant { name: "globex" });
+    txn.scoped(acme, |txn, _| {
+        // rows from before the scope can not be read in the scoped transaction
+        let name = txn.query_one(globex.name());
+        println!("{name}");
+    });
+}
+
+fn main() {}
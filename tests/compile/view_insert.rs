@@ -0,0 +1,25 @@
+use rust_query::migration::{schema, Config};
+use rust_query::LocalClient;
+
+#[schema]
+enum Schema {
+    User {
+        name: String,
+    },
+    #[view("SELECT id, name FROM user WHERE name != ''")]
+    NamedUser {
+        name: String,
+    },
+}
+use v0::*;
+
+fn main() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+    let mut txn = client.transaction_mut(&db);
+    txn.insert(NamedUser { name: "alice" });
+}
@@ -0,0 +1,89 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table, Transaction,
+};
+
+#[schema]
+enum Schema {
+    Artist { name: String },
+    Album { artist: Artist, title: String },
+    Single { artist: Artist, title: String },
+}
+use v0::*;
+
+fn with_albums_or_singles(txn: &Transaction<Schema>) -> Vec<String> {
+    txn.query(|rows| {
+        let artist = rows.union(
+            |rows| Album::join(rows).artist(),
+            |rows| Single::join(rows).artist(),
+        );
+        rows.into_vec(artist.name())
+    })
+}
+
+fn all_titles(txn: &Transaction<Schema>) -> Vec<String> {
+    txn.query(|rows| {
+        let title = rows.union_all(
+            |rows| Album::join(rows).title(),
+            |rows| Single::join(rows).title(),
+        );
+        rows.into_vec(title)
+    })
+}
+
+fn with_albums_and_singles(txn: &Transaction<Schema>) -> Vec<String> {
+    txn.query(|rows| {
+        let artist = rows.intersect(
+            |rows| Album::join(rows).artist(),
+            |rows| Single::join(rows).artist(),
+        );
+        rows.into_vec(artist.name())
+    })
+}
+
+fn without_albums(txn: &Transaction<Schema>) -> Vec<String> {
+    txn.query(|rows| {
+        let artist = rows.except(|rows| Artist::join(rows), |rows| Album::join(rows).artist());
+        rows.into_vec(artist.name())
+    })
+}
+
+#[test]
+fn compound() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let queen = txn.insert(Artist { name: "Queen" });
+    let abba = txn.insert(Artist { name: "ABBA" });
+    txn.insert(Artist { name: "Nobody" });
+    txn.insert(Album {
+        artist: queen,
+        title: "Innuendo",
+    });
+    txn.insert(Album {
+        artist: queen,
+        title: "Jazz",
+    });
+    txn.insert(Single {
+        artist: queen,
+        title: "Innuendo",
+    });
+    txn.insert(Single {
+        artist: abba,
+        title: "Waterloo",
+    });
+
+    assert_eq!(with_albums_or_singles(&txn), ["ABBA", "Queen"]);
+    // duplicates are kept with `UNION ALL`
+    assert_eq!(
+        all_titles(&txn),
+        ["Innuendo", "Innuendo", "Jazz", "Waterloo"]
+    );
+    assert_eq!(with_albums_and_singles(&txn), ["Queen"]);
+    assert_eq!(without_albums(&txn), ["ABBA", "Nobody"]);
+}
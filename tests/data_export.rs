@@ -0,0 +1,81 @@
+use rust_query::{
+    migration::{schema, Config},
+    DataExport, ImportValue, LocalClient,
+};
+
+#[schema]
+enum Schema {
+    Customer {
+        name: String,
+        email: String,
+    },
+    Invoice {
+        customer: Customer,
+        total: f64,
+    },
+    Track {
+        name: String,
+    },
+    InvoiceLine {
+        invoice: Invoice,
+        track: Track,
+        quantity: i64,
+    },
+}
+use v0::*;
+
+#[test]
+fn data_export() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let track = txn.insert(Track { name: "intro" });
+    let alice = txn.insert(Customer {
+        name: "alice",
+        email: "alice@example.com",
+    });
+    let bob = txn.insert(Customer {
+        name: "bob",
+        email: "bob@example.com",
+    });
+    for (customer, total) in [(alice, 1.5), (bob, 2.5), (alice, 3.5)] {
+        let invoice = txn.insert(Invoice { customer, total });
+        txn.insert(InvoiceLine {
+            invoice,
+            track,
+            quantity: 1,
+        });
+    }
+
+    let export = DataExport::new().redact::<Customer>("email", |_| ImportValue::Null);
+    let bundle = export.collect(&txn, alice);
+
+    let names: Vec<_> = bundle.tables.iter().map(|x| x.name.as_str()).collect();
+    // tracks are referenced by the invoice lines, but they do not belong to the customer
+    assert_eq!(names, ["customer", "invoice", "invoice_line"]);
+    let customers = &bundle.tables[0];
+    assert_eq!(customers.columns, ["id", "email", "name"]);
+    assert_eq!(
+        customers.rows,
+        [vec![
+            ImportValue::Integer(1),
+            ImportValue::Null,
+            ImportValue::from("alice")
+        ]]
+    );
+    let totals: Vec<_> = bundle.tables[1].rows.iter().map(|x| x[2].clone()).collect();
+    assert_eq!(totals, [ImportValue::Float(1.5), ImportValue::Float(3.5)]);
+    let lines: Vec<_> = bundle.tables[2].rows.iter().map(|x| x[0].clone()).collect();
+    assert_eq!(lines, [ImportValue::Integer(1), ImportValue::Integer(3)]);
+
+    #[cfg(feature = "serde")]
+    assert_eq!(
+        serde_json::to_value(&bundle).unwrap()["customer"],
+        serde_json::json!([{"id": 1, "name": "alice", "email": null}])
+    );
+}
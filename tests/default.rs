@@ -0,0 +1,86 @@
+use rust_query::{
+    migration::{schema, Alter, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+#[version(0..=1)]
+enum Schema {
+    Track {
+        name: String,
+        milliseconds: i64,
+        #[version(1..)]
+        #[default = 0]
+        plays: i64,
+        #[version(1..)]
+        #[default = "unknown"]
+        genre: String,
+        #[version(1..)]
+        #[default = -1.5]
+        score: f64,
+        #[version(1..)]
+        #[default = None]
+        rating: Option<i64>,
+    },
+}
+use v1::*;
+
+#[test]
+fn default_values() {
+    let mut client = LocalClient::try_new().unwrap();
+    let config = Config::open_in_memory().init_stmt(
+        "INSERT INTO track (name, milliseconds) VALUES ('It''s a Kind of Magic', 264000)",
+    );
+    let db = client
+        .migrator(config)
+        .unwrap()
+        .migrate(v1::update::Schema {
+            track: Box::new(|_| Alter::identity()),
+        })
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let intro = txn.insert(Track {
+        name: "Intro",
+        milliseconds: 1000,
+    });
+    txn.insert(
+        Track {
+            name: "Outro",
+            milliseconds: 2000,
+        }
+        .with_plays(3)
+        .with_genre("rock")
+        .with_rating(Some(5)),
+    );
+
+    // the update only changes the columns that are written
+    txn.update(intro, Track::dummy(intro).with_plays(intro.plays().add(1)));
+
+    let mut res = txn.query(|rows| {
+        let track = Track::join(rows);
+        rows.into_vec((
+            track.name(),
+            track.plays(),
+            track.genre(),
+            track.score(),
+            track.rating(),
+        ))
+    });
+    res.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        res,
+        [
+            ("Intro".to_owned(), 1, "unknown".to_owned(), -1.5, None),
+            (
+                "It's a Kind of Magic".to_owned(),
+                0,
+                "unknown".to_owned(),
+                -1.5,
+                None
+            ),
+            ("Outro".to_owned(), 3, "rock".to_owned(), -1.5, Some(5)),
+        ]
+    );
+}
@@ -0,0 +1,105 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table, TableRow,
+};
+
+#[schema]
+enum Schema {
+    Customer {
+        name: String,
+    },
+    Invoice {
+        customer: Customer,
+        total: f64,
+    },
+    Track {
+        name: String,
+    },
+    InvoiceLine {
+        invoice: Invoice,
+        track: Track,
+    },
+    Comment {
+        author: Customer,
+        reply_to: Option<Comment>,
+    },
+    Review {
+        #[on_delete(set_null)]
+        customer: Option<Customer>,
+        track: Track,
+    },
+}
+use v0::*;
+
+#[test]
+fn delete_graph() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let track = txn.insert(Track { name: "intro" });
+    let alice = txn.insert(Customer { name: "alice" });
+    let bob = txn.insert(Customer { name: "bob" });
+    for customer in [alice, bob, alice] {
+        let invoice = txn.insert(Invoice {
+            customer,
+            total: 1.0,
+        });
+        for _ in 0..3 {
+            txn.insert(InvoiceLine { invoice, track });
+        }
+    }
+    // bob replies to alice, so his comment is deleted too
+    let first = txn.insert(Comment {
+        author: alice,
+        reply_to: None::<TableRow<Comment>>,
+    });
+    let reply = txn.insert(Comment {
+        author: bob,
+        reply_to: Some(first),
+    });
+    txn.insert(Comment {
+        author: alice,
+        reply_to: Some(reply),
+    });
+    txn.insert(Review {
+        customer: Some(alice),
+        track,
+    });
+
+    let mut txn = txn.deletor();
+    let counts = txn.delete_graph(alice, Some(2)).unwrap();
+    let counts: Vec<_> = counts.iter().map(|(x, n)| (x.as_str(), *n)).collect();
+    assert_eq!(
+        counts,
+        [
+            ("invoice_line", 6),
+            ("invoice", 2),
+            ("comment", 3),
+            ("customer", 1)
+        ]
+    );
+    txn.commit();
+
+    let txn = client.transaction(&db);
+    let names = txn.query(|rows| {
+        let customer = Customer::join(rows);
+        rows.into_vec(customer.name())
+    });
+    assert_eq!(names, ["bob"]);
+    let lines = txn.query(|rows| {
+        InvoiceLine::join(rows);
+        rows.count()
+    });
+    assert_eq!(lines, 3);
+    // the review is kept without a customer
+    let reviews = txn.query(|rows| {
+        let review = Review::join(rows);
+        rows.into_vec(review.customer())
+    });
+    assert_eq!(reviews, [None]);
+}
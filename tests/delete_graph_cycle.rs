@@ -0,0 +1,77 @@
+use rust_query::{
+    migration::{schema, Config},
+    DeleteGraphError, ForeignKeyError, IntoColumn, LocalClient, Table, TableRow,
+};
+
+#[schema]
+enum Schema {
+    Author {
+        name: String,
+        favorite: Option<Book>,
+    },
+    Book {
+        author: Author,
+    },
+    Review {
+        book: Book,
+    },
+}
+use v0::*;
+
+#[test]
+fn delete_graph_cycle() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let author = txn.insert(Author {
+        name: "alice",
+        favorite: None::<TableRow<Book>>,
+    });
+    let book = txn.insert(Book { author });
+    // the reviews can be deleted before the cycle is found
+    txn.insert(Review { book });
+    txn.update(
+        author,
+        Author {
+            favorite: Some(book).into_column(),
+            ..Author::dummy(author)
+        },
+    );
+
+    let mut txn = txn.deletor();
+    let err = txn.delete_graph(author, None).unwrap_err();
+    assert_eq!(
+        err,
+        DeleteGraphError::ForeignKey(ForeignKeyError {
+            table: "book".to_owned(),
+            column: "author".to_owned(),
+            parent: "author".to_owned(),
+        })
+    );
+    assert_eq!(
+        err.to_string(),
+        "the foreign key `book.author` still references a row of `author`"
+    );
+
+    // nothing was deleted, so the transaction can still be committed
+    txn.commit();
+    let txn = client.transaction(&db);
+    let authors = txn.query(|rows| {
+        Author::join(rows);
+        rows.count()
+    });
+    let books = txn.query(|rows| {
+        Book::join(rows);
+        rows.count()
+    });
+    let reviews = txn.query(|rows| {
+        Review::join(rows);
+        rows.count()
+    });
+    assert_eq!((authors, books, reviews), (1, 1, 1));
+}
@@ -0,0 +1,55 @@
+use rust_query::{
+    migration::{schema, Config},
+    FromDummy, LocalClient, Table,
+};
+
+#[schema(derive(Debug, Clone, PartialEq))]
+enum Schema {
+    User { name: String, score: i64 },
+}
+use v0::*;
+
+#[derive(FromDummy, Debug, PartialEq)]
+#[dummy(derive(Debug, Clone, PartialEq))]
+struct Score {
+    name: String,
+    score: i64,
+}
+
+#[test]
+fn derive() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let fixture = User {
+        name: "alice",
+        score: 10,
+    };
+    assert_eq!(
+        format!("{fixture:?}"),
+        r#"User { name: "alice", score: 10 }"#
+    );
+
+    let mut txn = client.transaction_mut(&db);
+    txn.insert(fixture.clone());
+    txn.insert(fixture);
+
+    let dummy = ScoreDummy {
+        name: "bob",
+        score: 3,
+    };
+    assert_eq!(dummy.clone(), dummy);
+    let scores = txn.query(|rows| {
+        let user = User::join(rows);
+        rows.into_vec(ScoreDummy {
+            name: user.name(),
+            score: user.score(),
+        })
+    });
+    assert_eq!(scores[0], scores[1]);
+    assert_eq!(txn.query_one(dummy.clone()).name, "bob");
+}
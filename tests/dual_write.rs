@@ -0,0 +1,146 @@
+use rust_query::{
+    migration::{schema, Alter, Config},
+    IntoColumn, LocalClient, Table,
+};
+
+mod app {
+    #[rust_query::migration::schema]
+    pub enum Schema {
+        User { name: String },
+    }
+    pub use v0::*;
+}
+
+#[schema(namespace = "blog")]
+#[version(0..=1)]
+enum Blog {
+    Post {
+        title: String,
+        #[version(..1)]
+        draft: i64,
+    },
+    Comment {
+        post: Post,
+        #[version(..1)]
+        text: String,
+        #[version(1..)]
+        body: String,
+    },
+}
+
+fn migration<'t>() -> v1::update::Blog<'t> {
+    v1::update::Blog {
+        post: Box::new(|_| Alter::identity()),
+        comment: Box::new(|comment| {
+            Alter::new(v1::update::CommentMigration {
+                body: comment.text(),
+            })
+        }),
+    }
+}
+
+#[test]
+fn dual_write() {
+    let path = std::env::temp_dir().join(format!(
+        "rust_query_dual_write_{}.sqlite",
+        std::process::id()
+    ));
+    let mut client = LocalClient::try_new().unwrap();
+    let app = client
+        .migrator::<app::Schema>(Config::open(&path))
+        .unwrap()
+        .finish()
+        .unwrap();
+    let blog = client
+        .namespace_migrator::<_, v0::Blog>(&app)
+        .unwrap()
+        .finish()
+        .unwrap();
+    let mut txn = client.transaction_mut(&blog);
+    let first = txn.insert(v0::Post {
+        title: "first",
+        draft: 0,
+    });
+    txn.insert(v0::Comment {
+        post: first,
+        text: "hi",
+    });
+    txn.commit();
+    drop(blog);
+
+    // the new version copies the posts ahead of time
+    let mut expanded = vec![];
+    let blog = client
+        .namespace_migrator::<_, v0::Blog>(&app)
+        .unwrap()
+        .on_progress(|p| expanded.push((p.table, p.rows)))
+        .expand(migration())
+        .finish()
+        .unwrap();
+    assert_eq!(expanded, [("blog_post", 1)]);
+
+    // the old version keeps writing to the old tables
+    let mut txn = client.transaction_mut(&blog);
+    let first = txn
+        .query(|rows| {
+            let post = v0::Post::join(rows);
+            rows.into_vec(post)
+        })
+        .pop()
+        .unwrap();
+    txn.update(
+        first,
+        v0::Post {
+            title: "first!".into_column(),
+            ..v0::Post::dummy(first)
+        },
+    );
+    let second = txn.insert(v0::Post {
+        title: "second",
+        draft: 1,
+    });
+    txn.insert(v0::Comment {
+        post: second,
+        text: "nice",
+    });
+    let third = txn.insert(v0::Post {
+        title: "third",
+        draft: 1,
+    });
+    let mut txn = txn.deletor();
+    assert_eq!(txn.try_delete(third), Ok(true));
+    txn.commit();
+    drop(blog);
+
+    // only the comments are copied when the migration is applied
+    let mut migrated = vec![];
+    let blog = client
+        .namespace_migrator::<_, v0::Blog>(&app)
+        .unwrap()
+        .on_progress(|p| migrated.push((p.table, p.rows)))
+        .migrate(migration())
+        .finish()
+        .unwrap();
+    assert_eq!(migrated, [("blog_comment", 2)]);
+
+    let txn = client.transaction(&blog);
+    let mut comments = txn.query(|rows| {
+        let comment = v1::Comment::join(rows);
+        rows.into_vec((comment.post().title(), comment.body()))
+    });
+    comments.sort();
+    assert_eq!(
+        comments,
+        [
+            ("first!".to_owned(), "hi".to_owned()),
+            ("second".to_owned(), "nice".to_owned())
+        ]
+    );
+    let posts = txn.query(|rows| {
+        let post = v1::Post::join(rows);
+        rows.into_vec(post.title())
+    });
+    assert_eq!(posts.len(), 2);
+    drop(txn);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,87 @@
+use rust_query::{
+    migration::{schema, Config},
+    DynFilter, DynFilterError, DynOp, ImportValue, LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    User {
+        name: String,
+        age: i64,
+        score: f64,
+        email: Option<String>,
+    },
+}
+use v0::*;
+
+#[test]
+fn dyn_filter() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    for (name, age, score, email) in [
+        ("alice", 30, 1.5, Some("alice@example.com")),
+        ("anna", 17, 3.0, None),
+        ("bob", 40, 2.0, None),
+    ] {
+        txn.insert(User {
+            name,
+            age,
+            score,
+            email,
+        });
+    }
+
+    let names = |filter: &DynFilter| {
+        txn.query(|rows| {
+            let user = User::join(rows);
+            rows.filter_dyn(&user, filter)?;
+            Ok(rows.into_vec(user.name()))
+        })
+    };
+
+    let filter = DynFilter::new()
+        .and("name", "like".parse().unwrap(), "a%")
+        .and("age", DynOp::Ge, 18);
+    assert_eq!(names(&filter), Ok(vec!["alice".to_owned()]));
+
+    // integers can be compared with floats and null values with nullable columns
+    let filter =
+        DynFilter::new()
+            .and("score", DynOp::Gt, 1)
+            .and("email", DynOp::Eq, ImportValue::Null);
+    assert_eq!(
+        names(&filter),
+        Ok(vec!["anna".to_owned(), "bob".to_owned()])
+    );
+
+    assert_eq!(
+        names(&DynFilter::new().and("password", DynOp::Eq, "x")),
+        Err(DynFilterError::UnknownColumn("password".to_owned()))
+    );
+    assert_eq!(
+        names(&DynFilter::new().and("age", DynOp::Lt, "old")),
+        Err(DynFilterError::TypeMismatch {
+            column: "age".to_owned(),
+            op: DynOp::Lt,
+            value: ImportValue::Text("old".to_owned()),
+        })
+    );
+    assert_eq!(
+        names(&DynFilter::new().and("name", DynOp::Eq, ImportValue::Null)),
+        Err(DynFilterError::TypeMismatch {
+            column: "name".to_owned(),
+            op: DynOp::Eq,
+            value: ImportValue::Null,
+        })
+    );
+    assert_eq!(
+        "between".parse::<DynOp>(),
+        Err(DynFilterError::UnknownOperator("between".to_owned()))
+    );
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "sqlcipher")]
+
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    User { name: String },
+}
+use v0::*;
+
+fn count_users(path: &std::path::Path, key: &str) -> rusqlite::Result<i64> {
+    let conn = rusqlite::Connection::open(path)?;
+    conn.pragma_update(None, "key", key)?;
+    conn.query_row("SELECT count(*) FROM user", [], |row| row.get(0))
+}
+
+#[test]
+fn encrypted() {
+    let path = std::env::temp_dir().join(format!("rust_query_enc_{}.sqlite", std::process::id()));
+
+    // create an empty database that is encrypted with the old key
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.pragma_update(None, "key", "old").unwrap();
+    conn.pragma_update(None, "user_version", 0).unwrap();
+    drop(conn);
+
+    let config = Config::open_encrypted(&path, "old").rekey("new");
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client.migrator(config).unwrap().finish().unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    txn.insert(User {
+        name: "alice".to_owned(),
+    });
+    txn.commit();
+    let names = client.transaction(&db).query(|rows| {
+        let user = User::join(rows);
+        rows.into_vec(user.name())
+    });
+    assert_eq!(names, ["alice"]);
+
+    assert_eq!(count_users(&path, "new"), Ok(1));
+    assert!(count_users(&path, "old").is_err());
+    assert!(count_users(&path, "").is_err());
+
+    for ext in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{ext}", path.display()));
+    }
+}
@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use rust_query::{
+    migration::{schema, Config},
+    Error, LocalClient,
+};
+
+#[schema]
+enum Schema {
+    User { name: String },
+}
+use v0::*;
+
+#[test]
+fn typed_errors() {
+    let path = std::env::temp_dir().join(format!("rust_query_error_{}.sqlite", std::process::id()));
+    let config = Config::open(&path)
+        .busy_timeout(Duration::from_millis(10))
+        .busy_retries(0, Duration::ZERO);
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client.try_migrator(config).unwrap().unwrap();
+    let db = db.try_finish().unwrap().unwrap();
+
+    // another connection holds the write lock
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute_batch("BEGIN IMMEDIATE").unwrap();
+    let res = client.try_transaction_mut(&db).map(|_| ());
+    assert_eq!(res, Err(Error::Busy));
+    conn.execute_batch("COMMIT").unwrap();
+
+    let mut txn = client.try_transaction_mut(&db).unwrap();
+    txn.insert(User {
+        name: "alice".to_owned(),
+    });
+    txn.try_commit().unwrap();
+
+    for ext in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{ext}", path.display()));
+    }
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "serde")]
+
+use rust_query::{
+    migration::{schema, Config},
+    FromDummy, LocalClient, Table, TableRow,
+};
+use serde::Serialize;
+
+#[schema]
+enum Schema {
+    User { name: String, score: Option<f64> },
+}
+use v0::*;
+
+#[derive(FromDummy, Serialize)]
+struct UserInfo<'t> {
+    id: TableRow<'t, User>,
+    name: String,
+    score: Option<f64>,
+}
+
+#[test]
+fn export_results() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    txn.insert(User {
+        name: "alice",
+        score: Some(1.5),
+    });
+    txn.insert(User {
+        name: "bob \"the, builder\"",
+        score: None::<f64>,
+    });
+
+    let (csv, json, tuples) = txn.query(|rows| {
+        let user = User::join(rows);
+        let info = || UserInfoDummy {
+            id: &user,
+            name: user.name(),
+            score: user.score(),
+        };
+        let mut csv = vec![];
+        rows.into_csv(info(), &mut csv).unwrap();
+        let mut json = vec![];
+        rows.into_json_lines(info(), &mut json).unwrap();
+        let mut tuples = vec![];
+        rows.into_json_lines((user.name(), 1), &mut tuples).unwrap();
+        (csv, json, tuples)
+    });
+
+    let csv = String::from_utf8(csv).unwrap();
+    assert_eq!(
+        csv,
+        "id,name,score\n1,alice,1.5\n2,\"bob \"\"the, builder\"\"\",\n"
+    );
+
+    let json = String::from_utf8(json).unwrap();
+    for line in json.lines() {
+        // every line is valid json
+        serde_json::from_str::<serde_json::Value>(line).unwrap();
+    }
+    assert_eq!(
+        json,
+        "{\"id\":1,\"name\":\"alice\",\"score\":1.5}\n\
+        {\"id\":2,\"name\":\"bob \\\"the, builder\\\"\",\"score\":null}\n"
+    );
+
+    let tuples = String::from_utf8(tuples).unwrap();
+    assert_eq!(tuples, "[\"alice\",1]\n[\"bob \\\"the, builder\\\"\",1]\n");
+}
@@ -0,0 +1,122 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use rust_query::{
+    migration::{schema, schema_hash, Config},
+    Database, Error, LocalClient,
+};
+
+#[schema(application_id = 0x52515259)]
+enum Schema {
+    User { name: String },
+}
+use v0::*;
+
+mod collated {
+    #[rust_query::migration::schema]
+    pub enum Schema {
+        Post {
+            #[collate("'nocase'")]
+            title: String,
+        },
+    }
+    pub use v0::*;
+}
+
+mod defaulted {
+    #[rust_query::migration::schema]
+    pub enum Schema {
+        Post {
+            #[default = "nocase"]
+            title: String,
+        },
+    }
+    pub use v0::*;
+}
+
+mod indexed {
+    #[rust_query::migration::schema]
+    pub enum Schema {
+        #[fts5(title)]
+        Post { title: String },
+    }
+    pub use v0::*;
+}
+
+mod checked {
+    #[rust_query::migration::schema]
+    pub enum Schema {
+        #[check("title")]
+        Post { title: String },
+    }
+    pub use v0::*;
+}
+
+/// Restore a backup after running `sql` on it, this returns the error or panic message if it fails.
+fn restore_modified(db: &mut Database<Schema>, sql: &str) -> Result<(), String> {
+    let path = std::env::temp_dir().join(format!(
+        "rust_query_fingerprint_{}.sqlite",
+        std::process::id()
+    ));
+    db.backup_to(&path).unwrap();
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute_batch(sql).unwrap();
+    drop(conn);
+    let res = catch_unwind(AssertUnwindSafe(|| db.restore_from(&path)));
+    std::fs::remove_file(&path).unwrap();
+    match res {
+        Ok(res) => res.map_err(|err| err.to_string()),
+        Err(payload) => Err(*payload.downcast::<String>().unwrap()),
+    }
+}
+
+#[test]
+fn fingerprint() {
+    let mut client = LocalClient::try_new().unwrap();
+    let mut db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    txn.insert(User { name: "alice" });
+    txn.commit();
+
+    let res = restore_modified(&mut db, "PRAGMA application_id = 42");
+    let err = Error::ApplicationId {
+        expected: 0x52515259,
+        found: 42,
+    };
+    assert_eq!(res, Err(err.to_string()));
+
+    let res = restore_modified(&mut db, "UPDATE _schema SET hash = 'abc'");
+    let err = Error::SchemaHash {
+        version: 0,
+        expected: schema_hash::<Schema>(),
+        found: "abc".to_owned(),
+    };
+    assert_eq!(res, Err(err.to_string()));
+
+    // the unmodified backup can be restored
+    assert_eq!(restore_modified(&mut db, ""), Ok(()));
+
+    // a hash of an older format is not compared, like in files of older versions without the format column
+    let res = restore_modified(&mut db, "UPDATE _schema SET hash = 'abc', format = 0");
+    assert_eq!(res, Ok(()));
+    let res = restore_modified(
+        &mut db,
+        "UPDATE _schema SET hash = 'abc'; ALTER TABLE _schema DROP COLUMN format",
+    );
+    assert_eq!(res, Ok(()));
+
+    // the same text as collation or default value gives a different hash
+    assert_ne!(
+        schema_hash::<collated::Schema>(),
+        schema_hash::<defaulted::Schema>()
+    );
+    // the same text as indexed column or check gives a different hash
+    assert_ne!(
+        schema_hash::<indexed::Schema>(),
+        schema_hash::<checked::Schema>()
+    );
+}
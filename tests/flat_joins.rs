@@ -0,0 +1,63 @@
+use std::sync::Mutex;
+
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    Artist { name: String },
+    Album { artist: Artist, title: String },
+    Track { album: Album, name: String },
+    InvoiceLine { track: Track, quantity: i64 },
+}
+use v0::*;
+
+static TRACES: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+#[test]
+fn flat_joins() {
+    let config = Config::open_in_memory()
+        .init_stmt("INSERT INTO artist (name) VALUES ('queen')")
+        .init_stmt("INSERT INTO album (artist, title) VALUES (1, 'jazz')")
+        .init_stmt("INSERT INTO track (album, name) VALUES (1, 'mustapha')")
+        .init_stmt("INSERT INTO invoice_line (track, quantity) VALUES (1, 2)")
+        .trace(|trace| TRACES.lock().unwrap().push(trace.sql.to_owned()));
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client.migrator(config).unwrap().finish().unwrap();
+    let txn = client.transaction(&db);
+
+    TRACES.lock().unwrap().clear();
+    let res = txn.query(|rows| {
+        let ivl = InvoiceLine::join(rows);
+        rows.into_vec((
+            ivl.track().album().artist().name(),
+            ivl.track().album().title(),
+            ivl.track().name(),
+            ivl.track().album().artist().name(),
+            ivl.quantity(),
+        ))
+    });
+    assert_eq!(
+        res,
+        [(
+            "queen".to_owned(),
+            "jazz".to_owned(),
+            "mustapha".to_owned(),
+            "queen".to_owned(),
+            2
+        )]
+    );
+
+    let traces = std::mem::take(&mut *TRACES.lock().unwrap());
+    let [sql] = &traces[..] else { panic!() };
+    // every navigation step reuses the join of the previous navigations
+    for table in ["invoice_line", "track", "album", "artist"] {
+        let count = sql
+            .split_whitespace()
+            .filter(|word| word.trim_matches('"') == table)
+            .count();
+        assert_eq!(count, 1, "{sql}");
+    }
+}
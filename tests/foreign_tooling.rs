@@ -0,0 +1,59 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    #[check("price >= 0")]
+    Item {
+        #[collate("nocase")]
+        name: String,
+        price: f64,
+        #[generated("price * 2")]
+        double: f64,
+    },
+}
+use v0::*;
+
+#[test]
+fn foreign_tooling() {
+    let path = std::env::temp_dir().join(format!(
+        "rust_query_foreign_tooling_{}.sqlite",
+        std::process::id()
+    ));
+    // the table is created by other tooling, with other quoting, spacing and case
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute_batch(
+        "create table item (
+            id INTEGER PRIMARY KEY,
+            [name] TEXT NOT NULL collate `nocase`,
+            price REAL NOT NULL,
+            `double` REAL NOT NULL as (price * 2),
+            constraint positive check(price >= 0)
+        );
+        INSERT INTO item (name, price) VALUES ('pen', 1.5);",
+    )
+    .unwrap();
+    drop(conn);
+
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .try_migrator::<Schema>(Config::open(&path))
+        .unwrap()
+        .unwrap()
+        .finish()
+        .unwrap();
+    let txn = client.transaction(&db);
+    let items = txn.query(|rows| {
+        let item = Item::join(rows);
+        rows.into_vec((item.name(), item.double()))
+    });
+    assert_eq!(items, [("pen".to_owned(), 3.0)]);
+    drop(txn);
+    drop(db);
+
+    for ext in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{ext}", path.display()));
+    }
+}
@@ -0,0 +1,67 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table, Transaction,
+};
+
+#[schema]
+enum Schema {
+    #[fts5(title, body)]
+    Post { title: String, body: String },
+}
+use v0::*;
+
+fn search(txn: &Transaction<Schema>, query: &str) -> Vec<String> {
+    txn.query(|rows| {
+        let post = Post::join(rows);
+        rows.filter(post.match_(query));
+        // results are sorted by rank first, the best match has the lowest rank
+        let res = rows.into_vec((post.match_rank(query), post.title()));
+        res.into_iter().map(|(_rank, title)| title).collect()
+    })
+}
+
+#[test]
+fn full_text_search() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let intro = txn.insert(Post {
+        title: "intro",
+        body: "rust query is a query builder",
+    });
+    let sqlite = txn.insert(Post {
+        title: "sqlite",
+        body: "rust query uses sqlite",
+    });
+    txn.insert(Post {
+        title: "other",
+        body: "nothing to see here",
+    });
+
+    assert_eq!(search(&txn, "query"), ["intro", "sqlite"]);
+    assert_eq!(search(&txn, "sqlite"), ["sqlite"]);
+    assert_eq!(search(&txn, "missing"), Vec::<String>::new());
+
+    txn.try_update(
+        sqlite,
+        Post {
+            title: "sqlite",
+            body: "a database",
+        },
+    )
+    .unwrap();
+    assert_eq!(search(&txn, "query"), ["intro"]);
+    assert_eq!(search(&txn, "database"), ["sqlite"]);
+
+    let mut txn = txn.deletor();
+    assert!(txn.try_delete(intro).unwrap());
+    txn.commit();
+
+    let txn = client.transaction(&db);
+    assert_eq!(search(&txn, "query"), Vec::<String>::new());
+}
@@ -0,0 +1,44 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    City { name: String, x: f64, y: f64 },
+}
+use v0::*;
+
+#[test]
+fn function() {
+    let config = Config::open_in_memory().init_stmt(
+        "INSERT INTO city (name, x, y) VALUES ('here', 1.0, 1.0), ('there', 30.0, 40.0)",
+    );
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client.migrator(config).unwrap().finish().unwrap();
+
+    let distance = db.function("distance", |x: f64, y: f64| x.hypot(y));
+    let initials = db.function("initials", |name: String| {
+        name.chars().next().map(|c| c.to_uppercase().to_string())
+    });
+
+    let txn = client.transaction(&db);
+    let res = txn.query(|rows| {
+        let city = City::join(rows);
+        let distance = distance.call(city.x(), city.y());
+        rows.filter(distance.lt(10.0).not());
+        rows.into_vec((city.name(), distance, initials.call(city.name())))
+    });
+    assert_eq!(res, [("there".to_owned(), 50.0, Some("T".to_owned()))]);
+    drop(txn);
+
+    // the copy can also use the functions
+    let copy = db.clone_in_memory();
+    let txn = client.transaction(&copy);
+    let count = txn.query(|rows| {
+        let city = City::join(rows);
+        rows.filter(distance.call(city.x(), city.y()).lt(10.0));
+        rows.count()
+    });
+    assert_eq!(count, 1);
+}
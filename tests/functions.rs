@@ -0,0 +1,41 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    Item { name: String, low: i64, high: i64 },
+}
+use v0::*;
+
+#[test]
+fn functions() {
+    let config = Config::open_in_memory()
+        .init_stmt("INSERT INTO item (name, low, high) VALUES ('  Hello World ', 3, 1)");
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client.migrator(config).unwrap().finish().unwrap();
+    let txn = client.transaction(&db);
+
+    let res = txn.query(|rows| {
+        let item = Item::join(rows);
+        let name = item.name().trim();
+        rows.into_vec((
+            (name.length(), name.lower(), name.upper()),
+            (
+                name.substr(7, 3),
+                name.replace("o", "0"),
+                name.instr("World"),
+            ),
+            (item.low().min(item.high()), item.low().max(item.high())),
+        ))
+    });
+    assert_eq!(
+        res,
+        [(
+            (11, "hello world".to_owned(), "HELLO WORLD".to_owned()),
+            ("Wor".to_owned(), "Hell0 W0rld".to_owned(), 7),
+            (1, 3)
+        )]
+    );
+}
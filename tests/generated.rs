@@ -0,0 +1,72 @@
+use rust_query::{
+    migration::{schema, Alter, Config},
+    IntoColumn, LocalClient, Table,
+};
+
+#[schema]
+#[version(0..=1)]
+enum Schema {
+    OrderLine {
+        product: String,
+        price: f64,
+        quantity: i64,
+        #[version(1..)]
+        #[generated("price * quantity")]
+        total: f64,
+        #[version(1..)]
+        #[generated("upper(product) || ', ' || quantity", stored)]
+        label: String,
+    },
+}
+use v1::*;
+
+#[test]
+fn generated_columns() {
+    let mut client = LocalClient::try_new().unwrap();
+    let config = Config::open_in_memory()
+        .init_stmt("INSERT INTO order_line (product, price, quantity) VALUES ('apple', 0.5, 4)");
+    let db = client
+        .migrator(config)
+        .unwrap()
+        .migrate(v1::update::Schema {
+            order_line: Box::new(|_| Alter::identity()),
+        })
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let pear = txn.insert(OrderLine {
+        product: "pear",
+        price: 1.25,
+        quantity: 2,
+    });
+
+    let lines = |txn: &rust_query::Transaction<Schema>| {
+        txn.query(|rows| {
+            let line = OrderLine::join(rows);
+            rows.filter(line.total().lt(10.0));
+            rows.into_vec((line.label(), line.total()))
+        })
+    };
+    let mut res = lines(&txn);
+    res.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        res,
+        [("APPLE, 4".to_owned(), 2.0), ("PEAR, 2".to_owned(), 2.5)]
+    );
+
+    // generated columns are updated with the row
+    txn.update(
+        pear,
+        OrderLine {
+            quantity: 1i64.into_column(),
+            ..OrderLine::dummy(pear)
+        },
+    );
+    let mut res = lines(&txn);
+    res.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        res,
+        [("APPLE, 4".to_owned(), 2.0), ("PEAR, 1".to_owned(), 1.25)]
+    );
+}
@@ -0,0 +1,78 @@
+use rust_query::{
+    migration::{schema, Config},
+    Database, LocalClient, Table, Transaction,
+};
+
+#[schema]
+enum Schema {
+    Genre { name: String },
+    Track { genre: Genre, milliseconds: i64 },
+}
+use v0::*;
+
+fn setup(client: &mut LocalClient) -> Database<Schema> {
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+    let mut txn = client.transaction_mut(&db);
+    let rock = txn.insert(Genre { name: "rock" });
+    let jazz = txn.insert(Genre { name: "jazz" });
+    txn.insert(Genre { name: "pop" });
+    for milliseconds in [100, 200, 300] {
+        txn.insert(Track {
+            genre: rock,
+            milliseconds,
+        });
+    }
+    txn.insert(Track {
+        genre: jazz,
+        milliseconds: 1000,
+    });
+    txn.commit();
+    db
+}
+
+fn genre_stats(txn: &Transaction<Schema>) -> Vec<(String, i64, i64)> {
+    txn.query(|rows| {
+        let track = Track::join(rows);
+        rows.group_by(|group| {
+            let genre = group.key(track.genre());
+            group.into_vec((genre.name(), group.count(), group.sum(track.milliseconds())))
+        })
+    })
+}
+
+fn long_genres(txn: &Transaction<Schema>, min_total: i64) -> Vec<(String, i64)> {
+    txn.query(|rows| {
+        let track = Track::join(rows);
+        rows.group_by(|group| {
+            let genre = group.key(track.genre());
+            let total = group.sum(track.milliseconds());
+            group.filter(total.lt(min_total).not());
+            group.into_vec((genre.name(), total))
+        })
+    })
+}
+
+#[test]
+fn group_by() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = setup(&mut client);
+    let txn = client.transaction(&db);
+
+    // genres without tracks are not in any group
+    assert_eq!(
+        genre_stats(&txn),
+        [("jazz".to_owned(), 1, 1000), ("rock".to_owned(), 3, 600)]
+    );
+
+    // the groups are filtered with `HAVING`
+    assert_eq!(
+        long_genres(&txn, 600),
+        [("jazz".to_owned(), 1000), ("rock".to_owned(), 600)]
+    );
+    assert_eq!(long_genres(&txn, 601), [("jazz".to_owned(), 1000)]);
+    assert!(long_genres(&txn, 2000).is_empty());
+}
@@ -0,0 +1,60 @@
+use rust_query::{
+    aggregate,
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    Employee {
+        name: String,
+        reports_to: Option<Employee>,
+    },
+}
+use v0::*;
+
+#[test]
+fn hierarchy() {
+    let config = Config::open_in_memory().init_stmt(
+        "INSERT INTO employee (name, reports_to) VALUES
+            ('ceo', NULL), ('cto', 1), ('cfo', 1), ('dev', 2), ('intern', 4)",
+    );
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client.migrator(config).unwrap().finish().unwrap();
+    let txn = client.transaction(&db);
+
+    let bosses = txn.query(|rows| {
+        let employee = Employee::join(rows);
+        rows.filter(employee.name().eq("intern"));
+        let boss = employee.ancestors(rows, 2);
+        rows.into_vec(boss.name())
+    });
+    assert_eq!(bosses, ["cto", "dev"]);
+
+    let team_sizes = txn.query(|rows| {
+        let employee = Employee::join(rows);
+        let team = aggregate(|rows| {
+            let member = employee.descendants(rows, 10);
+            rows.count_distinct(member)
+        });
+        rows.into_vec((employee.name(), team))
+    });
+    assert_eq!(
+        team_sizes,
+        [
+            ("ceo".to_owned(), 4),
+            ("cfo".to_owned(), 0),
+            ("cto".to_owned(), 2),
+            ("dev".to_owned(), 1),
+            ("intern".to_owned(), 0),
+        ]
+    );
+
+    // nothing is joined when the depth is zero
+    let count = txn.query(|rows| {
+        let employee = Employee::join(rows);
+        employee.descendants(rows, 0);
+        rows.count()
+    });
+    assert_eq!(count, 0);
+}
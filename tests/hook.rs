@@ -0,0 +1,82 @@
+use std::sync::{Arc, Mutex};
+
+use rust_query::{
+    migration::{schema, Config},
+    HookEvent, IntoColumn, LocalClient,
+};
+
+#[schema]
+enum Schema {
+    #[soft_delete]
+    User {
+        name: String,
+    },
+    Note {
+        text: String,
+    },
+}
+use v0::*;
+
+#[test]
+fn row_hooks() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let log = Arc::new(Mutex::new(vec![]));
+    for event in [
+        HookEvent::AfterInsert,
+        HookEvent::BeforeUpdate,
+        HookEvent::AfterUpdate,
+        HookEvent::BeforeDelete,
+    ] {
+        let log = log.clone();
+        db.hook::<User>(event, move |txn, user| {
+            let name = txn.query_one(user.name());
+            log.lock().unwrap().push((event, name));
+        });
+    }
+    let note_log = log.clone();
+    db.hook::<Note>(HookEvent::BeforeDelete, move |txn, note| {
+        let text = txn.query_one(note.text());
+        note_log
+            .lock()
+            .unwrap()
+            .push((HookEvent::BeforeDelete, text));
+    });
+
+    let mut txn = client.transaction_mut(&db);
+    let alice = txn.insert(User { name: "alice" });
+    txn.update(
+        alice,
+        User {
+            name: "alicia".into_column(),
+        },
+    );
+    assert!(txn.delete(alice));
+    // the row is already deleted, so the hooks are not called
+    assert!(!txn.delete(alice));
+    assert!(txn.restore(alice));
+    let note = txn.insert(Note { text: "todo" });
+
+    let mut txn = txn.deletor();
+    assert_eq!(txn.try_delete(note), Ok(true));
+    txn.commit();
+
+    use HookEvent::*;
+    let expected = [
+        (AfterInsert, "alice"),
+        (BeforeUpdate, "alice"),
+        (AfterUpdate, "alicia"),
+        (BeforeDelete, "alicia"),
+        (BeforeUpdate, "alicia"),
+        (AfterUpdate, "alicia"),
+        (BeforeDelete, "todo"),
+    ];
+    let log = log.lock().unwrap();
+    let log: Vec<_> = log.iter().map(|(e, name)| (*e, name.as_str())).collect();
+    assert_eq!(log, expected);
+}
@@ -0,0 +1,68 @@
+use rust_query::{
+    migration::{schema, Alter, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+#[version(0..=1)]
+enum Schema {
+    Artist {
+        name: String,
+    },
+    Album {
+        title: String,
+        #[version(..1)]
+        year: i64,
+        #[version(..1)]
+        artist: Artist,
+        #[version(1..)]
+        #[on_delete(cascade)]
+        artist: Artist,
+    },
+}
+use v1::*;
+
+#[test]
+fn identity_migration() {
+    let mut client = LocalClient::try_new().unwrap();
+    let config = Config::open_in_memory().init_stmt(
+        "INSERT INTO artist (id, name) VALUES (1, 'Queen'), (2, 'ABBA');
+        INSERT INTO album (id, title, year, artist) VALUES
+            (5, 'Jazz', 1978, 1), (7, 'Arrival', 1976, 2), (9, 'Innuendo', 1991, 1);",
+    );
+
+    let mut copied = vec![];
+    let db = client
+        .migrator(config)
+        .unwrap()
+        .on_progress(|p| copied.push((p.table, p.rows)))
+        .migrate(v1::update::Schema {
+            album: Box::new(|_| Alter::identity()),
+        })
+        .finish()
+        .unwrap();
+    assert_eq!(copied, [("album", 3)]);
+
+    let txn = client.transaction_mut(&db);
+    let queen = txn
+        .query(|rows| {
+            let artist = Artist::join(rows);
+            rows.filter(artist.name().eq("Queen"));
+            rows.into_vec(artist)
+        })
+        .pop()
+        .unwrap();
+
+    // the albums of queen are deleted with the new foreign key action
+    let mut del = txn.deletor();
+    assert_eq!(del.try_delete(queen), Ok(true));
+    del.commit();
+
+    let txn = client.transaction(&db);
+
+    let albums = txn.query(|rows| {
+        let album = Album::join(rows);
+        rows.into_vec((album.title(), album.artist().name()))
+    });
+    assert_eq!(albums, [("Arrival".to_owned(), "ABBA".to_owned())]);
+}
@@ -0,0 +1,48 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient,
+};
+
+#[schema]
+enum Schema {
+    User { name: String },
+}
+use v0::*;
+
+#[test]
+fn immediate() {
+    let path = std::env::temp_dir().join(format!(
+        "rust_query_immediate_{}.sqlite",
+        std::process::id()
+    ));
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open(&path))
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.busy_timeout(std::time::Duration::ZERO).unwrap();
+
+    // the write lock is taken before anything is written
+    let mut txn = client.transaction_mut(&db);
+    let err = conn.execute_batch("BEGIN IMMEDIATE").unwrap_err();
+    assert_eq!(
+        err.sqlite_error_code(),
+        Some(rusqlite::ErrorCode::DatabaseBusy)
+    );
+    // reading is still possible
+    let count: i64 = conn
+        .query_row("SELECT count(*) FROM user", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(count, 0);
+
+    txn.insert(User { name: "alice" });
+    txn.commit();
+    conn.execute_batch("BEGIN IMMEDIATE; COMMIT").unwrap();
+
+    for ext in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{ext}", path.display()));
+    }
+}
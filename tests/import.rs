@@ -0,0 +1,98 @@
+use rust_query::{
+    migration::{schema, Config},
+    ImportError, ImportOptions, LineError, LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    Artist {
+        #[unique_name]
+        name: String,
+    },
+    Track {
+        artist: Artist,
+        title: String,
+        rating: Option<f64>,
+    },
+}
+use v0::*;
+
+const ARTISTS: &str = "\
+id,name
+1,Queen
+2,\"Earth, Wind & Fire\"
+
+3,Queen
+";
+
+const TRACKS: &str = "\
+title,artist,rating
+\"Bohemian
+Rhapsody\",1,4.5
+September,2,
+Unknown,9,1
+Broken,1,high
+";
+
+#[test]
+fn import_csv() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let options = ImportOptions {
+        batch_size: 2,
+        ..Default::default()
+    };
+    let report = db
+        .import_csv::<Artist>(ARTISTS.as_bytes(), options)
+        .unwrap();
+    assert_eq!(report.inserted, 2);
+    // the name of the third artist is not unique
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].line, 5);
+
+    let report = db.import_csv::<Track>(TRACKS.as_bytes(), options).unwrap();
+    assert_eq!(report.inserted, 2);
+    let lines: Vec<_> = report.errors.iter().map(|err| err.line).collect();
+    // artist 9 does not exist and the rating is not a number
+    assert_eq!(lines, [5, 6]);
+    assert_eq!(
+        report.errors[1],
+        LineError {
+            line: 6,
+            message: "column `rating` can not be `high`".to_owned()
+        }
+    );
+
+    // the header is checked before importing
+    let res = db.import_csv::<Track>("title,length\n".as_bytes(), options);
+    assert_eq!(res, Err(ImportError::UnknownColumn("length".to_owned())));
+    let res = db.import_csv::<Track>("title,rating\nJazz,1\n".as_bytes(), options);
+    assert_eq!(res, Err(ImportError::MissingColumn("artist".to_owned())));
+
+    let txn = client.transaction(&db);
+    let mut tracks = txn.query(|rows| {
+        let track = Track::join(rows);
+        rows.into_vec((track.title(), track.artist().name(), track.rating()))
+    });
+    tracks.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        tracks,
+        [
+            (
+                "Bohemian\nRhapsody".to_owned(),
+                "Queen".to_owned(),
+                Some(4.5)
+            ),
+            (
+                "September".to_owned(),
+                "Earth, Wind & Fire".to_owned(),
+                None
+            ),
+        ]
+    );
+}
@@ -0,0 +1,124 @@
+use rust_query::{
+    migration::{schema, Config},
+    ImportError, ImportValue, Importer, LineError, LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    Artist {
+        #[unique_name]
+        name: String,
+    },
+    Track {
+        artist: Artist,
+        title: String,
+        rating: Option<f64>,
+    },
+}
+use v0::*;
+
+/// Rows from another database, the source fails after `fail_after` rows if it is set.
+struct Rows {
+    columns: &'static [&'static str],
+    rows: std::vec::IntoIter<Vec<ImportValue>>,
+    fail_after: Option<usize>,
+}
+
+impl Rows {
+    fn new(columns: &'static [&'static str], rows: Vec<Vec<ImportValue>>) -> Self {
+        Self {
+            columns,
+            rows: rows.into_iter(),
+            fail_after: None,
+        }
+    }
+}
+
+impl Importer for Rows {
+    fn columns(&self) -> Vec<String> {
+        self.columns.iter().map(|x| x.to_string()).collect()
+    }
+
+    fn next_row(&mut self) -> Result<Option<Vec<ImportValue>>, ImportError> {
+        if let Some(n) = &mut self.fail_after {
+            if *n == 0 {
+                return Err(ImportError::Io("connection reset".to_owned()));
+            }
+            *n -= 1;
+        }
+        Ok(self.rows.next())
+    }
+}
+
+#[test]
+fn import_rows() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let artists = Rows::new(
+        &["id", "name"],
+        vec![
+            vec![1.into(), "Queen".to_owned().into()],
+            vec![2.into(), "ABBA".to_owned().into()],
+            vec![3.into(), "Queen".to_owned().into()],
+        ],
+    );
+    let report = db.import::<Artist>(artists).unwrap();
+    assert_eq!(report.inserted, 2);
+    // the name of the third artist is not unique
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].line, 3);
+
+    let tracks = || {
+        Rows::new(
+            &["title", "artist", "rating"],
+            vec![
+                vec!["Innuendo".to_owned().into(), 1.into(), 4.into()],
+                vec!["Waterloo".to_owned().into(), 2.into(), ImportValue::Null],
+                vec![
+                    "Unknown".to_owned().into(),
+                    1.into(),
+                    "high".to_owned().into(),
+                ],
+            ],
+        )
+    };
+
+    // nothing is imported when the source fails
+    let res = db.import::<Track>(Rows {
+        fail_after: Some(1),
+        ..tracks()
+    });
+    assert_eq!(res, Err(ImportError::Io("connection reset".to_owned())));
+
+    let report = db.import::<Track>(tracks()).unwrap();
+    assert_eq!(report.inserted, 2);
+    assert_eq!(
+        report.errors,
+        [LineError {
+            line: 3,
+            message: "column `rating` can not be `high`".to_owned()
+        }]
+    );
+
+    let res = db.import::<Track>(Rows::new(&["title", "length"], vec![]));
+    assert_eq!(res, Err(ImportError::UnknownColumn("length".to_owned())));
+
+    let txn = client.transaction(&db);
+    let mut tracks = txn.query(|rows| {
+        let track = Track::join(rows);
+        rows.into_vec((track.title(), track.artist().name(), track.rating()))
+    });
+    tracks.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        tracks,
+        [
+            ("Innuendo".to_owned(), "Queen".to_owned(), Some(4.0)),
+            ("Waterloo".to_owned(), "ABBA".to_owned(), None),
+        ]
+    );
+}
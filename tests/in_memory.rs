@@ -0,0 +1,69 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use rust_query::{
+    migration::{schema, Config},
+    AuditOperation, Database, HookEvent, LocalClient, RowId, Table,
+};
+
+#[schema]
+enum Schema {
+    User { name: String },
+}
+use v0::*;
+
+fn user_names(client: &mut LocalClient, db: &Database<Schema>) -> Vec<String> {
+    let txn = client.transaction(db);
+    txn.query(|rows| {
+        let user = User::join(rows);
+        rows.into_vec(user.name())
+    })
+}
+
+#[test]
+fn clone_in_memory() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory().audit())
+        .unwrap()
+        .finish()
+        .unwrap();
+    let inserts = Arc::new(AtomicUsize::new(0));
+    db.hook::<User>(HookEvent::AfterInsert, {
+        let inserts = inserts.clone();
+        move |_, _| {
+            inserts.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+
+    let mut txn = client.transaction_mut(&db);
+    txn.insert(User { name: "alice" });
+    txn.commit();
+
+    let copy = db.clone_in_memory();
+    let mut txn = client.transaction_mut(&copy);
+    let bob = RowId::from(txn.insert(User { name: "bob" }));
+    txn.commit();
+
+    // the copy has the same hooks and audit log
+    assert_eq!(inserts.load(Ordering::Relaxed), 2);
+    let txn = client.transaction(&copy);
+    let history = txn.audit_history(bob);
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].operation, AuditOperation::Insert);
+    drop(txn);
+
+    assert_eq!(user_names(&mut client, &db), ["alice"]);
+    assert_eq!(user_names(&mut client, &copy), ["alice", "bob"]);
+
+    // copies can be used from other threads
+    let copy = db.clone_in_memory();
+    std::thread::spawn(move || {
+        let mut client = LocalClient::try_new().unwrap();
+        assert_eq!(user_names(&mut client, &copy), ["alice"]);
+    })
+    .join()
+    .unwrap();
+}
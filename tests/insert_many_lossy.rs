@@ -0,0 +1,42 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table, WriteError,
+};
+
+#[schema]
+enum Schema {
+    #[unique(email)]
+    User {
+        #[check("length(email) > 3")]
+        email: String,
+    },
+}
+use v0::*;
+
+#[test]
+fn insert_many_lossy() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let emails = ["a@b.c", "x", "d@e.f", "a@b.c"];
+    let report = txn.insert_many_lossy(emails.iter().map(|email| User { email }));
+    assert_eq!(report.inserted.len(), 2);
+    let [(1, WriteError::Check(_)), (3, WriteError::Conflict(conflict))] = &report.failed[..]
+    else {
+        panic!("unexpected errors: {:?}", report.failed)
+    };
+    assert_eq!(*conflict, report.inserted[0]);
+    txn.commit();
+
+    let txn = client.transaction(&db);
+    let emails = txn.query(|rows| {
+        let user = User::join(rows);
+        rows.into_vec(user.email())
+    });
+    assert_eq!(emails, ["a@b.c", "d@e.f"]);
+}
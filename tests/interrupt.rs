@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, QueryError, Table, Transaction,
+};
+
+#[schema]
+enum Schema {
+    Number { value: i64 },
+}
+use v0::*;
+
+// counting the combinations of many rows takes a long time
+fn slow_count(txn: &Transaction<Schema>) -> Result<i64, QueryError> {
+    txn.try_query(|rows| {
+        for _ in 0..5 {
+            Number::join(rows);
+        }
+        rows.count()
+    })
+}
+
+#[test]
+fn interrupt() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    for value in 0..100 {
+        txn.insert(Number { value });
+    }
+    txn.commit();
+
+    let txn = client.transaction(&db);
+    let res = txn.query_timeout(Duration::from_millis(50), |rows| {
+        for _ in 0..5 {
+            Number::join(rows);
+        }
+        rows.count()
+    });
+    assert_eq!(res, Err(QueryError::Timeout));
+    // the deadline only applies to the queries in the closure
+    let count = txn.query(|rows| {
+        Number::join(rows);
+        rows.count()
+    });
+    assert_eq!(count, 100);
+
+    let handle = db.interrupt_handle();
+    let thread = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        handle.interrupt();
+    });
+    assert_eq!(slow_count(&txn), Err(QueryError::Interrupted));
+    thread.join().unwrap();
+    // slow queries in the same transaction are stopped too
+    assert_eq!(slow_count(&txn), Err(QueryError::Interrupted));
+    drop(txn);
+
+    // new transactions are not affected
+    let txn = client.transaction(&db);
+    let count = txn.query(|rows| {
+        Number::join(rows);
+        rows.count()
+    });
+    assert_eq!(count, 100);
+}
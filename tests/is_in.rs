@@ -0,0 +1,71 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table, TableRow, Transaction,
+};
+
+#[schema]
+enum Schema {
+    Genre { name: String },
+    Track { genre: Genre, name: String },
+}
+use v0::*;
+
+fn genres_named(txn: &Transaction<Schema>, names: &[&str]) -> Vec<String> {
+    txn.query(|rows| {
+        let genre = Genre::join(rows);
+        rows.filter(genre.name().is_in(names.iter().copied()));
+        rows.into_vec(genre.name())
+    })
+}
+
+fn genres_with_tracks(txn: &Transaction<Schema>) -> Vec<String> {
+    txn.query(|rows| {
+        let genre = Genre::join(rows);
+        rows.filter(genre.is_in_query(|rows| Track::join(rows).genre()));
+        rows.into_vec(genre.name())
+    })
+}
+
+fn tracks_in_genres<'a>(
+    txn: &Transaction<'a, Schema>,
+    genres: Vec<TableRow<'a, Genre>>,
+) -> Vec<String> {
+    txn.query(|rows| {
+        let track = Track::join(rows);
+        rows.filter(track.genre().is_in(genres));
+        rows.into_vec(track.name())
+    })
+}
+
+#[test]
+fn is_in() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let rock = txn.insert(Genre { name: "rock" });
+    let jazz = txn.insert(Genre { name: "jazz" });
+    txn.insert(Genre { name: "pop" });
+    txn.insert(Track {
+        genre: rock,
+        name: "a",
+    });
+    txn.insert(Track {
+        genre: jazz,
+        name: "b",
+    });
+
+    assert_eq!(
+        genres_named(&txn, &["pop", "rock", "blues"]),
+        ["pop", "rock"]
+    );
+    assert!(genres_named(&txn, &[]).is_empty());
+    assert_eq!(genres_with_tracks(&txn), ["jazz", "rock"]);
+    assert_eq!(tracks_in_genres(&txn, vec![rock]), ["a"]);
+    assert_eq!(tracks_in_genres(&txn, vec![rock, jazz]), ["a", "b"]);
+    assert!(tracks_in_genres(&txn, vec![]).is_empty());
+}
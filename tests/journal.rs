@@ -0,0 +1,106 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use rust_query::{
+    migration::{schema, Alter, Config},
+    Dummy, LocalClient, Table,
+};
+
+mod app {
+    #[rust_query::migration::schema]
+    pub enum Schema {
+        User { name: String },
+    }
+    pub use v0::*;
+}
+
+#[schema(namespace = "blog")]
+#[version(0..=1)]
+enum Blog {
+    Post {
+        title: String,
+        #[version(1..)]
+        title_len: i64,
+    },
+    Comment {
+        post: Post,
+        text: String,
+        #[version(1..)]
+        text_len: i64,
+    },
+}
+
+#[test]
+fn journal() {
+    let path =
+        std::env::temp_dir().join(format!("rust_query_journal_{}.sqlite", std::process::id()));
+    let mut client = LocalClient::try_new().unwrap();
+    let app = client
+        .migrator::<app::Schema>(Config::open(&path))
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let blog = client
+        .namespace_migrator::<_, v0::Blog>(&app)
+        .unwrap()
+        .finish()
+        .unwrap();
+    let mut txn = client.transaction_mut(&blog);
+    let post = txn.insert(v0::Post { title: "hello" });
+    txn.insert(v0::Comment { post, text: "hi" });
+    txn.insert(v0::Comment { post, text: "nice" });
+    txn.commit();
+    drop(blog);
+
+    // the migration stops after the posts are copied
+    let res = catch_unwind(AssertUnwindSafe(|| {
+        client
+            .namespace_migrator::<_, v0::Blog>(&app)
+            .unwrap()
+            .journaled()
+            .migrate(v1::update::Blog {
+                post: Box::new(|post| {
+                    Alter::new(v1::update::PostMigration {
+                        title_len: post.title().map_dummy(|x| x.len() as i64),
+                    })
+                }),
+                comment: Box::new(|_| panic!("interrupted")),
+            });
+    }));
+    assert!(res.is_err());
+
+    // the posts are not copied again when the migration runs again
+    let mut copied = vec![];
+    let blog = client
+        .namespace_migrator::<_, v0::Blog>(&app)
+        .unwrap()
+        .journaled()
+        .on_progress(|p| copied.push((p.table, p.rows)))
+        .migrate(v1::update::Blog {
+            post: Box::new(|_| panic!("copied again")),
+            comment: Box::new(|comment| {
+                Alter::new(v1::update::CommentMigration {
+                    text_len: comment.text().map_dummy(|x| x.len() as i64),
+                })
+            }),
+        })
+        .finish()
+        .unwrap();
+    assert_eq!(copied, [("blog_comment", 2)]);
+
+    let txn = client.transaction(&blog);
+    let comments = txn.query(|rows| {
+        let comment = v1::Comment::join(rows);
+        rows.into_vec((
+            comment.post().title(),
+            comment.post().title_len(),
+            comment.text_len(),
+        ))
+    });
+    assert_eq!(
+        comments,
+        [("hello".to_owned(), 5, 2), ("hello".to_owned(), 5, 4)]
+    );
+    drop(txn);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,159 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use rust_query::{
+    migration::{schema, Alter, Config},
+    Database, LocalClient, Table,
+};
+
+mod app {
+    #[rust_query::migration::schema]
+    pub enum Schema {
+        User { name: String },
+    }
+    pub use v0::*;
+}
+
+#[schema(namespace = "shop")]
+#[version(0..=1)]
+enum Shop {
+    Author {
+        name: String,
+        #[version(..1)]
+        note: String,
+    },
+    Book {
+        author: Author,
+        title: String,
+        #[version(..1)]
+        stars: i64,
+        #[version(1..)]
+        score: f64,
+    },
+    Review {
+        book: Book,
+        #[version(..1)]
+        text: String,
+        #[version(1..)]
+        body: String,
+    },
+}
+
+/// Run the migration, it panics when the table `stop` is copied.
+fn migrate(client: &mut LocalClient, app: &Database<app::Schema>, stop: &'static str) -> bool {
+    catch_unwind(AssertUnwindSafe(|| {
+        client
+            .namespace_migrator::<_, v0::Shop>(app)
+            .unwrap()
+            .journaled()
+            .migrate(v1::update::Shop {
+                author: Box::new(|_| Alter::identity()),
+                book: Box::new(move |book| {
+                    assert_ne!(stop, "book", "interrupted");
+                    Alter::new(v1::update::BookMigration {
+                        score: book.stars().as_float(),
+                    })
+                }),
+                review: Box::new(move |review| {
+                    assert_ne!(stop, "review", "interrupted");
+                    Alter::new(v1::update::ReviewMigration {
+                        body: review.text(),
+                    })
+                }),
+            })
+            .finish()
+            .unwrap();
+    }))
+    .is_ok()
+}
+
+#[test]
+fn journal_writes() {
+    let path = std::env::temp_dir().join(format!(
+        "rust_query_journal_writes_{}.sqlite",
+        std::process::id()
+    ));
+    let mut client = LocalClient::try_new().unwrap();
+    let app = client
+        .migrator::<app::Schema>(Config::open(&path))
+        .unwrap()
+        .finish()
+        .unwrap();
+    let shop = client
+        .namespace_migrator::<_, v0::Shop>(&app)
+        .unwrap()
+        .finish()
+        .unwrap();
+    let mut txn = client.transaction_mut(&shop);
+    let tolkien = txn.insert(v0::Author {
+        name: "Tolkien",
+        note: "",
+    });
+    txn.insert(v0::Author {
+        name: "Pratchett",
+        note: "",
+    });
+    let book = txn.insert(v0::Book {
+        author: tolkien,
+        title: "The Hobbit",
+        stars: 4,
+    });
+    txn.insert(v0::Review { book, text: "nice" });
+    txn.commit();
+    drop(shop);
+
+    // the authors are copied and committed before the migration stops
+    assert!(!migrate(&mut client, &app, "book"));
+
+    // another connection writes to the authors that were already copied
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute_batch(
+        "INSERT INTO shop_author (id, name, note) VALUES (3, 'Le Guin', '');
+        UPDATE shop_author SET name = 'J.R.R. Tolkien' WHERE id = 1;
+        DELETE FROM shop_author WHERE id = 2;",
+    )
+    .unwrap();
+
+    // the books are copied with rust code, so the old tables are read-only afterwards
+    assert!(!migrate(&mut client, &app, "review"));
+    let err = conn
+        .execute("UPDATE shop_book SET title = 'The Lord of the Rings'", [])
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("table `shop_book` is read-only until the migration is finished"));
+    assert!(conn
+        .execute(
+            "INSERT INTO shop_author (name, note) VALUES ('Pratchett', '')",
+            []
+        )
+        .is_err());
+    drop(conn);
+
+    assert!(migrate(&mut client, &app, ""));
+    let db = client
+        .namespace_migrator::<_, v1::Shop>(&app)
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let authors = txn.query(|rows| {
+        let author = v1::Author::join(rows);
+        rows.into_vec(author.name())
+    });
+    assert_eq!(authors, ["J.R.R. Tolkien", "Le Guin"]);
+    let books = txn.query(|rows| {
+        let book = v1::Book::join(rows);
+        rows.into_vec((book.author().name(), book.title(), book.score()))
+    });
+    assert_eq!(
+        books,
+        [("J.R.R. Tolkien".to_owned(), "The Hobbit".to_owned(), 4.0)]
+    );
+
+    // the tables can be written again when the migration is finished
+    txn.insert(v1::Author { name: "Pratchett" });
+    txn.commit();
+    drop(db);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,78 @@
+use rust_query::{
+    aggregate,
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    Playlist {
+        name: String,
+    },
+    Track {
+        name: String,
+    },
+    #[link_table]
+    #[unique(playlist, track)]
+    PlaylistTrack {
+        playlist: Playlist,
+        track: Track,
+    },
+}
+use v0::*;
+
+#[test]
+fn link_table() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let rock = txn.insert(Playlist { name: "rock" });
+    let jazz = txn.insert(Playlist { name: "jazz" });
+    let a = txn.insert(Track { name: "a" });
+    let b = txn.insert(Track { name: "b" });
+
+    assert!(rock.add_track(&mut txn, a));
+    assert!(rock.add_track(&mut txn, b));
+    assert!(!rock.add_track(&mut txn, a));
+    assert!(b.add_playlist(&mut txn, jazz));
+
+    let names = txn.query(|rows| {
+        let track = rock.tracks(rows);
+        rows.into_vec(track.name())
+    });
+    assert_eq!(names, ["a", "b"]);
+
+    let counts = txn.query(|rows| {
+        let track = Track::join(rows);
+        let count = aggregate(|rows| {
+            let playlist = track.playlists(rows);
+            rows.count_distinct(playlist)
+        });
+        rows.into_vec((track.name(), count))
+    });
+    assert_eq!(counts, [("a".to_owned(), 1), ("b".to_owned(), 2)]);
+
+    let mut deletor = txn.deletor();
+    assert!(rock.remove_track(&mut deletor, b));
+    assert!(!rock.remove_track(&mut deletor, b));
+    deletor.commit();
+
+    let txn = client.transaction(&db);
+    let mut links = txn.query(|rows| {
+        let link = PlaylistTrack::join(rows);
+        rows.into_vec((link.playlist().name(), link.track().name()))
+    });
+    links.sort();
+    assert_eq!(
+        links,
+        [
+            ("jazz".to_owned(), "b".to_owned()),
+            ("rock".to_owned(), "a".to_owned())
+        ]
+    );
+}
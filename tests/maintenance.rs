@@ -0,0 +1,52 @@
+use rust_query::{
+    migration::{schema, Config},
+    Database, LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    User { name: String },
+}
+use v0::*;
+
+fn user_names(client: &mut LocalClient, db: &Database<Schema>) -> Vec<String> {
+    let txn = client.transaction(db);
+    txn.query(|rows| {
+        let user = User::join(rows);
+        rows.into_vec(user.name())
+    })
+}
+
+#[test]
+fn maintenance() {
+    let mut client = LocalClient::try_new().unwrap();
+    let mut db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let alice = txn.insert(User { name: "alice" });
+    txn.insert(User { name: "bob" });
+    let mut del = txn.deletor();
+    del.try_delete(alice).unwrap();
+    del.commit();
+
+    db.vacuum().unwrap();
+    db.analyze().unwrap();
+    assert_eq!(db.integrity_check().unwrap(), []);
+    assert_eq!(user_names(&mut client, &db), ["bob"]);
+
+    let path =
+        std::env::temp_dir().join(format!("rust_query_vacuum_{}.sqlite", std::process::id()));
+    db.vacuum_into(&path).unwrap();
+    // the file already exists
+    assert!(db.vacuum_into(&path).is_err());
+
+    // the statistics table of `ANALYZE` is not part of the schema
+    db.restore_from(&path).unwrap();
+    assert_eq!(user_names(&mut client, &db), ["bob"]);
+
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,77 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    User { name: String },
+}
+use v0::*;
+
+static SELECTS: AtomicUsize = AtomicUsize::new(0);
+
+fn take_selects() -> usize {
+    SELECTS.swap(0, Ordering::Relaxed)
+}
+
+#[test]
+fn memoize() {
+    let config = Config::open_in_memory().trace(|trace| {
+        if trace.sql.starts_with("SELECT") {
+            SELECTS.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client.migrator(config).unwrap().finish().unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    txn.memoize();
+    txn.insert(User { name: "alice" });
+    take_selects();
+
+    let names = || {
+        txn.query(|rows| {
+            let user = User::join(rows);
+            rows.into_vec(user.name())
+        })
+    };
+    assert_eq!(names(), ["alice"]);
+    assert_eq!(names(), ["alice"]);
+    assert_eq!(take_selects(), 1);
+
+    // the results are forgotten when the transaction changes rows
+    txn.insert(User { name: "bob" });
+    take_selects();
+    let count = || {
+        txn.query(|rows| {
+            User::join(rows);
+            rows.count()
+        })
+    };
+    assert_eq!(count(), 2);
+    assert_eq!(count(), 2);
+    assert_eq!(take_selects(), 1);
+    txn.commit();
+
+    let mut txn = client.transaction(&db);
+    txn.memoize();
+    for _ in 0..3 {
+        let names = txn.query(|rows| {
+            let user = User::join(rows);
+            rows.filter(user.name().eq("bob"));
+            rows.into_vec(user.name())
+        });
+        assert_eq!(names, ["bob"]);
+    }
+    let alice = txn.query(|rows| {
+        let user = User::join(rows);
+        rows.filter(user.name().eq("alice"));
+        rows.into_vec(user.name())
+    });
+    assert_eq!(alice, ["alice"]);
+    // the parameters are part of the key
+    assert_eq!(take_selects(), 2);
+}
@@ -0,0 +1,108 @@
+#![cfg(feature = "metrics")]
+
+use std::sync::{Arc, Mutex};
+
+use metrics::{
+    Counter, CounterFn, Gauge, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder,
+    SharedString, Unit,
+};
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    User { name: String },
+}
+use v0::*;
+
+type Log = Arc<Mutex<Vec<(String, f64)>>>;
+
+/// Records every metric with its labels.
+struct TestRecorder(Log);
+
+struct Handle(String, Log);
+
+impl CounterFn for Handle {
+    fn increment(&self, value: u64) {
+        self.1.lock().unwrap().push((self.0.clone(), value as f64));
+    }
+
+    fn absolute(&self, _value: u64) {
+        unimplemented!()
+    }
+}
+
+impl HistogramFn for Handle {
+    fn record(&self, value: f64) {
+        self.1.lock().unwrap().push((self.0.clone(), value));
+    }
+}
+
+impl TestRecorder {
+    fn handle(&self, key: &Key) -> Arc<Handle> {
+        let mut name = key.name().to_owned();
+        for label in key.labels() {
+            name += &format!(" {}={}", label.key(), label.value());
+        }
+        Arc::new(Handle(name, self.0.clone()))
+    }
+}
+
+impl Recorder for TestRecorder {
+    fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+    fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+    fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+        Counter::from_arc(self.handle(key))
+    }
+
+    fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+        Gauge::noop()
+    }
+
+    fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+        Histogram::from_arc(self.handle(key))
+    }
+}
+
+#[test]
+fn metrics() {
+    let log = Log::default();
+    metrics::set_global_recorder(TestRecorder(log.clone())).unwrap();
+
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+    log.lock().unwrap().clear();
+
+    let mut txn = client.transaction_mut(&db);
+    txn.insert(User { name: "alice" });
+    txn.insert(User { name: "bob" });
+    let names = txn.query(|rows| {
+        let user = User::join(rows);
+        rows.into_vec(user.name())
+    });
+    assert_eq!(names.len(), 2);
+    txn.commit();
+
+    let log = log.lock().unwrap();
+    let count = |name: &str| log.iter().filter(|(n, _)| n == name).count();
+    assert_eq!(count("rust_query_statements_total"), 3);
+    let rows: Vec<_> = log
+        .iter()
+        .filter(|(n, _)| n == "rust_query_rows_returned")
+        .map(|(_, v)| *v)
+        .collect();
+    assert_eq!(rows, [2.]);
+    assert_eq!(
+        count("rust_query_transaction_duration_seconds kind=write"),
+        1
+    );
+    assert_eq!(count("rust_query_busy_retries_total"), 0);
+}
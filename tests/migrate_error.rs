@@ -0,0 +1,34 @@
+use std::time::Duration;
+
+use rust_query::{
+    migration::{schema, Config},
+    Error, LocalClient,
+};
+
+#[schema]
+enum Schema {
+    User { name: String },
+}
+use v0::*;
+
+#[test]
+fn migrator_busy() {
+    let path = std::env::temp_dir().join(format!(
+        "rust_query_migrate_error_{}.sqlite",
+        std::process::id()
+    ));
+
+    // another connection holds the write lock
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute_batch("BEGIN IMMEDIATE").unwrap();
+
+    let config = Config::open(&path).busy_timeout(Duration::from_millis(10));
+    let mut client = LocalClient::try_new().unwrap();
+    let res = client.try_migrator::<Schema>(config).map(|_| ());
+    assert_eq!(res, Err(Error::Busy));
+    conn.execute_batch("COMMIT").unwrap();
+
+    for ext in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{ext}", path.display()));
+    }
+}
@@ -0,0 +1,60 @@
+use rust_query::{
+    migration::{schema, Alter, Config, Progress},
+    LocalClient, Table,
+};
+
+#[schema]
+#[version(0..=1)]
+enum Schema {
+    Measurement {
+        #[version(..1)]
+        value: i64,
+        #[version(1..)]
+        value: f64,
+        unit: String,
+    },
+}
+
+#[test]
+fn migration_progress() {
+    let mut client = LocalClient::try_new().unwrap();
+    // the database is created with 2500 rows
+    let config = Config::open_in_memory().init_stmt(
+        "INSERT INTO measurement (value, unit)
+        WITH RECURSIVE n(x) AS (SELECT 0 UNION ALL SELECT x + 1 FROM n WHERE x < 2499)
+        SELECT x, 'm' FROM n",
+    );
+
+    let mut progress = vec![];
+    let db = client
+        .migrator(config)
+        .unwrap()
+        .on_progress(|p| progress.push(p))
+        .migrate(v1::update::Schema {
+            measurement: Box::new(|m| {
+                Alter::new(v1::update::MeasurementMigration {
+                    value: m.value().as_float(),
+                })
+            }),
+        })
+        .finish()
+        .unwrap();
+
+    let expected = [(1000, false), (2000, false), (2500, true)].map(|(rows, done)| Progress {
+        table: "measurement",
+        rows,
+        done,
+    });
+    assert_eq!(progress, expected);
+
+    let txn = client.transaction(&db);
+    let values = txn.query(|rows| {
+        let m = v1::Measurement::join(rows);
+        rows.into_vec((m.value(), m.unit()))
+    });
+    // the unit column is not changed, so it is copied from the old row
+    assert!(values.iter().all(|(_, unit)| unit == "m"));
+    let mut values: Vec<_> = values.into_iter().map(|(value, _)| value).collect();
+    values.sort_by(f64::total_cmp);
+    assert!(values.into_iter().eq((0..2500).map(f64::from)));
+}
@@ -0,0 +1,57 @@
+use rust_query::migration::{migration_skeleton, schema};
+
+#[schema]
+#[version(0..=1)]
+enum Schema {
+    Artist {
+        name: String,
+    },
+    Album {
+        title: String,
+        #[version(..1)]
+        year: i64,
+        #[version(..1)]
+        artist: Artist,
+        #[version(1..)]
+        #[on_delete(cascade)]
+        artist: Artist,
+        #[version(1..)]
+        rating: Option<f64>,
+    },
+    #[version(..1)]
+    Review {
+        text: String,
+    },
+    #[version(1..)]
+    Label {
+        name: String,
+    },
+    Song {
+        title: String,
+        #[version(..1)]
+        plays: i64,
+    },
+}
+
+#[test]
+fn skeleton() {
+    let expected = "\
+let m = m.migrate(v1::update::Schema {
+    album: Box::new(|album| {
+        Alter::new(v1::update::AlbumMigration {
+            artist: todo!(), // Artist
+            rating: todo!(), // Option<f64>
+        })
+    }),
+    label: Box::new(|rows| {
+        Create::new(v1::update::LabelMigration {
+            name: todo!(), // String
+        })
+    }),
+    song: Box::new(|_| {
+        Alter::identity()
+    }),
+});
+";
+    assert_eq!(migration_skeleton::<v0::Schema, v1::Schema>(), expected);
+}
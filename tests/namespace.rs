@@ -0,0 +1,102 @@
+use rust_query::{
+    migration::{schema, Alter, Config},
+    LocalClient, Table,
+};
+
+mod app {
+    #[rust_query::migration::schema]
+    pub enum Schema {
+        User { name: String },
+    }
+    pub use v0::*;
+}
+
+#[schema(namespace = "blog")]
+#[version(0..=1)]
+enum Blog {
+    Post {
+        title: String,
+        #[version(1..)]
+        #[default = 0]
+        likes: i64,
+    },
+    // the table has the same name as a table of the app, but it is prefixed
+    User {
+        handle: String,
+    },
+}
+
+#[test]
+fn namespace() {
+    let path = std::env::temp_dir().join(format!(
+        "rust_query_namespace_{}.sqlite",
+        std::process::id()
+    ));
+    let mut client = LocalClient::try_new().unwrap();
+
+    let app = client
+        .migrator::<app::Schema>(Config::open(&path))
+        .unwrap()
+        .finish()
+        .unwrap();
+    let mut txn = client.transaction_mut(&app);
+    txn.insert(app::User { name: "alice" });
+    txn.commit();
+
+    // the blog starts at its own first version
+    let blog = client
+        .namespace_migrator::<_, v0::Blog>(&app)
+        .unwrap()
+        .finish()
+        .unwrap();
+    let mut txn = client.transaction_mut(&blog);
+    txn.insert(v0::Post { title: "hello" });
+    txn.commit();
+    drop(blog);
+
+    // every schema is migrated separately
+    let blog = client
+        .namespace_migrator(&app)
+        .unwrap()
+        .migrate(v1::update::Blog {
+            post: Box::new(|_| Alter::identity()),
+        })
+        .finish()
+        .unwrap();
+    let txn = client.transaction(&blog);
+    let posts = txn.query(|rows| {
+        let post = v1::Post::join(rows);
+        rows.into_vec((post.title(), post.likes()))
+    });
+    assert_eq!(posts, [("hello".to_owned(), 0)]);
+    drop(txn);
+
+    // the app is not changed by the migrations of the blog
+    let app = client
+        .namespace_migrator::<_, app::Schema>(&app)
+        .unwrap()
+        .finish()
+        .unwrap();
+    let txn = client.transaction(&app);
+    let names = txn.query(|rows| {
+        let user = app::User::join(rows);
+        rows.into_vec(user.name())
+    });
+    assert_eq!(names, ["alice"]);
+    drop(txn);
+
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    let tables: Vec<String> = conn
+        .prepare("SELECT name FROM sqlite_schema WHERE type = 'table' AND name NOT LIKE '\\_%' ESCAPE '\\' ORDER BY name")
+        .unwrap()
+        .query_map([], |r| r.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(tables, ["blog_post", "blog_user", "user"]);
+    drop(conn);
+
+    for ext in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{ext}", path.display()));
+    }
+}
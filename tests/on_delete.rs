@@ -0,0 +1,92 @@
+use rust_query::{
+    migration::{schema, Alter, Config},
+    LocalClient, Table, TableRow, Transaction,
+};
+
+#[schema]
+#[version(0..=1)]
+enum Schema {
+    Artist {
+        name: String,
+    },
+    Album {
+        title: String,
+        #[version(..1)]
+        artist: Artist,
+        #[version(1..)]
+        #[on_delete(cascade)]
+        artist: Artist,
+    },
+    Review {
+        #[on_delete(restrict)]
+        album: Album,
+    },
+    Employee {
+        name: String,
+        #[on_delete(set_null)]
+        reports_to: Option<Employee>,
+    },
+}
+use v1::*;
+
+fn album_titles(txn: &Transaction<Schema>) -> Vec<String> {
+    txn.query(|rows| {
+        let album = Album::join(rows);
+        rows.into_vec(album.title())
+    })
+}
+
+#[test]
+fn foreign_key_actions() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .migrate(v1::update::Schema {
+            album: Box::new(|album| {
+                Alter::new(v1::update::AlbumMigration {
+                    artist: album.artist(),
+                })
+            }),
+        })
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let queen = txn.insert(Artist { name: "Queen" });
+    let abba = txn.insert(Artist { name: "ABBA" });
+    txn.insert(Album {
+        title: "Jazz",
+        artist: queen,
+    });
+    let arrival = txn.insert(Album {
+        title: "Arrival",
+        artist: abba,
+    });
+    txn.insert(Review { album: arrival });
+    let boss = txn.insert(Employee {
+        name: "boss",
+        reports_to: None::<TableRow<Employee>>,
+    });
+    txn.insert(Employee {
+        name: "worker",
+        reports_to: Some(boss),
+    });
+
+    let mut del = txn.deletor();
+    // the album of queen is deleted too
+    assert_eq!(del.try_delete(queen), Ok(true));
+    // the album of abba has a review, which restricts deleting it
+    assert_eq!(del.try_delete(abba), Err(()));
+    // the worker no longer reports to anyone
+    assert_eq!(del.try_delete(boss), Ok(true));
+    del.commit();
+
+    let txn = client.transaction(&db);
+    assert_eq!(album_titles(&txn), ["Arrival"]);
+    let reports_to = txn.query(|rows| {
+        let employee = Employee::join(rows);
+        rows.into_vec((employee.name(), employee.reports_to().is_some()))
+    });
+    assert_eq!(reports_to, [("worker".to_owned(), false)]);
+}
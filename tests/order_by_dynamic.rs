@@ -0,0 +1,65 @@
+use rust_query::{
+    migration::{schema, Config},
+    DynFilterError, LocalClient, Order, Table,
+};
+
+#[schema]
+enum Schema {
+    User { name: String, age: i64 },
+}
+use v0::*;
+
+#[test]
+fn order_by_dynamic() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    for (name, age) in [("bob", 30), ("alice", 30), ("carol", 20)] {
+        txn.insert(User { name, age });
+    }
+
+    let names = |order: &[(&str, Order)]| {
+        txn.query(|rows| {
+            let user = User::join(rows);
+            for (column, order) in order {
+                rows.order_by_dynamic(&user, column, *order)?;
+            }
+            Ok(rows.into_vec(user.name()))
+        })
+    };
+
+    // ties are sorted by the retrieved values
+    assert_eq!(
+        names(&[("age", Order::Desc)]),
+        Ok(vec![
+            "alice".to_owned(),
+            "bob".to_owned(),
+            "carol".to_owned()
+        ])
+    );
+    assert_eq!(
+        names(&[("age", Order::Asc), ("name", Order::Desc)]),
+        Ok(vec![
+            "carol".to_owned(),
+            "bob".to_owned(),
+            "alice".to_owned()
+        ])
+    );
+    assert_eq!(
+        names(&[("id", Order::Desc)]),
+        Ok(vec![
+            "carol".to_owned(),
+            "alice".to_owned(),
+            "bob".to_owned()
+        ])
+    );
+    assert_eq!(
+        names(&[("age", Order::Asc), ("password", Order::Asc)]),
+        Err(DynFilterError::UnknownColumn("password".to_owned()))
+    );
+}
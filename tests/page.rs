@@ -0,0 +1,48 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    User { name: String, score: i64 },
+}
+use v0::*;
+
+#[test]
+fn page() {
+    let config = Config::open_in_memory().init_stmt(
+        "INSERT INTO user (name, score) VALUES ('a', 5), ('b', 1), ('c', 4), ('d', 3), ('e', 2)",
+    );
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client.migrator(config).unwrap().finish().unwrap();
+    let txn = client.transaction(&db);
+
+    let mut cursor = None;
+    let mut pages = vec![];
+    loop {
+        let page = txn.query(|rows| {
+            let user = User::join(rows);
+            rows.filter(user.score().lt(5));
+            // the score would sort the results differently, but pages are ordered by id
+            rows.paginate(&user)
+                .after(cursor)
+                .limit(2)
+                .into_page((user.score(), user.name()))
+        });
+        let names: Vec<_> = page.items.into_iter().map(|(_, name)| name).collect();
+        pages.push(names);
+        cursor = page.next;
+        if cursor.is_none() {
+            break;
+        }
+    }
+    assert_eq!(pages, [vec!["b", "c"], vec!["d", "e"]]);
+
+    let page = txn.query(|rows| {
+        let user = User::join(rows);
+        rows.paginate(&user).into_page(user.name())
+    });
+    assert_eq!(page.items, ["a", "b", "c", "d", "e"]);
+    assert!(page.next.is_none());
+}
@@ -0,0 +1,53 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Paginated, Table,
+};
+
+#[schema]
+enum Schema {
+    User { name: String, score: i64 },
+}
+use v0::*;
+
+#[test]
+fn paginated() {
+    let config = Config::open_in_memory().init_stmt(
+        "INSERT INTO user (name, score) VALUES ('a', 5), ('b', 1), ('c', 4), ('d', 3), ('e', 2)",
+    );
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client.migrator(config).unwrap().finish().unwrap();
+    let txn = client.transaction(&db);
+
+    let page = |page| {
+        txn.query(|rows| {
+            let user = User::join(rows);
+            rows.filter(user.score().lt(5));
+            rows.paginated((user.score(), user.name()), page, 3)
+        })
+    };
+    let names = |page: Paginated<(i64, String)>| {
+        let names: Vec<_> = page.items.into_iter().map(|(_, name)| name).collect();
+        (names, page.total)
+    };
+    // the results are sorted by score
+    assert_eq!(
+        names(page(0)),
+        (vec!["b".to_owned(), "e".into(), "d".into()], 4)
+    );
+    assert_eq!(names(page(1)), (vec!["c".to_owned()], 4));
+    // the total is also known after the last page
+    assert_eq!(names(page(2)), (vec![], 4));
+
+    let empty = txn.query(|rows| {
+        let user = User::join(rows);
+        rows.filter(user.score().lt(0));
+        rows.paginated(user.name(), 0, 3)
+    });
+    assert_eq!(
+        empty,
+        Paginated {
+            items: vec![],
+            total: 0
+        }
+    );
+}
@@ -0,0 +1,117 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rust_query::{
+    migration::{schema, Alter, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+#[version(0..=1)]
+enum Schema {
+    Author {
+        name: String,
+        #[version(..1)]
+        note: String,
+    },
+    Book {
+        author: Author,
+        title: String,
+        #[version(1..)]
+        #[default = 0]
+        pages: i64,
+    },
+    Review {
+        book: Book,
+        #[version(..1)]
+        stars: i64,
+        #[version(1..)]
+        score: f64,
+    },
+}
+use v1::*;
+
+static IMPORTS: AtomicUsize = AtomicUsize::new(0);
+
+#[test]
+fn parallel_copy() {
+    let path = std::env::temp_dir().join(format!(
+        "rust_query_parallel_copy_{}.sqlite",
+        std::process::id()
+    ));
+    let config = Config::open(&path).init_stmt(
+        "INSERT INTO author (id, name, note) VALUES (1, 'Tolkien', ''), (2, 'Pratchett', '');
+        INSERT INTO book (author, title)
+        WITH RECURSIVE n(x) AS (SELECT 0 UNION ALL SELECT x + 1 FROM n WHERE x < 999)
+        SELECT x % 2 + 1, 'book ' || x FROM n;
+        INSERT INTO review (book, stars) VALUES (1, 4), (2, 5);",
+    );
+    let config = config.trace(|trace| {
+        if trace.sql.contains(r#"FROM "copy"."copy""#) {
+            IMPORTS.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+
+    // author and book are copied by workers, while review is copied by the migration
+    let mut client = LocalClient::try_new().unwrap();
+    let mut progress = vec![];
+    let db = client
+        .migrator(config)
+        .unwrap()
+        .journaled()
+        .parallel_copy(2)
+        .on_progress(|p| progress.push((p.table, p.rows, p.done)))
+        .migrate(v1::update::Schema {
+            author: Box::new(|_| Alter::identity()),
+            book: Box::new(|_| Alter::identity()),
+            review: Box::new(|review| {
+                Alter::new(v1::update::ReviewMigration {
+                    score: review.stars().as_float(),
+                })
+            }),
+        })
+        .finish()
+        .unwrap();
+    assert_eq!(IMPORTS.load(Ordering::Relaxed), 2);
+    progress.sort();
+    assert_eq!(
+        progress,
+        [
+            ("author", 2, true),
+            ("book", 1000, true),
+            ("review", 2, true)
+        ]
+    );
+
+    let txn = client.transaction(&db);
+    let mut books = txn.query(|rows| {
+        let book = Book::join(rows);
+        rows.filter(book.author().name().eq("Tolkien"));
+        rows.into_vec((book.title(), book.pages()))
+    });
+    books.sort();
+    assert_eq!(books.len(), 500);
+    assert_eq!(books[0], ("book 0".to_owned(), 0));
+
+    let scores = txn.query(|rows| {
+        let review = Review::join(rows);
+        rows.into_vec((review.book().title(), review.score()))
+    });
+    assert_eq!(
+        scores,
+        [("book 0".to_owned(), 4.0), ("book 1".to_owned(), 5.0)]
+    );
+    drop(txn);
+
+    // the files of the workers are removed
+    let dir = path.parent().unwrap();
+    let name = path.file_name().unwrap().to_str().unwrap();
+    let left = std::fs::read_dir(dir)
+        .unwrap()
+        .filter(|x| {
+            let file = x.as_ref().unwrap().file_name();
+            file.to_str().unwrap().starts_with(&format!("{name}-_tmp"))
+        })
+        .count();
+    assert_eq!(left, 0);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,49 @@
+use rust_query::{
+    migration::{schema, Config, Synchronous, TempStore},
+    FromRaw, LocalClient,
+};
+
+#[schema]
+enum Schema {
+    User { name: String },
+}
+
+#[derive(FromRaw)]
+struct Pragmas {
+    page_size: i64,
+    cache_size: i64,
+    synchronous: i64,
+    temp_store: i64,
+    foreign_keys: bool,
+}
+
+#[test]
+fn pragmas() {
+    let config = Config::open_in_memory()
+        .page_size(8192)
+        .cache_size(-4000)
+        .synchronous(Synchronous::Full)
+        .temp_store(TempStore::Memory)
+        .foreign_keys(false);
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator::<v0::Schema>(config)
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let txn = client.transaction(&db);
+    let res: Vec<Pragmas> = txn
+        .raw_query(
+            "SELECT page_size, cache_size, synchronous, temp_store, foreign_keys
+            FROM pragma_page_size, pragma_cache_size, pragma_synchronous, pragma_temp_store, pragma_foreign_keys",
+            &[],
+        )
+        .unwrap();
+    let [res] = &res[..] else { panic!() };
+    assert_eq!(res.page_size, 8192);
+    assert_eq!(res.cache_size, -4000);
+    assert_eq!(res.synchronous, 2);
+    assert_eq!(res.temp_store, 2);
+    assert!(!res.foreign_keys);
+}
@@ -0,0 +1,80 @@
+use rust_query::{
+    migration::{schema, Alter, Config},
+    LocalClient, PublicId,
+};
+
+#[schema]
+#[version(0..=1)]
+enum Schema {
+    #[public_id(uuid)]
+    Post {
+        title: String,
+        #[version(1..)]
+        #[default = 0]
+        likes: i64,
+    },
+    #[public_id(ulid)]
+    User { name: String },
+}
+
+#[test]
+fn public_id() {
+    let path = std::env::temp_dir().join(format!(
+        "rust_query_public_id_{}.sqlite",
+        std::process::id()
+    ));
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator::<v0::Schema>(Config::open(&path))
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let hello = txn.insert(v0::Post { title: "hello" });
+    let world = txn.insert(v0::Post { title: "world" });
+    let alice = txn.insert(v0::User { name: "alice" });
+    let (hello_id, world_id) = txn.query_one((hello.public_id(), world.public_id()));
+    let alice_id = txn.query_one(alice.public_id());
+    assert_ne!(hello_id, world_id);
+
+    // a UUIDv7 in its hyphenated lowercase form
+    let uuid = hello_id.as_str();
+    assert_eq!(uuid.len(), 36);
+    assert_eq!(&uuid[14..15], "7");
+    assert_eq!(uuid, uuid.to_lowercase());
+    // a ULID in its uppercase form
+    assert_eq!(alice_id.as_str().len(), 26);
+    assert_eq!(alice_id.to_string(), alice_id.as_str().to_uppercase());
+
+    // ids are parsed in any case and can be used to find the row
+    let parsed = PublicId::<v0::Post>::parse(&uuid.to_uppercase()).unwrap();
+    assert_eq!(parsed, hello_id);
+    assert_eq!(txn.query_one(v0::Post::by_public_id(&parsed)), Some(hello));
+    let parsed = PublicId::<v0::User>::parse(&alice_id.as_str().to_lowercase()).unwrap();
+    assert_eq!(txn.query_one(v0::User::by_public_id(parsed)), Some(alice));
+    assert_eq!(PublicId::<v0::User>::parse(uuid), None);
+    assert_eq!(PublicId::<v0::Post>::parse("not an id"), None);
+
+    let missing = PublicId::<v0::Post>::parse("00000000-0000-7000-8000-000000000000").unwrap();
+    assert_eq!(txn.query_one(v0::Post::by_public_id(missing)), None);
+    txn.commit();
+
+    // migrations keep the public ids
+    let db = client
+        .namespace_migrator::<_, v0::Schema>(&db)
+        .unwrap()
+        .migrate(v1::update::Schema {
+            post: Box::new(|_| Alter::identity()),
+        })
+        .finish()
+        .unwrap();
+    let txn = client.transaction(&db);
+    let post = txn.query_one(v1::Post::by_public_id(PublicId::parse(uuid).unwrap()));
+    assert_eq!(txn.query_one(post.unwrap().title()), "hello");
+    drop(txn);
+
+    for ext in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{ext}", path.display()));
+    }
+}
@@ -0,0 +1,77 @@
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    time::Duration,
+};
+
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, QueryError, QueryPolicy, Table,
+};
+
+#[schema]
+enum Schema {
+    Number { value: i64 },
+}
+use v0::*;
+
+#[test]
+fn query_policy() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    for value in 0..100 {
+        txn.insert(Number { value });
+    }
+    txn.commit();
+
+    let mut txn = client.transaction(&db);
+    txn.set_policy(QueryPolicy {
+        max_rows: Some(10),
+        max_duration: Some(Duration::from_millis(50)),
+    });
+
+    let res = txn.try_query(|rows| {
+        let number = Number::join(rows);
+        rows.into_vec(number.value())
+    });
+    assert_eq!(res, Err(QueryError::TooManyRows));
+
+    let res = txn.try_query(|rows| {
+        let number = Number::join(rows);
+        rows.filter(number.value().lt(10));
+        rows.into_vec(number.value())
+    });
+    assert_eq!(res.map(|x| x.len()), Ok(10));
+
+    // counting the combinations of many rows takes a long time
+    let res = txn.try_query(|rows| {
+        for _ in 0..5 {
+            Number::join(rows);
+        }
+        rows.count()
+    });
+    assert_eq!(res, Err(QueryError::Timeout));
+
+    // without `try_query` the query panics with the reason
+    let res = catch_unwind(AssertUnwindSafe(|| {
+        txn.query(|rows| {
+            let number = Number::join(rows);
+            rows.into_vec(number.value())
+        })
+    }));
+    let msg = res.unwrap_err().downcast::<String>().unwrap();
+    assert_eq!(*msg, "the query returned more rows than its limit");
+
+    // the policy only applies to this transaction
+    let txn = client.transaction(&db);
+    let count = txn.query(|rows| {
+        let number = Number::join(rows);
+        rows.into_vec(number.value()).len()
+    });
+    assert_eq!(count, 100);
+}
@@ -0,0 +1,102 @@
+use rust_query::{
+    migration::{schema, Config},
+    FromRaw, IntoColumn, LocalClient, RawError, RawExpr, Table,
+};
+
+#[schema]
+enum Schema {
+    User { name: String, age: i64 },
+}
+use v0::*;
+
+#[derive(Debug, PartialEq, FromRaw)]
+struct NameAge {
+    name: String,
+    age: Option<i64>,
+}
+
+#[derive(Debug, FromRaw)]
+struct Name {
+    #[allow(unused)]
+    name: i64,
+}
+
+#[test]
+fn raw() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    txn.insert(User {
+        name: "alice",
+        age: 30,
+    });
+    txn.insert(User {
+        name: "bob",
+        age: 17,
+    });
+    txn.commit();
+
+    let txn = client.transaction(&db);
+    let res: Vec<NameAge> = txn
+        .raw_query(
+            "SELECT name, NULLIF(age, ?) AS age FROM user ORDER BY name",
+            &[&17i64],
+        )
+        .unwrap();
+    let expected = [
+        NameAge {
+            name: "alice".to_owned(),
+            age: Some(30),
+        },
+        NameAge {
+            name: "bob".to_owned(),
+            age: None,
+        },
+    ];
+    assert_eq!(res, expected);
+
+    let res = txn.raw_query::<NameAge>("SELECT name FROM user", &[]);
+    assert_eq!(res.unwrap_err(), RawError::MissingColumn("age".to_owned()));
+    let res = txn.raw_query::<Name>("SELECT name FROM user", &[]);
+    let err = RawError::WrongType {
+        column: "name".to_owned(),
+        expected: "Integer",
+        found: "Text".to_owned(),
+    };
+    assert_eq!(res.unwrap_err(), err);
+    let res = txn.raw_query::<Name>("DELETE FROM user RETURNING id AS name", &[]);
+    assert_eq!(res.unwrap_err(), RawError::NotReadOnly);
+    let res = txn.raw_query::<Name>("SELECT name FROM missing", &[]);
+    assert!(matches!(res, Err(RawError::Sql(_))));
+
+    // a number is not used as a column position when the rows are ordered
+    let res = txn.query(|rows| {
+        User::join(rows);
+        rows.into_vec(RawExpr::<_, i64>::new("1").into_column())
+    });
+    assert_eq!(res, [1, 1]);
+
+    let res = txn.query(|rows| {
+        let user = User::join(rows);
+        let adult = RawExpr::new("? >= 18").arg(user.age()).into_column();
+        rows.filter(adult);
+        let len = RawExpr::<_, i64>::new("length(?)").arg(user.name());
+        rows.into_vec((user.name(), len.into_column()))
+    });
+    assert_eq!(res, [("alice".to_owned(), 5)]);
+
+    // the value is converted to the declared type
+    let res = txn.query(|rows| {
+        rows.into_vec(
+            RawExpr::<_, Option<String>>::new("nullif(?, 2)")
+                .arg(1)
+                .into_column(),
+        )
+    });
+    assert_eq!(res, [Some("1".to_owned())]);
+}
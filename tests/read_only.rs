@@ -0,0 +1,84 @@
+use std::{path::Path, process::Command};
+
+use rust_query::{
+    migration::{schema, Config},
+    Error, LocalClient, Table,
+};
+
+#[schema]
+#[version(0..=1)]
+enum Schema {
+    User {
+        name: String,
+        #[version(1..)]
+        #[default = 0]
+        score: i64,
+    },
+}
+
+/// Run a step of the test in a new process, because every process can only create one [Config].
+fn run_step(path: &Path, step: &str) -> bool {
+    Command::new(std::env::current_exe().unwrap())
+        .args(["read_only", "--exact", "--quiet"])
+        .env("READ_ONLY_STEP", step)
+        .env("READ_ONLY_PATH", path)
+        .output()
+        .unwrap()
+        .status
+        .success()
+}
+
+#[test]
+fn read_only() {
+    if let Ok(step) = std::env::var("READ_ONLY_STEP") {
+        let path = std::env::var("READ_ONLY_PATH").unwrap();
+        let mut client = LocalClient::try_new().unwrap();
+        match step.as_str() {
+            "create" => {
+                let config = Config::open(path)
+                    .init_stmt("INSERT INTO user (name) VALUES ('alice'), ('bob')");
+                client.migrator::<v0::Schema>(config).unwrap().finish();
+            }
+            // the database is still at version 0
+            "newer" => {
+                let db = client.read_only::<v1::Schema>(Config::open_read_only(path));
+                assert!(db.is_none());
+            }
+            "migrate" => {
+                let res = client.try_migrator::<v0::Schema>(Config::open_read_only(path));
+                assert_eq!(res.err(), Some(Error::ReadOnly));
+            }
+            _ => unreachable!(),
+        }
+        return;
+    }
+
+    let path = std::env::temp_dir().join(format!(
+        "rust_query_read_only_{}.sqlite",
+        std::process::id()
+    ));
+    assert!(run_step(&path, "create"));
+    assert!(run_step(&path, "newer"));
+    // migrating would write to the database, so it is rejected
+    assert!(run_step(&path, "migrate"));
+
+    let before = std::fs::read(&path).unwrap();
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .read_only::<v0::Schema>(Config::open_read_only(&path))
+        .unwrap();
+    let txn = client.transaction_read_only(&db);
+    let mut names = txn.query(|rows| {
+        let user = v0::User::join(rows);
+        rows.into_vec(user.name())
+    });
+    names.sort();
+    assert_eq!(names, ["alice", "bob"]);
+    drop(txn);
+    drop(db);
+    // nothing was written to the file
+    assert_eq!(std::fs::read(&path).unwrap(), before);
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+    }
+}
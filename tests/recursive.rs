@@ -0,0 +1,69 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table, TableRow, Transaction,
+};
+
+#[schema]
+enum Schema {
+    Employee {
+        name: String,
+        reports_to: Option<Employee>,
+    },
+}
+use v0::*;
+
+fn all_reports<'a>(txn: &Transaction<'a, Schema>, boss: TableRow<'a, Employee>) -> Vec<String> {
+    txn.query(|rows| {
+        let employee = rows.recursive(
+            |rows| {
+                let employee = Employee::join(rows);
+                let reports_to = rows.filter_some(employee.reports_to());
+                rows.filter(reports_to.eq(boss));
+                employee
+            },
+            |rows, prev| {
+                let employee = Employee::join(rows);
+                let reports_to = rows.filter_some(employee.reports_to());
+                rows.filter(reports_to.eq(prev));
+                employee
+            },
+        );
+        rows.into_vec(employee.name())
+    })
+}
+
+#[test]
+fn recursive() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let ceo = txn.insert(Employee {
+        name: "ceo",
+        reports_to: None::<TableRow<Employee>>,
+    });
+    let cto = txn.insert(Employee {
+        name: "cto",
+        reports_to: Some(ceo),
+    });
+    let dev = txn.insert(Employee {
+        name: "dev",
+        reports_to: Some(cto),
+    });
+    txn.insert(Employee {
+        name: "intern",
+        reports_to: Some(dev),
+    });
+    txn.insert(Employee {
+        name: "cfo",
+        reports_to: Some(ceo),
+    });
+
+    assert_eq!(all_reports(&txn, ceo), ["cfo", "cto", "dev", "intern"]);
+    assert_eq!(all_reports(&txn, cto), ["dev", "intern"]);
+    assert_eq!(all_reports(&txn, dev), ["intern"]);
+}
@@ -0,0 +1,103 @@
+use rust_query::{
+    aggregate,
+    migration::{schema, Config},
+    LocalClient, RefreshReport, Table,
+};
+
+#[schema]
+enum Schema {
+    Genre {
+        name: String,
+    },
+    Track {
+        genre: Genre,
+        milliseconds: i64,
+    },
+    GenreStats {
+        #[unique_genre]
+        genre: Genre,
+        total: i64,
+    },
+}
+use v0::*;
+
+fn refresh(client: &mut LocalClient, db: &rust_query::Database<Schema>) -> RefreshReport {
+    client.refresh::<GenreStats>(db, |txn| {
+        let stats = txn.query(|rows| {
+            let genre = Genre::join(rows);
+            let total = aggregate(|rows| {
+                let track = Track::join(rows);
+                rows.filter_on(track.genre(), &genre);
+                rows.sum(track.milliseconds())
+            });
+            rows.filter(total.lt(1).not());
+            rows.into_vec((genre, total))
+        });
+        for (genre, total) in stats {
+            txn.upsert::<genre_stats::UniqueGenre>(GenreStats { genre, total });
+        }
+    })
+}
+
+#[test]
+fn refresh_derived_table() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let rock = txn.insert(Genre { name: "rock" });
+    let jazz = txn.insert(Genre { name: "jazz" });
+    for (genre, milliseconds) in [(rock, 100), (rock, 200), (jazz, 50)] {
+        txn.insert(Track {
+            genre,
+            milliseconds,
+        });
+    }
+    txn.commit();
+
+    let report = refresh(&mut client, &db);
+    assert_eq!(
+        report,
+        RefreshReport {
+            written: 2,
+            deleted: 0
+        }
+    );
+
+    // the jazz track becomes a rock track, so the statistics of jazz are deleted by the next refresh
+    let mut txn = client.transaction_mut(&db);
+    let (track, rock) = txn.query(|rows| {
+        let track = Track::join(rows);
+        let rock = Genre::join(rows);
+        rows.filter(track.genre().name().eq("jazz"));
+        rows.filter(rock.name().eq("rock"));
+        rows.into_vec((track, rock))
+    })[0];
+    txn.update(
+        track,
+        Track {
+            genre: rock,
+            milliseconds: 50,
+        },
+    );
+    txn.commit();
+
+    let report = refresh(&mut client, &db);
+    assert_eq!(
+        report,
+        RefreshReport {
+            written: 1,
+            deleted: 1
+        }
+    );
+    let txn = client.transaction(&db);
+    let stats = txn.query(|rows| {
+        let stats = GenreStats::join(rows);
+        rows.into_vec((stats.genre().name(), stats.total()))
+    });
+    assert_eq!(stats, [("rock".to_owned(), 350)]);
+}
@@ -0,0 +1,61 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    Measurement { value: f64, count: i64 },
+}
+use v0::*;
+
+#[test]
+fn rounding() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+    let mut txn = client.transaction_mut(&db);
+    for (value, count) in [(2.5, 3), (-2.5, -3), (-1.2, 0), (3.7, 10)] {
+        txn.insert(Measurement { value, count });
+    }
+
+    // the results are sorted by the selected values
+    let rounded = txn.query(|rows| {
+        let m = Measurement::join(rows);
+        let value = m.value();
+        rows.into_vec((
+            value.round(),
+            (value.floor(), (value.ceil(), value.as_int())),
+        ))
+    });
+    let rounded: Vec<_> = rounded
+        .into_iter()
+        .map(|(round, (floor, (ceil, int)))| (round, floor, ceil, int))
+        .collect();
+    assert_eq!(
+        rounded,
+        [
+            (-3.0, -3.0, -2.0, -2),
+            (-1.0, -2.0, -1.0, -1),
+            (3.0, 2.0, 3.0, 2),
+            (4.0, 3.0, 4.0, 3)
+        ]
+    );
+
+    let text = txn.query(|rows| {
+        let m = Measurement::join(rows);
+        rows.into_vec((m.count().as_text(), m.value().as_text()))
+    });
+    assert_eq!(
+        text,
+        [
+            ("-3".to_owned(), "-2.5".to_owned()),
+            ("0".to_owned(), "-1.2".to_owned()),
+            ("10".to_owned(), "3.7".to_owned()),
+            ("3".to_owned(), "2.5".to_owned())
+        ]
+    );
+}
@@ -0,0 +1,56 @@
+use std::collections::HashSet;
+
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    User { name: String },
+}
+use v0::*;
+
+#[test]
+fn sample() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+    let mut txn = client.transaction_mut(&db);
+    for i in 0..100 {
+        txn.insert(User {
+            name: format!("user{i}"),
+        });
+    }
+
+    let sample = txn.query(|rows| {
+        let user = User::join(rows);
+        rows.sample(10, user.name())
+    });
+    assert_eq!(sample.iter().collect::<HashSet<_>>().len(), 10);
+
+    let seeded = |seed| {
+        txn.query(|rows| {
+            let user = User::join(rows);
+            rows.sample_seeded(5, seed, user.name())
+        })
+    };
+    assert_eq!(seeded(42), seeded(42));
+    assert_ne!(seeded(42), seeded(43));
+
+    // the same shuffle calculated in rust
+    let prime = 2147483647;
+    let mut ids: Vec<i64> = (1..=100).collect();
+    ids.sort_by_key(|id| {
+        let hash = (42 + id) * 48271 % prime;
+        hash * hash % prime
+    });
+    let expected: Vec<_> = ids[..5]
+        .iter()
+        .map(|id| format!("user{}", id - 1))
+        .collect();
+    assert_eq!(seeded(42), expected);
+}
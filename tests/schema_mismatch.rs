@@ -0,0 +1,80 @@
+use std::{path::Path, process::Command};
+
+use rust_query::{
+    migration::{schema, Config},
+    Error, LocalClient,
+};
+
+#[schema]
+enum Schema {
+    User { name: String },
+    Post { author: User },
+}
+
+/// Run a step of the test in a new process, because every process can only create one [Config].
+fn run_step(path: &Path, step: &str) -> bool {
+    Command::new(std::env::current_exe().unwrap())
+        .args(["schema_mismatch", "--exact", "--quiet"])
+        .env("SCHEMA_MISMATCH_STEP", step)
+        .env("SCHEMA_MISMATCH_PATH", path)
+        .output()
+        .unwrap()
+        .status
+        .success()
+}
+
+#[test]
+fn schema_mismatch() {
+    if let Ok(step) = std::env::var("SCHEMA_MISMATCH_STEP") {
+        let path = std::env::var("SCHEMA_MISMATCH_PATH").unwrap();
+        let mut client = LocalClient::try_new().unwrap();
+        match step.as_str() {
+            "create" => {
+                let config =
+                    Config::open(path).init_stmt("INSERT INTO user (name) VALUES ('alice')");
+                client.migrator::<v0::Schema>(config).unwrap().finish();
+            }
+            "foreign_key" => {
+                let res = client.try_read_only::<v0::Schema>(Config::open_read_only(path));
+                assert_eq!(
+                    res.err(),
+                    Some(Error::ForeignKeyViolation {
+                        table: "post".to_owned(),
+                        parent: "user".to_owned()
+                    })
+                );
+            }
+            "schema" => {
+                let res = client.try_migrator::<v0::Schema>(Config::open(path));
+                let Some(Error::SchemaMismatch(diff)) = res.err() else {
+                    panic!("expected a schema mismatch")
+                };
+                assert!(diff.contains("extra"));
+            }
+            _ => unreachable!(),
+        }
+        return;
+    }
+
+    let path = std::env::temp_dir().join(format!(
+        "rust_query_schema_mismatch_{}.sqlite",
+        std::process::id()
+    ));
+    assert!(run_step(&path, "create"));
+
+    // other tooling writes a row that references a user that does not exist
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute_batch("INSERT INTO post (author) VALUES (99)")
+        .unwrap();
+    assert!(run_step(&path, "foreign_key"));
+
+    // other tooling changes the schema
+    conn.execute_batch("DELETE FROM post; ALTER TABLE user ADD COLUMN extra INTEGER")
+        .unwrap();
+    assert!(run_step(&path, "schema"));
+    drop(conn);
+
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+    }
+}
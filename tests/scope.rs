@@ -0,0 +1,124 @@
+use rust_query::{
+    aggregate,
+    migration::{schema, Config},
+    LocalClient, RowId, Table,
+};
+
+#[schema]
+enum Schema {
+    Tenant {
+        name: String,
+    },
+    Customer {
+        #[scope]
+        tenant: Tenant,
+        #[unique_by_name]
+        name: String,
+    },
+    Invoice {
+        #[scope]
+        tenant: Tenant,
+        customer: Customer,
+        amount: i64,
+    },
+    Currency {
+        code: String,
+    },
+}
+use v0::*;
+
+#[test]
+fn scoped_transaction() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    txn.insert(Currency { code: "EUR" });
+    let acme = txn.insert(Tenant { name: "acme" });
+    let globex = txn.insert(Tenant { name: "globex" });
+    let alice = txn
+        .try_insert(Customer {
+            tenant: acme,
+            name: "alice",
+        })
+        .unwrap();
+    let bob = txn
+        .try_insert(Customer {
+            tenant: globex,
+            name: "bob",
+        })
+        .unwrap();
+    txn.insert(Invoice {
+        tenant: acme,
+        customer: alice,
+        amount: 10,
+    });
+    txn.insert(Invoice {
+        tenant: globex,
+        customer: bob,
+        amount: 20,
+    });
+    let (bob, globex) = (RowId::from(bob), RowId::from(globex));
+
+    txn.scoped(acme, |mut txn, acme| {
+        // new rows of the tenant can be inserted and updated
+        let carol = txn
+            .try_insert(Customer {
+                tenant: acme,
+                name: "carol",
+            })
+            .unwrap();
+        txn.try_update(
+            carol,
+            Customer {
+                tenant: acme,
+                name: "carol b.",
+            },
+        )
+        .unwrap();
+
+        let customers = txn.query(|rows| {
+            let customer = Customer::join(rows);
+            rows.into_vec(customer.name())
+        });
+        assert_eq!(customers, ["alice", "carol b."]);
+        let tenants = txn.query(|rows| {
+            let tenant = Tenant::join(rows);
+            rows.into_vec(tenant.name())
+        });
+        assert_eq!(tenants, ["acme"]);
+        assert_eq!(txn.query_one(acme.name()), "acme");
+        // unscoped tables are not filtered
+        let currencies = txn.query(|rows| {
+            let currency = Currency::join(rows);
+            rows.into_vec(currency.code())
+        });
+        assert_eq!(currencies, ["EUR"]);
+        // rows of other tenants can not be found by id or by a unique column
+        assert_eq!(txn.find(bob), None);
+        assert_eq!(txn.find(globex), None);
+        assert!(txn.lookup::<customer::UniqueByName>(("bob",)).is_none());
+        assert!(txn.lookup::<customer::UniqueByName>(("alice",)).is_some());
+
+        let total = txn.query_one(aggregate(|rows| {
+            let invoice = Invoice::join(rows);
+            rows.sum(invoice.amount())
+        }));
+        assert_eq!(total, 10);
+        txn.commit();
+    });
+
+    let txn = client.transaction(&db);
+    let globex = txn.find(globex).unwrap();
+    let invoices = txn.scoped(globex, |txn, _| {
+        txn.query(|rows| {
+            let invoice = Invoice::join(rows);
+            rows.into_vec((invoice.customer().name(), invoice.amount()))
+        })
+    });
+    assert_eq!(invoices, [("bob".to_owned(), 20)]);
+}
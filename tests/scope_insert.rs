@@ -0,0 +1,42 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient,
+};
+
+#[schema]
+enum Schema {
+    Tenant {
+        name: String,
+    },
+    Customer {
+        #[scope]
+        tenant: Tenant,
+        name: String,
+    },
+}
+use v0::*;
+
+#[test]
+#[should_panic = "the new row of `customer` references another tenant"]
+fn insert_other_tenant() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let acme = txn.insert(Tenant { name: "acme" });
+
+    txn.scoped(acme, |mut txn, _| {
+        // rows of other tenants can not be found, but a new tenant row can be reserved
+        txn.batch(|batch| {
+            let other = batch.reserve::<Tenant>();
+            batch.insert(Customer {
+                tenant: other,
+                name: "mallory",
+            });
+        })
+    });
+}
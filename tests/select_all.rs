@@ -0,0 +1,65 @@
+use rust_query::{
+    migration::{schema, Config},
+    FromDummy, LocalClient, Table, TableRow,
+};
+
+#[schema]
+enum Schema {
+    Artist {
+        name: String,
+    },
+    Album {
+        title: String,
+        artist: Artist,
+        #[default = None]
+        year: Option<i64>,
+    },
+}
+use v0::*;
+
+#[derive(FromDummy, Debug, PartialEq)]
+#[from_row(table = Album)]
+struct AlbumInfo<'t> {
+    title: String,
+    artist: TableRow<'t, Artist>,
+}
+
+#[test]
+fn select_all() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let artist = txn.insert(Artist { name: "a" });
+    let album = txn.insert(Album {
+        title: "first",
+        artist,
+    });
+
+    let albums = txn.query(|rows| {
+        let album = Album::join(rows);
+        rows.into_vec(album.select_all())
+    });
+    assert_eq!(
+        albums,
+        [AlbumRow {
+            id: album,
+            title: "first".to_owned(),
+            artist,
+            year: None,
+        }]
+    );
+
+    let info = txn.query_one(AlbumInfo::select(album));
+    assert_eq!(
+        info,
+        AlbumInfo {
+            title: "first".to_owned(),
+            artist
+        }
+    );
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "serde")]
+
+use rust_query::{
+    migration::{schema, Config},
+    FromDummy, LocalClient, RowId, Table, TableRow,
+};
+use serde::Serialize;
+
+#[schema]
+enum Schema {
+    User { name: String },
+    Post { author: User, title: String },
+}
+use v0::*;
+
+#[derive(FromDummy, Serialize)]
+struct UserInfo<'t> {
+    id: TableRow<'t, User>,
+    name: String,
+}
+
+#[test]
+fn serialize_results() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let alice = txn.insert(User { name: "alice" });
+
+    let users = txn.query(|rows| {
+        let user = User::join(rows);
+        rows.into_vec(UserInfoDummy {
+            id: &user,
+            name: user.name(),
+        })
+    });
+    let json = serde_json::to_string(&users).unwrap();
+    assert_eq!(json, r#"[{"id":1,"name":"alice"}]"#);
+
+    let json = serde_json::to_string(&RowId::from(alice)).unwrap();
+    let id: RowId<User> = serde_json::from_str(&json).unwrap();
+    assert_eq!(txn.find(id), Some(alice));
+
+    let post = txn.insert(Post {
+        author: alice,
+        title: "hello",
+    });
+    let posts = txn.query(|rows| {
+        let post = Post::join(rows);
+        rows.into_vec(post.select_all())
+    });
+    let json = serde_json::to_string(&posts).unwrap();
+    assert_eq!(json, r#"[{"id":1,"author":1,"title":"hello"}]"#);
+
+    let json = serde_json::to_string(&RowId::from(post)).unwrap();
+    let id: RowId<Post> = serde_json::from_str(&json).unwrap();
+    assert_eq!(txn.find(id), Some(post));
+}
@@ -0,0 +1,71 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    User { name: String, age: i64 },
+}
+use v0::*;
+
+#[test]
+fn slow_query() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    for i in 0..100 {
+        txn.insert(User {
+            name: format!("user{i}"),
+            age: i,
+        });
+    }
+    txn.commit();
+
+    let log = Arc::new(Mutex::new(vec![]));
+    db.slow_query_log(Duration::ZERO, {
+        let log = log.clone();
+        move |query| {
+            let entry = (query.sql.to_owned(), query.fullscan_steps, query.vm_steps);
+            log.lock().unwrap().push(entry);
+        }
+    });
+
+    let txn = client.transaction(&db);
+    let old = txn.query(|rows| {
+        let user = User::join(rows);
+        rows.filter(user.age().lt(9));
+        rows.into_vec(user.name())
+    });
+    assert_eq!(old.len(), 9);
+
+    let entries = log.lock().unwrap().clone();
+    let [(sql, fullscan_steps, vm_steps)] = &entries[..] else {
+        panic!("expected one slow query, got {entries:?}")
+    };
+    assert!(sql.contains("\"age\" < ?"));
+    // the table has no index on `age`, so every row is scanned
+    assert_eq!(*fullscan_steps, 99);
+    assert!(*vm_steps > 100);
+
+    // statements that are faster than the threshold are not logged
+    db.slow_query_log(Duration::from_secs(60), {
+        let log = log.clone();
+        move |query| log.lock().unwrap().push((query.sql.to_owned(), 0, 0))
+    });
+    txn.query(|rows| {
+        User::join(rows);
+        rows.count()
+    });
+    assert_eq!(log.lock().unwrap().len(), 1);
+}
@@ -0,0 +1,97 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table, Transaction,
+};
+
+#[schema]
+enum Schema {
+    User {
+        name: String,
+    },
+    #[soft_delete]
+    Post {
+        #[unique_title]
+        title: String,
+        author: User,
+    },
+    Comment {
+        post: Post,
+        text: String,
+    },
+}
+use v0::*;
+
+fn post_titles(txn: &Transaction<Schema>) -> Vec<String> {
+    txn.query(|rows| {
+        let post = Post::join(rows);
+        rows.into_vec(post.title())
+    })
+}
+
+#[test]
+fn soft_delete() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let alice = txn.insert(User { name: "alice" });
+    let hello = txn
+        .try_insert(Post {
+            title: "hello",
+            author: alice,
+        })
+        .unwrap();
+    txn.try_insert(Post {
+        title: "world",
+        author: alice,
+    })
+    .unwrap();
+    txn.insert(Comment {
+        post: hello,
+        text: "first",
+    });
+
+    assert!(txn.delete(hello));
+    // deleting twice does nothing
+    assert!(!txn.delete(hello));
+    assert!(txn.query_one(hello.deleted_at()).is_some());
+
+    assert_eq!(post_titles(&txn), ["world"]);
+    let mut all = txn.query(|rows| {
+        let post = Post::join_with_deleted(rows);
+        rows.into_vec(post.title())
+    });
+    all.sort();
+    assert_eq!(all, ["hello", "world"]);
+
+    // joining child rows also skips deleted posts
+    let posts = txn.query(|rows| {
+        let user = User::join(rows);
+        let post = user.posts(rows);
+        rows.into_vec(post.title())
+    });
+    assert_eq!(posts, ["world"]);
+
+    // the comment still references the deleted post
+    let comments = txn.query(|rows| {
+        let comment = Comment::join(rows);
+        rows.into_vec((comment.text(), comment.post().title()))
+    });
+    assert_eq!(comments, [("first".to_owned(), "hello".to_owned())]);
+
+    // the unique constraint includes deleted rows
+    assert_eq!(txn.query_one(Post::unique_title("hello")), Some(hello));
+
+    assert!(txn.restore(hello));
+    assert!(!txn.restore(hello));
+    txn.commit();
+
+    let txn = client.transaction(&db);
+    let mut titles = post_titles(&txn);
+    titles.sort();
+    assert_eq!(titles, ["hello", "world"]);
+}
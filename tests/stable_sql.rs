@@ -0,0 +1,46 @@
+use std::sync::Mutex;
+
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    Author { name: String },
+    Book { author: Author, title: String },
+}
+use v0::*;
+
+static TRACES: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+#[test]
+fn stable_sql() {
+    let config = Config::open_in_memory()
+        .init_stmt("INSERT INTO author (name) VALUES ('tolkien')")
+        .init_stmt("INSERT INTO book (author, title) VALUES (1, 'the hobbit')")
+        .trace(|trace| TRACES.lock().unwrap().push(trace.sql.to_owned()));
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client.migrator(config).unwrap().finish().unwrap();
+    let txn = client.transaction(&db);
+
+    TRACES.lock().unwrap().clear();
+    for _ in 0..2 {
+        let res = txn.query(|rows| {
+            let book = Book::join(rows);
+            rows.into_vec((book.title(), book.author().name()))
+        });
+        assert_eq!(res, [("the hobbit".to_owned(), "tolkien".to_owned())]);
+    }
+
+    // aliases are numbered per query, so the sql is the same for every run and process
+    let traces = std::mem::take(&mut *TRACES.lock().unwrap());
+    assert_eq!(traces.len(), 2);
+    assert_eq!(traces[0], traces[1]);
+    assert_eq!(
+        traces[0],
+        "SELECT \"_0\".\"title\" AS \"_1\", \"_2\".\"name\" AS \"_3\" \
+        FROM book AS \"_0\" LEFT JOIN \"author\" AS \"_2\" ON \"_0\".\"author\" = \"_2\".\"id\" \
+        ORDER BY \"_0\".\"title\" ASC, \"_2\".\"name\" ASC"
+    );
+}
@@ -0,0 +1,69 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Statement, Table,
+};
+
+#[schema]
+enum Schema {
+    #[unique(email)]
+    User { email: String },
+    #[soft_delete]
+    #[versioned]
+    Post { author: User, title: String },
+}
+use v0::*;
+
+#[test]
+fn statement() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    // the statements are made before the transaction is started
+    let emails = ["a@b.c", "d@e.f"];
+    let statements: Vec<_> = emails
+        .iter()
+        .map(|email| Statement::insert(User { email }))
+        .collect();
+    let mut txn = client.transaction_mut(&db);
+    txn.execute_batch_typed(&statements).unwrap();
+    let users = txn.query(|rows| {
+        let user = User::join(rows);
+        rows.into_vec((user.clone(), user.email()))
+    });
+    assert_eq!(users.len(), 2);
+    let (alice, _) = users[0];
+
+    // a statement that fails undoes the statements before it
+    let err = txn
+        .execute_batch_typed(&[
+            Statement::insert(User { email: "g@h.i" }),
+            Statement::insert(User { email: "a@b.c" }),
+        ])
+        .unwrap_err();
+    assert_eq!(err.index, 1);
+    assert_eq!(txn.query_one(User::unique("g@h.i")), None);
+
+    let post = txn.insert(Post {
+        author: alice,
+        title: "hello",
+    });
+    txn.execute_batch_typed(&[
+        Statement::update(
+            post,
+            Post {
+                author: alice,
+                title: "world",
+            },
+        ),
+        Statement::delete(post),
+    ])
+    .unwrap();
+    assert_eq!(txn.query_one(post.title()), "world");
+    assert_eq!(txn.query_one(post.version()), 1);
+    assert!(txn.query_one(post.deleted_at()).is_some());
+    txn.commit();
+}
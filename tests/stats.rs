@@ -0,0 +1,52 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient,
+};
+
+#[schema]
+enum Schema {
+    #[unique(email)]
+    User { name: String, email: String },
+}
+use v0::*;
+
+#[test]
+fn stats() {
+    let mut client = LocalClient::try_new().unwrap();
+    let mut db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    for i in 0..10 {
+        txn.try_insert(User {
+            name: "name",
+            email: &format!("user{i}@example.com"),
+        })
+        .unwrap();
+    }
+    txn.commit();
+
+    let stats = db.stats().unwrap();
+    assert_eq!(stats.file_size, stats.pages * stats.page_size);
+    let user = stats.tables.iter().find(|t| t.name == "user").unwrap();
+    // not analyzed yet
+    assert_eq!(user.rows, None);
+    let [index] = &user.indices[..] else {
+        panic!("expected one index")
+    };
+    assert!(index.unique);
+    assert_eq!(index.columns, ["email"]);
+    if let Some(pages) = user.pages {
+        assert!(pages > 0);
+    }
+
+    db.analyze().unwrap();
+    let stats = db.stats().unwrap();
+    let user = stats.tables.iter().find(|t| t.name == "user").unwrap();
+    assert_eq!(user.rows, Some(10));
+    // internal tables of sqlite are not included
+    assert!(stats.tables.iter().all(|t| !t.name.starts_with("sqlite_")));
+}
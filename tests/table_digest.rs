@@ -0,0 +1,53 @@
+use rust_query::{
+    migration::{schema, Config},
+    Database, LocalClient,
+};
+
+#[schema]
+enum Schema {
+    Track {
+        name: String,
+        milliseconds: i64,
+        price: Option<f64>,
+    },
+}
+use v0::*;
+
+fn insert(client: &mut LocalClient, db: &Database<Schema>, tracks: &[(&str, i64)]) {
+    let mut txn = client.transaction_mut(db);
+    for (name, milliseconds) in tracks {
+        txn.insert(Track {
+            name: *name,
+            milliseconds: *milliseconds,
+            price: None::<f64>,
+        });
+    }
+    txn.commit();
+}
+
+#[test]
+fn table_digest() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+    let empty = db.table_digest::<Track>().unwrap();
+    let swapped = db.clone_in_memory();
+    insert(&mut client, &db, &[("intro", 1000), ("outro", 2000)]);
+    let digest = db.table_digest::<Track>().unwrap();
+    assert_ne!(digest, empty);
+
+    // a copy with the same content has the same digest
+    let copy = db.clone_in_memory();
+    assert_eq!(copy.table_digest::<Track>().unwrap(), digest);
+
+    // the values and the order of the rows matter
+    let other = db.clone_in_memory();
+    insert(&mut client, &other, &[("bonus", 1000)]);
+    assert_ne!(other.table_digest::<Track>().unwrap(), digest);
+
+    insert(&mut client, &swapped, &[("outro", 2000), ("intro", 1000)]);
+    assert_ne!(swapped.table_digest::<Track>().unwrap(), digest);
+}
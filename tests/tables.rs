@@ -0,0 +1,74 @@
+use rust_query::{
+    migration::{schema, Config},
+    ColumnInfo, LocalClient,
+};
+
+#[schema]
+enum Schema {
+    Album {
+        title: String,
+    },
+    #[soft_delete]
+    Track {
+        album: Album,
+        name: String,
+        milliseconds: Option<i64>,
+    },
+}
+use v0::*;
+
+#[test]
+fn tables() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    assert_eq!(
+        SchemaTables::ALL,
+        [SchemaTables::Album, SchemaTables::Track]
+    );
+    let names: Vec<_> = SchemaTables::ALL.iter().map(|x| x.name()).collect();
+    assert_eq!(names, ["album", "track"]);
+
+    let column = |name: &str, sql_type, nullable, references: Option<&str>| ColumnInfo {
+        name: name.to_owned(),
+        sql_type,
+        nullable,
+        references: references.map(str::to_owned),
+    };
+    assert_eq!(
+        SchemaTables::Track.columns(),
+        [
+            column("id", "integer", false, None),
+            column("album", "integer", false, Some("album")),
+            column("deleted_at", "integer", true, None),
+            column("milliseconds", "integer", true, None),
+            column("name", "text", false, None),
+        ]
+    );
+
+    let mut txn = client.transaction_mut(&db);
+    let album = txn.insert(Album { title: "debut" });
+    let intro = txn.insert(Track {
+        album,
+        name: "intro",
+        milliseconds: None::<i64>,
+    });
+    txn.insert(Track {
+        album,
+        name: "outro",
+        milliseconds: Some(1000),
+    });
+    txn.delete(intro);
+
+    // deleted rows are not counted, just like they are not joined
+    let counts: Vec<_> = SchemaTables::ALL
+        .iter()
+        .map(|table| (table.name(), table.count(&txn)))
+        .collect();
+    assert_eq!(counts, [("album", 1), ("track", 1)]);
+    assert!(SchemaTables::Track.exists(&txn));
+}
@@ -0,0 +1,67 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, OneError, Table, Transaction,
+};
+
+#[schema]
+enum Schema {
+    User { name: String, score: i64 },
+}
+use v0::*;
+
+#[test]
+fn terminal() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let with_score = |txn: &Transaction<Schema>, min: i64| {
+        txn.query(|rows| {
+            let user = User::join(rows);
+            rows.filter(user.score().lt(min).not());
+            (
+                rows.count(),
+                rows.exists(),
+                rows.first(user.name()),
+                rows.one(user.name()),
+            )
+        })
+    };
+    assert_eq!(with_score(&txn, 0), (0, false, None, Err(OneError::NoRows)));
+
+    txn.insert(User {
+        name: "bob",
+        score: 10,
+    });
+    assert_eq!(
+        with_score(&txn, 0),
+        (1, true, Some("bob".to_owned()), Ok("bob".to_owned()))
+    );
+
+    txn.insert(User {
+        name: "alice",
+        score: 20,
+    });
+    txn.insert(User {
+        name: "carol",
+        score: -5,
+    });
+    // the first result is sorted like `into_vec`
+    assert_eq!(
+        with_score(&txn, 0),
+        (
+            2,
+            true,
+            Some("alice".to_owned()),
+            Err(OneError::MultipleRows)
+        )
+    );
+    assert_eq!(
+        with_score(&txn, 15),
+        (1, true, Some("alice".to_owned()), Ok("alice".to_owned()))
+    );
+}
@@ -0,0 +1,83 @@
+use rust_query::{
+    migration::{schema, Config},
+    testing::RowGenerator,
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    #[unique(email)]
+    #[public_id(ulid)]
+    #[timestamps]
+    User {
+        email: String,
+        #[check("length(name) > 3")]
+        name: String,
+        age: Option<i64>,
+    },
+    #[soft_delete]
+    #[versioned]
+    Post {
+        author: User,
+        reply_to: Option<Post>,
+        title: String,
+        score: f64,
+    },
+}
+use v0::*;
+
+#[test]
+fn testing() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let mut generator = RowGenerator::new(1);
+    // the users are created for the foreign keys of the posts
+    let posts: Vec<_> = (0..200)
+        .map(|_| generator.insert::<Post>(&mut txn))
+        .collect();
+    assert_eq!(posts.len(), 200);
+    for _ in 0..50 {
+        generator.insert::<User>(&mut txn);
+    }
+
+    let (users, short_names, with_age, ulids) = txn.query(|rows| {
+        let user = User::join(rows);
+        let users = rows.into_vec((user.name(), user.age(), user.public_id()));
+        let short = users.iter().filter(|x| x.0.len() <= 3).count();
+        let with_age = users.iter().filter(|x| x.1.is_some()).count();
+        let ulids = users.iter().filter(|x| x.2.as_str().len() == 26).count();
+        (users.len(), short, with_age, ulids)
+    });
+    assert!(users > 50);
+    assert_eq!(short_names, 0);
+    assert!(with_age > 0 && with_age < users);
+    assert_eq!(ulids, users);
+
+    let replies = txn.query(|rows| {
+        let post = Post::join(rows);
+        rows.filter_some(post.reply_to());
+        rows.count()
+    });
+    assert!(replies > 0 && replies < 200);
+    assert_eq!(
+        txn.query(|rows| {
+            let post = Post::join(rows);
+            rows.filter(post.version().eq(0));
+            rows.count()
+        }),
+        200
+    );
+
+    // the same seed gives the same values
+    let first = txn.query_one(posts[0].title());
+    drop(txn);
+    let mut txn2 = client.transaction_mut(&db);
+    let post = RowGenerator::new(1).insert::<Post>(&mut txn2);
+    assert_eq!(txn2.query_one(post.title()), first);
+}
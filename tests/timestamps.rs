@@ -0,0 +1,59 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, RowId,
+};
+
+#[schema]
+enum Schema {
+    #[timestamps]
+    Post { title: String },
+}
+use v0::*;
+
+#[test]
+fn timestamps() {
+    let path = std::env::temp_dir().join(format!(
+        "rust_query_timestamps_{}.sqlite",
+        std::process::id()
+    ));
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open(&path))
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    let mut txn = client.transaction_mut(&db);
+    let post = txn.insert(Post { title: "hello" });
+    let (created_at, updated_at) = txn.query_one((post.created_at(), post.updated_at()));
+    assert!((now - created_at).abs() <= 1);
+    assert_eq!(created_at, updated_at);
+    let id = RowId::from(post);
+    txn.commit();
+
+    // move the timestamps into the past, so that the update is visible
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute("UPDATE post SET created_at = 10, updated_at = 10", [])
+        .unwrap();
+    drop(conn);
+
+    let mut txn = client.transaction_mut(&db);
+    let post = txn.find(id).unwrap();
+    txn.update(post, Post { title: "world" });
+    let (created_at, updated_at) = txn.query_one((post.created_at(), post.updated_at()));
+    assert_eq!(created_at, 10);
+    assert!((now - updated_at).abs() <= 1);
+    assert_eq!(txn.query_one(post.title()), "world");
+    txn.commit();
+
+    for ext in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{ext}", path.display()));
+    }
+}
@@ -0,0 +1,62 @@
+use std::sync::Mutex;
+
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    User { name: String },
+}
+use v0::*;
+
+static TRACES: Mutex<Vec<(String, usize)>> = Mutex::new(vec![]);
+
+fn take_traces() -> Vec<(String, usize)> {
+    std::mem::take(&mut *TRACES.lock().unwrap())
+}
+
+#[test]
+fn trace() {
+    let config = Config::open_in_memory().trace(|trace| {
+        TRACES
+            .lock()
+            .unwrap()
+            .push((trace.sql.to_owned(), trace.params));
+    });
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client.migrator(config).unwrap().finish().unwrap();
+
+    let traces = take_traces();
+    assert!(traces[0].0.starts_with("CREATE TABLE \"user\""));
+
+    let mut txn = client.transaction_mut(&db);
+    let alice = txn.insert(User {
+        name: "alice".to_owned(),
+    });
+    let traces = take_traces();
+    assert_eq!(traces.len(), 1);
+    assert!(traces[0].0.starts_with("INSERT INTO \"user\""));
+    // the name is a bound parameter
+    assert!(traces[0].1 >= 1);
+
+    let names = txn.query(|rows| {
+        let user = User::join(rows);
+        rows.into_vec(user.name())
+    });
+    assert_eq!(names, ["alice"]);
+    let traces = take_traces();
+    assert_eq!(traces.len(), 1);
+    assert!(traces[0].0.starts_with("SELECT"));
+
+    txn.update(
+        alice,
+        User {
+            name: "bob".to_owned(),
+        },
+    );
+    let traces = take_traces();
+    assert!(traces.last().unwrap().0.starts_with("UPDATE \"user\""));
+    txn.commit();
+}
@@ -0,0 +1,143 @@
+#![cfg(feature = "tracing")]
+
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+use rust_query::{
+    migration::{schema, Alter, Config},
+    LocalClient, Table,
+};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+};
+
+#[schema]
+#[version(0..=1)]
+enum Schema {
+    User {
+        name: String,
+        #[version(1..)]
+        #[default = 0]
+        age: i64,
+    },
+}
+
+/// A span with its fields and the index of its parent.
+#[derive(Debug, Clone)]
+struct Recorded {
+    name: &'static str,
+    fields: String,
+    parent: Option<usize>,
+}
+
+/// Records all spans, the ids are the index in the list plus one.
+#[derive(Default)]
+struct Recorder {
+    spans: Arc<Mutex<Vec<Recorded>>>,
+    stack: Mutex<Vec<usize>>,
+}
+
+impl Visit for Recorded {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.fields += &format!("{}={value:?} ", field.name());
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attrs: &span::Attributes<'_>) -> span::Id {
+        let parent = match attrs.parent() {
+            Some(id) => Some(id.into_u64() as usize - 1),
+            None if attrs.is_contextual() => self.stack.lock().unwrap().last().copied(),
+            None => None,
+        };
+        let mut span = Recorded {
+            name: attrs.metadata().name(),
+            fields: String::new(),
+            parent,
+        };
+        attrs.record(&mut span);
+        let mut spans = self.spans.lock().unwrap();
+        spans.push(span);
+        span::Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, id: &span::Id) {
+        self.stack.lock().unwrap().push(id.into_u64() as usize - 1);
+    }
+
+    fn exit(&self, _: &span::Id) {
+        self.stack.lock().unwrap().pop();
+    }
+}
+
+#[test]
+fn tracing() {
+    let recorder = Recorder::default();
+    let spans = recorder.spans.clone();
+    let _guard = tracing::subscriber::set_default(recorder);
+
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator::<v0::Schema>(Config::open_in_memory())
+        .unwrap()
+        .migrate(v1::update::Schema {
+            user: Box::new(|_| Alter::identity()),
+        })
+        .finish()
+        .unwrap();
+
+    let spans_of = |name: &str| -> Vec<(usize, Recorded)> {
+        let spans = spans.lock().unwrap();
+        spans
+            .iter()
+            .cloned()
+            .enumerate()
+            .filter(|(_, span)| span.name == name)
+            .collect()
+    };
+
+    let [(migration, span)] = &spans_of("migration")[..] else {
+        panic!()
+    };
+    assert_eq!(span.fields, "from=0 to=1 ");
+    let [(_, span)] = &spans_of("migrate_table")[..] else {
+        panic!()
+    };
+    assert_eq!(span.fields, "table=\"user\" ");
+    assert_eq!(span.parent, Some(*migration));
+
+    let mut txn = client.transaction_mut(&db);
+    txn.insert(v1::User { name: "alice" });
+    txn.query(|rows| {
+        let user = v1::User::join(rows);
+        rows.into_vec(user.name())
+    });
+    txn.commit();
+
+    let [(transaction, span)] = &spans_of("transaction")[..] else {
+        panic!()
+    };
+    assert!(span.fields.starts_with("kind=\"write\" schema=\""));
+    assert!(span.fields.contains("v1::Schema"));
+    let execs: Vec<_> = spans_of("exec")
+        .into_iter()
+        .filter(|(_, span)| span.parent == Some(*transaction))
+        .collect();
+    assert_eq!(execs.len(), 2);
+    assert!(execs[0]
+        .1
+        .fields
+        .starts_with("sql=\"INSERT INTO \\\"user\\\""));
+    assert!(execs[1].1.fields.starts_with("sql=\"SELECT"));
+}
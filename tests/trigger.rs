@@ -0,0 +1,101 @@
+use rust_query::{
+    migration::{schema, Alter, Config},
+    Database, Error, LocalClient, Table,
+};
+
+#[schema]
+#[version(0..=1)]
+enum Schema {
+    Album {
+        title: String,
+        #[default = 0]
+        track_count: i64,
+    },
+    #[trigger(
+        track_insert,
+        "AFTER INSERT",
+        "UPDATE album SET track_count = track_count + 1 WHERE id = new.album;"
+    )]
+    #[trigger(
+        track_delete,
+        "AFTER DELETE",
+        "UPDATE album SET track_count = track_count - 1 WHERE id = old.album;"
+    )]
+    Track {
+        album: Album,
+        name: String,
+        #[version(..1)]
+        note: String,
+    },
+}
+use v1::*;
+
+fn track_counts(client: &mut LocalClient, db: &Database<Schema>) -> Vec<(String, i64)> {
+    let txn = client.transaction(db);
+    let mut counts = txn.query(|rows| {
+        let album = Album::join(rows);
+        rows.into_vec((album.title(), album.track_count()))
+    });
+    counts.sort();
+    counts
+}
+
+#[test]
+fn trigger() {
+    let mut client = LocalClient::try_new().unwrap();
+    // the triggers already exist when the init statement runs
+    let config = Config::open_in_memory().init_stmt(
+        "INSERT INTO album (id, title) VALUES (1, 'Jazz'), (2, 'Arrival');
+        INSERT INTO track (album, name, note) VALUES (1, 'Mustapha', ''), (1, 'Fat Bottomed Girls', '');",
+    );
+
+    // the track table is created again, the triggers are created again as well
+    let mut db = client
+        .migrator(config)
+        .unwrap()
+        .migrate(v1::update::Schema {
+            track: Box::new(|_| Alter::identity()),
+        })
+        .finish()
+        .unwrap();
+    assert_eq!(
+        track_counts(&mut client, &db),
+        [("Arrival".to_owned(), 0), ("Jazz".to_owned(), 2)]
+    );
+
+    let mut txn = client.transaction_mut(&db);
+    let arrival = txn
+        .query(|rows| {
+            let album = Album::join(rows);
+            rows.filter(album.title().eq("Arrival"));
+            rows.into_vec(album)
+        })
+        .pop()
+        .unwrap();
+    let track = txn.insert(Track {
+        album: arrival,
+        name: "Dancing Queen",
+    });
+    txn.insert(Track {
+        album: arrival,
+        name: "Money, Money, Money",
+    });
+    let mut del = txn.deletor();
+    assert_eq!(del.try_delete(track), Ok(true));
+    del.commit();
+    assert_eq!(
+        track_counts(&mut client, &db),
+        [("Arrival".to_owned(), 1), ("Jazz".to_owned(), 2)]
+    );
+
+    // a database without the trigger is different from the schema
+    let path =
+        std::env::temp_dir().join(format!("rust_query_trigger_{}.sqlite", std::process::id()));
+    db.backup_to(&path).unwrap();
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute("DROP TRIGGER track_delete", []).unwrap();
+    drop(conn);
+    let res = db.restore_from(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(res, Err(Error::SchemaMismatch(_))));
+}
@@ -0,0 +1,68 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient,
+};
+
+#[schema]
+enum Schema {
+    User {
+        #[unique_email]
+        email: String,
+        #[unique_username]
+        username: String,
+        score: i64,
+    },
+}
+use v0::*;
+
+#[test]
+fn unique_markers() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let alice = txn
+        .insert_or_update::<user::UniqueEmail>(User {
+            email: "alice@example.com",
+            username: "alice",
+            score: 1,
+        })
+        .unwrap();
+    assert_eq!(txn.lookup::<user::UniqueUsername>(("alice",)), Some(alice));
+    assert_eq!(txn.lookup::<user::UniqueUsername>(("bob",)), None);
+
+    // the row with the same email is updated
+    let res = txn.insert_or_update::<user::UniqueEmail>(User {
+        email: "alice@example.com",
+        username: "alice2",
+        score: 2,
+    });
+    assert_eq!(res, Some(alice));
+    assert_eq!(txn.query_one(alice.username()), "alice2");
+    assert_eq!(txn.query_one(alice.score()), 2);
+
+    let bob = txn
+        .insert_or_update::<user::UniqueUsername>(User {
+            email: "bob@example.com",
+            username: "bob",
+            score: 3,
+        })
+        .unwrap();
+    assert_ne!(alice, bob);
+    assert_eq!(
+        txn.lookup::<user::UniqueEmail>(("bob@example.com",)),
+        Some(bob)
+    );
+
+    // bob can not get the email of alice
+    let res = txn.insert_or_update::<user::UniqueUsername>(User {
+        email: "alice@example.com",
+        username: "bob",
+        score: 4,
+    });
+    assert_eq!(res, None);
+}
@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table, Version,
+};
+
+#[schema]
+enum Schema {
+    Comment { text: String },
+}
+use v0::*;
+
+#[test]
+fn read_your_writes() {
+    let path =
+        std::env::temp_dir().join(format!("rust_query_version_{}.sqlite", std::process::id()));
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open(&path))
+        .unwrap()
+        .finish()
+        .unwrap();
+    assert_eq!(client.transaction(&db).version(), Version::from_i64(0));
+
+    let mut txn = client.transaction_mut(&db);
+    txn.insert(Comment { text: "first" });
+    let first = txn.version();
+    txn.commit();
+    let txn = client.transaction_at_least(&db, first, Duration::ZERO);
+    assert_eq!(txn.unwrap().version(), first);
+
+    // a reader on another thread waits for the next version
+    let next = Version::from_i64(first.as_i64() + 1);
+    std::thread::scope(|s| {
+        let reader = s.spawn(|| {
+            let mut client = LocalClient::try_new().unwrap();
+            let txn = client.transaction_at_least(&db, next, Duration::from_secs(10));
+            txn.unwrap().query(|rows| {
+                let comment = Comment::join(rows);
+                rows.into_vec(comment.text())
+            })
+        });
+        std::thread::sleep(Duration::from_millis(50));
+        let mut txn = client.transaction_mut(&db);
+        txn.insert(Comment { text: "second" });
+        assert_eq!(txn.version(), next);
+        txn.commit();
+        assert_eq!(reader.join().unwrap(), ["first", "second"]);
+    });
+
+    let later = Version::from_i64(next.as_i64() + 1);
+    let txn = client.transaction_at_least(&db, later, Duration::from_millis(10));
+    assert!(txn.is_none());
+
+    for ext in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{ext}", path.display()));
+    }
+}
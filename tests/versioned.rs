@@ -0,0 +1,63 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, RowId, VersionConflict,
+};
+
+#[schema]
+enum Schema {
+    #[versioned]
+    Account { balance: i64 },
+}
+use v0::*;
+
+#[test]
+fn update_if_version() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let account = txn.insert(Account { balance: 10 });
+    let id = RowId::from(account);
+    txn.commit();
+
+    // two clients read the same row
+    let txn = client.transaction(&db);
+    let account = txn.find(id).unwrap();
+    let (balance, version) = txn.query_one((account.balance(), account.version()));
+    assert_eq!(version, 0);
+    drop(txn);
+
+    let mut txn = client.transaction_mut(&db);
+    let account = txn.find(id).unwrap();
+    let res = txn.update_if_version(
+        account,
+        version,
+        Account {
+            balance: balance + 5,
+        },
+    );
+    assert_eq!(res, Ok(1));
+    txn.commit();
+
+    // the second update is based on the old version
+    let mut txn = client.transaction_mut(&db);
+    let account = txn.find(id).unwrap();
+    let res = txn.update_if_version(
+        account,
+        version,
+        Account {
+            balance: balance - 5,
+        },
+    );
+    assert_eq!(res, Err(VersionConflict { current: 1 }));
+    assert_eq!(txn.query_one(account.balance()), 15);
+
+    // normal updates also increment the version
+    txn.update(account, Account { balance: 20 });
+    assert_eq!(txn.query_one(account.version()), 2);
+    txn.commit();
+}
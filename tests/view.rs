@@ -0,0 +1,80 @@
+use rust_query::{
+    migration::{schema, Alter, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+#[version(0..=1)]
+enum Schema {
+    Customer {
+        name: String,
+    },
+    Invoice {
+        customer: Customer,
+        total: i64,
+        #[version(..1)]
+        note: String,
+    },
+    #[view(
+        "SELECT customer.id, customer.id AS customer, count(invoice.id) AS invoices
+        FROM customer LEFT JOIN invoice ON invoice.customer = customer.id
+        GROUP BY customer.id"
+    )]
+    CustomerSummary {
+        customer: Customer,
+        invoices: i64,
+    },
+    #[version(..1)]
+    #[view("SELECT id, total FROM invoice WHERE total > 100")]
+    BigInvoice {
+        total: i64,
+    },
+}
+use v1::*;
+
+#[test]
+fn view() {
+    let mut client = LocalClient::try_new().unwrap();
+    let config = Config::open_in_memory().init_stmt(
+        "INSERT INTO customer (id, name) VALUES (1, 'alice'), (2, 'bob');
+        INSERT INTO invoice (id, customer, total, note) VALUES (1, 1, 50, ''), (2, 1, 150, '');",
+    );
+
+    // the invoice table is created again, so the views that use it are created again as well
+    let db = client
+        .migrator(config)
+        .unwrap()
+        .migrate(v1::update::Schema {
+            invoice: Box::new(|_| Alter::identity()),
+        })
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    let bob = txn
+        .query(|rows| {
+            let customer = Customer::join(rows);
+            rows.filter(customer.name().eq("bob"));
+            rows.into_vec(customer)
+        })
+        .pop()
+        .unwrap();
+    txn.insert(Invoice {
+        customer: bob,
+        total: 10,
+    });
+
+    let summary = txn.query(|rows| {
+        let summary = CustomerSummary::join(rows);
+        rows.into_vec((summary.customer().name(), summary.invoices()))
+    });
+    assert_eq!(summary, [("alice".to_owned(), 2), ("bob".to_owned(), 1)]);
+
+    let summary = txn.query(|rows| {
+        let summary = CustomerSummary::join(rows);
+        rows.filter(summary.customer().eq(bob));
+        rows.into_vec(summary.select_all())
+    });
+    assert_eq!(summary[0].customer, bob);
+    assert_eq!(summary[0].invoices, 1);
+}
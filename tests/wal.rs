@@ -0,0 +1,43 @@
+use rust_query::{
+    migration::{schema, Config},
+    CheckpointMode, LocalClient,
+};
+
+#[schema]
+enum Schema {
+    User { name: String },
+}
+use v0::*;
+
+#[test]
+fn checkpoint() {
+    let path = std::env::temp_dir().join(format!("rust_query_wal_{}.sqlite", std::process::id()));
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open(&path))
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    // keep a connection open, otherwise sqlite removes the WAL file when the last connection closes
+    let _conn = rusqlite::Connection::open(&path).unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    for i in 0..100 {
+        txn.insert(User {
+            name: format!("user {i}"),
+        });
+    }
+    txn.commit();
+
+    let stats = db.wal_stats().unwrap();
+    assert!(stats.frames > 0);
+
+    let res = db.checkpoint(CheckpointMode::Truncate).unwrap();
+    assert!(!res.busy);
+    assert_eq!(db.wal_stats().unwrap().size, 0);
+
+    for ext in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{ext}", path.display()));
+    }
+}
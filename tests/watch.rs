@@ -0,0 +1,85 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, RowId,
+};
+
+#[schema]
+enum Schema {
+    User {
+        name: String,
+    },
+    Post {
+        #[on_delete(cascade)]
+        author: User,
+        title: String,
+    },
+}
+use v0::*;
+
+fn counter() -> (Arc<AtomicUsize>, impl Fn() + Send + Sync + 'static) {
+    let count = Arc::new(AtomicUsize::new(0));
+    let inner = count.clone();
+    (count, move || {
+        inner.fetch_add(1, Ordering::Relaxed);
+    })
+}
+
+#[test]
+fn watch() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let (users, callback) = counter();
+    db.watch::<User>(callback);
+    let (posts, callback) = counter();
+    db.watch::<Post>(callback);
+
+    // changes that are rolled back are not notified
+    let mut txn = client.transaction_mut(&db);
+    txn.insert(User {
+        name: "alice".to_owned(),
+    });
+    drop(txn);
+    assert_eq!(users.load(Ordering::Relaxed), 0);
+
+    let mut txn = client.transaction_mut(&db);
+    let alice = txn.insert(User {
+        name: "alice".to_owned(),
+    });
+    txn.insert(Post {
+        author: alice,
+        title: "hello".to_owned(),
+    });
+    assert_eq!(users.load(Ordering::Relaxed), 0);
+    let alice = RowId::from(alice);
+    txn.commit();
+    assert_eq!(users.load(Ordering::Relaxed), 1);
+    assert_eq!(posts.load(Ordering::Relaxed), 1);
+
+    // only the callbacks of changed tables are called
+    let mut txn = client.transaction_mut(&db);
+    txn.insert(User {
+        name: "bob".to_owned(),
+    });
+    txn.commit();
+    assert_eq!(users.load(Ordering::Relaxed), 2);
+    assert_eq!(posts.load(Ordering::Relaxed), 1);
+
+    // rows deleted by a cascade are also noticed
+    let txn = client.transaction_mut(&db);
+    let alice = txn.find(alice).unwrap();
+    let mut txn = txn.deletor();
+    txn.try_delete(alice).unwrap();
+    txn.commit();
+    assert_eq!(users.load(Ordering::Relaxed), 3);
+    assert_eq!(posts.load(Ordering::Relaxed), 2);
+}
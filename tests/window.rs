@@ -0,0 +1,67 @@
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table,
+};
+
+#[schema]
+enum Schema {
+    Player { name: String },
+    Score { player: Player, points: i64 },
+}
+use v0::*;
+
+#[test]
+fn window() {
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open_in_memory())
+        .unwrap()
+        .finish()
+        .unwrap();
+    let mut txn = client.transaction_mut(&db);
+    let alice = txn.insert(Player { name: "alice" });
+    let bob = txn.insert(Player { name: "bob" });
+    for (player, points) in [(alice, 10), (alice, 30), (alice, 20), (bob, 5), (bob, 15)] {
+        txn.insert(Score { player, points });
+    }
+
+    // the second window only sees the best two scores of every player
+    let (best, sql) = txn.query(|rows| {
+        let score = Score::join(rows);
+        let rank = rows
+            .window()
+            .partition_by(score.player())
+            .order_by_desc(score.points())
+            .row_number();
+        let unused = rows.window().order_by(score.points()).rank();
+        rows.filter(rank.lt(3));
+        let prev = rows.window().order_by(score.points()).lag(score.player());
+        let prev = rows.filter_some(prev);
+        rows.filter(prev.name().eq("alice"));
+        let res = (score.player().name(), score.points());
+        (rows.into_vec(res.clone()), rows.to_sql((res, unused)).0)
+    });
+    assert_eq!(best, [("alice".to_owned(), 30)]);
+    // every window is joined once in the query and once in the window that uses it
+    assert_eq!(sql.matches("ROW_NUMBER").count(), 2);
+    assert_eq!(sql.matches("RANK").count(), 1);
+
+    // rows from `union_all` are not identified by their value alone
+    let numbers = txn.query(|rows| {
+        let points = rows.union_all(
+            |rows| Score::join(rows).points(),
+            |rows| Score::join(rows).points(),
+        );
+        rows.filter(points.lt(15));
+        let nr = rows.window().order_by(points).row_number();
+        rows.into_vec(nr)
+    });
+    assert_eq!(numbers, [1, 2, 3, 4]);
+
+    // without tables there is a single row
+    let numbers = txn.query(|rows| {
+        let nr = rows.window().row_number();
+        rows.into_vec(nr)
+    });
+    assert_eq!(numbers, [1]);
+}
@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+use rust_query::{
+    migration::{schema, Config},
+    LocalClient, Table, WriteLock,
+};
+
+#[schema]
+enum Schema {
+    User { name: String },
+}
+use v0::*;
+
+fn user_count(lock: &WriteLock<Schema>) -> usize {
+    let mut client = LocalClient::try_new().unwrap();
+    let txn = client.transaction_locked(lock);
+    txn.query(|rows| {
+        let user = User::join(rows);
+        rows.into_vec(user.name())
+    })
+    .len()
+}
+
+#[test]
+fn write_lock() {
+    let path = std::env::temp_dir().join(format!(
+        "rust_query_write_lock_{}.sqlite",
+        std::process::id()
+    ));
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client
+        .migrator(Config::open(&path))
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut txn = client.transaction_mut(&db);
+    txn.insert(User {
+        name: "alice".to_owned(),
+    });
+    txn.commit();
+
+    std::thread::scope(|s| {
+        let lock = client.write_lock(&db);
+
+        // the writer has to wait until the lock is dropped
+        let writer = s.spawn(|| {
+            let mut client = LocalClient::try_new().unwrap();
+            let mut txn = client.transaction_mut(&db);
+            txn.insert(User {
+                name: "bob".to_owned(),
+            });
+            txn.commit();
+        });
+        std::thread::sleep(Duration::from_millis(50));
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let lock = lock.clone();
+                s.spawn(move || user_count(&lock))
+            })
+            .collect();
+        for reader in readers {
+            assert_eq!(reader.join().unwrap(), 1);
+        }
+        assert!(!writer.is_finished());
+
+        drop(lock);
+        writer.join().unwrap();
+    });
+
+    let txn = client.transaction(&db);
+    let names = txn.query(|rows| {
+        let user = User::join(rows);
+        rows.into_vec(user.name())
+    });
+    assert_eq!(names, ["alice", "bob"]);
+
+    for ext in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{ext}", path.display()));
+    }
+}
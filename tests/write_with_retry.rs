@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use rust_query::{
+    migration::{schema, Config},
+    Error, LocalClient, RetryPolicy, Table,
+};
+
+#[schema]
+enum Schema {
+    User { name: String },
+}
+use v0::*;
+
+#[test]
+fn write_with_retry() {
+    let path = std::env::temp_dir().join(format!("rust_query_retry_{}.sqlite", std::process::id()));
+    let config = Config::open(&path).busy_timeout(Duration::from_millis(10));
+    let mut client = LocalClient::try_new().unwrap();
+    let db = client.migrator(config).unwrap().finish().unwrap();
+
+    // another connection holds the write lock for longer than the busy_timeout
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute_batch("BEGIN IMMEDIATE").unwrap();
+
+    let no_retries = RetryPolicy {
+        retries: 0,
+        ..Default::default()
+    };
+    let res = client.write_with_retry(&db, no_retries, |_| Ok::<_, Error>(()));
+    assert_eq!(res, Err(Error::Busy));
+
+    let handle = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(200));
+        conn.execute_batch("COMMIT").unwrap();
+    });
+    let policy = RetryPolicy {
+        retries: 20,
+        initial_delay: Duration::from_millis(10),
+        max_delay: Duration::from_millis(50),
+    };
+    let mut calls = 0;
+    let res = client.write_with_retry(&db, policy, |txn| {
+        calls += 1;
+        txn.insert(User { name: "alice" });
+        Ok::<_, Error>(())
+    });
+    assert_eq!(res, Ok(()));
+    assert_eq!(calls, 1);
+    handle.join().unwrap();
+
+    // the error of the closure is returned and the transaction is rolled back
+    let res = client.write_with_retry(&db, policy, |txn| {
+        txn.insert(User { name: "bob" });
+        Err::<(), _>(Error::Sqlite("stop".to_owned()))
+    });
+    assert_eq!(res, Err(Error::Sqlite("stop".to_owned())));
+    let txn = client.transaction(&db);
+    let names = txn.query(|rows| {
+        let user = User::join(rows);
+        rows.into_vec(user.name())
+    });
+    assert_eq!(names, ["alice"]);
+    drop(txn);
+
+    for ext in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{ext}", path.display()));
+    }
+}